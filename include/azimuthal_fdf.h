@@ -0,0 +1,78 @@
+#ifndef AZIMUTHAL_FDF_H
+#define AZIMUTHAL_FDF_H
+
+/* Generated with cbindgen, do not edit manually. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Opaque handle to the settings of a simulation.
+typedef struct AfdfSettings AfdfSettings;
+
+// Create the settings from a null-terminated JSON string.
+//
+// Returns a null pointer if the JSON is invalid (or the settings could not be
+// created for another reason). The settings must be freed
+// with [`afdf_settings_free`].
+//
+// # Safety
+//
+// `json` must be a valid pointer to a null-terminated string.
+struct AfdfSettings *afdf_settings_from_json(const char *json);
+
+// Free the settings created by [`afdf_settings_from_json`].
+//
+// # Safety
+//
+// `settings` must be a pointer returned by [`afdf_settings_from_json`] (or
+// null), and must not be used after this call.
+void afdf_settings_free(struct AfdfSettings *settings);
+
+// Run the simulation.
+//
+// # Safety
+//
+// `settings` must be a valid pointer returned by [`afdf_settings_from_json`].
+int afdf_run(struct AfdfSettings *settings);
+
+#if defined(AFDF_HDF5)
+// Save the results of the simulation, as given by the observer.
+//
+// Returns `-2` if saving failed, and `-3` if it panicked.
+//
+// # Safety
+//
+// `settings` must be a valid pointer returned by [`afdf_settings_from_json`].
+int afdf_save(const struct AfdfSettings *settings);
+#endif
+
+// Number of modes logged by the time series observer.
+//
+// Returns zero if the observer is not a time series.
+//
+// # Safety
+//
+// `settings` must be a valid pointer returned by [`afdf_settings_from_json`].
+size_t afdf_time_series_len(const struct AfdfSettings *settings);
+
+// Copy the logged time series to the arrays provided by the caller.
+//
+// Each array must have room for `len` elements, where `len` is the value
+// returned by [`afdf_time_series_len`]. Arrays given as null are skipped.
+//
+// # Safety
+//
+// `settings` must be a valid pointer returned by [`afdf_settings_from_json`],
+// and the arrays must be null or valid for writing `len` doubles.
+int afdf_time_series_copy(const struct AfdfSettings *settings,
+                          size_t len,
+                          double *time,
+                          double *amplitude,
+                          double *orientation_angle,
+                          double *phase,
+                          double *nature_angle);
+
+#endif  /* AZIMUTHAL_FDF_H */
@@ -1,6 +1,7 @@
 //! Describes the azimuthal mode in terms of the four state space parameters.
 
-use crate::{Float, FRAC_PI_4, PI};
+use crate::settings::RNG;
+use crate::{Float, Sobol, FRAC_PI_4, PI};
 use serde::{Deserialize, Serialize};
 
 /// Acoustic or heat release rate mode.
@@ -56,6 +57,157 @@ impl From<SystemMode> for Mode {
     }
 }
 
+/// Uniform distribution of modes, used to sample initial conditions.
+///
+/// Each state space variable is drawn independently and uniformly
+/// from the range `[min, max]` given for that variable.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct ModeDistribution {
+    pub amplitude: [Float; 2],
+    pub orientation_angle: [Float; 2],
+    pub phase: [Float; 2],
+    pub nature_angle: [Float; 2],
+}
+
+impl ModeDistribution {
+    /// Check that all the sampled modes are valid.
+    pub fn is_valid(&self) -> bool {
+        let ranges = [
+            self.amplitude,
+            self.orientation_angle,
+            self.phase,
+            self.nature_angle,
+        ];
+        let ordered = ranges.iter().all(|[min, max]| min <= max);
+
+        ordered
+            && self.amplitude[0] > 0.0
+            && self.nature_angle.iter().all(|chi| chi.abs() <= FRAC_PI_4)
+    }
+
+    /// Draw a [`Mode`] from the distribution.
+    pub fn sample(&self, rng: &mut RNG) -> Mode {
+        let mut draw = |[min, max]: [Float; 2]| min + (max - min) * rng.get_uniform();
+
+        Mode::new(
+            draw(self.amplitude),
+            draw(self.orientation_angle),
+            draw(self.phase),
+            draw(self.nature_angle),
+        )
+    }
+
+    /// Mode at the point `index` of the (at least four-dimensional) Sobol sequence `sobol`.
+    pub fn sobol_point(&self, sobol: &Sobol, index: u64) -> Mode {
+        let point = sobol.point(index);
+        let scale = |[min, max]: [Float; 2], u: Float| min + (max - min) * u;
+
+        Mode::new(
+            scale(self.amplitude, point[0]),
+            scale(self.orientation_angle, point[1]),
+            scale(self.phase, point[2]),
+            scale(self.nature_angle, point[3]),
+        )
+    }
+
+    /// Draw `n` modes using Latin hypercube sampling.
+    ///
+    /// The range of each state space variable is divided into `n` strata of
+    /// equal width, and each stratum is sampled exactly once per variable.
+    pub fn latin_hypercube(&self, n: usize, rng: &mut RNG) -> Vec<Mode> {
+        let mut draw = |[min, max]: [Float; 2]| -> Vec<Float> {
+            // Random permutation of the strata (Fisher-Yates shuffle)
+            let mut strata: Vec<usize> = (0..n).collect();
+            for ind in (1..n).rev() {
+                let other = ((ind + 1) as Float * rng.get_uniform()) as usize;
+                strata.swap(ind, other.min(ind));
+            }
+
+            strata
+                .into_iter()
+                .map(|stratum| {
+                    let u = (stratum as Float + rng.get_uniform()) / (n as Float);
+                    min + (max - min) * u
+                })
+                .collect()
+        };
+
+        let amplitude = draw(self.amplitude);
+        let orientation_angle = draw(self.orientation_angle);
+        let phase = draw(self.phase);
+        let nature_angle = draw(self.nature_angle);
+
+        (0..n)
+            .map(|ind| {
+                Mode::new(
+                    amplitude[ind],
+                    orientation_angle[ind],
+                    phase[ind],
+                    nature_angle[ind],
+                )
+            })
+            .collect()
+    }
+}
+
+impl Default for ModeDistribution {
+    /// Unit amplitude with uniformly distributed orientation, phase and nature angle.
+    fn default() -> Self {
+        Self {
+            amplitude: [1.0, 1.0],
+            orientation_angle: [-PI, PI],
+            phase: [-PI, PI],
+            nature_angle: [-FRAC_PI_4, FRAC_PI_4],
+        }
+    }
+}
+
+/// Initial mode of a simulation, either fixed or drawn from a distribution.
+///
+/// When drawn from a distribution, the concrete draw is stored in `draw`
+/// such that it is included when the parameters are saved.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum InitialMode {
+    Fixed(Mode),
+    Random {
+        distribution: ModeDistribution,
+        #[serde(default)]
+        draw: Option<Mode>,
+    },
+}
+
+impl InitialMode {
+    /// Get the initial mode, drawing a new one if the initial mode is random.
+    pub fn resolve(&mut self, rng: &mut RNG) -> Mode {
+        match self {
+            Self::Fixed(mode) => *mode,
+            Self::Random { distribution, draw } => {
+                let mode = distribution.sample(rng);
+                *draw = Some(mode);
+                mode
+            }
+        }
+    }
+}
+
+/// How the random initial modes of the repetitions of a simulation are drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum InitialModeSampling {
+    /// Independently for each repetition.
+    #[default]
+    PseudoRandom,
+    /// At consecutive points of a Sobol sequence (quasi-Monte Carlo), such
+    /// that the repetitions cover the distribution evenly.
+    Sobol,
+}
+
+impl Default for InitialMode {
+    fn default() -> Self {
+        Self::Fixed(Mode::default())
+    }
+}
+
 /// Acoustic or heat release rate mode as expressed in the model.
 ///
 /// Acoustic or heat release rate mode as expressed in the model.
@@ -145,6 +297,94 @@ impl SystemMode {
     pub fn chi(&self) -> Float {
         0.5 * self.tan_2chi.atan()
     }
+
+    /// Check whether the state of the mode is finite, i.e. it has not diverged.
+    #[inline]
+    pub fn is_finite(&self) -> bool {
+        // The tangent of twice the nature angle is infinite for the spinning modes
+        self.a().is_finite()
+            && self.nth0.is_finite()
+            && self.phi.is_finite()
+            && !self.tan_2chi.is_nan()
+    }
+
+    /// Returns the position of the mode on the Poincaré sphere.
+    ///
+    /// The mode is mapped to the unit sphere using twice the nature angle
+    /// as latitude and twice the orientation angle as longitude, such that
+    /// standing modes lie on the equator and spinning modes on the poles.
+    #[inline]
+    pub fn poincare_sphere(&self) -> [Float; 3] {
+        let two_chi = 2.0 * self.chi();
+        let two_nth0 = 2.0 * self.nth0();
+
+        [
+            two_chi.cos() * two_nth0.cos(),
+            two_chi.cos() * two_nth0.sin(),
+            two_chi.sin(),
+        ]
+    }
+}
+
+/// Decomposition of a mode into an anticlockwise and a clockwise spinning wave.
+///
+/// The mode `A [cos(psi) cos(chi) cos(tau) + sin(psi) sin(chi) sin(tau)]`,
+/// with `psi = n theta - n theta_0` and `tau = 2 pi t + phi`, is the sum of
+/// the waves `A+ cos(n theta - 2 pi t - phi+)` and `A- cos(n theta + 2 pi t + phi-)`.
+/// A positive nature angle thereby corresponds to a dominant anticlockwise wave.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct SpinningWaves {
+    /// Amplitude `A+ = A (cos(chi) + sin(chi)) / 2` of the anticlockwise wave.
+    pub anticlockwise: Float,
+    /// Amplitude `A- = A (cos(chi) - sin(chi)) / 2` of the clockwise wave.
+    pub clockwise: Float,
+    /// Temporal phase `phi+ = phi + n theta_0` of the anticlockwise wave at `theta = 0`.
+    pub phase_anticlockwise: Float,
+    /// Temporal phase `phi- = phi - n theta_0` of the clockwise wave at `theta = 0`.
+    pub phase_clockwise: Float,
+}
+
+impl SpinningWaves {
+    /// Phase difference `phi+ - phi- = 2 n theta_0` of the two waves.
+    #[inline]
+    pub fn phase_difference(&self) -> Float {
+        self.phase_anticlockwise - self.phase_clockwise
+    }
+}
+
+impl From<Mode> for SpinningWaves {
+    #[inline]
+    fn from(value: Mode) -> Self {
+        let (sin, cos) = value.nature_angle.sin_cos();
+
+        Self {
+            anticlockwise: 0.5 * value.amplitude * (cos + sin),
+            clockwise: 0.5 * value.amplitude * (cos - sin),
+            phase_anticlockwise: value.phase + value.orientation_angle,
+            phase_clockwise: value.phase - value.orientation_angle,
+        }
+    }
+}
+
+impl From<SystemMode> for SpinningWaves {
+    #[inline]
+    fn from(value: SystemMode) -> Self {
+        Self::from(Mode::from(value))
+    }
+}
+
+impl From<SpinningWaves> for Mode {
+    #[inline]
+    fn from(value: SpinningWaves) -> Self {
+        let (plus, minus) = (value.anticlockwise, value.clockwise);
+
+        Self::new(
+            (2.0 * (plus.powi(2) + minus.powi(2))).sqrt(),
+            0.5 * value.phase_difference(),
+            0.5 * (value.phase_anticlockwise + value.phase_clockwise),
+            (plus - minus).atan2(plus + minus),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -178,4 +418,84 @@ mod tests {
     fn invalid_chi_panic() {
         SystemMode::new(1.0, 0.0, 0.0, -1.0);
     }
+
+    #[test]
+    fn diverged_mode_is_not_finite() {
+        let mode = SystemMode::new(1.0, 0.0, 0.0, FRAC_PI_4);
+        assert!(mode.is_finite());
+
+        let diverged = SystemMode {
+            ln_a: Float::INFINITY,
+            ..mode
+        };
+        assert!(!diverged.is_finite());
+        let diverged = SystemMode {
+            phi: Float::NAN,
+            ..mode
+        };
+        assert!(!diverged.is_finite());
+    }
+
+    #[test]
+    fn latin_hypercube() {
+        let distribution = ModeDistribution::default();
+        let n = 8;
+        let modes = distribution.latin_hypercube(n, &mut RNG::from_seed(3));
+
+        // Exactly one nature angle in each stratum
+        let [min, max] = distribution.nature_angle;
+        let mut strata: Vec<usize> = modes
+            .iter()
+            .map(|mode| ((mode.nature_angle - min) / (max - min) * n as Float) as usize)
+            .collect();
+        strata.sort();
+        assert_eq!(strata, (0..n).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn poincare_sphere() {
+        let [x, y, z] = SystemMode::new(1.0, 0.3, 0.0, 0.2).poincare_sphere();
+        assert!((x.powi(2) + y.powi(2) + z.powi(2) - 1.0).abs() < 10.0 * Float::EPSILON);
+
+        // Spinning modes are located at the poles
+        let [_, _, z] = SystemMode::new(1.0, 0.3, 0.0, FRAC_PI_4).poincare_sphere();
+        assert!((z - 1.0).abs() < 10.0 * Float::EPSILON);
+    }
+
+    #[test]
+    fn spinning_waves() {
+        let mode = Mode::new(1.3, 0.4, -0.2, 0.3);
+        let waves = SpinningWaves::from(mode);
+        assert!((waves.phase_difference() - 0.8).abs() < 10.0 * Float::EPSILON);
+
+        let round_trip = Mode::from(waves);
+        for (a, b) in [
+            (round_trip.amplitude, mode.amplitude),
+            (round_trip.orientation_angle, mode.orientation_angle),
+            (round_trip.phase, mode.phase),
+            (round_trip.nature_angle, mode.nature_angle),
+        ] {
+            assert!((a - b).abs() < 10.0 * Float::EPSILON);
+        }
+
+        // Only the anticlockwise wave remains for an anticlockwise spinning mode
+        let spinning = SpinningWaves::from(Mode::new(1.0, 0.0, 0.0, FRAC_PI_4));
+        assert!(spinning.clockwise.abs() < 10.0 * Float::EPSILON);
+    }
+
+    #[test]
+    fn initial_mode_json() {
+        let fixed =
+            r#"{"amplitude": 1.0, "orientation_angle": 0.0, "phase": 0.0, "nature_angle": 0.1}"#;
+        let fixed: InitialMode = serde_json::from_str(fixed).unwrap();
+        assert!(matches!(fixed, InitialMode::Fixed(_)));
+
+        let random = serde_json::to_string(&ModeDistribution::default()).unwrap();
+        let random = format!(r#"{{"distribution": {}}}"#, random);
+        let mut random: InitialMode = serde_json::from_str(&random).unwrap();
+
+        let mode = random.resolve(&mut RNG::new());
+        assert!(mode.is_valid());
+        assert!(matches!(random, InitialMode::Random { draw: Some(_), .. }));
+    }
 }
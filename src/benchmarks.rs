@@ -0,0 +1,238 @@
+//! Limiting cases with closed-form stationary probability densities.
+//!
+//! With a symmetric describing function (the conventional FDF, or the
+//! simplified AFDF with `r = 1`) and [`Saturation::Cubic`], the Fourier
+//! coefficients of the saturated gain are quadratic in the amplitude, and the
+//! drift of the amplitude and nature angle is the gradient of a potential.
+//! The stationary Fokker–Planck equation then has a zero-flux solution, which
+//! the histograms of long simulations have to reproduce, making these cases a
+//! regression test of the physics (the describing function, the
+//! noise-induced drift, and the time integration) rather than of the numbers.
+use crate::hrr_integral::DescribingFunction;
+use crate::{Float, Quaternion, Saturation, Settings, FRAC_PI_4};
+
+/// Number of quadrature points in each direction of the marginal densities.
+const QUADRATURE_POINTS: usize = 400;
+
+/// Possible errors for [`CubicSaturationBenchmark`].
+#[derive(Clone, Debug)]
+pub enum BenchmarkError {
+    DescribingFunction,
+    Saturation,
+    SaturationCoefficient,
+    Noise,
+    Ramp,
+}
+
+impl std::error::Error for BenchmarkError {}
+
+impl std::fmt::Display for BenchmarkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::DescribingFunction => {
+                "the describing function has to be symmetric (conventional, or simplified with r = 1)"
+            }
+            Self::Saturation => "the saturation has to be cubic",
+            Self::SaturationCoefficient => "the saturation coefficient has to be positive",
+            Self::Noise => "the stationary density is only defined with noise",
+            Self::Ramp => "the parameters cannot be ramped",
+        };
+
+        write!(f, "error in the benchmark: {}", msg)
+    }
+}
+
+/// Linear gain with cubic saturation in the symmetric case.
+///
+/// For the saturated gain `beta (1 - kappa a^2)` at the local amplitude `a`,
+/// damping `zeta`, and noise `sigma`, the stationary density of the amplitude
+/// `A` and nature angle `chi` is
+///
+/// `p(A, chi) ~ A^2 cos^(5/2)(2 chi) exp[2 (nu A^2 - c A^4 (1 + cos^2(2 chi) / 2) / 2) / sigma^2]`,
+///
+/// with the linear growth rate `nu = beta - zeta` and the saturation
+/// coefficient `c = beta kappa / 2`. The saturation is strongest for the
+/// standing modes (`chi = 0`), such that the density peaks towards the
+/// spinning modes, while the noise-induced drift (the `cos^(5/2)` factor)
+/// keeps it from reaching them.
+#[derive(Clone, Copy, Debug)]
+pub struct CubicSaturationBenchmark {
+    /// Linear growth rate `nu`.
+    pub growth_rate: Float,
+    /// Saturation coefficient `c`.
+    pub saturation_coefficient: Float,
+    /// Noise amplitude `sigma`.
+    pub noise: Float,
+}
+
+impl CubicSaturationBenchmark {
+    pub fn new(
+        growth_rate: Float,
+        saturation_coefficient: Float,
+        noise: Float,
+    ) -> Result<Self, BenchmarkError> {
+        if saturation_coefficient.is_nan() || saturation_coefficient <= 0.0 {
+            return Err(BenchmarkError::SaturationCoefficient);
+        }
+        if noise.is_nan() || noise <= 0.0 {
+            return Err(BenchmarkError::Noise);
+        }
+
+        Ok(Self {
+            growth_rate,
+            saturation_coefficient,
+            noise,
+        })
+    }
+
+    /// Benchmark of the describing function, saturation, and parameters of `settings`.
+    pub fn from_settings(settings: &Settings) -> Result<Self, BenchmarkError> {
+        if settings.ramp.is_some() {
+            return Err(BenchmarkError::Ramp);
+        }
+
+        let parameters = &settings.parameters;
+        let gain = match &settings.describing_function {
+            DescribingFunction::Conventional(hrr) => hrr.get_gain_scale() * parameters.get_gain(),
+            DescribingFunction::Simplified(hrr) if hrr.get_gain_ratio_r() == 1.0 => {
+                parameters.get_gain()
+            }
+            _ => return Err(BenchmarkError::DescribingFunction),
+        };
+        let Saturation::Cubic(kappa) = settings.saturation else {
+            return Err(BenchmarkError::Saturation);
+        };
+
+        Self::new(
+            gain - parameters.get_damping(),
+            0.5 * gain * kappa,
+            parameters.get_noise(),
+        )
+    }
+
+    /// Deterministic right hand side, including the noise-induced drift.
+    ///
+    /// Only the real and `imag_k` components are non-zero, as the drift is
+    /// independent of the orientation angle and temporal phase.
+    pub fn drift(&self, amplitude: Float, chi: Float) -> Quaternion {
+        let (nu, c) = (self.growth_rate, self.saturation_coefficient);
+        let a2 = amplitude.powi(2);
+        let (sin, cos) = (2.0 * chi).sin_cos();
+        let noise_drift = self.noise.powi(2) / (4.0 * a2);
+
+        Quaternion::new(
+            nu - c * a2 * (1.0 + 0.5 * cos.powi(2)) + noise_drift,
+            0.0,
+            0.0,
+            -0.5 * c * a2 * cos * sin + noise_drift * sin / cos,
+        )
+    }
+
+    /// Logarithm of the (non-normalized) joint density of the amplitude and nature angle.
+    pub fn log_density(&self, amplitude: Float, chi: Float) -> Float {
+        let (nu, c) = (self.growth_rate, self.saturation_coefficient);
+        let cos = (2.0 * chi).cos();
+        let a2 = amplitude.powi(2);
+        let exponent = nu * a2 - 0.5 * c * a2.powi(2) * (1.0 + 0.5 * cos.powi(2));
+
+        2.0 * amplitude.ln() + 2.5 * cos.ln() + 2.0 * exponent / self.noise.powi(2)
+    }
+
+    /// Amplitude above which the density is negligible.
+    fn amplitude_cutoff(&self) -> Float {
+        let c = self.saturation_coefficient;
+        let saturated = (self.growth_rate.max(0.0) / c).sqrt();
+        let fluctuations = (self.noise.powi(2) / c).powf(0.25);
+
+        4.0 * (saturated + fluctuations)
+    }
+
+    /// Marginal density of the amplitude.
+    pub fn amplitude_density(&self, amplitude: &[Float]) -> Vec<Float> {
+        marginal_density(
+            amplitude,
+            (0.0, self.amplitude_cutoff()),
+            (-FRAC_PI_4, FRAC_PI_4),
+            |a, chi| self.log_density(a, chi),
+        )
+    }
+
+    /// Marginal density of the nature angle.
+    pub fn nature_angle_density(&self, chi: &[Float]) -> Vec<Float> {
+        marginal_density(
+            chi,
+            (-FRAC_PI_4, FRAC_PI_4),
+            (0.0, self.amplitude_cutoff()),
+            |chi, a| self.log_density(a, chi),
+        )
+    }
+}
+
+/// Marginal density of `x` at `values`, for the joint log density `log_density(x, y)`.
+///
+/// The joint density is integrated over `y` in `y_range`, and normalized over
+/// `x_range` and `y_range`, by the midpoint rule.
+fn marginal_density(
+    values: &[Float],
+    x_range: (Float, Float),
+    y_range: (Float, Float),
+    log_density: impl Fn(Float, Float) -> Float,
+) -> Vec<Float> {
+    let midpoints = |(lower, upper): (Float, Float)| {
+        let width = (upper - lower) / QUADRATURE_POINTS as Float;
+        let points: Vec<Float> = (0..QUADRATURE_POINTS)
+            .map(|ind| lower + (ind as Float + 0.5) * width)
+            .collect();
+        (points, width)
+    };
+    let (x, dx) = midpoints(x_range);
+    let (y, dy) = midpoints(y_range);
+
+    // The densities are scaled by the maximum to avoid overflow
+    let table: Vec<Vec<Float>> = x
+        .iter()
+        .map(|&x| y.iter().map(|&y| log_density(x, y)).collect())
+        .collect();
+    let scale = table
+        .iter()
+        .flatten()
+        .copied()
+        .fold(Float::NEG_INFINITY, Float::max);
+    let integrate = |log_values: &[Float]| -> Float {
+        log_values.iter().map(|l| (l - scale).exp()).sum::<Float>() * dy
+    };
+    let normalization: Float = table.iter().map(|row| integrate(row)).sum::<Float>() * dx;
+
+    values
+        .iter()
+        .map(|&value| {
+            let row: Vec<Float> = y.iter().map(|&y| log_density(value, y)).collect();
+            integrate(&row) / normalization
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::azimuthal_mode::SystemMode;
+    use crate::hrr_integral::{self, ConventionalFDF, HeatReleaseRate};
+
+    #[test]
+    fn drift_matches_describing_function() {
+        let mut settings = Settings::default();
+        settings.set_saturation(Saturation::Cubic(2.0));
+        settings.describing_function =
+            DescribingFunction::Conventional(ConventionalFDF::with_gain_scale(1.5).unwrap());
+        let benchmark = CubicSaturationBenchmark::from_settings(&settings).unwrap();
+
+        for (a, chi) in [(0.1, 0.0), (0.4, 0.3), (0.8, -0.6)] {
+            let mode = SystemMode::new(a, 0.7, 0.2, chi);
+            let expected = settings.describing_function.integral(&mode, &settings)
+                + hrr_integral::noise_drift(&mode, &settings.parameters);
+            let drift = benchmark.drift(a, chi);
+            assert!((drift.real - expected.real).abs() < 1e-10);
+            assert!((drift.imag_k - expected.imag_k).abs() < 1e-10);
+        }
+    }
+}
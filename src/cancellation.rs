@@ -0,0 +1,69 @@
+//! Cooperative cancellation of running simulations.
+//!
+//! Simulations check the [`CancellationToken`] at every save step, and stop
+//! cleanly when it has been cancelled or its wall-clock budget is exceeded.
+//! The results up to that point are kept, and are marked as partial when saved.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Shared token used to stop simulations early.
+///
+/// Clones of the token share the same state, such that a batch of
+/// simulations can be stopped together.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a token which is cancelled when `budget` has passed.
+    pub fn with_budget(budget: Duration) -> Self {
+        Self {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            deadline: Instant::now().checked_add(budget),
+        }
+    }
+
+    /// Cancel all the simulations sharing this token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Check whether the token is cancelled (or the wall-clock budget is exceeded).
+    pub fn is_cancelled(&self) -> bool {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return true;
+        }
+
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                self.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_cancellation() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+
+        token.cancel();
+        assert!(clone.is_cancelled());
+
+        assert!(CancellationToken::with_budget(Duration::ZERO).is_cancelled());
+    }
+}
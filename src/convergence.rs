@@ -0,0 +1,289 @@
+//! Strong and weak convergence of the time integration.
+//!
+//! The simulation is repeated at the time step of the settings and at
+//! successively halved time steps, where the noise of each coarse time step
+//! is the sum of the noise of the corresponding finer time steps. Hence, all
+//! the simulations of a realization follow the same Brownian path, and the
+//! differences of the final amplitudes are caused by the time step alone.
+//! Compared with the finest time step as the reference, the errors give the
+//! convergence orders of the [`crate::Integrator`], and show whether the
+//! time step of the settings is adequate for the parameters.
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::azimuthal_mode::SystemMode;
+use crate::hrr_integral::DescribingFunction;
+#[cfg(feature = "hdf5")]
+use crate::observers::{self, SaveInfo};
+use crate::observers::{Observer, TimeSeriesObserver};
+use crate::{Float, ParametersError, SeedSequence, Settings, SimulationHooks};
+
+/// Possible errors for [`ConvergenceTest`].
+#[derive(Clone, Debug)]
+pub enum ConvergenceError {
+    Levels,
+    Samples,
+    Steps,
+    Stochastic,
+    Parameters(ParametersError),
+}
+
+impl std::error::Error for ConvergenceError {}
+
+impl std::fmt::Display for ConvergenceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Levels => {
+                "at least two time steps (besides the reference) are required".to_owned()
+            }
+            Self::Samples => "at least one sample is required".to_owned(),
+            Self::Steps => {
+                "the number of cycles has to be a multiple of the time step of the settings"
+                    .to_owned()
+            }
+            Self::Stochastic => {
+                "the stochastic describing function draws random numbers at every time step"
+                    .to_owned()
+            }
+            Self::Parameters(e) => e.to_string(),
+        };
+
+        write!(f, "error in the convergence test: {}", msg)
+    }
+}
+
+impl From<ParametersError> for ConvergenceError {
+    fn from(value: ParametersError) -> Self {
+        Self::Parameters(value)
+    }
+}
+
+/// Convergence test of the time integration around the settings.
+#[derive(Clone, Copy, Debug)]
+pub struct ConvergenceTest {
+    /// Number of tested time steps, the time step of the settings halved `levels - 1` times.
+    pub levels: usize,
+    /// Number of independent realizations (Brownian paths).
+    pub samples: usize,
+}
+
+/// Errors of the final amplitude at each tested time step.
+#[derive(Clone, Debug)]
+pub struct ConvergenceResult {
+    /// Time step of the reference simulations.
+    pub reference_timestep: Float,
+    /// Tested time steps, from the largest (the time step of the settings).
+    pub timesteps: Vec<Float>,
+    /// Mean absolute error of the final amplitude of each realization.
+    pub strong_error: Vec<Float>,
+    /// Absolute error of the mean final amplitude over the realizations.
+    pub weak_error: Vec<Float>,
+    /// Number of realizations diverging at any time step, excluded from the errors.
+    pub diverged: usize,
+}
+
+/// Slope of the least squares fit of `ln y` against `ln x`.
+fn log_log_slope(x: &[Float], y: &[Float]) -> Float {
+    let points: Vec<(Float, Float)> = x
+        .iter()
+        .zip(y)
+        .filter(|(_, &y)| y > 0.0)
+        .map(|(&x, &y)| (x.ln(), y.ln()))
+        .collect();
+    let n = points.len() as Float;
+    let mean_x = points.iter().map(|p| p.0).sum::<Float>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<Float>() / n;
+    let covariance: Float = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let variance: Float = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+
+    covariance / variance
+}
+
+impl ConvergenceTest {
+    pub fn new(levels: usize, samples: usize) -> Result<Self, ConvergenceError> {
+        if levels < 2 {
+            return Err(ConvergenceError::Levels);
+        }
+        if samples == 0 {
+            return Err(ConvergenceError::Samples);
+        }
+
+        Ok(Self { levels, samples })
+    }
+
+    /// Run the simulations around `settings` and compute the errors.
+    ///
+    /// The reference time step is the time step of the settings halved
+    /// `levels` times. The realizations use the seeds of the children of a
+    /// [`SeedSequence`] of the seed of the settings (drawn randomly if
+    /// unseeded), and are run in
+    /// parallel with the `parallel` feature.
+    pub fn compute(&self, settings: &Settings) -> Result<ConvergenceResult, ConvergenceError> {
+        if let DescribingFunction::Stochastic(_) = settings.describing_function {
+            return Err(ConvergenceError::Stochastic);
+        }
+
+        let timestep = settings.parameters.get_timestep();
+        let reference_timestep = timestep / (1 << self.levels) as Float;
+        let timesteps: Vec<Float> = (0..self.levels)
+            .map(|level| timestep / (1 << level) as Float)
+            .collect();
+
+        // Each coarse time step has to cover a whole number of reference time steps
+        let mut reference_parameters = settings.parameters.clone();
+        reference_parameters.set_timestep(reference_timestep)?;
+        let reference_steps = reference_parameters.get_total_steps();
+        if settings.parameters.get_total_steps() << self.levels != reference_steps {
+            return Err(ConvergenceError::Steps);
+        }
+
+        let sequence = SeedSequence::new(settings.seed.unwrap_or_else(rand::random));
+        #[cfg(feature = "parallel")]
+        let realizations = (0..self.samples as u64).into_par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let realizations = 0..self.samples as u64;
+        let final_amplitudes: Vec<Vec<Float>> = realizations
+            .map(|sample| {
+                // The reference is the last level, with a single noise substep
+                (0..=self.levels)
+                    .map(|level| {
+                        let substeps = 1 << (self.levels - level);
+                        let timestep = timestep / (1 << level) as Float;
+                        final_mode(settings, sequence.child(sample).seed(), timestep, substeps).a()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let (final_amplitudes, diverged): (Vec<_>, Vec<_>) = final_amplitudes
+            .into_iter()
+            .partition(|a| a.iter().all(|a| a.is_finite()));
+        let samples = final_amplitudes.len() as Float;
+        let (strong_error, weak_error) = (0..self.levels)
+            .map(|level| {
+                let strong = final_amplitudes
+                    .iter()
+                    .map(|a| (a[level] - a[self.levels]).abs())
+                    .sum::<Float>()
+                    / samples;
+                let weak = final_amplitudes
+                    .iter()
+                    .map(|a| a[level] - a[self.levels])
+                    .sum::<Float>()
+                    .abs()
+                    / samples;
+                (strong, weak)
+            })
+            .unzip();
+
+        Ok(ConvergenceResult {
+            reference_timestep,
+            timesteps,
+            strong_error,
+            weak_error,
+            diverged: diverged.len(),
+        })
+    }
+}
+
+/// Final mode of a simulation of the settings with the given seed and time step.
+///
+/// Each time step sums `substeps` standard normal samples for the noise.
+fn final_mode(settings: &Settings, seed: u64, timestep: Float, substeps: usize) -> SystemMode {
+    let mut settings = settings.clone();
+    settings
+        .parameters
+        .set_timestep(timestep)
+        .expect("the time steps are checked before running");
+    settings.set_seed(Some(seed));
+    settings.noise_substeps = substeps;
+    settings.set_observer(Observer::TimeSeries(TimeSeriesObserver::default()));
+
+    let final_mode = Arc::new(Mutex::new(SystemMode::default()));
+    let mut hooks = SimulationHooks::new();
+    let finish = Arc::clone(&final_mode);
+    hooks.on_finish(move |state| *finish.lock().unwrap() = *state.mode);
+    settings.hooks = hooks;
+
+    settings.run();
+
+    let mode = *final_mode.lock().unwrap();
+    mode
+}
+
+impl ConvergenceResult {
+    /// Strong convergence order, the slope of the strong error against the time step.
+    pub fn strong_order(&self) -> Float {
+        log_log_slope(&self.timesteps, &self.strong_error)
+    }
+
+    /// Weak convergence order, the slope of the weak error against the time step.
+    pub fn weak_order(&self) -> Float {
+        log_log_slope(&self.timesteps, &self.weak_error)
+    }
+
+    /// Save the time steps, errors, and orders.
+    #[cfg(feature = "hdf5")]
+    pub fn save(&self, save_info: &SaveInfo, settings: &Settings) -> hdf5::Result<()> {
+        let file = hdf5::File::append(save_info.get_path())?;
+        let group = file.create_group(save_info.get_group())?;
+
+        observers::write_dataset(&group, &self.timesteps, "timesteps")?;
+        observers::write_dataset(&group, &self.strong_error, "strong_error")?;
+        observers::write_dataset(&group, &self.weak_error, "weak_error")?;
+
+        observers::save_attr(
+            &group,
+            &ndarray::arr0(self.reference_timestep),
+            "reference_timestep",
+        )?;
+        observers::save_attr(&group, &ndarray::arr0(self.strong_order()), "strong_order")?;
+        observers::save_attr(&group, &ndarray::arr0(self.weak_order()), "weak_order")?;
+        observers::save_attr(&group, &ndarray::arr0(self.diverged as u64), "diverged")?;
+        observers::save_str_attr(&group, &format!("{:?}", settings.integrator), "integrator")?;
+
+        observers::save_parameters_as_attribute_json(&group, &settings.parameters)
+    }
+}
+
+impl std::fmt::Display for ConvergenceResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines = vec![format!(
+            "reference time step {}: strong order {}, weak order {} ({} diverged realizations)",
+            self.reference_timestep,
+            self.strong_order(),
+            self.weak_order(),
+            self.diverged
+        )];
+        for ((dt, strong), weak) in self
+            .timesteps
+            .iter()
+            .zip(&self.strong_error)
+            .zip(&self.weak_error)
+        {
+            lines.push(format!(
+                "dt = {}: strong error = {}, weak error = {}",
+                dt, strong, weak
+            ));
+        }
+
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn order_of_power_law() {
+        let timesteps = [0.04, 0.02, 0.01];
+        let errors: Vec<Float> = timesteps.iter().map(|dt: &Float| 3.0 * dt.sqrt()).collect();
+        assert!((log_log_slope(&timesteps, &errors) - 0.5).abs() < 1e-12);
+    }
+}
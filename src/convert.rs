@@ -0,0 +1,314 @@
+//! Conversion of the HDF5 results to CSV or NumPy `.npz` files.
+//!
+//! The datasets of the selected groups (and their subgroups) are re-exported
+//! for collaborators without HDF5 tooling, and the attributes of the groups
+//! and datasets (the settings, seeds, units, ...) are preserved in a sidecar
+//! JSON file next to the output, with the path of the output and the
+//! extension `.json`. As CSV, each dataset is written to the file
+//! `<group>/<dataset>.csv` in the output directory, with a header row naming
+//! the columns (`<dataset>[<column>]` for two dimensional datasets). As
+//! `.npz`, the datasets keep their shape, with the groups as prefixes of the
+//! array names as in [`crate::observers::Observer::save_npz`].
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use hdf5::types::{TypeDescriptor, VarLenUnicode};
+use serde_json::{json, Map, Value};
+
+use crate::Float;
+
+/// Possible errors for [`convert`].
+#[derive(Clone, Debug)]
+pub enum ConvertError {
+    Format(String),
+    Group(String),
+    OutputExists(PathBuf),
+    Dimensions(String),
+    NpzNotEnabled,
+}
+
+impl std::error::Error for ConvertError {}
+
+impl std::fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Format(format) => format!("unknown format '{}' (csv or npz)", format),
+            Self::Group(group) => format!("the group '{}' does not exist", group),
+            Self::OutputExists(path) => format!("'{}' already exists", path.to_string_lossy()),
+            Self::Dimensions(name) => format!(
+                "the dataset '{}' has more than two dimensions, which CSV does not support",
+                name
+            ),
+            Self::NpzNotEnabled => "the npz feature is required for the npz format".to_owned(),
+        };
+
+        write!(f, "error in the conversion: {}", msg)
+    }
+}
+
+/// Format of the converted results.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConvertFormat {
+    /// One CSV file per dataset, in a directory.
+    Csv,
+    /// A NumPy `.npz` archive (requires the `npz` feature).
+    Npz,
+}
+
+impl FromStr for ConvertFormat {
+    type Err = ConvertError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "npz" => Ok(Self::Npz),
+            _ => Err(ConvertError::Format(s.to_owned())),
+        }
+    }
+}
+
+/// Path of the sidecar JSON file with the metadata of the converted `output`.
+pub fn sidecar_path(output: &Path) -> PathBuf {
+    output.with_extension("json")
+}
+
+/// Convert the `groups` (all the groups if empty) of the HDF5 file `input` to `output`.
+///
+/// The output and its sidecar JSON file must not exist already. Returns the
+/// names of the converted groups.
+pub fn convert(
+    input: &Path,
+    groups: &[String],
+    output: &Path,
+    format: ConvertFormat,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    if cfg!(not(feature = "npz")) && format == ConvertFormat::Npz {
+        return Err(Box::new(ConvertError::NpzNotEnabled));
+    }
+    let sidecar = sidecar_path(output);
+    for path in [output, &sidecar] {
+        if path.exists() {
+            return Err(Box::new(ConvertError::OutputExists(path.to_owned())));
+        }
+    }
+
+    let file = hdf5::File::open(input)?;
+    let groups = if groups.is_empty() {
+        file.member_names()?
+            .into_iter()
+            .filter(|name| file.group(name).is_ok())
+            .collect()
+    } else {
+        groups.to_vec()
+    };
+
+    let mut metadata = Map::new();
+    for name in &groups {
+        let group = file
+            .group(name)
+            .map_err(|_| ConvertError::Group(name.to_owned()))?;
+        metadata.insert(
+            name.to_owned(),
+            convert_group(&group, name, output, format)?,
+        );
+    }
+
+    let sidecar_json = json!({
+        "source": input.to_string_lossy(),
+        "format": match format {
+            ConvertFormat::Csv => "csv",
+            ConvertFormat::Npz => "npz",
+        },
+        "groups": metadata,
+    });
+    if let Some(directory) = sidecar.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(directory)?;
+    }
+    serde_json::to_writer_pretty(File::create(&sidecar)?, &sidecar_json)?;
+
+    Ok(groups)
+}
+
+/// Convert the datasets of `group` (at `path` in the file) and its subgroups, and return their metadata.
+fn convert_group(
+    group: &hdf5::Group,
+    path: &str,
+    output: &Path,
+    format: ConvertFormat,
+) -> Result<Value, Box<dyn Error>> {
+    let mut datasets = Map::new();
+    let mut skipped = Vec::new();
+    for dataset in group.datasets()? {
+        let name = member_name(&dataset.name());
+        let shape = dataset.shape();
+        // Only numerical datasets are converted
+        let Ok(data) = dataset.read_raw::<Float>() else {
+            skipped.push(Value::from(name));
+            continue;
+        };
+
+        let file = match format {
+            ConvertFormat::Csv => {
+                if shape.len() > 2 {
+                    return Err(Box::new(ConvertError::Dimensions(format!(
+                        "{}/{}",
+                        path, name
+                    ))));
+                }
+                let file = PathBuf::from(path).join(format!("{}.csv", name));
+                write_csv(&output.join(&file), &name, &data, &shape)?;
+                file.to_string_lossy().into_owned()
+            }
+            ConvertFormat::Npz => {
+                write_npz(output, path, &name, &data, &shape)?;
+                format!("{}/{}", path, name)
+            }
+        };
+
+        datasets.insert(
+            name,
+            json!({
+                "file": file,
+                "shape": shape,
+                "attributes": read_attributes(&dataset),
+            }),
+        );
+    }
+
+    let mut subgroups = Map::new();
+    for subgroup in group.groups()? {
+        let name = member_name(&subgroup.name());
+        let subpath = format!("{}/{}", path, name);
+        subgroups.insert(name, convert_group(&subgroup, &subpath, output, format)?);
+    }
+
+    Ok(json!({
+        "attributes": read_attributes(group),
+        "datasets": datasets,
+        "skipped_datasets": skipped,
+        "groups": subgroups,
+    }))
+}
+
+/// Last component of the full path `name` of a member of the file.
+pub(crate) fn member_name(name: &str) -> String {
+    name.rsplit('/').next().unwrap_or(name).to_owned()
+}
+
+/// Values of the numerical, boolean, and string attributes of `location`.
+///
+/// The integers are read as such, such that e.g. the 64-bit seeds are preserved exactly.
+pub(crate) fn read_attributes(location: &hdf5::Location) -> Value {
+    let mut attributes = Map::new();
+    for name in location.attr_names().unwrap_or_default() {
+        let Ok(attr) = location.attr(&name) else {
+            continue;
+        };
+        let Ok(descriptor) = attr.dtype().and_then(|dtype| dtype.to_descriptor()) else {
+            continue;
+        };
+        let scalar = attr.shape().is_empty();
+        let value = match descriptor {
+            TypeDescriptor::Unsigned(_) => attr.read_raw::<u64>().map(|v| to_json(v, scalar)),
+            TypeDescriptor::Integer(_) => attr.read_raw::<i64>().map(|v| to_json(v, scalar)),
+            TypeDescriptor::Float(_) => attr.read_raw::<Float>().map(|v| to_json(v, scalar)),
+            TypeDescriptor::Boolean => attr.read_raw::<bool>().map(|v| to_json(v, scalar)),
+            TypeDescriptor::VarLenUnicode => attr
+                .read_scalar::<VarLenUnicode>()
+                .map(|value| json!(value.as_str())),
+            _ => continue,
+        };
+        if let Ok(value) = value {
+            attributes.insert(name, value);
+        }
+    }
+
+    Value::Object(attributes)
+}
+
+/// The `values` of an attribute as JSON, as a single value if it is a `scalar`.
+fn to_json<T: Into<Value>>(values: Vec<T>, scalar: bool) -> Value {
+    match values.into_iter().map(Into::into).collect::<Vec<Value>>() {
+        values if !scalar => Value::Array(values),
+        mut values => values.pop().unwrap_or(Value::Null),
+    }
+}
+
+/// Write `data` of the given `shape` (at most two dimensions) as a CSV file.
+fn write_csv(path: &Path, name: &str, data: &[Float], shape: &[usize]) -> std::io::Result<()> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    let columns = match shape {
+        [_, columns] => *columns,
+        _ => 1,
+    };
+    let header: Vec<String> = match shape {
+        [_, columns] => (0..*columns)
+            .map(|column| format!("{}[{}]", name, column))
+            .collect(),
+        _ => vec![name.to_owned()],
+    };
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "{}", header.join(","))?;
+    for row in data.chunks(columns.max(1)) {
+        let row: Vec<String> = row.iter().map(|value| value.to_string()).collect();
+        writeln!(writer, "{}", row.join(","))?;
+    }
+
+    writer.flush()
+}
+
+/// Append `data` of the given `shape` as the array `name` of the group `group` of the `.npz` file `path`.
+#[cfg(feature = "npz")]
+fn write_npz(
+    path: &Path,
+    group: &str,
+    name: &str,
+    data: &[Float],
+    shape: &[usize],
+) -> std::io::Result<()> {
+    let save_info = crate::observers::SaveInfo::new(&path.to_path_buf(), group);
+    let mut writer = crate::observers::npz::NpzWriter::open(&save_info)?;
+    writer.write_with_shape(name, data, shape)?;
+
+    writer.finish()
+}
+
+#[cfg(not(feature = "npz"))]
+fn write_npz(
+    _path: &Path,
+    _group: &str,
+    _name: &str,
+    _data: &[Float],
+    _shape: &[usize],
+) -> std::io::Result<()> {
+    unreachable!("the npz format is rejected without the npz feature")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_has_a_header_and_rows() {
+        let directory = std::env::temp_dir().join(format!("convert_test_{}", std::process::id()));
+        let path = directory.join("group").join("counts.csv");
+        write_csv(&path, "counts", &[1.0, 2.0, 3.0, 4.5, 5.0, 6.0], &[3, 2]).unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_eq!(contents, "counts[0],counts[1]\n1,2\n3,4.5\n5,6\n");
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(member_name("/rep_000/spinning_waves"), "spinning_waves");
+        assert_eq!(
+            sidecar_path(Path::new("results.npz")),
+            PathBuf::from("results.json")
+        );
+        assert!("parquet".parse::<ConvertFormat>().is_err());
+    }
+}
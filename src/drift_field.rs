@@ -0,0 +1,188 @@
+//! Deterministic drift field of the amplitude and nature angle.
+//!
+//! Evaluates the deterministic right hand side of the governing equations
+//! over a grid of the amplitude `A` and nature angle `chi`, at a fixed
+//! orientation angle and temporal phase, for the describing function and
+//! saturation of the settings. The resulting vector field gives the phase
+//! portraits of the noise-free dynamics without re-implementing the model.
+use crate::azimuthal_mode::SystemMode;
+use crate::hrr_integral::{self, HeatReleaseRate};
+#[cfg(feature = "hdf5")]
+use crate::observers::{self, SaveInfo};
+use crate::{Float, Settings, FRAC_PI_4};
+
+/// Possible errors for [`DriftField`].
+#[derive(Clone, Debug)]
+pub enum DriftFieldError {
+    Points,
+    AmplitudeLimit,
+}
+
+impl std::error::Error for DriftFieldError {}
+
+impl std::fmt::Display for DriftFieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Points => "at least one grid point is required in each direction",
+            Self::AmplitudeLimit => "the amplitude limit has to be positive",
+        };
+
+        write!(f, "error in the drift field: {}", msg)
+    }
+}
+
+/// Grid of the amplitude and nature angle the drift field is evaluated on.
+///
+/// The grid points are the centres of `amplitude_points` equal intervals of
+/// `(0, amplitude_limit)` and `chi_points` equal intervals of `(-pi/4, pi/4)`,
+/// avoiding the singular zero amplitude and purely spinning modes.
+#[derive(Clone, Copy, Debug)]
+pub struct DriftFieldGrid {
+    pub amplitude_limit: Float,
+    pub amplitude_points: usize,
+    pub chi_points: usize,
+    pub orientation_angle: Float,
+    pub phase: Float,
+    /// Include the deterministic drift induced by the noise.
+    pub noise_drift: bool,
+}
+
+impl Default for DriftFieldGrid {
+    fn default() -> Self {
+        Self {
+            amplitude_limit: 5.0,
+            amplitude_points: 100,
+            chi_points: 100,
+            orientation_angle: 0.0,
+            phase: 0.0,
+            noise_drift: true,
+        }
+    }
+}
+
+impl DriftFieldGrid {
+    fn is_valid(&self) -> Result<(), DriftFieldError> {
+        if self.amplitude_points == 0 || self.chi_points == 0 {
+            return Err(DriftFieldError::Points);
+        }
+        if self.amplitude_limit.is_nan() || self.amplitude_limit <= 0.0 {
+            return Err(DriftFieldError::AmplitudeLimit);
+        }
+
+        Ok(())
+    }
+}
+
+/// Centres of `points` equal intervals of `(lower, upper)`.
+pub(crate) fn cell_centres(lower: Float, upper: Float, points: usize) -> Vec<Float> {
+    let width = (upper - lower) / points as Float;
+    (0..points)
+        .map(|ind| lower + (ind as Float + 0.5) * width)
+        .collect()
+}
+
+/// Rates of change of the amplitude and nature angle over a grid.
+///
+/// The rates are stored with the amplitude along the rows and the nature
+/// angle along the columns, i.e. element `(i, j)` is at `amplitude[i]` and
+/// `chi[j]`. The amplitude rate is the right hand side of the equation for
+/// `ln A` multiplied by `A`.
+#[derive(Clone, Debug)]
+pub struct DriftField {
+    pub grid: DriftFieldGrid,
+    pub amplitude: Vec<Float>,
+    pub chi: Vec<Float>,
+    pub amplitude_rate: Vec<Float>,
+    pub chi_rate: Vec<Float>,
+}
+
+impl DriftField {
+    /// Evaluate the drift field of the settings on the grid.
+    pub fn compute(settings: &Settings, grid: &DriftFieldGrid) -> Result<Self, DriftFieldError> {
+        grid.is_valid()?;
+
+        let amplitude = cell_centres(0.0, grid.amplitude_limit, grid.amplitude_points);
+        let chi = cell_centres(-FRAC_PI_4, FRAC_PI_4, grid.chi_points);
+
+        let size = amplitude.len() * chi.len();
+        let mut amplitude_rate = Vec::with_capacity(size);
+        let mut chi_rate = Vec::with_capacity(size);
+        for &a in &amplitude {
+            for &c in &chi {
+                let mode = SystemMode::new(a, grid.orientation_angle, grid.phase, c);
+                let mut rhs = settings.describing_function.integral(&mode, settings);
+                if grid.noise_drift {
+                    rhs = rhs + hrr_integral::noise_drift(&mode, &settings.parameters);
+                }
+
+                // tan 2chi changes at the rate -2 imag_k / cos^2 2chi
+                amplitude_rate.push(a * rhs.real);
+                chi_rate.push(-rhs.imag_k);
+            }
+        }
+
+        Ok(Self {
+            grid: *grid,
+            amplitude,
+            chi,
+            amplitude_rate,
+            chi_rate,
+        })
+    }
+
+    /// Save the grid and the rates, with the rates as (amplitude, chi) matrices.
+    #[cfg(feature = "hdf5")]
+    pub fn save(&self, save_info: &SaveInfo, settings: &Settings) -> hdf5::Result<()> {
+        let file = hdf5::File::append(save_info.get_path())?;
+        let group = file.create_group(save_info.get_group())?;
+
+        observers::write_dataset(&group, &self.amplitude, "amplitude")?;
+        observers::write_dataset(&group, &self.chi, "chi")?;
+
+        let shape = (self.amplitude.len(), self.chi.len());
+        for (name, rate) in [
+            ("amplitude_rate", &self.amplitude_rate),
+            ("chi_rate", &self.chi_rate),
+        ] {
+            let rate =
+                ndarray::Array2::from_shape_vec(shape, rate.clone()).map_err(|e| e.to_string())?;
+            group.new_dataset_builder().with_data(&rate).create(name)?;
+        }
+
+        observers::save_attr(
+            &group,
+            &ndarray::arr0(self.grid.orientation_angle),
+            "orientation_angle",
+        )?;
+        observers::save_attr(&group, &ndarray::arr0(self.grid.phase), "phase")?;
+        observers::save_attr(&group, &ndarray::arr0(self.grid.noise_drift), "noise_drift")?;
+        observers::save_str_attr(
+            &group,
+            settings.describing_function.name(),
+            "describing_function",
+        )?;
+
+        observers::save_parameters_as_attribute_json(&group, &settings.parameters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standing_modes_grow_at_small_amplitudes() {
+        let settings = Settings::default();
+        let grid = DriftFieldGrid {
+            amplitude_points: 4,
+            chi_points: 3,
+            ..Default::default()
+        };
+        let field = DriftField::compute(&settings, &grid).unwrap();
+        assert_eq!(field.amplitude_rate.len(), 12);
+
+        // The middle column is the standing mode, and the first row the smallest amplitude
+        assert!(field.chi[1].abs() < 1e-12);
+        assert!(field.amplitude_rate[1] > 0.0);
+    }
+}
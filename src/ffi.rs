@@ -0,0 +1,179 @@
+//! C interface to the simulation.
+//!
+//! Allows the model to be embedded in existing C/C++/Fortran toolchains. The
+//! settings are created from a JSON string (the same format as the settings
+//! files), and the logged time series is copied to arrays owned by the caller.
+//! The header `include/azimuthal_fdf.h` is generated with cbindgen, see
+//! `cbindgen.toml`.
+//!
+//! Functions returning an integer status return `0` on success and a negative
+//! value on failure.
+use std::ffi::{c_char, c_int, CStr};
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::observers::Observer;
+#[cfg(feature = "hdf5")]
+use crate::observers::ObserverTrait;
+use crate::Settings;
+
+/// Opaque handle to the settings of a simulation.
+pub struct AfdfSettings {
+    settings: Settings,
+}
+
+/// Create the settings from a null-terminated JSON string.
+///
+/// Returns a null pointer if the JSON is invalid (or the settings could not be
+/// created for another reason). The settings must be freed
+/// with [`afdf_settings_free`].
+///
+/// # Safety
+///
+/// `json` must be a valid pointer to a null-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn afdf_settings_from_json(json: *const c_char) -> *mut AfdfSettings {
+    if json.is_null() {
+        return std::ptr::null_mut();
+    }
+
+    let json = match CStr::from_ptr(json).to_str() {
+        Ok(json) => json,
+        Err(_) => return std::ptr::null_mut(),
+    };
+
+    // Panics must not unwind into the calling language
+    match panic::catch_unwind(|| Settings::from_json(json)) {
+        Ok(Ok(settings)) => Box::into_raw(Box::new(AfdfSettings { settings })),
+        Ok(Err(e)) => {
+            eprintln!("{}", e);
+            std::ptr::null_mut()
+        }
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Free the settings created by [`afdf_settings_from_json`].
+///
+/// # Safety
+///
+/// `settings` must be a pointer returned by [`afdf_settings_from_json`] (or
+/// null), and must not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn afdf_settings_free(settings: *mut AfdfSettings) {
+    if !settings.is_null() {
+        drop(Box::from_raw(settings));
+    }
+}
+
+/// Run the simulation.
+///
+/// # Safety
+///
+/// `settings` must be a valid pointer returned by [`afdf_settings_from_json`].
+#[no_mangle]
+pub unsafe extern "C" fn afdf_run(settings: *mut AfdfSettings) -> c_int {
+    let settings = match settings.as_mut() {
+        Some(settings) => &mut settings.settings,
+        None => return -1,
+    };
+
+    // Panics must not unwind into the calling language
+    match panic::catch_unwind(AssertUnwindSafe(|| settings.run())) {
+        Ok(_) => 0,
+        Err(_) => -2,
+    }
+}
+
+/// Save the results of the simulation, as given by the observer.
+///
+/// Returns `-2` if saving failed, and `-3` if it panicked.
+///
+/// # Safety
+///
+/// `settings` must be a valid pointer returned by [`afdf_settings_from_json`].
+#[cfg(feature = "hdf5")]
+#[no_mangle]
+pub unsafe extern "C" fn afdf_save(settings: *const AfdfSettings) -> c_int {
+    let settings = match settings.as_ref() {
+        Some(settings) => &settings.settings,
+        None => return -1,
+    };
+
+    // Panics must not unwind into the calling language
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        settings
+            .observer
+            .save(&settings.parameters, &settings.describing_function)
+    }));
+    match result {
+        Ok(Ok(_)) => 0,
+        Ok(Err(e)) => {
+            eprintln!("could not save: {}", e);
+            -2
+        }
+        Err(_) => -3,
+    }
+}
+
+/// Number of modes logged by the time series observer.
+///
+/// Returns zero if the observer is not a time series.
+///
+/// # Safety
+///
+/// `settings` must be a valid pointer returned by [`afdf_settings_from_json`].
+#[no_mangle]
+pub unsafe extern "C" fn afdf_time_series_len(settings: *const AfdfSettings) -> usize {
+    match settings.as_ref().map(|s| &s.settings.observer) {
+        Some(Observer::TimeSeries(observer)) => observer.get_time().len(),
+        _ => 0,
+    }
+}
+
+/// Copy the logged time series to the arrays provided by the caller.
+///
+/// Each array must have room for `len` elements, where `len` is the value
+/// returned by [`afdf_time_series_len`]. Arrays given as null are skipped.
+///
+/// # Safety
+///
+/// `settings` must be a valid pointer returned by [`afdf_settings_from_json`],
+/// and the arrays must be null or valid for writing `len` doubles.
+#[no_mangle]
+pub unsafe extern "C" fn afdf_time_series_copy(
+    settings: *const AfdfSettings,
+    len: usize,
+    time: *mut f64,
+    amplitude: *mut f64,
+    orientation_angle: *mut f64,
+    phase: *mut f64,
+    nature_angle: *mut f64,
+) -> c_int {
+    let observer = match settings.as_ref().map(|s| &s.settings.observer) {
+        Some(Observer::TimeSeries(observer)) => observer,
+        Some(_) => return -2,
+        None => return -1,
+    };
+
+    let modes = observer.get_modes();
+    if len != modes.len() {
+        return -3;
+    }
+
+    let copy = |destination: *mut f64, values: &mut dyn Iterator<Item = f64>| {
+        if !destination.is_null() {
+            let destination = std::slice::from_raw_parts_mut(destination, len);
+            for (d, v) in destination.iter_mut().zip(values) {
+                *d = v;
+            }
+        }
+    };
+
+    copy(time, &mut observer.get_time().iter().copied());
+    copy(amplitude, &mut modes.iter().map(|m| m.a()));
+    copy(orientation_angle, &mut modes.iter().map(|m| m.nth0()));
+    copy(phase, &mut modes.iter().map(|m| m.phi()));
+    copy(nature_angle, &mut modes.iter().map(|m| m.chi()));
+
+    0
+}
@@ -0,0 +1,283 @@
+//! Mean first-passage times between the spin states.
+//!
+//! The nature angle of a logged time series is divided into clockwise
+//! spinning, standing, and anti-clockwise spinning regions, and the times to
+//! first reach each region after entering another are collected. Their means,
+//! with bootstrap confidence intervals, quantify the intermittent switching
+//! between the spinning directions.
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::azimuthal_mode::SystemMode;
+#[cfg(feature = "hdf5")]
+use crate::observers::{self, SaveInfo};
+use crate::{Float, OutputStatistics, Settings, FRAC_PI_4};
+
+/// Number of bootstrap resamples of the confidence intervals.
+const RESAMPLES: usize = 1000;
+
+/// Possible errors for [`FirstPassageTimes`].
+#[derive(Clone, Debug)]
+pub enum FirstPassageError {
+    Length,
+    Threshold,
+}
+
+impl std::error::Error for FirstPassageError {}
+
+impl std::fmt::Display for FirstPassageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Length => "the times and modes have to be of equal length",
+            Self::Threshold => "the threshold has to be between 0 and pi/4",
+        };
+
+        write!(f, "error in the first-passage times: {}", msg)
+    }
+}
+
+/// Region of the nature angle.
+///
+/// Positive nature angles spin anti-clockwise, i.e. in the direction of
+/// increasing azimuthal angle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpinState {
+    Clockwise,
+    Standing,
+    AntiClockwise,
+}
+
+impl SpinState {
+    pub const ALL: [SpinState; 3] = [Self::Clockwise, Self::Standing, Self::AntiClockwise];
+
+    /// Region of the nature angle `chi`, spinning if `|chi|` is above `threshold`.
+    pub fn of(chi: Float, threshold: Float) -> Self {
+        if chi > threshold {
+            Self::AntiClockwise
+        } else if chi < -threshold {
+            Self::Clockwise
+        } else {
+            Self::Standing
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Clockwise => "Clockwise",
+            Self::Standing => "Standing",
+            Self::AntiClockwise => "AntiClockwise",
+        }
+    }
+
+    #[inline]
+    fn index(&self) -> usize {
+        match self {
+            Self::Clockwise => 0,
+            Self::Standing => 1,
+            Self::AntiClockwise => 2,
+        }
+    }
+}
+
+/// First-passage times between each pair of spin states.
+///
+/// A passage from one state to another starts when the mode enters the
+/// first state, and ends when it first enters the second. Re-entering the
+/// first state before reaching the second does not restart the passage, and
+/// passages still running at the end of the series are discarded. The state
+/// at the start of the series is not counted as an entry.
+#[derive(Clone, Debug)]
+pub struct FirstPassageTimes {
+    pub threshold: Float,
+    passage_times: [[Vec<Float>; 3]; 3],
+}
+
+impl FirstPassageTimes {
+    /// Collect the passage times of a logged time series.
+    pub fn from_series(
+        time: &[Float],
+        modes: &[SystemMode],
+        threshold: Float,
+    ) -> Result<Self, FirstPassageError> {
+        if time.len() != modes.len() {
+            return Err(FirstPassageError::Length);
+        }
+        if !(0.0..FRAC_PI_4).contains(&threshold) {
+            return Err(FirstPassageError::Threshold);
+        }
+
+        // Times of entering each state
+        let mut entries: Vec<(SpinState, Float)> = Vec::new();
+        let mut current = None;
+        for (&t, mode) in time.iter().zip(modes) {
+            let state = SpinState::of(mode.chi(), threshold);
+            if current.is_some_and(|c| c != state) {
+                entries.push((state, t));
+            }
+            current = Some(state);
+        }
+
+        let mut passage_times: [[Vec<Float>; 3]; 3] = Default::default();
+        for from in SpinState::ALL {
+            for to in SpinState::ALL.into_iter().filter(|&to| to != from) {
+                let mut start = None;
+                for &(state, t) in &entries {
+                    if state == to {
+                        if let Some(start) = start.take() {
+                            passage_times[from.index()][to.index()].push(t - start);
+                        }
+                    } else if state == from && start.is_none() {
+                        start = Some(t);
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            threshold,
+            passage_times,
+        })
+    }
+
+    /// Run the simulation and collect the passage times of the result.
+    ///
+    /// The observer of `settings` is replaced by a time series kept in memory,
+    /// and the modes are counted as spinning above [`OutputStatistics::SPINNING_THRESHOLD`].
+    pub fn from_simulation(settings: Settings) -> Self {
+        let observer = crate::sensitivity::run_time_series(settings);
+
+        Self::from_series(
+            observer.get_time(),
+            observer.get_modes(),
+            OutputStatistics::SPINNING_THRESHOLD,
+        )
+        .expect("the time series and threshold are valid")
+    }
+
+    /// Collected passage times from one state to another.
+    pub fn passage_times(&self, from: SpinState, to: SpinState) -> &[Float] {
+        &self.passage_times[from.index()][to.index()]
+    }
+
+    /// Mean first-passage time from one state to another (NaN without passages).
+    pub fn mean(&self, from: SpinState, to: SpinState) -> Float {
+        let times = self.passage_times(from, to);
+        times.iter().sum::<Float>() / times.len() as Float
+    }
+
+    /// Bootstrap 95% confidence interval of the mean first-passage time.
+    ///
+    /// The passage times are resampled with replacement using the given seed.
+    /// Returns NaN bounds without passages.
+    pub fn confidence_interval(&self, from: SpinState, to: SpinState, seed: u64) -> (Float, Float) {
+        let times = self.passage_times(from, to);
+        if times.is_empty() {
+            return (Float::NAN, Float::NAN);
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut means: Vec<Float> = (0..RESAMPLES)
+            .map(|_| {
+                let sum: Float = (0..times.len())
+                    .map(|_| times[rng.gen_range(0..times.len())])
+                    .sum();
+                sum / times.len() as Float
+            })
+            .collect();
+        means.sort_by(|a, b| a.total_cmp(b));
+
+        let percentile = |q: Float| means[((RESAMPLES - 1) as Float * q).round() as usize];
+        (percentile(0.025), percentile(0.975))
+    }
+
+    /// Save the number, mean, and confidence interval of the passage times.
+    ///
+    /// Each is saved as a 3x3 matrix, where element `(i, j)` is the passage
+    /// from state `i` to state `j`, in the order given by the `states` attribute.
+    #[cfg(feature = "hdf5")]
+    pub fn save(&self, save_info: &SaveInfo, settings: &Settings, seed: u64) -> hdf5::Result<()> {
+        let file = hdf5::File::append(save_info.get_path())?;
+        let group = file.create_group(save_info.get_group())?;
+
+        let mut count = ndarray::Array2::<u64>::zeros((3, 3));
+        let mut mean = ndarray::Array2::from_elem((3, 3), Float::NAN);
+        let mut lower = mean.clone();
+        let mut upper = mean.clone();
+        for from in SpinState::ALL {
+            for to in SpinState::ALL.into_iter().filter(|&to| to != from) {
+                let (i, j) = (from.index(), to.index());
+                count[[i, j]] = self.passage_times(from, to).len() as u64;
+                mean[[i, j]] = self.mean(from, to);
+                (lower[[i, j]], upper[[i, j]]) = self.confidence_interval(from, to, seed);
+            }
+        }
+        group
+            .new_dataset_builder()
+            .with_data(&count)
+            .create("count")?;
+        group
+            .new_dataset_builder()
+            .with_data(&mean)
+            .create("mean")?;
+        group
+            .new_dataset_builder()
+            .with_data(&lower)
+            .create("lower")?;
+        group
+            .new_dataset_builder()
+            .with_data(&upper)
+            .create("upper")?;
+
+        let names: Vec<&str> = SpinState::ALL.iter().map(|s| s.name()).collect();
+        observers::save_str_attr(&group, &names.join(","), "states")?;
+        observers::save_attr(&group, &ndarray::arr0(self.threshold), "threshold")?;
+
+        observers::save_parameters_as_attribute_json(&group, &settings.parameters)
+    }
+}
+
+impl std::fmt::Display for FirstPassageTimes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines = Vec::new();
+        for from in SpinState::ALL {
+            for to in SpinState::ALL.into_iter().filter(|&to| to != from) {
+                lines.push(format!(
+                    "{} -> {}: mean first-passage time = {} ({} passages)",
+                    from.name(),
+                    to.name(),
+                    self.mean(from, to),
+                    self.passage_times(from, to).len()
+                ));
+            }
+        }
+
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passages_between_states() {
+        // Clockwise -> standing -> anti-clockwise -> standing -> clockwise
+        let chis = [-0.7, -0.7, 0.0, 0.0, 0.7, 0.7, 0.7, 0.0, -0.7, -0.7];
+        let modes: Vec<SystemMode> = chis
+            .iter()
+            .map(|&chi| SystemMode::new(1.0, 0.0, 0.0, chi))
+            .collect();
+        let time: Vec<Float> = (0..chis.len()).map(|ind| ind as Float).collect();
+
+        let passages = FirstPassageTimes::from_series(&time, &modes, 0.3).unwrap();
+        use SpinState::*;
+        assert_eq!(passages.passage_times(Standing, AntiClockwise), [2.0]);
+        assert_eq!(passages.passage_times(AntiClockwise, Clockwise), [4.0]);
+        // The initial clockwise state is not an entry
+        assert!(passages.passage_times(Clockwise, Standing).is_empty());
+        assert!(passages.mean(Clockwise, Standing).is_nan());
+
+        let (lower, upper) = passages.confidence_interval(AntiClockwise, Clockwise, 0);
+        assert_eq!((lower, upper), (4.0, 4.0));
+    }
+}
@@ -0,0 +1,121 @@
+//! Azimuthal profile of the flame strength.
+//!
+//! The gain of the flames is multiplied by a weight depending on the
+//! azimuthal angle `theta` before the Fourier projection, modelling e.g.
+//! circumferential staging, where some sectors of the annulus burn a richer
+//! mixture than others. The profile is fixed in space, i.e. not rotated
+//! with the `burner_offset` of the parameters.
+use crate::{Float, PI};
+use serde::{Deserialize, Serialize};
+
+/// Harmonic component `amplitude * cos(order * theta - phase)` of a [`FlameProfile`].
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ProfileHarmonic {
+    pub order: u32,
+    pub amplitude: Float,
+    pub phase: Float,
+}
+
+/// Weight of the flame strength as a function of the azimuthal angle.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum FlameProfile {
+    /// The weight `1 + sum_k amplitude_k * cos(order_k * theta - phase_k)`.
+    Harmonics(Vec<ProfileHarmonic>),
+    /// Weights tabulated at the angles `theta` (in radians, strictly
+    /// increasing within one period), linearly interpolated around the annulus.
+    Tabulated {
+        theta: Vec<Float>,
+        weight: Vec<Float>,
+    },
+}
+
+impl FlameProfile {
+    /// Weight of the flame strength at the azimuthal angle `theta`.
+    pub fn weight(&self, theta: Float) -> Float {
+        match self {
+            Self::Harmonics(harmonics) => {
+                1.0 + harmonics
+                    .iter()
+                    .map(|h| h.amplitude * (h.order as Float * theta - h.phase).cos())
+                    .sum::<Float>()
+            }
+            Self::Tabulated {
+                theta: x,
+                weight: y,
+            } => {
+                // Periodic interpolation, with the first point repeated one period later
+                let theta = x[0] + (theta - x[0]).rem_euclid(2.0 * PI);
+                let upper = x.partition_point(|&xi| xi <= theta);
+                let (x1, y1) = if upper == x.len() {
+                    (x[0] + 2.0 * PI, y[0])
+                } else {
+                    (x[upper], y[upper])
+                };
+                let (x0, y0) = (x[upper - 1], y[upper - 1]);
+
+                y0 + (theta - x0) / (x1 - x0) * (y1 - y0)
+            }
+        }
+    }
+
+    /// Profile mirrored about `theta = 0`, i.e. weighting `theta` as this profile weights `-theta`.
+    pub fn mirrored(&self) -> Self {
+        match self {
+            Self::Harmonics(harmonics) => Self::Harmonics(
+                harmonics
+                    .iter()
+                    .map(|h| ProfileHarmonic {
+                        phase: -h.phase,
+                        ..*h
+                    })
+                    .collect(),
+            ),
+            Self::Tabulated { theta, weight } => Self::Tabulated {
+                theta: theta.iter().rev().map(|t| -t).collect(),
+                weight: weight.iter().rev().copied().collect(),
+            },
+        }
+    }
+
+    /// Whether the profile is finite, and a tabulated profile is well formed.
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Self::Harmonics(harmonics) => harmonics
+                .iter()
+                .all(|h| h.amplitude.is_finite() && h.phase.is_finite()),
+            Self::Tabulated { theta, weight } => {
+                !theta.is_empty()
+                    && theta.len() == weight.len()
+                    && weight.iter().all(|w| w.is_finite())
+                    && theta.iter().all(|t| t.is_finite())
+                    && theta.windows(2).all(|w| w[0] < w[1])
+                    && theta[theta.len() - 1] - theta[0] < 2.0 * PI
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tabulated_profile_is_periodic() {
+        let profile = FlameProfile::Tabulated {
+            theta: vec![0.0, PI],
+            weight: vec![1.0, 2.0],
+        };
+        assert!(profile.is_valid());
+        assert!((profile.weight(0.5 * PI) - 1.5).abs() < 1e-12);
+        assert!((profile.weight(1.5 * PI) - 1.5).abs() < 1e-12);
+        assert!((profile.weight(-0.5 * PI) - 1.5).abs() < 1e-12);
+        assert!((profile.weight(2.0 * PI) - 1.0).abs() < 1e-12);
+
+        let harmonics = FlameProfile::Harmonics(vec![ProfileHarmonic {
+            order: 1,
+            amplitude: 0.2,
+            phase: 0.0,
+        }]);
+        assert!((harmonics.weight(PI) - 0.8).abs() < 1e-12);
+    }
+}
@@ -1,7 +1,7 @@
 use crate::Float;
 
 /// Fourier series component.
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub struct Fourier {
     pub amplitude: Float,
     pub phase: Float,
@@ -83,46 +83,294 @@ impl Fourier {
             };
         }
 
-        // Cast the order to a floating point number
-        let forder = order as Float;
+        Self::complex_coefficient(thetas, signal, order, ntheta0).to_fourier(order)
+    }
+
+    /// Calculate specific Fourier component for a given signal in complex form
+    ///
+    /// Returns the Fourier component of order `order` as the complex number
+    /// `amplitude * exp(i * order * phase)`, using the definitions of
+    /// [`Fourier::coefficient`]. The real and imaginary parts are the cosine
+    /// and sine terms of the Fourier series, respectively. The zeroth
+    /// component is the (real valued) mean of the signal.
+    pub fn complex_coefficient(
+        thetas: &[Float],
+        signal: &[Float],
+        order: u32,
+        ntheta0: Float,
+    ) -> ComplexFourier {
+        // Treat the special case of wanting the zeroth coefficient
+        if order == 0 {
+            let mean = signal.iter().sum::<Float>() / (signal.len() as Float);
+            return ComplexFourier::new(mean, 0.0);
+        }
+
+        let mut sums = FourierSums::new(order, ntheta0);
+        for (&th, &s) in thetas.iter().zip(signal) {
+            sums.add(th, s);
+        }
+
+        sums.complex_coefficient()
+    }
+
+    /// Calculate all the resolvable Fourier components of a given signal
+    ///
+    /// Returns the Fourier components of order `0..=thetas.len() / 2`, using the
+    /// same definitions as [`Fourier::coefficient`], such that element `i` of the
+    /// returned vector is equal to `Fourier::coefficient(thetas, signal, i, ntheta0)`.
+    /// All the components are found in a single pass over the signal.
+    pub fn spectrum(thetas: &[Float], signal: &[Float], ntheta0: Float) -> Vec<Self> {
+        let max_order = thetas.len() / 2;
 
-        // Calcluate the sine and cosine terms
-        let mut sin_term: Float = 0.0;
-        let mut cos_term: Float = 0.0;
+        // Calculate the sine and cosine terms of all the orders at once
+        let mut sin_terms = vec![0.0; max_order + 1];
+        let mut cos_terms = vec![0.0; max_order + 1];
         for (&th, &s) in thetas.iter().zip(signal) {
-            let idth = (forder * th) - ntheta0;
+            // Use the angle sum identities to step from one order to the next,
+            // i.e. exp(i * (k * th - ntheta0)) = exp(-i * ntheta0) * exp(i * th)^k
+            let (sin_th, cos_th) = th.sin_cos();
+            let (mut sin_k, mut cos_k) = (-ntheta0).sin_cos();
+            for (sin_term, cos_term) in sin_terms.iter_mut().zip(cos_terms.iter_mut()) {
+                *sin_term += s * sin_k;
+                *cos_term += s * cos_k;
 
-            sin_term += s * idth.sin();
-            cos_term += s * idth.cos();
+                (sin_k, cos_k) = (
+                    sin_k * cos_th + cos_k * sin_th,
+                    cos_k * cos_th - sin_k * sin_th,
+                );
+            }
         }
 
-        // NOTE: This takes care of both having even and odd number of
-        //       elements (thetas.len() is even or odd) as the following
-        //       check use integer division. If we define M = thetas.len(),
-        //       then the special cases are order == M/2 (if M is even) or
-        //       order == (M-1)/2 (if M is odd). However, integer division
-        //       ensures both cases are covered here (i.e. 13u32 / 2u32 == 6u32
-        //       which again is equal to 12u32 / 2u32 == 6u32)
-        let pre_factor: Float = if order == thetas.len() as u32 / 2 {
-            1.0
-        } else {
-            2.0
-        };
         let n_terms = thetas.len() as Float;
-        let sin_term = pre_factor * sin_term / n_terms;
-        let cos_term = pre_factor * cos_term / n_terms;
+        (0..=max_order)
+            .map(|order| {
+                // Treat the special case of the zeroth coefficient
+                if order == 0 {
+                    return Fourier {
+                        amplitude: signal.iter().sum::<Float>() / n_terms,
+                        phase: Float::NAN,
+                    };
+                }
+
+                let pre_factor = nyquist_pre_factor(order, thetas.len());
+                let sin_term = pre_factor * sin_terms[order] / n_terms;
+                let cos_term = pre_factor * cos_terms[order] / n_terms;
+
+                ComplexFourier::new(cos_term, sin_term).to_fourier(order as u32)
+            })
+            .collect()
+    }
+
+    /// Reconstruct a signal from its Fourier components
+    ///
+    /// Inverse of [`Fourier::spectrum`], where element `i` of `coefficients` is
+    /// the component of order `i`. The signal is evaluated at the locations
+    /// `thetas` as
+    ///
+    /// signal(theta) = sum_{i} amplitude_i * cos(i * (theta - phase_i) - ntheta0)
+    ///
+    /// where the zeroth component is the mean of the signal.
+    pub fn synthesize(coefficients: &[Fourier], thetas: &[Float], ntheta0: Float) -> Vec<Float> {
+        thetas
+            .iter()
+            .map(|&th| {
+                coefficients
+                    .iter()
+                    .enumerate()
+                    .map(|(order, fourier)| match order {
+                        0 => fourier.amplitude,
+                        _ => {
+                            let forder = order as Float;
+                            fourier.amplitude * (forder * (th - fourier.phase) - ntheta0).cos()
+                        }
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+}
+
+/// Running sums over the samples of a signal, giving its mean and a single Fourier component.
+///
+/// Equivalent to [`Fourier::complex_coefficient`] without storing the
+/// signal. The samples are added one at a time, and the sums over separate
+/// parts of the signal can be combined with [`FourierSums::merge`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FourierSums {
+    order: u32,
+    ntheta0: Float,
+    samples: usize,
+    sum: Float,
+    sin_term: Float,
+    cos_term: Float,
+}
+
+impl FourierSums {
+    /// Empty sums for the component of order `order`, relative to the reference angle `ntheta0`.
+    pub fn new(order: u32, ntheta0: Float) -> Self {
+        Self {
+            order,
+            ntheta0,
+            samples: 0,
+            sum: 0.0,
+            sin_term: 0.0,
+            cos_term: 0.0,
+        }
+    }
+
+    /// Add the sample `value` of the signal at `theta`.
+    #[inline]
+    pub fn add(&mut self, theta: Float, value: Float) {
+        let idth = (self.order as Float * theta) - self.ntheta0;
+
+        self.samples += 1;
+        self.sum += value;
+        self.sin_term += value * idth.sin();
+        self.cos_term += value * idth.cos();
+    }
+
+    /// Combine the sums over two parts of the same signal.
+    #[inline]
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            samples: self.samples + other.samples,
+            sum: self.sum + other.sum,
+            sin_term: self.sin_term + other.sin_term,
+            cos_term: self.cos_term + other.cos_term,
+            ..self
+        }
+    }
+
+    /// Mean of the added samples.
+    #[inline]
+    pub fn mean(&self) -> Float {
+        self.sum / self.samples as Float
+    }
+
+    /// Fourier component of the added samples in complex form.
+    ///
+    /// The samples are assumed to be equidistant around the annulus, as for
+    /// [`Fourier::complex_coefficient`].
+    pub fn complex_coefficient(&self) -> ComplexFourier {
+        if self.order == 0 {
+            return ComplexFourier::new(self.mean(), 0.0);
+        }
 
-        // Calculate the magnitude and the angle used in the
-        // definition of the Fourier coefficients here
-        let amplitude = (sin_term.powi(2) + cos_term.powi(2)).sqrt();
+        // NOTE: If we define M = thetas.len(), the component of order M/2
+        //       is its own complex conjugate when M is even (the Nyquist
+        //       component), and should not be doubled. When M is odd, all
+        //       the components up to order (M-1)/2 have a distinct conjugate.
+        let pre_factor = nyquist_pre_factor(self.order as usize, self.samples);
+        let n_terms = self.samples as Float;
 
-        // Calculate the value of thetai
-        let phase = Float::atan2(sin_term, cos_term) / forder;
+        ComplexFourier::new(
+            pre_factor * self.cos_term / n_terms,
+            pre_factor * self.sin_term / n_terms,
+        )
+    }
+}
+
+/// Pre-factor of the sine and cosine terms of the given order, for `len` samples.
+#[inline]
+fn nyquist_pre_factor(order: usize, len: usize) -> Float {
+    if 2 * order == len {
+        1.0
+    } else {
+        2.0
+    }
+}
+
+/// Fourier series component in complex form.
+///
+/// The component `amplitude * exp(i * order * phase)` of a [`Fourier`]
+/// component of a given order, where the real and imaginary parts are
+/// the cosine and sine terms of the Fourier series, respectively.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ComplexFourier {
+    pub real: Float,
+    pub imag: Float,
+}
 
-        // The amplitude is non-negative by definition,
+impl ComplexFourier {
+    pub fn new(real: Float, imag: Float) -> Self {
+        Self { real, imag }
+    }
+
+    /// Convert a [`Fourier`] component of order `order` to complex form.
+    pub fn from_fourier(fourier: &Fourier, order: u32) -> Self {
+        // The zeroth component is real valued (and has no phase)
+        if order == 0 {
+            return Self::new(fourier.amplitude, 0.0);
+        }
+
+        let angle = order as Float * fourier.phase;
+        Self::new(
+            fourier.amplitude * angle.cos(),
+            fourier.amplitude * angle.sin(),
+        )
+    }
+
+    /// Convert to a [`Fourier`] component of order `order`.
+    pub fn to_fourier(&self, order: u32) -> Fourier {
+        if order == 0 {
+            return Fourier {
+                amplitude: self.real,
+                phase: Float::NAN,
+            };
+        }
+
+        // The norm is non-negative by definition,
         // allowing for the use of unwrap without the possibility
         // of causing a panic in the program
-        return Self::new(amplitude, phase).unwrap();
+        Fourier::new(self.norm(), self.arg() / order as Float).unwrap()
+    }
+
+    /// Magnitude of the complex number.
+    #[inline]
+    pub fn norm(&self) -> Float {
+        self.real.hypot(self.imag)
+    }
+
+    /// Argument of the complex number in the range [-pi, pi].
+    #[inline]
+    pub fn arg(&self) -> Float {
+        Float::atan2(self.imag, self.real)
+    }
+
+    /// Complex conjugate.
+    #[inline]
+    pub fn conj(&self) -> Self {
+        Self::new(self.real, -self.imag)
+    }
+}
+
+impl std::ops::Add for ComplexFourier {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.real + rhs.real, self.imag + rhs.imag)
+    }
+}
+
+impl std::ops::Mul for ComplexFourier {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.real * rhs.real - self.imag * rhs.imag,
+            self.real * rhs.imag + self.imag * rhs.real,
+        )
+    }
+}
+
+impl std::ops::Mul<Float> for ComplexFourier {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Float) -> Self::Output {
+        Self::new(self.real * rhs, self.imag * rhs)
     }
 }
 
@@ -155,6 +403,23 @@ mod test {
         assert!((coeff.amplitude - amplitude).abs() < Float::EPSILON);
     }
 
+    #[test]
+    fn odd_number_of_points_highest_order() {
+        // Component of the highest order of 13 points, which has a distinct conjugate.
+        // The amplitude used to be halved (0.5 instead of 1.0) by treating it as the
+        // Nyquist component of 12 points, which is unchanged
+        for (npoints, expected) in [(13, 1.0), (12, 1.0)] {
+            let dtheta = 2.0 * PI / (npoints as Float);
+            let thetas: Vec<Float> = (0..npoints).map(|i| (i as Float) * dtheta).collect();
+            let signal: Vec<Float> = thetas.iter().map(|th| (6.0 * th).cos()).collect();
+
+            let fourier = Fourier::coefficient(&thetas, &signal, 6, 0.0);
+            assert!((fourier.amplitude - expected).abs() < 1e-12);
+            let spectrum = Fourier::spectrum(&thetas, &signal, 0.0);
+            assert!((spectrum[6].amplitude - expected).abs() < 1e-12);
+        }
+    }
+
     #[test]
     fn nonzero_fourier_coeff() {
         let amplitude = 0.36;
@@ -183,4 +448,61 @@ mod test {
         assert!((coeff.amplitude - amplitude) < precision);
         assert!((coeff.phase - thi).abs() <= precision);
     }
+
+    #[test]
+    fn spectrum_matches_coefficients() {
+        const NPOINTS: usize = 13;
+        let dtheta = 2.0 * PI / (NPOINTS as Float);
+        let thetas: Vec<Float> = (0..NPOINTS).map(|i| (i as Float) * dtheta).collect();
+
+        let ntheta0 = 0.3;
+        let signal: Vec<Float> = thetas
+            .iter()
+            .map(|th| 0.5 + 0.2 * (2.0 * th - 0.1).cos() + 0.1 * (5.0 * th + 0.4).cos())
+            .collect();
+
+        let spectrum = Fourier::spectrum(&thetas, &signal, ntheta0);
+        assert_eq!(spectrum.len(), NPOINTS / 2 + 1);
+
+        for (order, fourier) in spectrum.iter().enumerate().skip(1) {
+            let coeff = Fourier::coefficient(&thetas, &signal, order as u32, ntheta0);
+            assert!((fourier.amplitude - coeff.amplitude).abs() < 1e-12);
+
+            // The phase is only well defined for the orders present in the signal
+            if coeff.amplitude > 1e-6 {
+                assert!((fourier.phase - coeff.phase).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn complex_round_trip() {
+        let fourier = Fourier::new(0.4, 0.3).unwrap();
+        let complex = ComplexFourier::from_fourier(&fourier, 2);
+        let back = complex.to_fourier(2);
+
+        assert!((back.amplitude - fourier.amplitude).abs() < Float::EPSILON);
+        assert!((back.phase - fourier.phase).abs() < Float::EPSILON);
+    }
+
+    #[test]
+    fn synthesis_round_trip() {
+        for npoints in [12, 13] {
+            let dtheta = 2.0 * PI / (npoints as Float);
+            let thetas: Vec<Float> = (0..npoints).map(|i| (i as Float) * dtheta).collect();
+
+            // Arbitrary signal without any particular structure
+            let signal: Vec<Float> = (0..npoints)
+                .map(|i| ((i * 7919) % 17) as Float / 17.0)
+                .collect();
+
+            let ntheta0 = 0.7;
+            let spectrum = Fourier::spectrum(&thetas, &signal, ntheta0);
+            let reconstructed = Fourier::synthesize(&spectrum, &thetas, ntheta0);
+
+            for (s, r) in signal.iter().zip(reconstructed) {
+                assert!((s - r).abs() < 1e-12);
+            }
+        }
+    }
 }
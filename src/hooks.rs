@@ -0,0 +1,153 @@
+//! Callbacks executed while a simulation runs.
+//!
+//! The [`SimulationHooks`] of the [`crate::Settings`] are called when the
+//! simulation starts, every time the mode is logged, and when it finishes,
+//! which allows e.g. live plotting or external logging without implementing
+//! an observer. A hook can stop the simulation early by cancelling the
+//! [`crate::CancellationToken`] of the settings.
+use std::sync::Arc;
+
+use crate::azimuthal_mode::SystemMode;
+use crate::Float;
+
+/// State of the simulation passed to the hooks.
+#[derive(Clone, Copy, Debug)]
+pub struct SimulationState<'a> {
+    /// Number of completed time steps.
+    pub step: usize,
+    /// Time (in cycles of the oscillation).
+    pub time: Float,
+    /// Current (or logged) mode, in the same frame as the logged modes of the
+    /// observer: the orientation angle is relative to the `reference_angle` of
+    /// the observer (if set).
+    pub mode: &'a SystemMode,
+}
+
+type Hook = Arc<dyn Fn(&SimulationState) + Send + Sync>;
+
+/// Closures executed at the start, save points, and end of [`crate::Settings::run`].
+///
+/// Clones share the same closures, such that the hooks also apply to the
+/// repetitions of the simulation, which may run in parallel.
+#[derive(Clone, Default)]
+pub struct SimulationHooks {
+    start: Vec<Hook>,
+    save: Vec<Hook>,
+    finish: Vec<Hook>,
+}
+
+impl SimulationHooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a hook called with the initial mode, before the first time step.
+    pub fn on_start(&mut self, hook: impl Fn(&SimulationState) + Send + Sync + 'static) {
+        self.start.push(Arc::new(hook));
+    }
+
+    /// Add a hook called every time the observer logs the mode.
+    pub fn on_save(&mut self, hook: impl Fn(&SimulationState) + Send + Sync + 'static) {
+        self.save.push(Arc::new(hook));
+    }
+
+    /// Add a hook called with the final mode, also when stopped early.
+    pub fn on_finish(&mut self, hook: impl Fn(&SimulationState) + Send + Sync + 'static) {
+        self.finish.push(Arc::new(hook));
+    }
+
+    /// Check whether no hooks are set.
+    pub fn is_empty(&self) -> bool {
+        self.start.is_empty() && self.save.is_empty() && self.finish.is_empty()
+    }
+
+    pub(crate) fn start(&self, state: &SimulationState) {
+        self.start.iter().for_each(|hook| hook(state));
+    }
+
+    #[inline]
+    pub(crate) fn save(&self, state: &SimulationState) {
+        self.save.iter().for_each(|hook| hook(state));
+    }
+
+    pub(crate) fn finish(&self, state: &SimulationState) {
+        self.finish.iter().for_each(|hook| hook(state));
+    }
+}
+
+impl std::fmt::Debug for SimulationHooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SimulationHooks")
+            .field("start", &self.start.len())
+            .field("save", &self.save.len())
+            .field("finish", &self.finish.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observers::Observer;
+    use crate::Settings;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    #[test]
+    fn hooks_are_called() {
+        let mut settings = Settings::default();
+        settings.parameters.set_number_of_cycles(1.0).unwrap();
+
+        let saves = Arc::new(AtomicUsize::new(0));
+        let counter = saves.clone();
+        settings.hooks.on_save(move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+
+        let final_step = Arc::new(AtomicUsize::new(0));
+        let step = final_step.clone();
+        settings.hooks.on_finish(move |state| {
+            step.store(state.step, Ordering::Relaxed);
+        });
+
+        settings.run();
+
+        let parameters = &settings.parameters;
+        let num_saves = parameters.get_total_steps() / parameters.get_skip_per_save();
+        assert_eq!(saves.load(Ordering::Relaxed), num_saves);
+        assert_eq!(
+            final_step.load(Ordering::Relaxed),
+            parameters.get_total_steps()
+        );
+    }
+
+    #[test]
+    fn modes_are_relative_to_the_reference() {
+        let mut settings = Settings::default();
+        settings.parameters.set_number_of_cycles(1.0).unwrap();
+        if let Observer::TimeSeries(observer) = &mut settings.observer {
+            observer.logging.reference_angle = Some(0.7);
+        }
+
+        let modes = Arc::new(Mutex::new(Vec::new()));
+        let start_modes = modes.clone();
+        settings.hooks.on_start(move |state| {
+            start_modes.lock().unwrap().push(*state.mode);
+        });
+        let save_modes = modes.clone();
+        settings.hooks.on_save(move |state| {
+            save_modes.lock().unwrap().push(*state.mode);
+        });
+
+        settings.run();
+
+        let Observer::TimeSeries(observer) = &settings.observer else {
+            panic!("expected a time series observer");
+        };
+        let modes = modes.lock().unwrap();
+        assert_eq!(modes.len(), observer.get_modes().len());
+        for (hook_mode, logged_mode) in modes.iter().zip(observer.get_modes()) {
+            assert_eq!(hook_mode.nth0(), logged_mode.nth0());
+        }
+    }
+}
@@ -1,20 +1,68 @@
-use super::{AFDFSimplified, HeatReleaseRate};
+use super::{
+    AFDFSimplified, DescribingFunctionError, GainCoefficients, HeatReleaseRate, IntegralTerms,
+};
 use crate::azimuthal_mode::SystemMode;
-use crate::{Quaternion, Settings};
+use crate::{Float, Quaternion, Settings};
 use serde::{Deserialize, Serialize};
 
 /// Conventional Flame Describing Function (FDF).
+///
+/// The linear gain of the flame is `gain_scale` times the gain given in
+/// [`crate::Parameters`], which is useful when comparing against the AFDF
+/// at a fixed set of parameters.
 #[derive(Copy, Clone, Debug, Deserialize, Serialize)]
 pub struct ConventionalFDF {
+    #[serde(default = "default_gain_scale")]
+    gain_scale: Float,
+
     #[serde(skip)]
     model: AFDFSimplified,
 }
 
+fn default_gain_scale() -> Float {
+    1.0
+}
+
 impl ConventionalFDF {
     pub fn new() -> Self {
         let model = AFDFSimplified::new(1.0);
 
-        Self { model }
+        Self {
+            gain_scale: default_gain_scale(),
+            model,
+        }
+    }
+
+    /// Create a new [`ConventionalFDF`] with the linear gain scaled by `gain_scale`.
+    pub fn with_gain_scale(gain_scale: Float) -> Result<Self, DescribingFunctionError> {
+        if gain_scale.is_nan() || gain_scale < 0.0 {
+            return Err(DescribingFunctionError::GainScale);
+        }
+
+        Ok(Self {
+            gain_scale,
+            ..Self::new()
+        })
+    }
+
+    /// Get the scaling of the linear gain.
+    pub fn get_gain_scale(&self) -> Float {
+        self.gain_scale
+    }
+
+    /// Get the underlying (symmetric) AFDF model.
+    pub fn get_model(&self) -> &AFDFSimplified {
+        &self.model
+    }
+
+    /// Fourier coefficients of the scaled saturated gain.
+    fn coefficients(
+        &self,
+        hrr_mode: &SystemMode,
+        acoustic_mode: &SystemMode,
+        setup: &Settings,
+    ) -> GainCoefficients {
+        super::saturated_coefficients(hrr_mode, acoustic_mode, setup, self.gain_scale)
     }
 }
 
@@ -26,7 +74,36 @@ impl Default for ConventionalFDF {
 
 impl HeatReleaseRate for ConventionalFDF {
     fn integral(&self, acoustic_mode: &SystemMode, setup: &Settings) -> Quaternion {
-        self.model.integral(acoustic_mode, setup)
+        let hrr_mode = self.model.mode(acoustic_mode);
+        let coefficients = self.coefficients(&hrr_mode, acoustic_mode, setup);
+
+        self.model
+            .integral_with_coefficients(acoustic_mode, &hrr_mode, coefficients, setup)
+    }
+
+    fn gain_coefficients(
+        &self,
+        acoustic_mode: &SystemMode,
+        setup: &Settings,
+    ) -> Option<GainCoefficients> {
+        let hrr_mode = self.model.mode(acoustic_mode);
+        let coefficients = self.coefficients(&hrr_mode, acoustic_mode, setup);
+
+        Some(coefficients)
+    }
+
+    fn integral_terms(
+        &self,
+        acoustic_mode: &SystemMode,
+        setup: &Settings,
+    ) -> Option<IntegralTerms> {
+        let hrr_mode = self.model.mode(acoustic_mode);
+        let coefficients = self.coefficients(&hrr_mode, acoustic_mode, setup);
+
+        Some(
+            self.model
+                .terms_with_coefficients(acoustic_mode, &hrr_mode, coefficients, setup),
+        )
     }
 
     fn mode(&self, acoustic_mode: &SystemMode) -> SystemMode {
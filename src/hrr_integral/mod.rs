@@ -5,24 +5,185 @@
 //! into the model without changing the resut of the codebase.
 mod conventional;
 mod simplified;
+mod stochastic;
+mod tabulated;
 
 use crate::azimuthal_mode::SystemMode;
-use crate::{Float, Parameters, Quaternion, Settings};
+use crate::integrator;
+use crate::{
+    ComplexFourier, FlameDistribution, Float, FourierSums, Parameters, Quaternion, Saturation,
+    Settings, RNG,
+};
 pub use conventional::ConventionalFDF;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 pub use simplified::AFDFSimplified;
+use std::error::Error;
+pub use stochastic::StochasticFDF;
+pub use tabulated::TabulatedFDF;
+
+/// Possible errors when constructing a describing function.
+#[derive(Clone, Debug)]
+pub enum DescribingFunctionError {
+    GainRatio,
+    GainScale,
+    Variance,
+    CorrelationTime,
+    ContinuousFlames,
+}
+
+impl std::error::Error for DescribingFunctionError {}
+
+impl std::fmt::Display for DescribingFunctionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::GainRatio => "gain_ratio_r has to be a non-negative number",
+            Self::GainScale => "gain_scale has to be a non-negative number",
+            Self::Variance => "variance has to be a non-negative number",
+            Self::CorrelationTime => "correlation_time has to be a positive number",
+            Self::ContinuousFlames => {
+                "the stochastic describing function requires discrete burners"
+            }
+        };
+
+        write!(f, "error setting the describing function: {}", msg)
+    }
+}
+
+/// Sensitivity of the heat release rate integral to the state of the mode.
+///
+/// Element `[i][j]` is the derivative of component `i` of the integral
+/// (real, imag_i, imag_j, imag_k) with respect to the state variable `j`
+/// of [`SystemMode`] (ln_a, nth0, phi, tan_2chi).
+pub type Jacobian = [[Float; 4]; 4];
 
 /// Used to implement the heat release rate integral.
 pub trait HeatReleaseRate {
     fn integral(&self, acoustic_mode: &SystemMode, setup: &Settings) -> Quaternion;
     fn mode(&self, acoustic_mode: &SystemMode) -> SystemMode;
+
+    /// Jacobian of the heat release rate integral with respect to the mode.
+    ///
+    /// The default implementation uses central finite differences, and should
+    /// be overridden if an analytical expression is available.
+    fn jacobian(&self, acoustic_mode: &SystemMode, setup: &Settings) -> Jacobian {
+        let state = mode_to_state(acoustic_mode);
+        let mut jacobian = [[0.0; 4]; 4];
+
+        for (col, &value) in state.iter().enumerate() {
+            let step = 1e-6 * value.abs().max(1.0);
+
+            let mut forward = state;
+            forward[col] += step;
+            let mut backward = state;
+            backward[col] -= step;
+
+            let forward = self.integral(&state_to_mode(&forward), setup);
+            let backward = self.integral(&state_to_mode(&backward), setup);
+            let forward = [forward.real, forward.imag_i, forward.imag_j, forward.imag_k];
+            let backward = [
+                backward.real,
+                backward.imag_i,
+                backward.imag_j,
+                backward.imag_k,
+            ];
+
+            for row in 0..4 {
+                jacobian[row][col] = (forward[row] - backward[row]) / (2.0 * step);
+            }
+        }
+
+        jacobian
+    }
+
+    /// Advance any internal states of the heat release rate by one time step.
+    ///
+    /// Called at the start of every time step, before evaluating the integral.
+    fn step(&mut self, _parameters: &Parameters, _rng: &mut RNG) {}
+
+    /// Fourier coefficients of the saturated gain distribution at the given mode.
+    ///
+    /// Only available for the models integrating a gain at each burner.
+    fn gain_coefficients(
+        &self,
+        _acoustic_mode: &SystemMode,
+        _setup: &Settings,
+    ) -> Option<GainCoefficients> {
+        None
+    }
+
+    /// Individual contributions to the right hand side at the given mode.
+    ///
+    /// Only available for the models integrating a gain at each burner.
+    fn integral_terms(
+        &self,
+        _acoustic_mode: &SystemMode,
+        _setup: &Settings,
+    ) -> Option<IntegralTerms> {
+        None
+    }
+}
+
+/// Individual contributions to the (deterministic) right hand side of the governing equation.
+///
+/// The heat release rate integral is the sum of `n0`, `n2n`, and `damping`,
+/// while `noise_drift` is added by the simulation. Since `tan 2chi` changes
+/// at the rate `-2 imag_k / cos^2 2chi`, each term contributes `-imag_k` to
+/// the rate of change of the nature angle `chi`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IntegralTerms {
+    /// Contribution of the mean gain `n0`.
+    pub n0: Quaternion,
+    /// Contribution of the gain coefficient `n2n`.
+    pub n2n: Quaternion,
+    /// Acoustic damping.
+    pub damping: Quaternion,
+    /// Deterministic drift induced by the noise.
+    pub noise_drift: Quaternion,
+}
+
+/// Fourier coefficients `n0` and `n2n` of the gain distribution around the annulus.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct GainCoefficients {
+    /// Amplitude of the mean (zeroth order) coefficient.
+    pub n0: Float,
+    /// Amplitude of the coefficient of order `2n`.
+    pub n2n: Float,
+    /// Phase of the coefficient of order `2n`, relative to the orientation of the mode.
+    pub theta2n: Float,
 }
 
 /// Wrapper for the different structs implementing the [`HeatReleaseRate`] trait.
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum DescribingFunction {
     Conventional(ConventionalFDF),
     Simplified(AFDFSimplified),
+    Tabulated(TabulatedFDF),
+    Stochastic(StochasticFDF),
+}
+
+impl DescribingFunction {
+    /// Name of the describing function model.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Conventional(_) => "Conventional",
+            Self::Simplified(_) => "Simplified",
+            Self::Tabulated(_) => "Tabulated",
+            Self::Stochastic(_) => "Stochastic",
+        }
+    }
+
+    /// Load any external data required by the describing function.
+    ///
+    /// Used by [`crate::Settings`] when loading from file.
+    pub fn init(&mut self) -> Result<(), Box<dyn Error>> {
+        if let Self::Tabulated(hrr) = self {
+            hrr.load()?;
+        }
+
+        Ok(())
+    }
 }
 
 impl HeatReleaseRate for DescribingFunction {
@@ -30,6 +191,8 @@ impl HeatReleaseRate for DescribingFunction {
         match self {
             Self::Conventional(hrr) => hrr.integral(acoustic_mode, setup),
             Self::Simplified(hrr) => hrr.integral(acoustic_mode, setup),
+            Self::Tabulated(hrr) => hrr.integral(acoustic_mode, setup),
+            Self::Stochastic(hrr) => hrr.integral(acoustic_mode, setup),
         }
     }
 
@@ -37,6 +200,52 @@ impl HeatReleaseRate for DescribingFunction {
         match self {
             Self::Conventional(hrr) => hrr.mode(acoustic_mode),
             Self::Simplified(hrr) => hrr.mode(acoustic_mode),
+            Self::Tabulated(hrr) => hrr.mode(acoustic_mode),
+            Self::Stochastic(hrr) => hrr.mode(acoustic_mode),
+        }
+    }
+
+    fn jacobian(&self, acoustic_mode: &SystemMode, setup: &Settings) -> Jacobian {
+        match self {
+            Self::Conventional(hrr) => hrr.jacobian(acoustic_mode, setup),
+            Self::Simplified(hrr) => hrr.jacobian(acoustic_mode, setup),
+            Self::Tabulated(hrr) => hrr.jacobian(acoustic_mode, setup),
+            Self::Stochastic(hrr) => hrr.jacobian(acoustic_mode, setup),
+        }
+    }
+
+    fn step(&mut self, parameters: &Parameters, rng: &mut RNG) {
+        match self {
+            Self::Conventional(hrr) => hrr.step(parameters, rng),
+            Self::Simplified(hrr) => hrr.step(parameters, rng),
+            Self::Tabulated(hrr) => hrr.step(parameters, rng),
+            Self::Stochastic(hrr) => hrr.step(parameters, rng),
+        }
+    }
+
+    fn gain_coefficients(
+        &self,
+        acoustic_mode: &SystemMode,
+        setup: &Settings,
+    ) -> Option<GainCoefficients> {
+        match self {
+            Self::Conventional(hrr) => hrr.gain_coefficients(acoustic_mode, setup),
+            Self::Simplified(hrr) => hrr.gain_coefficients(acoustic_mode, setup),
+            Self::Tabulated(hrr) => hrr.gain_coefficients(acoustic_mode, setup),
+            Self::Stochastic(hrr) => hrr.gain_coefficients(acoustic_mode, setup),
+        }
+    }
+
+    fn integral_terms(
+        &self,
+        acoustic_mode: &SystemMode,
+        setup: &Settings,
+    ) -> Option<IntegralTerms> {
+        match self {
+            Self::Conventional(hrr) => hrr.integral_terms(acoustic_mode, setup),
+            Self::Simplified(hrr) => hrr.integral_terms(acoustic_mode, setup),
+            Self::Tabulated(hrr) => hrr.integral_terms(acoustic_mode, setup),
+            Self::Stochastic(hrr) => hrr.integral_terms(acoustic_mode, setup),
         }
     }
 }
@@ -47,28 +256,373 @@ impl Default for DescribingFunction {
     }
 }
 
-// Calculate the local amplitude at each flame location.
+/// State variables of the mode, in the order used by [`Jacobian`].
+#[inline]
+fn mode_to_state(mode: &SystemMode) -> [Float; 4] {
+    [mode.ln_a, mode.nth0, mode.phi, mode.tan_2chi]
+}
+
+#[inline]
+fn state_to_mode(state: &[Float; 4]) -> SystemMode {
+    SystemMode {
+        ln_a: state[0],
+        nth0: state[1],
+        phi: state[2],
+        tan_2chi: state[3],
+    }
+}
+
+/// Size of the chunks of burners evaluated in parallel, if the settings split the burner loops.
+#[cfg(feature = "parallel")]
+fn burner_chunk_size(setup: &Settings) -> Option<usize> {
+    let burners = setup.parameters.get_number_of_burners() as usize;
+
+    setup
+        .burner_tasks
+        .filter(|&tasks| tasks > 1)
+        .map(|tasks| burners.div_ceil(tasks).max(1))
+}
+
+/// Gain of the heat release rate mode before saturation.
 #[inline]
-fn local_amplitudes(mode: &SystemMode, parameters: &Parameters) -> Vec<Float> {
-    let mode_order = parameters.mode_order;
-    parameters
-        .get_thetas()
-        .iter()
-        .map(|&theta| mode.local_amplitude(theta, mode_order))
-        .collect()
+fn linear_gain(hrr_mode: &SystemMode, acoustic_mode: &SystemMode, setup: &Settings) -> Float {
+    setup.parameters.get_gain() * hrr_mode.a() / acoustic_mode.a()
+}
+
+/// Coefficients `n0` and `n2n` from the Fourier sums of the gain of order `2n`.
+fn coefficients_from_sums(sums: &FourierSums, mode_order: u32) -> GainCoefficients {
+    let fourier2n = sums.complex_coefficient().to_fourier(2 * mode_order);
+
+    GainCoefficients {
+        n0: sums.mean(),
+        n2n: fourier2n.amplitude,
+        theta2n: fourier2n.phase,
+    }
 }
 
-/// Calculated the saturated gain values.
-fn saturated_gain(
+/// Fourier coefficients of the gain at the burners, in a single pass over the burners.
+///
+/// `gain(index, a)` is the gain of burner `index` at the local amplitude `a`
+/// of the heat release rate mode. The local amplitude, the gain, and its
+/// contribution to the Fourier sums are evaluated together for each burner,
+/// without storing any of them, such that the cost is linear in the number
+/// of burners. The burners are split into parallel tasks if the settings
+/// split the burner loops (see [`Settings::burner_tasks`]).
+fn burner_coefficients<F>(hrr_mode: &SystemMode, setup: &Settings, gain: F) -> GainCoefficients
+where
+    F: Fn(usize, Float) -> Float + Sync,
+{
+    let n = setup.parameters.get_mode_order();
+    let thetas = setup.parameters.get_thetas();
+    let weights = setup.parameters.get_flame_weights();
+    // The gain of a standing pattern varies as cos(2 n theta - 2 ntheta_0), so the
+    // phase of the component of order 2n is measured from twice the orientation angle.
+    // Orders above the resolvable limit alias (only allowed with `allow_aliasing`)
+    let empty = FourierSums::new(2 * n, 2.0 * hrr_mode.nth0());
+    let chunk_sums = |offset: usize, chunk: &[Float]| {
+        let mut sums = empty;
+        for (ind, &theta) in chunk.iter().enumerate() {
+            let a = hrr_mode.local_amplitude(theta, n);
+            sums.add(theta, gain(offset + ind, a) * weights[offset + ind]);
+        }
+        sums
+    };
+
+    #[cfg(feature = "parallel")]
+    if let Some(chunk_size) = burner_chunk_size(setup) {
+        let sums = thetas
+            .par_chunks(chunk_size)
+            .enumerate()
+            .map(|(chunk, thetas)| chunk_sums(chunk * chunk_size, thetas))
+            .reduce(|| empty, FourierSums::merge);
+        return coefficients_from_sums(&sums, n);
+    }
+
+    coefficients_from_sums(&chunk_sums(0, thetas), n)
+}
+
+/// Fourier coefficients of the gain of flames distributed continuously around the annulus.
+///
+/// The limit of [`burner_coefficients`] for infinitely many burners, where
+/// the sums become integrals over `psi = n theta - n theta_0`. The gain
+/// repeats itself in every period of `psi`, and the integrals are evaluated
+/// by the trapezoidal rule with `points` points over a single period.
+///
+/// A [`crate::FlameProfile`] breaks this periodicity, and the weighted gain
+/// is instead integrated over `theta`, with `points` points per period of `psi`.
+fn continuous_coefficients<F>(
+    hrr_mode: &SystemMode,
+    setup: &Settings,
+    points: usize,
+    gain: F,
+) -> GainCoefficients
+where
+    F: Fn(Float) -> Float,
+{
+    let parameters = &setup.parameters;
+    let n = parameters.get_mode_order();
+    if let Some(profile) = parameters.get_flame_profile() {
+        let points = points * n as usize;
+        let dtheta = 2.0 * crate::PI / points as Float;
+        let mut sums = FourierSums::new(2 * n, 2.0 * hrr_mode.nth0());
+        for ind in 0..points {
+            let theta = ind as Float * dtheta;
+            let a = hrr_mode.local_amplitude(theta, n);
+            sums.add(theta, gain(a) * profile.weight(theta));
+        }
+
+        return coefficients_from_sums(&sums, n);
+    }
+
+    // In terms of psi, the component of order 2n is of order 2 relative to zero
+    let mode = SystemMode {
+        nth0: 0.0,
+        ..*hrr_mode
+    };
+    let dpsi = 2.0 * crate::PI / points as Float;
+    let mut sums = FourierSums::new(2, 0.0);
+    for ind in 0..points {
+        let psi = ind as Float * dpsi;
+        sums.add(psi, gain(mode.local_amplitude(psi, 1)));
+    }
+
+    coefficients_from_sums(&sums, n)
+}
+
+/// Fourier coefficients of the cubically saturated gain `gain * (1 - kappa a^2)` of continuous flames.
+///
+/// The squared local amplitude `A^2 (1 + cos 2psi cos 2chi) / 2` is a
+/// trigonometric polynomial, such that the integrals are exact.
+fn cubic_continuous_coefficients(
+    hrr_mode: &SystemMode,
+    setup: &Settings,
+    gain: Float,
+    kappa: Float,
+) -> GainCoefficients {
+    let a_sq = hrr_mode.a().powi(2);
+    let n2n = ComplexFourier::new(
+        -0.5 * gain * kappa * a_sq * (2.0 * hrr_mode.chi()).cos(),
+        0.0,
+    )
+    .to_fourier(2 * setup.parameters.get_mode_order());
+
+    GainCoefficients {
+        n0: gain * (1.0 - 0.5 * kappa * a_sq),
+        n2n: n2n.amplitude,
+        theta2n: n2n.phase,
+    }
+}
+
+/// Fourier coefficients of a gain depending only on the local amplitude `a`.
+///
+/// Summed over the burners or integrated around the annulus, depending on
+/// the [`FlameDistribution`] of the parameters.
+fn deterministic_coefficients<F>(
+    hrr_mode: &SystemMode,
+    setup: &Settings,
+    gain: F,
+) -> GainCoefficients
+where
+    F: Fn(Float) -> Float + Sync,
+{
+    match setup.parameters.get_flame_distribution() {
+        FlameDistribution::Discrete => burner_coefficients(hrr_mode, setup, |_, a| gain(a)),
+        FlameDistribution::Continuous { quadrature_points } => {
+            continuous_coefficients(hrr_mode, setup, quadrature_points, gain)
+        }
+    }
+}
+
+/// Fourier coefficients of the saturated gain, scaled by `scale`.
+fn saturated_coefficients(
     hrr_mode: &SystemMode,
     acoustic_mode: &SystemMode,
     setup: &Settings,
-) -> Vec<Float> {
-    let local_amplitudes = local_amplitudes(&hrr_mode, &setup.parameters);
-    let saturation_factor = setup.saturation.factor(&local_amplitudes);
+    scale: Float,
+) -> GainCoefficients {
+    let gain = scale * linear_gain(hrr_mode, acoustic_mode, setup);
+    let saturation = &setup.saturation;
+    if let (FlameDistribution::Continuous { .. }, Saturation::Cubic(kappa), None) = (
+        setup.parameters.get_flame_distribution(),
+        saturation,
+        setup.parameters.get_flame_profile(),
+    ) {
+        return cubic_continuous_coefficients(hrr_mode, setup, gain, *kappa);
+    }
 
-    let ref_gain = setup.parameters.gain;
-    let gain = ref_gain * hrr_mode.a() / acoustic_mode.a();
+    deterministic_coefficients(hrr_mode, setup, |a| gain * saturation.factor_at(a))
+}
 
-    saturation_factor.into_iter().map(|sf| gain * sf).collect()
+/// Damping term of the right hand side.
+#[inline]
+fn damping(parameters: &Parameters) -> Quaternion {
+    Quaternion::new(-parameters.get_damping(), 0.0, 0.0, 0.0)
+}
+
+/// Deterministic drift of the right hand side induced by the noise.
+///
+/// The drift of the nature angle diverges at the spinning states, and is
+/// bounded at [`integrator::MAX_TAN_2CHI`], beyond which the mode is advanced
+/// as a quaternion. The noise increments of the quaternion step keep the
+/// mode off the spinning states by themselves.
+#[inline]
+pub(crate) fn noise_drift(mode: &SystemMode, parameters: &Parameters) -> Quaternion {
+    let nd_noise_sq = parameters.get_noise().powi(2) / (4.0 * mode.a().powi(2));
+    let tan_2chi = mode
+        .tan_2chi
+        .clamp(-integrator::MAX_TAN_2CHI, integrator::MAX_TAN_2CHI);
+
+    Quaternion::new(nd_noise_sq, 0.0, 0.0, nd_noise_sq * tan_2chi)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FRAC_PI_4;
+
+    #[test]
+    fn jacobian_matches_integral() {
+        let setup = Settings::default();
+        let hrr = AFDFSimplified::new(1.6);
+        let mode = SystemMode::new(0.3, 0.4, 0.0, 0.2);
+        let jacobian = hrr.jacobian(&mode, &setup);
+
+        // The integral is independent of the temporal phase
+        for row in jacobian.iter() {
+            assert!(row[2].abs() < 1e-8);
+        }
+
+        // First order prediction of a small change in the amplitude
+        let step = 1e-4;
+        let mut perturbed = mode;
+        perturbed.ln_a += step;
+        let change = hrr.integral(&perturbed, &setup).real - hrr.integral(&mode, &setup).real;
+        assert!((change - jacobian[0][0] * step).abs() < 1e-7);
+    }
+
+    #[test]
+    fn spinning_mode_has_uniform_gain() {
+        let setup = Settings::default();
+        let hrr = DescribingFunction::default();
+
+        // The local amplitude, and thus the saturated gain, is the same at all burners
+        let spinning = SystemMode::new(0.5, 0.3, 0.0, FRAC_PI_4);
+        let coefficients = hrr.gain_coefficients(&spinning, &setup).unwrap();
+        assert!(coefficients.n0 > 0.0);
+        assert!(coefficients.n2n < 1e-9 * coefficients.n0);
+
+        let standing = SystemMode::new(0.5, 0.3, 0.0, 0.0);
+        let coefficients = hrr.gain_coefficients(&standing, &setup).unwrap();
+        assert!(coefficients.n2n > 1e-3 * coefficients.n0);
+    }
+
+    #[test]
+    fn integral_terms_sum_to_integral() {
+        let setup = Settings::default();
+        let hrr = DescribingFunction::default();
+        let mode = SystemMode::new(0.3, 0.4, 0.0, 0.2);
+
+        let terms = hrr.integral_terms(&mode, &setup).unwrap();
+        let sum = terms.n0 + terms.n2n + terms.damping;
+        let integral = hrr.integral(&mode, &setup);
+        for (a, b) in [
+            (sum.real, integral.real),
+            (sum.imag_i, integral.imag_i),
+            (sum.imag_j, integral.imag_j),
+            (sum.imag_k, integral.imag_k),
+        ] {
+            assert!((a - b).abs() < 1e-12);
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn burner_tasks_match_sequential() {
+        let mut setup = Settings::default();
+        setup.parameters.set_number_of_burners(37).unwrap();
+        let hrr = DescribingFunction::default();
+        let mode = SystemMode::new(0.3, 0.4, 0.1, 0.2);
+        let sequential = hrr.integral(&mode, &setup);
+
+        setup.burner_tasks = Some(4);
+        let parallel = hrr.integral(&mode, &setup);
+        for (a, b) in [
+            (sequential.real, parallel.real),
+            (sequential.imag_i, parallel.imag_i),
+            (sequential.imag_j, parallel.imag_j),
+            (sequential.imag_k, parallel.imag_k),
+        ] {
+            assert!((a - b).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn continuous_flames_match_many_burners() {
+        let mut setup = Settings::default();
+        setup.parameters.set_number_of_burners(500).unwrap();
+        let hrr = DescribingFunction::default();
+        let mode = SystemMode::new(0.3, 0.4, 0.1, 0.2);
+
+        // The fused pass gives the Fourier coefficients of the gain at the burners
+        let hrr_mode = hrr.mode(&mode);
+        let thetas = setup.parameters.get_thetas();
+        let gains: Vec<Float> = thetas
+            .iter()
+            .map(|&theta| {
+                let a = hrr_mode.local_amplitude(theta, 1);
+                linear_gain(&hrr_mode, &mode, &setup) * setup.saturation.factor_at(a)
+            })
+            .collect();
+        let mean = crate::Fourier::coefficient(thetas, &gains, 0, 0.0);
+        let fourier2 = crate::Fourier::coefficient(thetas, &gains, 2, 2.0 * hrr_mode.nth0());
+        let discrete = hrr.gain_coefficients(&mode, &setup).unwrap();
+        assert!((discrete.n0 - mean.amplitude).abs() < 1e-12);
+        assert!((discrete.n2n - fourier2.amplitude).abs() < 1e-12);
+
+        let continuous = FlameDistribution::Continuous {
+            quadrature_points: 64,
+        };
+        setup.parameters.set_flame_distribution(continuous).unwrap();
+        let continuous = hrr.gain_coefficients(&mode, &setup).unwrap();
+        assert!((discrete.n0 - continuous.n0).abs() < 1e-9);
+        assert!((discrete.n2n - continuous.n2n).abs() < 1e-9);
+        let (a, b) = (2.0 * discrete.theta2n, 2.0 * continuous.theta2n);
+        assert!((a - b).sin().abs() < 1e-9);
+
+        // The analytical integrals of the cubic saturation match the quadrature
+        let hrr_mode = hrr.mode(&mode);
+        let gain = linear_gain(&hrr_mode, &mode, &setup);
+        let analytical = cubic_continuous_coefficients(&hrr_mode, &setup, gain, 2.0);
+        let quadrature =
+            continuous_coefficients(&hrr_mode, &setup, 8, |a| gain * (1.0 - 2.0 * a.powi(2)));
+        assert!((analytical.n0 - quadrature.n0).abs() < 1e-12);
+        assert!((analytical.n2n - quadrature.n2n).abs() < 1e-12);
+        assert!((analytical.theta2n - quadrature.theta2n).abs() < 1e-12);
+    }
+
+    #[test]
+    fn flame_profile_weights_the_gain() {
+        let mut setup = Settings::default();
+        let profile = crate::FlameProfile::Harmonics(vec![crate::ProfileHarmonic {
+            order: 2,
+            amplitude: 0.3,
+            phase: 0.5,
+        }]);
+        setup.parameters.set_flame_profile(Some(profile)).unwrap();
+        let hrr = DescribingFunction::default();
+
+        // The gain of a spinning mode is uniform, such that n2n is due to the profile
+        let spinning = SystemMode::new(0.5, 0.3, 0.0, FRAC_PI_4);
+        let uniform = hrr.gain_coefficients(&spinning, &setup).unwrap();
+        assert!((uniform.n2n - 0.3 * uniform.n0).abs() < 1e-12);
+
+        let continuous = FlameDistribution::Continuous {
+            quadrature_points: 16,
+        };
+        setup.parameters.set_flame_distribution(continuous).unwrap();
+        let coefficients = hrr.gain_coefficients(&spinning, &setup).unwrap();
+        assert!((coefficients.n0 - uniform.n0).abs() < 1e-12);
+        assert!((coefficients.n2n - uniform.n2n).abs() < 1e-12);
+        assert!((coefficients.theta2n - uniform.theta2n).abs() < 1e-12);
+    }
 }
@@ -1,6 +1,6 @@
-use super::HeatReleaseRate;
+use super::{DescribingFunctionError, GainCoefficients, HeatReleaseRate, IntegralTerms};
 use crate::azimuthal_mode::SystemMode;
-use crate::{Float, Fourier, Quaternion, Settings};
+use crate::{Float, Quaternion, Settings};
 use serde::{Deserialize, Serialize};
 
 /// Simplified version of the Azimuthal Flame Describing Function (AFDF).
@@ -15,12 +15,87 @@ pub struct AFDFSimplified {
 }
 
 impl AFDFSimplified {
+    /// Create a new [`AFDFSimplified`], panicking for invalid `gain_ratio_r`.
+    ///
+    /// See [`AFDFSimplified::try_new`] for the fallible version.
     pub fn new(gain_ratio_r: Float) -> Self {
-        if gain_ratio_r < 0.0 {
-            panic!("gain_ratio_r should be positive!")
+        match Self::try_new(gain_ratio_r) {
+            Ok(model) => model,
+            Err(_) => panic!("gain_ratio_r should be positive!"),
         }
+    }
+
+    /// Create a new [`AFDFSimplified`], requiring a non-negative `gain_ratio_r`.
+    pub fn try_new(gain_ratio_r: Float) -> Result<Self, DescribingFunctionError> {
+        if gain_ratio_r.is_nan() || gain_ratio_r < 0.0 {
+            return Err(DescribingFunctionError::GainRatio);
+        }
+
+        Ok(Self { gain_ratio_r })
+    }
 
-        Self { gain_ratio_r }
+    /// Get the AFDF gain ratio `r`.
+    pub fn get_gain_ratio_r(&self) -> Float {
+        self.gain_ratio_r
+    }
+
+    /// Contributions of the given `n0` and `n2n` coefficients of the gain to the integral.
+    fn gain_terms(
+        &self,
+        acoustic_mode: &SystemMode,
+        hrr_mode: &SystemMode,
+        coefficients: GainCoefficients,
+        setup: &Settings,
+    ) -> (Quaternion, Quaternion) {
+        let n = setup.parameters.get_mode_order();
+        let GainCoefficients { n0, n2n, theta2n } = coefficients;
+
+        // Nature angle difference between HRR and acoustic mode
+        let delta_chi = hrr_mode.chi() - acoustic_mode.chi();
+        let chi = acoustic_mode.chi();
+
+        let n0_term = Quaternion::new(n0 * delta_chi.cos(), 0.0, 0.0, -n0 * delta_chi.sin());
+
+        let n = n as Float;
+        let n2n_term = Quaternion {
+            real: 0.5 * n2n * (2.0 * n * theta2n).cos() * (2.0 * chi + delta_chi).cos(),
+            imag_i: 0.5 * (n2n * (2.0 * n * theta2n).sin() * (2.0 * chi + delta_chi).cos()),
+            imag_j: 0.5 * (-n2n * (2.0 * n * theta2n).sin() * (2.0 * chi + delta_chi).sin()),
+            imag_k: 0.5 * n2n * (2.0 * n * theta2n).cos() * (2.0 * chi + delta_chi).sin(),
+        };
+
+        (n0_term, n2n_term)
+    }
+
+    /// Evaluate the heat release rate integral for the given coefficients of the gain.
+    pub(super) fn integral_with_coefficients(
+        &self,
+        acoustic_mode: &SystemMode,
+        hrr_mode: &SystemMode,
+        coefficients: GainCoefficients,
+        setup: &Settings,
+    ) -> Quaternion {
+        let (n0_term, n2n_term) = self.gain_terms(acoustic_mode, hrr_mode, coefficients, setup);
+
+        n2n_term + (n0_term + super::damping(&setup.parameters))
+    }
+
+    /// Decompose the heat release rate integral for the given coefficients of the gain.
+    pub(super) fn terms_with_coefficients(
+        &self,
+        acoustic_mode: &SystemMode,
+        hrr_mode: &SystemMode,
+        coefficients: GainCoefficients,
+        setup: &Settings,
+    ) -> IntegralTerms {
+        let (n0, n2n) = self.gain_terms(acoustic_mode, hrr_mode, coefficients, setup);
+
+        IntegralTerms {
+            n0,
+            n2n,
+            damping: super::damping(&setup.parameters),
+            noise_drift: super::noise_drift(acoustic_mode, &setup.parameters),
+        }
     }
 }
 
@@ -33,50 +108,35 @@ impl Default for AFDFSimplified {
 impl HeatReleaseRate for AFDFSimplified {
     fn integral(&self, acoustic_mode: &SystemMode, setup: &Settings) -> Quaternion {
         let hrr_mode = self.mode(acoustic_mode);
-        // let aq = local_amplitudes(&hrr_mode, &setup.parameters);
-        let gain_values = super::saturated_gain(&hrr_mode, acoustic_mode, setup);
+        let coefficients = super::saturated_coefficients(&hrr_mode, acoustic_mode, setup, 1.0);
 
-        // Calculate the Fourier components
-        let n = setup.parameters.mode_order;
-        let thetas = setup.parameters.get_thetas();
-        let fourier0 = Fourier::coefficient(thetas, &gain_values, 0, hrr_mode.nth0());
-        let fourier2n = Fourier::coefficient(thetas, &gain_values, 2 * n, hrr_mode.nth0());
+        self.integral_with_coefficients(acoustic_mode, &hrr_mode, coefficients, setup)
+    }
 
-        // Make shorthand for the Fourier coefficient components
-        let n0 = fourier0.amplitude;
-        let n2n = fourier2n.amplitude;
-        let theta2n = fourier2n.phase;
+    fn gain_coefficients(
+        &self,
+        acoustic_mode: &SystemMode,
+        setup: &Settings,
+    ) -> Option<GainCoefficients> {
+        let hrr_mode = self.mode(acoustic_mode);
 
-        // Nature angle difference between HRR and acoustic mode
-        let delta_chi = hrr_mode.chi() - acoustic_mode.chi();
-        let chi = acoustic_mode.chi();
-        // let a = acoustic_mode.a();
+        Some(super::saturated_coefficients(
+            &hrr_mode,
+            acoustic_mode,
+            setup,
+            1.0,
+        ))
+    }
 
-        let alpha = setup.parameters.damping;
-        // let sigma = setup.parameters.noise;
-        // First element
-        let n = n as Float;
-        let mut real = 0.5 * n2n * (2.0 * n * theta2n).cos() * (2.0 * chi + delta_chi).cos();
-        real += n0 * delta_chi.cos() - alpha;
-        // real += sigma.powi(2) / (4.0 * a.powi(2));
-
-        // Second element
-        let imag_i = 0.5 * (n2n * (2.0 * n * theta2n).sin() * (2.0 * chi + delta_chi).cos());
-
-        // Third element
-        let imag_j = 0.5 * (-n2n * (2.0 * n * theta2n).sin() * (2.0 * chi + delta_chi).sin());
-
-        // Fourth element
-        let mut imag_k = 0.5 * n2n * (2.0 * n * theta2n).cos() * (2.0 * chi + delta_chi).sin();
-        imag_k += -n0 * delta_chi.sin();
-        // imag_k += (sigma.powi(2) / (4.0 * a.powi(2))) * acoustic_mode.tan_2chi;
-
-        Quaternion {
-            real,
-            imag_i,
-            imag_j,
-            imag_k,
-        }
+    fn integral_terms(
+        &self,
+        acoustic_mode: &SystemMode,
+        setup: &Settings,
+    ) -> Option<IntegralTerms> {
+        let hrr_mode = self.mode(acoustic_mode);
+        let coefficients = super::saturated_coefficients(&hrr_mode, acoustic_mode, setup, 1.0);
+
+        Some(self.terms_with_coefficients(acoustic_mode, &hrr_mode, coefficients, setup))
     }
 
     fn mode(&self, acoustic_mode: &SystemMode) -> SystemMode {
@@ -0,0 +1,215 @@
+use super::{
+    AFDFSimplified, DescribingFunctionError, GainCoefficients, HeatReleaseRate, IntegralTerms,
+};
+use crate::azimuthal_mode::SystemMode;
+use crate::{Float, Parameters, Quaternion, Settings, RNG};
+use serde::{Deserialize, Serialize};
+
+/// Describing function with stochastic gain fluctuations at each burner.
+///
+/// Turbulence causes the flame response to scatter around the describing
+/// function. This is modelled by multiplying the (saturated) gain of each
+/// burner by `1 + x`, where `x` is an Ornstein-Uhlenbeck process with zero
+/// mean, variance `variance`, and correlation time `correlation_time`
+/// (in units of acoustic cycles). The processes at the burners are independent.
+///
+/// The fluctuations are carried as extra states, which are advanced by
+/// [`HeatReleaseRate::step`] once per time step. As they belong to the
+/// individual burners, the flames have to be discrete burners.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(try_from = "StochasticFDFData")]
+pub struct StochasticFDF {
+    gain_ratio_r: Float,
+    variance: Float,
+    correlation_time: Float,
+
+    #[serde(skip)]
+    model: AFDFSimplified,
+
+    #[serde(skip)]
+    states: Vec<Float>,
+}
+
+impl StochasticFDF {
+    /// Create a new [`StochasticFDF`], requiring a non-negative `gain_ratio_r`
+    /// and `variance`, and a positive `correlation_time`.
+    pub fn new(
+        gain_ratio_r: Float,
+        variance: Float,
+        correlation_time: Float,
+    ) -> Result<Self, DescribingFunctionError> {
+        if variance.is_nan() || variance < 0.0 {
+            return Err(DescribingFunctionError::Variance);
+        }
+        if correlation_time.is_nan() || correlation_time <= 0.0 {
+            return Err(DescribingFunctionError::CorrelationTime);
+        }
+
+        Ok(Self {
+            gain_ratio_r,
+            variance,
+            correlation_time,
+            model: AFDFSimplified::try_new(gain_ratio_r)?,
+            states: Vec::new(),
+        })
+    }
+
+    /// Get the AFDF gain ratio `r`.
+    pub fn get_gain_ratio_r(&self) -> Float {
+        self.gain_ratio_r
+    }
+
+    /// Set the AFDF gain ratio `r`, which is left unchanged if negative.
+    pub fn set_gain_ratio_r(&mut self, gain_ratio_r: Float) -> Result<(), DescribingFunctionError> {
+        self.model = AFDFSimplified::try_new(gain_ratio_r)?;
+        self.gain_ratio_r = gain_ratio_r;
+
+        Ok(())
+    }
+
+    /// Get the variance of the relative gain fluctuations.
+    pub fn get_variance(&self) -> Float {
+        self.variance
+    }
+
+    /// Get the correlation time of the gain fluctuations, in acoustic cycles.
+    pub fn get_correlation_time(&self) -> Float {
+        self.correlation_time
+    }
+
+    /// Current relative gain fluctuation at each burner.
+    pub fn get_states(&self) -> &[Float] {
+        &self.states
+    }
+
+    /// The model mapping the acoustic mode to the heat release rate mode.
+    #[inline]
+    fn model(&self) -> AFDFSimplified {
+        self.model
+    }
+
+    /// Fourier coefficients of the perturbed saturated gain (no fluctuations before the first step).
+    ///
+    /// The fluctuations belong to the individual burners, such that the
+    /// gain is always summed over the burners.
+    fn coefficients(
+        &self,
+        hrr_mode: &SystemMode,
+        acoustic_mode: &SystemMode,
+        setup: &Settings,
+    ) -> GainCoefficients {
+        let gain = super::linear_gain(hrr_mode, acoustic_mode, setup);
+        let saturation = &setup.saturation;
+
+        super::burner_coefficients(hrr_mode, setup, |ind, a| {
+            let fluctuation = self.states.get(ind).map_or(1.0, |x| 1.0 + x);
+            gain * saturation.factor_at(a) * fluctuation
+        })
+    }
+}
+
+impl Default for StochasticFDF {
+    fn default() -> Self {
+        Self {
+            gain_ratio_r: 1.0,
+            variance: 0.01,
+            correlation_time: 1.0,
+            model: AFDFSimplified::new(1.0),
+            states: Vec::new(),
+        }
+    }
+}
+
+/// Serialized fields of [`StochasticFDF`], which are validated when deserializing.
+#[derive(Deserialize)]
+struct StochasticFDFData {
+    gain_ratio_r: Float,
+    variance: Float,
+    correlation_time: Float,
+}
+
+impl TryFrom<StochasticFDFData> for StochasticFDF {
+    type Error = DescribingFunctionError;
+
+    fn try_from(data: StochasticFDFData) -> Result<Self, Self::Error> {
+        Self::new(data.gain_ratio_r, data.variance, data.correlation_time)
+    }
+}
+
+impl HeatReleaseRate for StochasticFDF {
+    fn integral(&self, acoustic_mode: &SystemMode, setup: &Settings) -> Quaternion {
+        let model = self.model();
+        let hrr_mode = model.mode(acoustic_mode);
+
+        let coefficients = self.coefficients(&hrr_mode, acoustic_mode, setup);
+
+        model.integral_with_coefficients(acoustic_mode, &hrr_mode, coefficients, setup)
+    }
+
+    fn gain_coefficients(
+        &self,
+        acoustic_mode: &SystemMode,
+        setup: &Settings,
+    ) -> Option<GainCoefficients> {
+        let model = self.model();
+        let hrr_mode = model.mode(acoustic_mode);
+        let coefficients = self.coefficients(&hrr_mode, acoustic_mode, setup);
+
+        Some(coefficients)
+    }
+
+    fn integral_terms(
+        &self,
+        acoustic_mode: &SystemMode,
+        setup: &Settings,
+    ) -> Option<IntegralTerms> {
+        let model = self.model();
+        let hrr_mode = model.mode(acoustic_mode);
+        let coefficients = self.coefficients(&hrr_mode, acoustic_mode, setup);
+
+        Some(model.terms_with_coefficients(acoustic_mode, &hrr_mode, coefficients, setup))
+    }
+
+    fn mode(&self, acoustic_mode: &SystemMode) -> SystemMode {
+        self.model().mode(acoustic_mode)
+    }
+
+    fn step(&mut self, parameters: &Parameters, rng: &mut RNG) {
+        let std = self.variance.sqrt();
+        let number_of_burners = parameters.get_number_of_burners() as usize;
+
+        // Start from the stationary distribution
+        if self.states.len() != number_of_burners {
+            self.states = (0..number_of_burners)
+                .map(|_| std * rng.get_normal())
+                .collect();
+            return;
+        }
+
+        // Exact update of the Ornstein-Uhlenbeck process over one time step
+        let decay = (-parameters.get_timestep() / self.correlation_time).exp();
+        let forcing = std * (1.0 - decay.powi(2)).sqrt();
+        for x in self.states.iter_mut() {
+            *x = decay * *x + forcing * rng.get_normal();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        assert!(StochasticFDF::new(-1.0, 0.01, 1.0).is_err());
+        assert!(StochasticFDF::new(1.0, -0.01, 1.0).is_err());
+        assert!(StochasticFDF::new(1.0, 0.01, 0.0).is_err());
+
+        let json = r#"{"gain_ratio_r": -1.0, "variance": 0.01, "correlation_time": 1.0}"#;
+        assert!(serde_json::from_str::<StochasticFDF>(json).is_err());
+
+        let mut fdf = StochasticFDF::default();
+        assert!(fdf.set_gain_ratio_r(-0.5).is_err());
+        assert_eq!(fdf.get_gain_ratio_r(), 1.0);
+    }
+}
@@ -0,0 +1,292 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use super::{
+    AFDFSimplified, DescribingFunctionError, GainCoefficients, HeatReleaseRate, IntegralTerms,
+};
+use crate::azimuthal_mode::SystemMode;
+use crate::{Float, Quaternion, Settings};
+use serde::{Deserialize, Serialize};
+
+/// Flame Describing Function (FDF) tabulated from experiments.
+///
+/// The gain and phase of the flame response as a function of the local
+/// amplitude are loaded from `path`, and replace the saturation model.
+/// The gain is normalized by the linear gain (`Parameters::gain`) and the
+/// phase is relative to the linear phase, such that the gain should
+/// approach unity and the phase zero in the low amplitude limit. The
+/// response at each burner is linearly interpolated from the table, and
+/// the part in phase with the acoustics, `gain * cos(phase)`, drives the
+/// mode. The table is held constant beyond the tabulated amplitudes.
+///
+/// Two file formats are supported:
+/// - CSV files (`.csv`) with the three columns amplitude, gain, and phase
+///   (in radians), with an optional header line.
+/// - HDF5 files with the datasets `amplitude`, `gain`, and `phase` in `group`.
+///
+/// There is no time-delay describing function in this model, as the
+/// equations are averaged over the acoustic period and only resolve the
+/// response at a single frequency. An amplitude dependent time delay
+/// `tau(a)` (in acoustic cycles) can instead be represented through the
+/// phase column as `phase = -2 * pi * (tau(a) - tau(0))`, which is
+/// interpolated in the same way as a measured phase.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(try_from = "TabulatedFDFData")]
+pub struct TabulatedFDF {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub group: Option<String>,
+    gain_ratio_r: Float,
+
+    #[serde(skip)]
+    model: AFDFSimplified,
+
+    #[serde(skip)]
+    amplitude: Vec<Float>,
+
+    #[serde(skip)]
+    in_phase_gain: Vec<Float>,
+}
+
+impl TabulatedFDF {
+    /// Create a new [`TabulatedFDF`] and load the table from `path`.
+    ///
+    /// The `gain_ratio_r` has to be non-negative.
+    pub fn new(
+        path: &PathBuf,
+        group: Option<&str>,
+        gain_ratio_r: Float,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut fdf = Self {
+            path: path.to_owned(),
+            group: group.map(|g| g.to_owned()),
+            gain_ratio_r,
+            model: AFDFSimplified::try_new(gain_ratio_r)?,
+            amplitude: Vec::new(),
+            in_phase_gain: Vec::new(),
+        };
+        fdf.load()?;
+
+        Ok(fdf)
+    }
+
+    /// Get the AFDF gain ratio `r`.
+    pub fn get_gain_ratio_r(&self) -> Float {
+        self.gain_ratio_r
+    }
+
+    /// Set the AFDF gain ratio `r`, which is left unchanged if negative.
+    pub fn set_gain_ratio_r(&mut self, gain_ratio_r: Float) -> Result<(), DescribingFunctionError> {
+        self.model = AFDFSimplified::try_new(gain_ratio_r)?;
+        self.gain_ratio_r = gain_ratio_r;
+
+        Ok(())
+    }
+
+    /// Load the table from file.
+    ///
+    /// Required after loading the settings from JSON, which is done by [`crate::Settings::from_file`].
+    pub fn load(&mut self) -> Result<(), Box<dyn Error>> {
+        let (amplitude, gain, phase) = match self.path.extension().and_then(|e| e.to_str()) {
+            Some("csv") => read_csv(&self.path)?,
+            _ => read_hdf5(&self.path, self.group.as_deref().unwrap_or("/"))?,
+        };
+
+        if amplitude.is_empty() || amplitude.len() != gain.len() || amplitude.len() != phase.len() {
+            return Err(
+                "the describing function table columns are empty or of unequal length".into(),
+            );
+        }
+        if amplitude
+            .iter()
+            .chain(&gain)
+            .chain(&phase)
+            .any(|v| !v.is_finite())
+        {
+            return Err("the describing function table contains values that are not finite".into());
+        }
+        if amplitude.windows(2).any(|w| w[0] >= w[1]) {
+            return Err("the tabulated amplitudes must be strictly increasing".into());
+        }
+
+        self.in_phase_gain = gain.iter().zip(&phase).map(|(g, p)| g * p.cos()).collect();
+        self.amplitude = amplitude;
+
+        Ok(())
+    }
+
+    /// Interpolate the in-phase gain at the local amplitude `a`.
+    #[inline]
+    fn interpolate(&self, a: Float) -> Float {
+        let x = &self.amplitude;
+        let y = &self.in_phase_gain;
+
+        // Index of the first tabulated amplitude above `a`
+        let upper = x.partition_point(|&xi| xi <= a);
+        if upper == 0 {
+            return y[0];
+        }
+        if upper == x.len() {
+            return y[x.len() - 1];
+        }
+
+        let lower = upper - 1;
+        let fraction = (a - x[lower]) / (x[upper] - x[lower]);
+
+        y[lower] + fraction * (y[upper] - y[lower])
+    }
+
+    /// The model mapping the acoustic mode to the heat release rate mode.
+    #[inline]
+    fn model(&self) -> AFDFSimplified {
+        self.model
+    }
+
+    /// Fourier coefficients of the interpolated flame response.
+    fn coefficients(
+        &self,
+        hrr_mode: &SystemMode,
+        acoustic_mode: &SystemMode,
+        setup: &Settings,
+    ) -> GainCoefficients {
+        let gain = super::linear_gain(hrr_mode, acoustic_mode, setup);
+
+        super::deterministic_coefficients(hrr_mode, setup, |a| gain * self.interpolate(a))
+    }
+}
+
+impl HeatReleaseRate for TabulatedFDF {
+    fn integral(&self, acoustic_mode: &SystemMode, setup: &Settings) -> Quaternion {
+        let model = self.model();
+        let hrr_mode = model.mode(acoustic_mode);
+
+        let coefficients = self.coefficients(&hrr_mode, acoustic_mode, setup);
+
+        model.integral_with_coefficients(acoustic_mode, &hrr_mode, coefficients, setup)
+    }
+
+    fn gain_coefficients(
+        &self,
+        acoustic_mode: &SystemMode,
+        setup: &Settings,
+    ) -> Option<GainCoefficients> {
+        let model = self.model();
+        let hrr_mode = model.mode(acoustic_mode);
+        let coefficients = self.coefficients(&hrr_mode, acoustic_mode, setup);
+
+        Some(coefficients)
+    }
+
+    fn integral_terms(
+        &self,
+        acoustic_mode: &SystemMode,
+        setup: &Settings,
+    ) -> Option<IntegralTerms> {
+        let model = self.model();
+        let hrr_mode = model.mode(acoustic_mode);
+        let coefficients = self.coefficients(&hrr_mode, acoustic_mode, setup);
+
+        Some(model.terms_with_coefficients(acoustic_mode, &hrr_mode, coefficients, setup))
+    }
+
+    fn mode(&self, acoustic_mode: &SystemMode) -> SystemMode {
+        self.model().mode(acoustic_mode)
+    }
+}
+
+/// Serialized fields of [`TabulatedFDF`], which are validated when deserializing.
+///
+/// The table itself is loaded by [`TabulatedFDF::load`].
+#[derive(Deserialize)]
+struct TabulatedFDFData {
+    path: PathBuf,
+    #[serde(default)]
+    group: Option<String>,
+    gain_ratio_r: Float,
+}
+
+impl TryFrom<TabulatedFDFData> for TabulatedFDF {
+    type Error = DescribingFunctionError;
+
+    fn try_from(data: TabulatedFDFData) -> Result<Self, Self::Error> {
+        Ok(Self {
+            path: data.path,
+            group: data.group,
+            gain_ratio_r: data.gain_ratio_r,
+            model: AFDFSimplified::try_new(data.gain_ratio_r)?,
+            amplitude: Vec::new(),
+            in_phase_gain: Vec::new(),
+        })
+    }
+}
+
+type Table = (Vec<Float>, Vec<Float>, Vec<Float>);
+
+fn read_csv(path: &PathBuf) -> Result<Table, Box<dyn Error>> {
+    let content = std::fs::read_to_string(path)?;
+
+    let (mut amplitude, mut gain, mut phase) = (Vec::new(), Vec::new(), Vec::new());
+    for (ind, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let values: Result<Vec<Float>, _> = line.split(',').map(|v| v.trim().parse()).collect();
+        match values {
+            Ok(values) if values.len() == 3 => {
+                amplitude.push(values[0]);
+                gain.push(values[1]);
+                phase.push(values[2]);
+            }
+            // Allow for a header on the first line
+            Err(_) if ind == 0 => continue,
+            _ => {
+                return Err(
+                    format!("could not parse line {} of {}", ind + 1, path.display()).into(),
+                )
+            }
+        }
+    }
+
+    Ok((amplitude, gain, phase))
+}
+
+#[cfg(feature = "hdf5")]
+fn read_hdf5(path: &PathBuf, group: &str) -> Result<Table, Box<dyn Error>> {
+    let file = hdf5::File::open(path)?;
+    let group = file.group(group)?;
+
+    let amplitude = group.dataset("amplitude")?.read_raw()?;
+    let gain = group.dataset("gain")?.read_raw()?;
+    let phase = group.dataset("phase")?.read_raw()?;
+
+    Ok((amplitude, gain, phase))
+}
+
+#[cfg(not(feature = "hdf5"))]
+fn read_hdf5(_path: &PathBuf, _group: &str) -> Result<Table, Box<dyn Error>> {
+    Err("reading HDF5 files requires the \"hdf5\" feature, use a CSV file instead".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        let path = std::env::temp_dir().join("azimuthal_fdf_tabulated.csv");
+        std::fs::write(&path, "amplitude,gain,phase\n0.0,1.0,0.0\n0.5,NaN,0.0\n").unwrap();
+        assert!(TabulatedFDF::new(&path, None, 1.0).is_err());
+
+        std::fs::write(&path, "0.0,1.0,0.0\n0.5,0.8,-0.1\n").unwrap();
+        assert!(TabulatedFDF::new(&path, None, -1.0).is_err());
+        let mut fdf = TabulatedFDF::new(&path, None, 1.0).unwrap();
+        assert!(fdf.set_gain_ratio_r(-0.5).is_err());
+        assert_eq!(fdf.get_gain_ratio_r(), 1.0);
+        std::fs::remove_file(&path).unwrap();
+
+        let json = r#"{"path": "table.csv", "gain_ratio_r": -1.0}"#;
+        assert!(serde_json::from_str::<TabulatedFDF>(json).is_err());
+    }
+}
@@ -0,0 +1,283 @@
+//! Continuation with a slowly ramped parameter, and detection of hysteresis.
+//!
+//! One parameter is ramped linearly up and then back down within a single
+//! simulation. Where the saturation model predicts bistability, the mode
+//! stays on its current branch until the branch disappears, such that the
+//! amplitudes on the up and down sweeps differ over the bistable region.
+use serde::{Deserialize, Serialize};
+
+use crate::azimuthal_mode::SystemMode;
+#[cfg(feature = "hdf5")]
+use crate::observers::{self, SaveInfo};
+use crate::{Float, SensitivityError, SensitivityParameter, Settings};
+
+/// Relative difference between the branches above which a bin is bistable.
+const BISTABILITY_TOLERANCE: Float = 0.1;
+
+/// Possible errors for [`HysteresisLoop`].
+#[derive(Clone, Debug)]
+pub enum HysteresisError {
+    Parameter(SensitivityError),
+    Range,
+    Bins,
+    Length,
+}
+
+impl std::error::Error for HysteresisError {}
+
+impl std::fmt::Display for HysteresisError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Parameter(e) => e.to_string(),
+            Self::Range => "the lower value has to be below the upper value".to_owned(),
+            Self::Bins => "at least one bin is required".to_owned(),
+            Self::Length => "the times and modes have to be of equal length".to_owned(),
+        };
+
+        write!(f, "error in the hysteresis: {}", msg)
+    }
+}
+
+impl From<SensitivityError> for HysteresisError {
+    fn from(value: SensitivityError) -> Self {
+        Self::Parameter(value)
+    }
+}
+
+/// Linear ramp of a parameter from `lower` to `upper` and back.
+///
+/// The parameter reaches `upper` halfway through the simulation, and is back
+/// at `lower` at the end.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct ParameterRamp {
+    pub parameter: SensitivityParameter,
+    pub lower: Float,
+    pub upper: Float,
+}
+
+impl ParameterRamp {
+    pub fn new(
+        parameter: SensitivityParameter,
+        lower: Float,
+        upper: Float,
+    ) -> Result<Self, HysteresisError> {
+        if lower.is_nan() || upper.is_nan() || lower >= upper {
+            return Err(HysteresisError::Range);
+        }
+
+        Ok(Self {
+            parameter,
+            lower,
+            upper,
+        })
+    }
+
+    /// Value of the parameter at `time` of a simulation lasting `duration`.
+    #[inline]
+    pub fn value(&self, time: Float, duration: Float) -> Float {
+        let fraction = (time / duration).clamp(0.0, 1.0);
+        let progress = 1.0 - (2.0 * fraction - 1.0).abs();
+        self.lower + (self.upper - self.lower) * progress
+    }
+}
+
+/// Amplitude branches of a simulation with a ramped parameter.
+///
+/// The logged samples are split into the up sweep (the first half of the
+/// simulation) and the down sweep, and the amplitudes of each are averaged
+/// over equal bins of the parameter.
+#[derive(Clone, Debug)]
+pub struct HysteresisLoop {
+    pub ramp: ParameterRamp,
+    pub time: Vec<Float>,
+    /// Value of the parameter at each logged sample.
+    pub parameter: Vec<Float>,
+    pub amplitude: Vec<Float>,
+    pub chi: Vec<Float>,
+    /// Centres of the parameter bins.
+    pub bin_centres: Vec<Float>,
+    /// Mean amplitude of each bin on the up sweep (NaN without samples).
+    pub up_amplitude: Vec<Float>,
+    /// Mean amplitude of each bin on the down sweep (NaN without samples).
+    pub down_amplitude: Vec<Float>,
+}
+
+impl HysteresisLoop {
+    /// Collect the branches of a logged time series of a simulation lasting `duration`.
+    pub fn from_series(
+        ramp: ParameterRamp,
+        duration: Float,
+        time: &[Float],
+        modes: &[SystemMode],
+        bins: usize,
+    ) -> Result<Self, HysteresisError> {
+        if time.len() != modes.len() {
+            return Err(HysteresisError::Length);
+        }
+        if bins == 0 {
+            return Err(HysteresisError::Bins);
+        }
+
+        let parameter: Vec<Float> = time.iter().map(|&t| ramp.value(t, duration)).collect();
+        let amplitude: Vec<Float> = modes.iter().map(|mode| mode.a()).collect();
+        let chi: Vec<Float> = modes.iter().map(|mode| mode.chi()).collect();
+
+        let width = (ramp.upper - ramp.lower) / bins as Float;
+        let bin_centres = (0..bins)
+            .map(|ind| ramp.lower + (ind as Float + 0.5) * width)
+            .collect();
+
+        // Sums and counts of the amplitudes on the up and down sweeps
+        let mut sums = [vec![0.0; bins], vec![0.0; bins]];
+        let mut counts = [vec![0usize; bins], vec![0usize; bins]];
+        for ((&t, &p), &a) in time.iter().zip(&parameter).zip(&amplitude) {
+            let sweep = usize::from(t > 0.5 * duration);
+            let bin = (((p - ramp.lower) / width) as usize).min(bins - 1);
+            sums[sweep][bin] += a;
+            counts[sweep][bin] += 1;
+        }
+        let [up_amplitude, down_amplitude] = [0, 1].map(|sweep| {
+            sums[sweep]
+                .iter()
+                .zip(&counts[sweep])
+                .map(|(&sum, &count)| sum / count as Float)
+                .collect()
+        });
+
+        Ok(Self {
+            ramp,
+            time: time.to_vec(),
+            parameter,
+            amplitude,
+            chi,
+            bin_centres,
+            up_amplitude,
+            down_amplitude,
+        })
+    }
+
+    /// Run the simulation with the ramped parameter and collect the branches.
+    ///
+    /// The observer of `settings` is replaced by a time series kept in memory.
+    pub fn from_simulation(
+        mut settings: Settings,
+        ramp: ParameterRamp,
+        bins: usize,
+    ) -> Result<Self, HysteresisError> {
+        // Check the whole range, starting from the lower value as the ramp does
+        ramp.parameter.set(&mut settings, ramp.upper)?;
+        ramp.parameter.set(&mut settings, ramp.lower)?;
+        settings.set_ramp(Some(ramp));
+
+        let p = &settings.parameters;
+        let duration = p.get_total_steps() as Float * p.get_timestep();
+        let observer = crate::sensitivity::run_time_series(settings);
+
+        Self::from_series(
+            ramp,
+            duration,
+            observer.get_time(),
+            observer.get_modes(),
+            bins,
+        )
+    }
+
+    /// Whether the branches of each bin differ by more than 10% of the larger amplitude.
+    pub fn bistable(&self) -> Vec<bool> {
+        self.up_amplitude
+            .iter()
+            .zip(&self.down_amplitude)
+            .map(|(&up, &down)| (up - down).abs() > BISTABILITY_TOLERANCE * up.max(down))
+            .collect()
+    }
+
+    /// Parameter intervals of the neighbouring bistable bins.
+    pub fn bistable_regions(&self) -> Vec<(Float, Float)> {
+        let half_width =
+            0.5 * (self.ramp.upper - self.ramp.lower) / self.bin_centres.len() as Float;
+        let mut regions: Vec<(Float, Float)> = Vec::new();
+        let mut previous = false;
+        for (&centre, bistable) in self.bin_centres.iter().zip(self.bistable()) {
+            if bistable {
+                let (lower, upper) = (centre - half_width, centre + half_width);
+                match regions.last_mut() {
+                    Some(region) if previous => region.1 = upper,
+                    _ => regions.push((lower, upper)),
+                }
+            }
+            previous = bistable;
+        }
+
+        regions
+    }
+
+    /// Save the logged samples (with the parameter) and the branches.
+    #[cfg(feature = "hdf5")]
+    pub fn save(&self, save_info: &SaveInfo, settings: &Settings) -> hdf5::Result<()> {
+        let file = hdf5::File::append(save_info.get_path())?;
+        let group = file.create_group(save_info.get_group())?;
+
+        observers::write_dataset(&group, &self.time, "time")?;
+        observers::write_dataset(&group, &self.parameter, "parameter")?;
+        observers::write_dataset(&group, &self.amplitude, "amplitude")?;
+        observers::write_dataset(&group, &self.chi, "chi")?;
+        observers::write_dataset(&group, &self.bin_centres, "bin_centres")?;
+        observers::write_dataset(&group, &self.up_amplitude, "up_amplitude")?;
+        observers::write_dataset(&group, &self.down_amplitude, "down_amplitude")?;
+        observers::write_dataset(&group, &self.bistable(), "bistable")?;
+
+        observers::save_str_attr(&group, self.ramp.parameter.name(), "ramped_parameter")?;
+        observers::save_attr(&group, &ndarray::arr0(self.ramp.lower), "lower")?;
+        observers::save_attr(&group, &ndarray::arr0(self.ramp.upper), "upper")?;
+        observers::save_str_attr(
+            &group,
+            settings.describing_function.name(),
+            "describing_function",
+        )?;
+
+        observers::save_parameters_as_attribute_json(&group, &settings.parameters)
+    }
+}
+
+impl std::fmt::Display for HysteresisLoop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let regions = self.bistable_regions();
+        if regions.is_empty() {
+            return write!(f, "no bistable {} region", self.ramp.parameter.name());
+        }
+
+        let regions: Vec<String> = regions
+            .iter()
+            .map(|(lower, upper)| format!("{} < {} < {}", lower, self.ramp.parameter.name(), upper))
+            .collect();
+        write!(f, "bistable for {}", regions.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn branches_of_a_loop() {
+        let ramp = ParameterRamp::new(SensitivityParameter::Gain, 0.0, 1.0).unwrap();
+        assert_eq!(ramp.value(5.0, 10.0), 1.0);
+        assert_eq!(ramp.value(7.5, 10.0), 0.5);
+
+        // The mode jumps up at 0.75 on the up sweep, and down at 0.25 on the down sweep
+        let time: Vec<Float> = (0..100).map(|ind| ind as Float * 0.1).collect();
+        let modes: Vec<SystemMode> = time
+            .iter()
+            .map(|&t| {
+                let p = ramp.value(t, 10.0);
+                let upper_branch = if t < 5.0 { p > 0.75 } else { p > 0.25 };
+                let a = if upper_branch { 2.0 } else { 0.1 };
+                SystemMode::new(a, 0.0, 0.0, 0.0)
+            })
+            .collect();
+
+        let hysteresis = HysteresisLoop::from_series(ramp, 10.0, &time, &modes, 4).unwrap();
+        assert_eq!(hysteresis.bistable(), [false, true, true, false]);
+        assert_eq!(hysteresis.bistable_regions(), [(0.25, 0.75)]);
+    }
+}
@@ -0,0 +1,203 @@
+//! Importance sampling of rare events through exponential tilting of the noise.
+
+use crate::{Float, Quaternion};
+use serde::{Deserialize, Serialize};
+
+/// Possible errors for [`ImportanceSampling`].
+#[derive(Clone, Copy, Debug)]
+pub enum ImportanceSamplingError {
+    Tilt,
+}
+
+impl std::error::Error for ImportanceSamplingError {}
+
+impl std::fmt::Display for ImportanceSamplingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Tilt => "the tilts have to be finite numbers",
+        };
+
+        write!(f, "error in the importance sampling: {}", msg)
+    }
+}
+
+/// Exponential tilting of the stochastic forcing.
+///
+/// Each standard normal noise sample is shifted by the constant `tilt`, which
+/// corresponds to sampling the noise from an exponentially tilted distribution.
+/// Each logged sample is weighted with the likelihood ratio between the
+/// original and the tilted distribution of all the noise since the start of
+/// the simulation, such that the weighted statistics are unbiased estimates
+/// of the statistics of the original system.
+///
+/// The tilt is expressed in units of the standard deviation of the noise per
+/// time step, and should therefore be kept small. A positive `tilt.real` pushes
+/// the amplitude towards larger values, making high amplitude events more likely.
+///
+/// The variance of the logarithm of the likelihood ratio is `|tilt|^2` times
+/// the number of time steps, such that the weights of long simulations are
+/// dominated by a few values and the estimates, although unbiased, converge
+/// slowly. Importance sampling is therefore suited to many short simulations
+/// (e.g. the probability of reaching a high amplitude within a given time),
+/// with a tilt of the order of one over the square root of the number of steps.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct ImportanceSampling {
+    /// Tilt of the real (amplitude) component of the noise.
+    pub tilt_real: Float,
+    /// Tilt of the first imaginary (orientation angle) component of the noise.
+    pub tilt_imag_i: Float,
+    /// Tilt of the second imaginary (phase) component of the noise.
+    pub tilt_imag_j: Float,
+    /// Tilt of the third imaginary (nature angle) component of the noise.
+    pub tilt_imag_k: Float,
+}
+
+impl ImportanceSampling {
+    /// Create a new instance of [`ImportanceSampling`] only tilting the amplitude.
+    pub fn new(tilt_real: Float) -> Self {
+        Self {
+            tilt_real,
+            tilt_imag_i: 0.0,
+            tilt_imag_j: 0.0,
+            tilt_imag_k: 0.0,
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), ImportanceSamplingError> {
+        let tilts = [
+            self.tilt_real,
+            self.tilt_imag_i,
+            self.tilt_imag_j,
+            self.tilt_imag_k,
+        ];
+        if !tilts.iter().all(|tilt| tilt.is_finite()) {
+            return Err(ImportanceSamplingError::Tilt);
+        }
+
+        Ok(())
+    }
+
+    /// Tilt a standard normal noise sample.
+    ///
+    /// Returns the tilted sample together with the increment of the natural
+    /// logarithm of the likelihood ratio (original over tilted distribution).
+    #[inline]
+    pub fn tilt(&self, sample: Quaternion) -> (Quaternion, Float) {
+        let tilt = Quaternion::new(
+            self.tilt_real,
+            self.tilt_imag_i,
+            self.tilt_imag_j,
+            self.tilt_imag_k,
+        );
+        let tilted = sample + tilt;
+
+        // ln(dP/dQ) = -tilt . tilted + |tilt|^2 / 2
+        let log_likelihood_ratio = -dot(&tilt, &tilted) + 0.5 * dot(&tilt, &tilt);
+
+        (tilted, log_likelihood_ratio)
+    }
+}
+
+impl Default for ImportanceSampling {
+    fn default() -> Self {
+        Self::new(0.0)
+    }
+}
+
+/// Likelihood ratio of the noise since the start of the simulation.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct LikelihoodRatio {
+    /// Natural logarithm of the likelihood ratio.
+    log_ratio: Float,
+}
+
+impl LikelihoodRatio {
+    /// Add the logarithm of the likelihood ratio of a step.
+    #[inline]
+    pub(crate) fn add(&mut self, log_ratio: Float) {
+        self.log_ratio += log_ratio;
+    }
+
+    /// Weight of a sample logged at the current step.
+    #[inline]
+    pub(crate) fn weight(&self) -> Float {
+        self.log_ratio.exp()
+    }
+}
+
+#[inline]
+fn dot(lhs: &Quaternion, rhs: &Quaternion) -> Float {
+    lhs.real * rhs.real
+        + lhs.imag_i * rhs.imag_i
+        + lhs.imag_j * rhs.imag_j
+        + lhs.imag_k * rhs.imag_k
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RNG;
+
+    #[test]
+    fn zero_tilt_is_unweighted() {
+        let is = ImportanceSampling::default();
+        let sample = Quaternion::new(0.3, -1.2, 0.5, 2.0);
+        let (tilted, log_lr) = is.tilt(sample);
+
+        assert_eq!(tilted.real, sample.real);
+        assert_eq!(log_lr, 0.0);
+    }
+
+    #[test]
+    fn likelihood_ratio() {
+        let is = ImportanceSampling::new(0.5);
+        let (tilted, log_lr) = is.tilt(Quaternion::new(0.0, 0.0, 0.0, 0.0));
+
+        // Density ratio of N(0, 1) and N(0.5, 1) evaluated at the tilted sample
+        let expected = -0.5 * tilted.real.powi(2) + 0.5 * (tilted.real - 0.5).powi(2);
+
+        assert!((log_lr - expected).abs() < Float::EPSILON);
+    }
+
+    #[test]
+    fn weighted_tail_probability_is_unbiased() {
+        // Probability that the sum of a path of standard normal samples exceeds
+        // two standard deviations, estimated from paths tilted towards it
+        let steps = 16;
+        let paths = 20000;
+        let is = ImportanceSampling::new(0.5);
+        let mut rng = RNG::from_seed(3);
+
+        let mut estimate = 0.0;
+        let mut unweighted = 0.0;
+        for _ in 0..paths {
+            let mut ratio = LikelihoodRatio::default();
+            let mut sum = 0.0;
+            for _ in 0..steps {
+                let sample = Quaternion::new(rng.get_normal(), 0.0, 0.0, 0.0);
+                let (tilted, log_lr) = is.tilt(sample);
+                ratio.add(log_lr);
+                sum += tilted.real;
+            }
+            if sum > 2.0 * (steps as Float).sqrt() {
+                estimate += ratio.weight();
+                unweighted += 1.0;
+            }
+        }
+        estimate /= paths as Float;
+        unweighted /= paths as Float;
+
+        // 1 - Phi(2), while half of the tilted sums exceed it
+        let exact = 0.022750131948179195;
+        assert!((estimate - exact).abs() < 0.05 * exact, "{}", estimate);
+        assert!(unweighted > 0.4);
+    }
+
+    #[test]
+    fn tilts_are_validated() {
+        let mut is = ImportanceSampling::new(0.1);
+        assert!(is.validate().is_ok());
+        is.tilt_imag_k = Float::NAN;
+        assert!(is.validate().is_err());
+    }
+}
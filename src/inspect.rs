@@ -0,0 +1,201 @@
+//! Readable summary of the contents of a results file.
+//!
+//! The groups and datasets of an HDF5 results file are listed as a tree,
+//! with the shape of each dataset and the key attributes of each group: the
+//! main parameters, the seeds, the run time, and whether the simulation was
+//! interrupted. The names of the remaining attributes are listed, such that
+//! their values can be looked up with the HDF5 tools or `--convert`.
+use std::error::Error;
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::convert::{member_name, read_attributes};
+use crate::Float;
+
+/// Parameters shown for each group, out of the `parameters` (or `settings`) attribute.
+const KEY_PARAMETERS: [&str; 7] = [
+    "damping",
+    "gain",
+    "noise",
+    "mode_order",
+    "number_of_burners",
+    "timestep",
+    "number_of_cycles",
+];
+
+/// Attributes shown with their values.
+const KEY_ATTRIBUTES: [&str; 6] = [
+    "seed",
+    "master_seed",
+    "seed_spawn_key",
+    "run_time",
+    "partial",
+    "interrupted_at",
+];
+
+/// Tree of the groups, datasets, and key attributes of the HDF5 file `path`.
+pub fn inspect(path: &Path) -> Result<String, Box<dyn Error>> {
+    let file = hdf5::File::open(path)?;
+    let size = std::fs::metadata(path)?.len();
+
+    let mut lines = vec![format!(
+        "{} ({})",
+        path.to_string_lossy(),
+        readable_size(size)
+    )];
+    describe_group(&file, "", &mut lines)?;
+
+    Ok(lines.join("\n"))
+}
+
+/// Append the lines describing the members of `group`, indented by `prefix`.
+fn describe_group(
+    group: &hdf5::Group,
+    prefix: &str,
+    lines: &mut Vec<String>,
+) -> Result<(), Box<dyn Error>> {
+    let attributes = match read_attributes(group) {
+        Value::Object(attributes) => attributes,
+        _ => Map::new(),
+    };
+    let details = attribute_lines(&attributes);
+
+    let mut members: Vec<(String, Option<hdf5::Group>)> = Vec::new();
+    for dataset in group.datasets()? {
+        let shape: Vec<String> = dataset.shape().iter().map(|l| l.to_string()).collect();
+        let units = match dataset.attr("units") {
+            Ok(attr) => attr
+                .read_scalar::<hdf5::types::VarLenUnicode>()
+                .map(|units| format!(" [{}]", units))
+                .unwrap_or_default(),
+            Err(_) => String::new(),
+        };
+        let name = member_name(&dataset.name());
+        members.push((format!("{} ({}){}", name, shape.join(" x "), units), None));
+    }
+    for subgroup in group.groups()? {
+        members.push((
+            format!("{}/", member_name(&subgroup.name())),
+            Some(subgroup),
+        ));
+    }
+
+    let last = members.len();
+    for line in details {
+        let branch = if last == 0 { "    " } else { "│   " };
+        lines.push(format!("{}{}{}", prefix, branch, line));
+    }
+    for (ind, (line, subgroup)) in members.into_iter().enumerate() {
+        let (branch, indent) = if ind + 1 == last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        lines.push(format!("{}{}{}", prefix, branch, line));
+        if let Some(subgroup) = subgroup {
+            describe_group(&subgroup, &format!("{}{}", prefix, indent), lines)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Lines with the key parameters and attributes, and the names of the other attributes.
+fn attribute_lines(attributes: &Map<String, Value>) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    // The parameters are saved by the observers, and as part of the settings by the simulations
+    let parameters = attributes
+        .get("parameters")
+        .and_then(parse_json)
+        .or_else(|| {
+            attributes
+                .get("settings")
+                .and_then(parse_json)
+                .and_then(|settings| settings.get("parameters").cloned())
+        });
+    if let Some(parameters) = parameters {
+        let values: Vec<String> = KEY_PARAMETERS
+            .iter()
+            .filter_map(|&name| {
+                let value = parameters.get(name)?;
+                Some(format!("{} = {}", name, value))
+            })
+            .collect();
+        lines.push(format!("parameters: {}", values.join(", ")));
+    }
+
+    let values: Vec<String> = KEY_ATTRIBUTES
+        .iter()
+        .filter_map(|&name| {
+            let value = attributes.get(name)?;
+            match name {
+                "run_time" => Some(format!("{} = {} s", name, value)),
+                _ => Some(format!("{} = {}", name, value)),
+            }
+        })
+        .collect();
+    if !values.is_empty() {
+        lines.push(values.join(", "));
+    }
+
+    let others: Vec<&str> = attributes
+        .keys()
+        .map(|name| name.as_str())
+        .filter(|name| !KEY_ATTRIBUTES.contains(name) && !["parameters", "settings"].contains(name))
+        .collect();
+    if !others.is_empty() {
+        lines.push(format!("other attributes: {}", others.join(", ")));
+    }
+
+    lines
+}
+
+/// The JSON object stored in the string attribute `value`.
+fn parse_json(value: &Value) -> Option<Value> {
+    serde_json::from_str(value.as_str()?).ok()
+}
+
+/// File size with a binary prefix.
+fn readable_size(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as Float;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < units.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{} B", bytes),
+        _ => format!("{:.1} {}", size, units[unit]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_attributes_are_summarized() {
+        let attributes = serde_json::json!({
+            "parameters": "{\"damping\": 0.01, \"gain\": 0.05, \"noise\": 0.06}",
+            "seed": 7,
+            "run_time": 12.5,
+            "thetas": [0.0, 0.5],
+        });
+        let lines = attribute_lines(attributes.as_object().unwrap());
+        assert_eq!(
+            lines,
+            vec![
+                "parameters: damping = 0.01, gain = 0.05, noise = 0.06",
+                "seed = 7, run_time = 12.5 s",
+                "other attributes: thetas",
+            ]
+        );
+
+        assert_eq!(readable_size(512), "512 B");
+        assert_eq!(readable_size(3 * 1024 * 1024 / 2), "1.5 MiB");
+    }
+}
@@ -0,0 +1,422 @@
+//! Time integration schemes of the governing equations.
+//!
+//! The state `(ln_a, nth0, phi, tan_2chi)` of [`SystemMode`] is advanced from
+//! the right hand side of the equations, which is a quaternion (real, imag_i,
+//! imag_j, imag_k), by the increments of [`state_increment`], or in the
+//! quaternion representation by [`quaternion_step`].
+use serde::{Deserialize, Serialize};
+
+use crate::azimuthal_mode::SystemMode;
+use crate::hrr_integral::Jacobian;
+use crate::{Float, Quaternion, PI};
+
+/// Possible errors for the combination of [`Integrator`] and [`StateRepresentation`].
+#[derive(Clone, Copy, Debug)]
+pub enum IntegratorError {
+    Representation,
+}
+
+impl std::error::Error for IntegratorError {}
+
+impl std::fmt::Display for IntegratorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Representation => {
+                "the quaternion state representation requires the EulerMaruyama integrator"
+            }
+        };
+
+        write!(f, "error in the integrator: {}", msg)
+    }
+}
+
+/// Scheme used to advance the mode by one time step.
+///
+/// `EulerMaruyama` is the explicit scheme, which requires small time steps
+/// for very large gains or strong saturation (stiff settings). `SemiImplicit`
+/// treats the heat release rate integral (including the damping) implicitly,
+/// linearized with the [`Jacobian`] of the describing function, such that
+/// stiff settings run with realistic time steps. Each step of the latter is
+/// more expensive, as the Jacobian is evaluated at every time step.
+///
+/// The noise on `ln_a` and `tan_2chi` is multiplicative (divided by the
+/// amplitude, and by `cos^2 2chi`), such that Euler–Maruyama converges with
+/// strong order 1/2. `Milstein` adds the derivative corrections of these two
+/// channels, see [`milstein_correction`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Integrator {
+    #[default]
+    EulerMaruyama,
+    SemiImplicit,
+    Milstein,
+}
+
+/// Largest magnitude of `tan 2chi` at which the mode is advanced in the transformed representation.
+///
+/// Closer to the spinning states (`|chi| > pi/4 - 0.005`), the increments of
+/// the transformed variables are dominated by the divisions by `cos 2chi`,
+/// and may jump across a spinning state or overflow within a single step.
+pub(crate) const MAX_TAN_2CHI: Float = 100.0;
+
+/// Whether `mode` is too close to a spinning state to be advanced in the transformed representation.
+#[inline]
+pub(crate) fn near_spinning_state(mode: &SystemMode) -> bool {
+    mode.tan_2chi.abs() > MAX_TAN_2CHI
+}
+
+/// Representation of the mode in which it is advanced by one time step.
+///
+/// `Transformed` advances the four variables `(ln_a, nth0, phi, tan_2chi)`
+/// separately, where the increments of `phi` and `tan_2chi` are divided by
+/// `cos 2chi` and thereby singular at the spinning states `|chi| = pi/4`.
+/// `Quaternion` advances the mode `A e^(i n theta_0) e^(-k chi) e^(j phi)` as
+/// a whole, multiplying it by the exponential of the right hand side, which
+/// is regular at the spinning states. The mode is mapped back to the four
+/// variables after each step, such that the two can be compared on the same
+/// settings (e.g. with `--convergence`).
+///
+/// Close to the spinning states (see [`MAX_TAN_2CHI`]), the transformed
+/// representation is advanced as a quaternion as well, with any integrator.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum StateRepresentation {
+    #[default]
+    Transformed,
+    Quaternion,
+}
+
+impl StateRepresentation {
+    /// Check that the representation can be advanced with `integrator`.
+    ///
+    /// The semi-implicit and Milstein schemes are derived for the transformed variables.
+    pub fn check(&self, integrator: Integrator) -> Result<(), IntegratorError> {
+        match (self, integrator) {
+            (Self::Quaternion, Integrator::EulerMaruyama) | (Self::Transformed, _) => Ok(()),
+            (Self::Quaternion, _) => Err(IntegratorError::Representation),
+        }
+    }
+}
+
+/// Increment of the state of `mode` for the right hand side `rhs`.
+#[inline]
+pub(crate) fn state_increment(mode: &SystemMode, rhs: &Quaternion) -> [Float; 4] {
+    let chi = mode.chi();
+    [
+        rhs.real,
+        rhs.imag_i - mode.tan_2chi * rhs.imag_j,
+        rhs.imag_j / (2.0 * chi).cos(),
+        -2.0 * rhs.imag_k / (2.0 * chi).cos().powi(2),
+    ]
+}
+
+/// Mode advanced from `mode` by the right hand side `rhs` in the quaternion representation.
+///
+/// The right hand side is the rate of change `e^(-i n theta_0) dq q^-1 e^(i n theta_0)`
+/// of the mode `q = A e^(i n theta_0) e^(-k chi) e^(j phi)`, such that
+/// `q` is multiplied by `e^(i n theta_0) exp(rhs) e^(-i n theta_0)`. The
+/// amplitude follows from the real part, and the angles from the spinning
+/// waves of the rotated mode (see [`crate::azimuthal_mode::SpinningWaves`]),
+/// whose phases are unwrapped relative to the current mode. The phase of a
+/// vanishing wave is undefined, in which case the orientation angle is kept.
+///
+/// The nature angle is set rather than incremented, as `tan_2chi` diverges
+/// at the spinning states.
+#[inline]
+pub(crate) fn quaternion_step(mode: &SystemMode, rhs: &Quaternion) -> SystemMode {
+    let rotation = Quaternion::new(0.0, rhs.imag_i, rhs.imag_j, rhs.imag_k).exp();
+    let q = rotation * unit_quaternion(mode);
+
+    rotated_mode(mode, &q, mode.ln_a + rhs.real)
+}
+
+/// Mode after adding the jump `kick` to its quaternion (in the frame rotated by `-n theta_0`).
+///
+/// The quaternion `A e^(-k chi) e^(j phi)` of the mode becomes
+/// `A e^(-k chi) e^(j phi) + kick`, which is exact for kicks of any size.
+/// A kick cancelling the mode leaves it unchanged.
+#[inline]
+pub(crate) fn jump(mode: &SystemMode, kick: &Quaternion) -> SystemMode {
+    let q = unit_quaternion(mode) * mode.a() + *kick;
+    let a =
+        (q.real * q.real + q.imag_i * q.imag_i + q.imag_j * q.imag_j + q.imag_k * q.imag_k).sqrt();
+    if a <= Float::EPSILON {
+        return *mode;
+    }
+
+    rotated_mode(mode, &(q / a), a.ln())
+}
+
+/// Unit quaternion `e^(-k chi) e^(j phi)` of `mode`.
+#[inline]
+fn unit_quaternion(mode: &SystemMode) -> Quaternion {
+    let (sin_chi, cos_chi) = mode.chi().sin_cos();
+    let (sin_phi, cos_phi) = mode.phi().sin_cos();
+
+    Quaternion::new(cos_chi, 0.0, 0.0, -sin_chi) * Quaternion::new(cos_phi, 0.0, sin_phi, 0.0)
+}
+
+/// Mode with the logarithmic amplitude `ln_a` and the angles of the unit quaternion `q`.
+///
+/// The quaternion `q = e^(i alpha) e^(-k chi) e^(j phi)` is relative to the
+/// orientation angle of `mode`, i.e. the orientation angle is increased by
+/// `alpha`, and the phases are unwrapped relative to `mode`.
+#[inline]
+fn rotated_mode(mode: &SystemMode, q: &Quaternion, ln_a: Float) -> SystemMode {
+    // Anticlockwise (cos chi + sin chi) e^(i (n theta_0 + phi)) and
+    // clockwise (cos chi - sin chi) e^(i (n theta_0 - phi)) waves
+    let (plus_real, plus_imag) = (q.real - q.imag_k, q.imag_i + q.imag_j);
+    let (minus_real, minus_imag) = (q.real + q.imag_k, q.imag_i - q.imag_j);
+    let plus = plus_real.hypot(plus_imag);
+    let minus = minus_real.hypot(minus_imag);
+    let chi = (plus - minus).atan2(plus + minus);
+
+    let wrap = |angle: Float| (angle + PI).rem_euclid(2.0 * PI) - PI;
+    let mut sum = wrap(plus_imag.atan2(plus_real) - mode.phi());
+    let mut difference = wrap(minus_imag.atan2(minus_real) + mode.phi());
+    if minus <= Float::EPSILON {
+        difference = -sum;
+    } else if plus <= Float::EPSILON {
+        sum = -difference;
+    }
+
+    SystemMode {
+        ln_a,
+        nth0: mode.nth0 + 0.5 * (sum + difference),
+        phi: mode.phi + 0.5 * (sum - difference),
+        tan_2chi: (2.0 * chi).tan(),
+    }
+}
+
+/// Derivatives of [`state_increment`] with respect to the components of the right hand side.
+fn increment_matrix(mode: &SystemMode) -> [[Float; 4]; 4] {
+    let cos_2chi = (2.0 * mode.chi()).cos();
+    [
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, -mode.tan_2chi, 0.0],
+        [0.0, 0.0, 1.0 / cos_2chi, 0.0],
+        [0.0, 0.0, 0.0, -2.0 / cos_2chi.powi(2)],
+    ]
+}
+
+/// Increment of the drift-implicit (linearized) Euler–Maruyama scheme.
+///
+/// Solves `(I - dt M J) dy = dy_explicit`, where `M` is the
+/// [`increment_matrix`], `J` the Jacobian of the heat release rate integral,
+/// and `dy_explicit` the explicit increment of the total right hand side
+/// `rhs` (including the noise). The explicit increment is used if the system
+/// is singular.
+pub(crate) fn semi_implicit_increment(
+    mode: &SystemMode,
+    rhs: &Quaternion,
+    jacobian: &Jacobian,
+    dt: Float,
+) -> [Float; 4] {
+    let explicit = state_increment(mode, rhs);
+    let increment_matrix = increment_matrix(mode);
+
+    let mut matrix = [[0.0; 4]; 4];
+    for (row, matrix_row) in matrix.iter_mut().enumerate() {
+        for (col, element) in matrix_row.iter_mut().enumerate() {
+            let product: Float = (0..4)
+                .map(|k| increment_matrix[row][k] * jacobian[k][col])
+                .sum();
+            *element = Float::from(row == col) - dt * product;
+        }
+    }
+
+    solve(matrix, explicit).unwrap_or(explicit)
+}
+
+/// Milstein corrections of the state increments of `ln_a` and `tan_2chi`.
+///
+/// For a channel `dy = ... + h(y) dW`, the correction is
+/// `h h' (dW^2 - dt) / 2`. Here, `noise` is the stochastic forcing
+/// `relative_noise * dW / sqrt(dt)`, where `relative_noise` is the noise
+/// amplitude divided by `sqrt(2) A`. Only the diagonal terms are included, as
+/// the mixed terms (e.g. from the noise on `tan_2chi` depending on the
+/// amplitude) require iterated stochastic integrals.
+///
+/// The correction of `tan_2chi` grows as `tan^3 2chi`, such that the
+/// expansion breaks down close to the spinning states. Corrections larger
+/// than the stochastic increment of their channel are therefore dropped,
+/// which falls back to Euler–Maruyama there.
+#[inline]
+pub(crate) fn milstein_correction(
+    mode: &SystemMode,
+    noise: &Quaternion,
+    relative_noise: Float,
+    dt: Float,
+) -> [Float; 4] {
+    let variance = relative_noise.powi(2);
+    let tan_2chi = mode.tan_2chi;
+    let bounded = |correction: Float, increment: Float| {
+        if correction.abs() <= increment.abs() {
+            correction
+        } else {
+            0.0
+        }
+    };
+
+    // h = relative_noise for ln_a, and h = -2 relative_noise (1 + tan^2 2chi) for tan_2chi
+    let ln_a = -0.5 * (noise.real.powi(2) - variance) * dt;
+    let tan_2chi_correction =
+        4.0 * tan_2chi * (1.0 + tan_2chi.powi(2)) * (noise.imag_k.powi(2) - variance) * dt;
+    [
+        bounded(ln_a, noise.real * dt.sqrt()),
+        0.0,
+        0.0,
+        bounded(
+            tan_2chi_correction,
+            2.0 * (1.0 + tan_2chi.powi(2)) * noise.imag_k * dt.sqrt(),
+        ),
+    ]
+}
+
+/// Solve the linear system `matrix x = rhs` by Gaussian elimination with partial pivoting.
+fn solve(mut matrix: [[Float; 4]; 4], mut rhs: [Float; 4]) -> Option<[Float; 4]> {
+    for col in 0..4 {
+        let pivot =
+            (col..4).max_by(|&a, &b| matrix[a][col].abs().total_cmp(&matrix[b][col].abs()))?;
+        if matrix[pivot][col].abs() < Float::EPSILON || !matrix[pivot][col].is_finite() {
+            return None;
+        }
+        matrix.swap(col, pivot);
+        rhs.swap(col, pivot);
+
+        let pivot_row = matrix[col];
+        for row in (col + 1)..4 {
+            let factor = matrix[row][col] / pivot_row[col];
+            for (element, pivot_element) in matrix[row].iter_mut().zip(pivot_row).skip(col) {
+                *element -= factor * pivot_element;
+            }
+            rhs[row] -= factor * rhs[col];
+        }
+    }
+
+    let mut solution = [0.0; 4];
+    for row in (0..4).rev() {
+        let sum: Float = ((row + 1)..4).map(|k| matrix[row][k] * solution[k]).sum();
+        solution[row] = (rhs[row] - sum) / matrix[row][row];
+    }
+
+    Some(solution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn implicit_linear_decay() {
+        // d ln_a / dt = -lambda ln_a, stiff for lambda dt >> 1
+        let (lambda, dt) = (1000.0, 0.01);
+        let mode = SystemMode {
+            ln_a: 1.0,
+            ..Default::default()
+        };
+        let rhs = Quaternion::new(-lambda * mode.ln_a * dt, 0.0, 0.0, 0.0);
+        let mut jacobian = [[0.0; 4]; 4];
+        jacobian[0][0] = -lambda;
+
+        // Backward Euler: ln_a (1 + lambda dt) = 1
+        let increment = semi_implicit_increment(&mode, &rhs, &jacobian, dt);
+        let expected = 1.0 / (1.0 + lambda * dt) - 1.0;
+        assert!((increment[0] - expected).abs() < 1e-12);
+        assert_eq!(increment[1..], [0.0; 3]);
+    }
+
+    #[test]
+    fn milstein_correction_of_tan_2chi() {
+        let (relative_noise, xi, dt) = (0.3, 1.7, 0.01);
+        let mode = SystemMode::new(1.0, 0.0, 0.0, 0.2);
+
+        // Diffusion h of tan_2chi and its derivative by central differences
+        let diffusion = |tan_2chi: Float| {
+            let mode = SystemMode { tan_2chi, ..mode };
+            state_increment(&mode, &Quaternion::new(0.0, 0.0, 0.0, relative_noise))[3]
+        };
+        let step = 1e-6;
+        let derivative =
+            (diffusion(mode.tan_2chi + step) - diffusion(mode.tan_2chi - step)) / (2.0 * step);
+        let expected = 0.5 * diffusion(mode.tan_2chi) * derivative * (xi * xi - 1.0) * dt;
+
+        let noise = Quaternion::new(0.0, 0.0, 0.0, relative_noise * xi);
+        let correction = milstein_correction(&mode, &noise, relative_noise, dt);
+        assert!((correction[3] - expected).abs() < 1e-8);
+    }
+
+    #[test]
+    fn quaternion_step_matches_state_increment() {
+        let mode = SystemMode::new(1.0, 0.3, -0.5, 0.2);
+        let rhs = Quaternion::new(0.2, -0.4, 0.7, 0.3) * 1e-6;
+
+        let step = quaternion_step(&mode, &rhs);
+        let quaternion = [
+            step.ln_a - mode.ln_a,
+            step.nth0 - mode.nth0,
+            step.phi - mode.phi,
+            step.tan_2chi - mode.tan_2chi,
+        ];
+        let transformed = state_increment(&mode, &rhs);
+        for (q, t) in quaternion.iter().zip(transformed) {
+            assert!((q - t).abs() < 1e-10, "{:?} {:?}", quaternion, transformed);
+        }
+
+        // Regular at the spinning states
+        let spinning = SystemMode::new(1.0, 0.3, -0.5, crate::FRAC_PI_4);
+        let step = quaternion_step(&spinning, &Quaternion::new(0.0, 0.1, 0.2, 0.3));
+        assert!(step.nth0.is_finite() && step.phi.is_finite());
+        assert!(step.chi() < crate::FRAC_PI_4 && step.chi() > 0.0);
+    }
+
+    #[test]
+    fn small_jump_matches_quaternion_step() {
+        let mode = SystemMode::new(1.5, 0.3, -0.5, 0.6);
+        let kick = Quaternion::new(0.2, -0.4, 0.7, 0.3) * 1e-6;
+
+        // q + kick = (1 + kick q^-1) q, with the rotated quaternion q of the mode
+        let q = unit_quaternion(&mode) * mode.a();
+        let inverse = Quaternion::new(q.real, -q.imag_i, -q.imag_j, -q.imag_k) / mode.a().powi(2);
+        let stepped = quaternion_step(&mode, &(kick * inverse));
+        let jumped = jump(&mode, &kick);
+        for (j, s) in [
+            (jumped.ln_a, stepped.ln_a),
+            (jumped.nth0, stepped.nth0),
+            (jumped.phi, stepped.phi),
+            (jumped.tan_2chi, stepped.tan_2chi),
+        ] {
+            assert!((j - s).abs() < 1e-10, "{:?} {:?}", jumped, stepped);
+        }
+
+        // A kick along the mode only scales its amplitude
+        let doubled = jump(&mode, &q);
+        assert!((doubled.a() - 2.0 * mode.a()).abs() < 1e-12);
+        assert!((doubled.chi() - mode.chi()).abs() < 1e-12);
+        assert!((doubled.phi() - mode.phi()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn strongly_spinning_run_remains_finite() {
+        use crate::azimuthal_mode::Mode;
+        use crate::hrr_integral::{AFDFSimplified, DescribingFunction};
+        use crate::{Settings, FRAC_PI_4};
+
+        // Starting at and next to the spinning states, with little noise to push the mode away
+        for nature_angle in [FRAC_PI_4, -FRAC_PI_4 + 1e-5] {
+            let mut settings = Settings {
+                describing_function: DescribingFunction::Simplified(AFDFSimplified::new(50.0)),
+                ..Default::default()
+            };
+            settings.set_seed(Some(5));
+            let parameters = &mut settings.parameters;
+            parameters.set_noise(0.005).unwrap();
+            parameters.set_number_of_cycles(20.0).unwrap();
+            parameters.set_initial_mode(Mode::new(1.0, 0.3, 0.2, nature_angle));
+
+            let observer = crate::sensitivity::run_time_series(settings);
+            for mode in observer.get_modes() {
+                assert!(mode.a().is_finite() && mode.nth0().is_finite() && mode.phi().is_finite());
+                assert!(mode.chi().abs() <= FRAC_PI_4);
+            }
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Compound Poisson jumps of the acoustic state, modelling intermittent large disturbances.
+//!
+//! Besides the continuous Gaussian noise, localized events such as the
+//! blow-off and reignition of a flame kick the acoustic state by a finite
+//! amount at random times. The jumps arrive as a Poisson process, and each
+//! jump adds a kick of random magnitude in a uniformly distributed direction
+//! to the quaternion `A e^(-k chi) e^(j phi)` of the mode, i.e. the same
+//! directions as the Gaussian noise, but applied exactly rather than to
+//! first order in the time step.
+use serde::{Deserialize, Serialize};
+
+use crate::{Distribution, Float, Quaternion, RNG};
+
+/// Possible errors for [`JumpNoise`].
+#[derive(Clone, Copy, Debug)]
+pub enum JumpNoiseError {
+    Rate,
+    Size,
+}
+
+impl std::error::Error for JumpNoiseError {}
+
+impl std::fmt::Display for JumpNoiseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Rate => "the rate has to be a non-negative number",
+            Self::Size => "invalid distribution parameters of the jump size",
+        };
+
+        write!(f, "error in the jump noise: {}", msg)
+    }
+}
+
+/// Compound Poisson jump term of the stochastic forcing.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct JumpNoise {
+    /// Mean number of jumps per acoustic cycle.
+    pub rate: Float,
+    /// Distribution of the magnitude of each jump, in the units of the amplitude.
+    ///
+    /// A negative sample reverses the direction of the jump.
+    pub size: Distribution,
+}
+
+impl JumpNoise {
+    pub fn new(rate: Float, size: Distribution) -> Result<Self, JumpNoiseError> {
+        let jump_noise = Self { rate, size };
+        jump_noise.validate()?;
+
+        Ok(jump_noise)
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), JumpNoiseError> {
+        if !(self.rate.is_finite() && self.rate >= 0.0) {
+            return Err(JumpNoiseError::Rate);
+        }
+        if !self.size.is_valid() {
+            return Err(JumpNoiseError::Size);
+        }
+
+        Ok(())
+    }
+
+    /// Draw the sum of the jumps arriving within the time step `dt`, or `None` without jumps.
+    ///
+    /// The number of jumps is drawn from the Poisson distribution with mean
+    /// `rate * dt` (by inversion of a single uniform sample), so a time step
+    /// without jumps costs one uniform sample.
+    pub fn draw(&self, dt: Float, rng: &mut RNG) -> Option<Quaternion> {
+        let mut probability = (-self.rate * dt).exp();
+        let mut cumulative = probability;
+        let u = rng.get_uniform();
+        let mut jumps = 0;
+        while u > cumulative && probability > 0.0 {
+            jumps += 1;
+            probability *= self.rate * dt / jumps as Float;
+            cumulative += probability;
+        }
+        if jumps == 0 {
+            return None;
+        }
+
+        let kick = (0..jumps).fold(Quaternion::default(), |kick, _| {
+            let direction = rng.get_random();
+            let norm = norm(&direction);
+            if norm == 0.0 {
+                return kick;
+            }
+            kick + direction * (self.size.sample(rng) / norm)
+        });
+
+        Some(kick)
+    }
+}
+
+#[inline]
+fn norm(q: &Quaternion) -> Float {
+    (q.real * q.real + q.imag_i * q.imag_i + q.imag_j * q.imag_j + q.imag_k * q.imag_k).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jumps_arrive_at_the_rate() {
+        let jump_noise = JumpNoise::new(
+            0.5,
+            Distribution::Uniform {
+                lower: 1.0,
+                upper: 1.0,
+            },
+        )
+        .unwrap();
+        let mut rng = RNG::from_seed(4);
+        let (dt, steps) = (0.01, 200_000);
+
+        let mut count = 0;
+        for _ in 0..steps {
+            if let Some(kick) = jump_noise.draw(dt, &mut rng) {
+                count += 1;
+                // A single jump of unit magnitude (two jumps in one step are rare)
+                assert!(norm(&kick) <= 2.0 + 1e-12);
+            }
+        }
+        let expected = jump_noise.rate * dt * steps as Float;
+        assert!((count as Float - expected).abs() < 4.0 * expected.sqrt());
+
+        assert!(JumpNoise::new(-1.0, jump_noise.size).is_err());
+    }
+}
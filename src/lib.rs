@@ -1,18 +1,98 @@
 pub mod azimuthal_mode;
+mod benchmarks;
+mod cancellation;
+mod convergence;
+#[cfg(feature = "hdf5")]
+mod convert;
+mod drift_field;
+pub mod ffi;
+mod first_passage;
+mod flame_profile;
 mod fourier;
+mod hooks;
 pub mod hrr_integral;
+mod hysteresis;
+mod importance_sampling;
+#[cfg(feature = "hdf5")]
+mod inspect;
+mod integrator;
+mod jump_noise;
+mod lyapunov;
+#[cfg(feature = "monitor")]
+pub mod monitor;
+mod noise_schedule;
 pub mod observers;
+mod paired;
 mod parameters;
+mod physical_units;
+#[cfg(feature = "plot")]
+mod plot;
+mod potential;
+pub mod prelude;
+mod presets;
 mod quaternion;
+mod reflection;
+mod retry;
+mod rotation;
 mod saturation;
+mod seed_sequence;
+mod sensitivity;
+#[cfg(feature = "service")]
+pub mod service;
 mod settings;
 mod simulate;
+mod sobol;
+mod template;
+mod throughput;
+mod uncertainty;
+pub mod verify;
 
-pub use fourier::Fourier;
-pub use parameters::{Parameters, ParametersError};
+pub use benchmarks::{BenchmarkError, CubicSaturationBenchmark};
+pub use cancellation::CancellationToken;
+pub use convergence::{ConvergenceError, ConvergenceResult, ConvergenceTest};
+#[cfg(feature = "hdf5")]
+pub use convert::{convert, sidecar_path, ConvertError, ConvertFormat};
+pub use drift_field::{DriftField, DriftFieldError, DriftFieldGrid};
+pub use first_passage::{FirstPassageError, FirstPassageTimes, SpinState};
+pub use flame_profile::{FlameProfile, ProfileHarmonic};
+pub use fourier::{ComplexFourier, Fourier, FourierSums};
+pub use hooks::{SimulationHooks, SimulationState};
+pub use hrr_integral::{
+    DescribingFunction, DescribingFunctionError, GainCoefficients, HeatReleaseRate, IntegralTerms,
+};
+pub use hysteresis::{HysteresisError, HysteresisLoop, ParameterRamp};
+pub use importance_sampling::{ImportanceSampling, ImportanceSamplingError};
+#[cfg(feature = "hdf5")]
+pub use inspect::inspect;
+pub use integrator::{Integrator, IntegratorError, StateRepresentation};
+pub use jump_noise::{JumpNoise, JumpNoiseError};
+pub use lyapunov::{FiniteTimeExponents, Lyapunov};
+pub use noise_schedule::{NoiseSchedule, NoiseScheduleError, SchedulePoint};
+pub use paired::{PairedComparison, PairedComparisonError};
+pub use parameters::{FlameDistribution, Parameters, ParametersError};
+pub use physical_units::PhysicalUnits;
+#[cfg(feature = "plot")]
+pub use plot::{plot, PlotError};
+pub use potential::{PotentialError, PotentialGrid, PotentialLandscape};
+pub use presets::{Preset, PresetError, PAPER_2023, PRESETS};
 pub use quaternion::Quaternion;
+pub use reflection::{ReflectionError, ReflectionResult, ReflectionTest};
+pub use retry::{rescue_path, RetryError, RetryPolicy, SaveOutcome};
+pub use rotation::{RotationError, RotationResult, RotationTest};
 pub use saturation::Saturation;
-pub use settings::{SaveData, Settings};
+pub use seed_sequence::SeedSequence;
+pub use sensitivity::{
+    OutputStatistics, Sensitivity, SensitivityError, SensitivityParameter, SensitivityResult,
+};
+pub use settings::{NormalSampling, RngState, SaveData, Settings, RNG};
+pub use sobol::{Sobol, SobolError};
+pub use template::{SettingsTemplate, TemplateError, STDIN_PATH};
+pub use throughput::{Throughput, THROUGHPUT_STEPS};
+pub use uncertainty::{
+    Distribution, EnsembleSummary, UncertainParameter, UncertaintyError, UncertaintyQuantification,
+    UncertaintyResult,
+};
+pub use verify::{Verification, VerificationError, VerificationReport};
 
 /// Floating point precision.
 pub type Float = f64;
@@ -0,0 +1,134 @@
+//! Finite-time Lyapunov exponents of the amplitude and nature angle dynamics.
+
+use crate::azimuthal_mode::SystemMode;
+use crate::{Float, FRAC_PI_4};
+use serde::{Deserialize, Serialize};
+
+/// Finite-time Lyapunov exponents from a twin trajectory with shared noise.
+///
+/// A twin of the simulated mode, displaced by `separation` in the plane of
+/// `ln A` and the nature angle `chi`, is advanced with the same noise samples
+/// as the simulated mode. Every `interval` cycles, the growth rate of the
+/// separation gives a finite-time Lyapunov exponent, after which the twin is
+/// moved back to the initial separation along the current direction.
+///
+/// Negative exponents mean that nearby trajectories converge under the common
+/// noise, such that the observed switching is driven by the noise, while
+/// positive exponents indicate sensitive (deterministic) dynamics. The
+/// orientation angle and temporal phase, which are neutral in the symmetric
+/// case, are excluded from the separation.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct Lyapunov {
+    /// Initial (and renormalized) separation of the twin trajectory.
+    pub separation: Float,
+    /// Number of cycles between the renormalizations of the separation.
+    pub interval: Float,
+}
+
+impl Default for Lyapunov {
+    fn default() -> Self {
+        Self {
+            separation: 1e-6,
+            interval: 10.0,
+        }
+    }
+}
+
+/// Time series of the finite-time Lyapunov exponents.
+#[derive(Clone, Debug, Default)]
+pub struct FiniteTimeExponents {
+    /// Time at the end of each interval.
+    pub time: Vec<Float>,
+    pub exponents: Vec<Float>,
+}
+
+impl FiniteTimeExponents {
+    /// Mean of the finite-time exponents, i.e. the exponent over the whole simulation.
+    pub fn mean(&self) -> Float {
+        self.exponents.iter().sum::<Float>() / self.exponents.len() as Float
+    }
+}
+
+/// Distance between two modes in the plane of `ln A` and `chi`.
+#[inline]
+fn distance(first: &SystemMode, second: &SystemMode) -> Float {
+    (second.ln_a - first.ln_a).hypot(second.chi() - first.chi())
+}
+
+/// Twin trajectory advanced alongside the simulated mode.
+pub(crate) struct LyapunovState {
+    separation: Float,
+    /// Length of each interval (in time).
+    interval: Float,
+    pub(crate) twin: SystemMode,
+    pub(crate) exponents: FiniteTimeExponents,
+}
+
+impl LyapunovState {
+    pub(crate) fn new(lyapunov: &Lyapunov, mode: &SystemMode, interval: Float) -> Self {
+        Self {
+            separation: lyapunov.separation,
+            interval,
+            twin: displaced(mode, lyapunov.separation, 1.0, 1.0),
+            exponents: FiniteTimeExponents::default(),
+        }
+    }
+
+    /// Record the exponent of the interval ending at `time`, and reset the separation.
+    pub(crate) fn renormalize(&mut self, mode: &SystemMode, time: Float) {
+        let d = distance(mode, &self.twin);
+        if !(d > 0.0 && d.is_finite()) {
+            // The trajectories have collapsed (or diverged), so restart the twin
+            self.twin = displaced(mode, self.separation, 1.0, 1.0);
+            return;
+        }
+
+        self.exponents.time.push(time);
+        self.exponents
+            .exponents
+            .push((d / self.separation).ln() / self.interval);
+
+        let d_ln_a = self.twin.ln_a - mode.ln_a;
+        let d_chi = self.twin.chi() - mode.chi();
+        self.twin = displaced(mode, self.separation, d_ln_a / d, d_chi / d);
+    }
+}
+
+/// Mode displaced by `separation` along the direction `(ln_a, chi)`.
+///
+/// The nature angle is displaced in the opposite direction if it would
+/// otherwise leave the interval `[-pi/4, pi/4]`.
+fn displaced(mode: &SystemMode, separation: Float, ln_a: Float, chi: Float) -> SystemMode {
+    let norm = ln_a.hypot(chi);
+    let d_ln_a = separation * ln_a / norm;
+    let mut d_chi = separation * chi / norm;
+    if (mode.chi() + d_chi).abs() > FRAC_PI_4 {
+        d_chi = -d_chi;
+    }
+
+    SystemMode {
+        ln_a: mode.ln_a + d_ln_a,
+        tan_2chi: (2.0 * (mode.chi() + d_chi)).tan(),
+        ..*mode
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renormalized_separation() {
+        let lyapunov = Lyapunov::default();
+        let mode = SystemMode::new(1.0, 0.3, 0.1, 0.2);
+        let mut state = LyapunovState::new(&lyapunov, &mode, 2.0);
+        assert!((distance(&mode, &state.twin) - lyapunov.separation).abs() < 1e-12);
+
+        // Grow the separation by a factor e^2 over the interval
+        state.twin.ln_a = mode.ln_a + lyapunov.separation * (2.0 as Float).exp();
+        state.twin.tan_2chi = mode.tan_2chi;
+        state.renormalize(&mode, 2.0);
+        assert!((state.exponents.mean() - 1.0).abs() < 1e-6);
+        assert!((distance(&mode, &state.twin) - lyapunov.separation).abs() < 1e-12);
+    }
+}
@@ -1,14 +1,72 @@
-use std::path::PathBuf;
-use std::time::SystemTime;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
 
-use azimuthal_fdf::hrr_integral::{self, DescribingFunction};
-use azimuthal_fdf::observers::{self, Observer, ObserverTrait, SaveInfo};
-use azimuthal_fdf::{Parameters, Saturation, SaveData, Settings};
+use azimuthal_fdf::observers;
+use azimuthal_fdf::prelude::*;
+use azimuthal_fdf::{
+    CancellationToken, ConvergenceTest, DriftField, DriftFieldGrid, FirstPassageTimes,
+    HysteresisError, HysteresisLoop, PairedComparison, ParameterRamp, PotentialGrid,
+    PotentialLandscape, Preset, ReflectionTest, RetryPolicy, SaveData, SaveOutcome, Sensitivity,
+    SettingsTemplate, Throughput, UncertaintyQuantification, Verification, PAPER_2023, PRESETS,
+};
 use clap::{CommandFactory, Parser};
 use rayon::prelude::*;
 
-fn main() {
+/// Path standing for the standard output, e.g. `--summary-json -`.
+const STDOUT_PATH: &str = "-";
+
+/// Exit code of a batch in which some, but not all, of the jobs completed.
+const EXIT_PARTIAL_FAILURE: u8 = 2;
+
+/// Whether the standard output is reserved for the summary of the simulations.
+static STDOUT_RESERVED: AtomicBool = AtomicBool::new(false);
+
+/// Print a status message, to the standard error if the standard output is reserved.
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if STDOUT_RESERVED.load(Ordering::Relaxed) {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+fn main() -> ExitCode {
     let cli_arguments = CliParser::parse();
+    if cli_arguments.summary_json.as_deref() == Some(STDOUT_PATH) {
+        STDOUT_RESERVED.store(true, Ordering::Relaxed);
+    }
+
+    // Shared wall-clock budget of all the simulations
+    let cancellation = cli_arguments
+        .wall_time
+        .map(|seconds| CancellationToken::with_budget(Duration::from_secs_f64(seconds)));
+
+    // Retries of the failed saves of the simulations
+    let retry_policy = match RetryPolicy::new(
+        cli_arguments.save_retries,
+        cli_arguments.save_retry_delay,
+        2.0,
+    ) {
+        Ok(policy) => policy,
+        Err(e) => {
+            println!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Variables and overrides of the settings files
+    let template = match SettingsTemplate::from_assignments(&cli_arguments.set) {
+        Ok(template) => template,
+        Err(e) => {
+            println!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
 
     if cli_arguments.export_default_settings {
         println!(
@@ -17,10 +75,31 @@ fn main() {
         );
         let saturation = match cli_arguments.export_saturation.to_lowercase().as_str() {
             "exponential" => Saturation::Exponential(1.0),
+            "cubic" => Saturation::Cubic(1.0),
             _ => Saturation::default(),
         };
         let observer = match cli_arguments.export_observer.to_lowercase().as_str() {
             "histogram" => Observer::Histogram(observers::HistogramObserver::default()),
+            "localamplitude" => {
+                Observer::LocalAmplitude(observers::LocalAmplitudeObserver::default())
+            }
+            "energybudget" => Observer::EnergyBudget(observers::EnergyBudgetObserver::default()),
+            "transitionmatrix" => {
+                Observer::TransitionMatrix(observers::TransitionMatrixObserver::default())
+            }
+            "autocorrelation" => {
+                Observer::Autocorrelation(observers::AutocorrelationObserver::default())
+            }
+            "quantile" => Observer::Quantile(observers::QuantileObserver::default()),
+            "cycleaverage" => Observer::CycleAverage(observers::CycleAverageObserver::default()),
+            "stream" => Observer::Stream(observers::StreamObserver::default()),
+            "crossstatistics" => {
+                Observer::CrossStatistics(observers::CrossStatisticsObserver::default())
+            }
+            "fouriercoefficients" => {
+                Observer::FourierCoefficients(observers::FourierCoefficientsObserver::default())
+            }
+            "integralterms" => Observer::IntegralTerms(observers::IntegralTermsObserver::default()),
             _ => Observer::default(),
         };
         // TODO Make this selectable
@@ -33,11 +112,109 @@ fn main() {
             describing_function,
         );
         println!("Exporting to file...");
-        if let Err(e) = settings.export(&PathBuf::from(&cli_arguments.export_path)) {
+        let path = PathBuf::from(&cli_arguments.export_path);
+        let exported = if cli_arguments.export_derived {
+            settings.export_with_derived(&path)
+        } else {
+            settings.export(&path)
+        };
+        if let Err(e) = exported {
             println!("could not export the settings: {}", e);
-            return;
+            return ExitCode::FAILURE;
         }
         println!("Success!");
+    } else if cli_arguments.bench {
+        // Measure the throughput with as many simulations as threads
+        let threads = cli_arguments.threads.unwrap_or_else(num_cpus::get);
+        if let Err(e) = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+        {
+            println!("could not set the number of threads: {}", e);
+        }
+        println!(
+            "Running the standardized simulation on {} threads...",
+            threads
+        );
+        let throughput = Throughput::measure(threads);
+        println!("{}", throughput);
+
+        // Estimate the wall time of the given settings files
+        if !cli_arguments.settings_files.is_empty() {
+            let all_settings = load_jobs(
+                expand_jobs(&cli_arguments.settings_files, &template),
+                &template,
+                &mut Vec::new(),
+            );
+            for (filepath, settings) in &all_settings {
+                println!(
+                    "{}: {} estimated to take {:.0} s",
+                    filepath,
+                    settings.observer.save_info().get_group(),
+                    throughput.estimated_seconds(settings)
+                );
+            }
+            let seconds = throughput
+                .estimated_campaign_seconds(all_settings.iter().map(|(_, settings)| settings));
+            println!(
+                "All {} simulation(s) on {} threads: estimated {:.0} s ({:.1} h)",
+                all_settings.len(),
+                threads,
+                seconds,
+                seconds / 3600.0
+            );
+        }
+    } else if let Some(input) = &cli_arguments.convert {
+        // Re-export the results for use without HDF5 tooling
+        let format = match cli_arguments.convert_format.parse() {
+            Ok(format) => format,
+            Err(e) => {
+                println!("{}", e);
+                return ExitCode::FAILURE;
+            }
+        };
+        let output = PathBuf::from(&cli_arguments.convert_output);
+        match azimuthal_fdf::convert(
+            &PathBuf::from(input),
+            &cli_arguments.convert_groups,
+            &output,
+            format,
+        ) {
+            Ok(groups) => println!(
+                "Converted {} group(s) to: {} (metadata in {})",
+                groups.len(),
+                output.to_string_lossy(),
+                azimuthal_fdf::sidecar_path(&output).to_string_lossy()
+            ),
+            Err(e) => {
+                println!("could not convert {}: {}", input, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else if let Some(path) = &cli_arguments.inspect {
+        // Show what a results file contains
+        match azimuthal_fdf::inspect(&PathBuf::from(path)) {
+            Ok(tree) => println!("{}", tree),
+            Err(e) => {
+                println!("could not inspect {}: {}", path, e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else if let Some(path) = &cli_arguments.plot {
+        // Render a quick-look plot of a results group
+        return plot(
+            &PathBuf::from(path),
+            &cli_arguments.plot_group,
+            &PathBuf::from(&cli_arguments.plot_output),
+        );
+    } else if let Some(address) = &cli_arguments.serve {
+        // Run the HTTP service until the process is stopped
+        let directory = PathBuf::from(&cli_arguments.serve_directory);
+        let max_jobs = cli_arguments.threads.unwrap_or_else(num_cpus::get);
+        if let Err(e) = azimuthal_fdf::service::serve(address, &directory, max_jobs) {
+            println!("could not start the service: {}", e);
+            return ExitCode::FAILURE;
+        }
     } else if cli_arguments.example {
         // Run an example simualation
         println!("Setting up simulation...");
@@ -51,7 +228,7 @@ fn main() {
             Ok(_) => {}
             Err(e) => {
                 println!("{}\nSave conflict, aborting simulation", e);
-                return;
+                return ExitCode::FAILURE;
             }
         }
 
@@ -65,178 +242,1171 @@ fn main() {
                 "Results were succesfully saved to: {}",
                 settings.observer.save_info()
             ),
-            Err(e) => println!("Could not save: {}", e),
+            Err(e) => {
+                println!("Could not save: {}", e);
+                return ExitCode::FAILURE;
+            }
         }
-    } else if cli_arguments.experiment {
-        // Run the simulations related to the reported experiments
-        println!("Setting up simulations...");
-
-        // Different gain factors (gain = gain_factor * damping)
-        let gain_factors = [5.0, 3.75, 2.5, 1.25];
-
-        // Set up the saving
-        let path = PathBuf::from("experiment_simulation.hdf5");
-        let mut save_infos = vec![SaveInfo::default(); 4];
-        for (ind, gain_factor) in gain_factors.into_iter().enumerate() {
-            let group = format!("gain_factor_{}", gain_factor);
-            save_infos[ind] = SaveInfo::new(&path, &group);
+    } else if cli_arguments.list_presets {
+        for preset in PRESETS.iter() {
+            println!("{}", preset);
         }
+    } else if cli_arguments.experiment || !cli_arguments.preset.is_empty() {
+        // Run the simulations related to the reported experiments, or the given presets
+        println!("Setting up simulations...");
+        let presets: Vec<&Preset> = if cli_arguments.experiment {
+            Preset::of_publication(PAPER_2023).collect()
+        } else {
+            match cli_arguments
+                .preset
+                .iter()
+                .map(|name| Preset::find(name))
+                .collect()
+            {
+                Ok(presets) => presets,
+                Err(e) => {
+                    println!("{}", e);
+                    return ExitCode::FAILURE;
+                }
+            }
+        };
 
         // Set up how many threads to use for the computation
-        let num_threads = build_rayon_pool(gain_factors.len());
-
-        // Get the reference case damping
-        let damping = Settings::default().parameters.damping;
+        let num_threads = build_rayon_pool(presets.len());
 
         println!("Simulation started on {} threads...", num_threads);
         let start_time = SystemTime::now();
-        let save_data: Vec<Option<SaveData>> = gain_factors
+        let runs: Vec<JobRun> = presets
             .into_par_iter()
-            .zip(save_infos)
-            .map(|(gain_factor, save_info)| {
+            .map(|preset| {
                 // Need to create this inside the parallel iterator
                 // for the RNG initialization to work properly
-                let mut settings = Settings::default();
+                run_job(preset.name.to_owned(), preset.settings(), &cancellation)
+            })
+            .collect();
 
-                // Set the gain
-                settings.parameters.gain = gain_factor * damping;
-                settings.parameters.noise = 0.06;
+        // Save the data outside of the parallel for-loop
+        let reports = save(runs, &retry_policy, start_time);
+        print_status_table(&reports);
+        return batch_exit_code(&reports);
+    } else if let Some(parameter) = &cli_arguments.sensitivity {
+        // Calculate the sensitivity of the output statistics for each settings file
+        let sensitivity = match parameter
+            .parse()
+            .and_then(|parameter| Sensitivity::new(parameter, cli_arguments.sensitivity_step))
+        {
+            Ok(sensitivity) => sensitivity,
+            Err(e) => {
+                println!("{}", e);
+                return ExitCode::FAILURE;
+            }
+        };
 
-                // Set the time step
-                let new_timestep = settings.parameters.get_timestep() / 2.0;
-                if let Err(e) = settings.parameters.set_timestep(new_timestep) {
-                    println!("{}", e);
+        let (groups, mut succeeded) =
+            load_valid_groups(&cli_arguments.settings_files, &template, |_| None);
+        for group in groups {
+            match sensitivity.compute(&group.settings) {
+                Ok(result) => println!("{}: {}", group.filepath, result),
+                Err(e) => {
+                    println!("{}: {}", group.filepath, e);
+                    succeeded = false;
                 }
+            }
+        }
+        return exit_code(succeeded);
+    } else if cli_arguments.convergence {
+        // Check the convergence of the time integration of each settings file
+        let convergence = match ConvergenceTest::new(
+            cli_arguments.convergence_levels,
+            cli_arguments.convergence_samples,
+        ) {
+            Ok(convergence) => convergence,
+            Err(e) => {
+                println!("{}", e);
+                return ExitCode::FAILURE;
+            }
+        };
 
-                // Set the saving information
-                settings.observer.set_save_info(&save_info);
-
-                // Set the length of the simulation
-                settings.parameters.set_number_of_cycles(170_000.0).unwrap();
+        let (groups, mut succeeded) =
+            load_valid_groups(&cli_arguments.settings_files, &template, |filepath| {
+                analysis_group(&cli_arguments.convergence_path, "convergence", filepath)
+            });
+        for group in groups {
+            match convergence.compute(&group.settings) {
+                Ok(result) => {
+                    println!("{}:\n{}", group.filepath, result);
+                    succeeded &= group.save(|save_info, settings| result.save(save_info, settings));
+                }
+                Err(e) => {
+                    println!("{}: {}", group.filepath, e);
+                    succeeded = false;
+                }
+            }
+        }
+        return exit_code(succeeded);
+    } else if cli_arguments.verify {
+        // Check the invariants of the model around each settings file (or the default settings)
+        let verification = match Verification::new(cli_arguments.verify_cases, rand::random()) {
+            Ok(verification) => verification,
+            Err(e) => {
+                println!("{}", e);
+                return ExitCode::FAILURE;
+            }
+        };
 
-                let df = hrr_integral::ConventionalFDF::new();
-                let describing_function = hrr_integral::DescribingFunction::Conventional(df);
-                settings.describing_function = describing_function;
+        let (mut groups, mut succeeded) =
+            load_valid_groups(&cli_arguments.settings_files, &template, |_| None);
+        if cli_arguments.settings_files.is_empty() {
+            groups.push(ValidGroup {
+                filepath: String::from("default settings"),
+                settings: Settings::default(),
+                save_info: None,
+            });
+        }
 
-                run_settings(settings)
-            })
-            .collect();
+        for group in &groups {
+            let report = verification.run(&group.settings);
+            println!("{}:\n{}", group.filepath, report);
+            succeeded &= report.passed();
+        }
+        return exit_code(succeeded);
+    } else if cli_arguments.reflection {
+        // Compare each settings file (or the default settings) with its mirror image
+        let reflection = match ReflectionTest::new(cli_arguments.reflection_tolerance) {
+            Ok(reflection) => reflection,
+            Err(e) => {
+                println!("{}", e);
+                return ExitCode::FAILURE;
+            }
+        };
 
-        // Save the data outside of the parallel for-loop
-        save(save_data, start_time);
-    } else if !cli_arguments.settings_files.is_empty() {
-        // Run the simulations related to the reported experiments
-        println!("Loading the settings files...");
+        let (mut groups, mut succeeded) =
+            load_valid_groups(&cli_arguments.settings_files, &template, |_| None);
+        if cli_arguments.settings_files.is_empty() {
+            groups.push(ValidGroup {
+                filepath: String::from("default settings"),
+                settings: Settings::default(),
+                save_info: None,
+            });
+        }
 
-        // Load the settings from file
-        if cli_arguments.settings_files.len() == 1 {
-            // Keep it a bit general to allow for disabling rayon
-            let mut all_settings: Vec<Settings> = Vec::new();
-            for filepath in cli_arguments.settings_files {
-                println!("Loading settings from: {}", filepath);
-                match Settings::from_file(&filepath) {
-                    Ok(settings) => all_settings.push(settings),
-                    Err(e) => println!(
-                        "{}\ncould not load settings {}, skipping simulation",
-                        e, filepath
-                    ),
+        for group in &groups {
+            match reflection.compute(&group.settings) {
+                Ok(result) => {
+                    println!("{}: {}", group.filepath, result);
+                    succeeded &= result.consistent();
+                }
+                Err(e) => {
+                    println!("{}: {}", group.filepath, e);
+                    succeeded = false;
                 }
             }
+        }
+        return exit_code(succeeded);
+    } else if cli_arguments.paired {
+        // Compare two settings files with common random numbers
+        if cli_arguments.settings_files.len() != 2 {
+            println!("the '--paired' option requires exactly two '--settings-files'");
+            return ExitCode::FAILURE;
+        }
 
-            for settings in all_settings {
-                let start_time = SystemTime::now();
+        let (groups, all_valid) =
+            load_valid_groups(&cli_arguments.settings_files, &template, |_| None);
+        if !all_valid {
+            return ExitCode::FAILURE;
+        }
+        let all_settings: Vec<Settings> = groups.into_iter().map(|group| group.settings).collect();
+
+        let save_info = SaveInfo::new(&PathBuf::from(&cli_arguments.paired_path), "paired");
+        if let Err(e) = save_info.is_valid() {
+            println!("{}\nSave conflict, aborting comparison", e);
+            return ExitCode::FAILURE;
+        }
 
-                if let Some(save_data) = run_settings(settings) {
-                    if let Err(e) = save_data.save() {
+        match PairedComparison::run(&all_settings[0], &all_settings[1]) {
+            Ok(comparison) => {
+                println!("{}", comparison);
+                match comparison.save(&save_info, &all_settings[0]) {
+                    Ok(_) => println!("Results were successfully saved to: {}", save_info),
+                    Err(e) => {
                         println!("could not save: {}", e);
+                        return ExitCode::FAILURE;
                     }
+                }
+            }
+            Err(e) => {
+                println!("{}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else if let Some(parameter) = &cli_arguments.hysteresis {
+        // Ramp the parameter up and down for each settings file and detect the bistable regions
+        let [lower, upper] = cli_arguments.hysteresis_range[..] else {
+            println!("the '--hysteresis' option requires '--hysteresis-range LOWER UPPER'");
+            return ExitCode::FAILURE;
+        };
+        let ramp = match parameter
+            .parse()
+            .map_err(HysteresisError::from)
+            .and_then(|parameter| ParameterRamp::new(parameter, lower, upper))
+        {
+            Ok(ramp) => ramp,
+            Err(e) => {
+                println!("{}", e);
+                return ExitCode::FAILURE;
+            }
+        };
 
-                    if let Ok(elapsed_time) = save_data.finish_time.duration_since(start_time) {
-                        let save_info = save_data.get_save_info();
-                        println!(
-                            "{}: {} took {} seconds",
-                            save_info.get_path().to_string_lossy(),
-                            save_info.get_group(),
-                            elapsed_time.as_secs()
-                        );
-                    }
+        let (groups, mut succeeded) =
+            load_valid_groups(&cli_arguments.settings_files, &template, |filepath| {
+                analysis_group(&cli_arguments.hysteresis_path, "hysteresis", filepath)
+            });
+        for group in groups {
+            match HysteresisLoop::from_simulation(
+                group.settings.clone(),
+                ramp,
+                cli_arguments.hysteresis_bins,
+            ) {
+                Ok(hysteresis) => {
+                    println!("{}: {}", group.filepath, hysteresis);
+                    succeeded &=
+                        group.save(|save_info, settings| hysteresis.save(save_info, settings));
+                }
+                Err(e) => {
+                    println!("{}: {}", group.filepath, e);
+                    succeeded = false;
+                }
+            }
+        }
+        return exit_code(succeeded);
+    } else if cli_arguments.first_passage {
+        // Estimate the mean first-passage times between the spin states of each settings file
+        let (groups, mut succeeded) =
+            load_valid_groups(&cli_arguments.settings_files, &template, |filepath| {
+                analysis_group(&cli_arguments.first_passage_path, "first_passage", filepath)
+            });
+        for group in groups {
+            // Reuse the seed of the simulation (if any) for the bootstrap resampling
+            let seed = group.settings.seed.unwrap_or_else(rand::random);
+            let passages = FirstPassageTimes::from_simulation(group.settings.clone());
+            println!("{}:\n{}", group.filepath, passages);
+            succeeded &= group.save(|save_info, settings| passages.save(save_info, settings, seed));
+        }
+        return exit_code(succeeded);
+    } else if cli_arguments.drift_field {
+        // Evaluate the deterministic drift field of each settings file
+        let grid = DriftFieldGrid {
+            amplitude_limit: cli_arguments.drift_field_amplitude_limit,
+            ..Default::default()
+        };
+
+        let (groups, mut succeeded) =
+            load_valid_groups(&cli_arguments.settings_files, &template, |filepath| {
+                analysis_group(&cli_arguments.drift_field_path, "drift_field", filepath)
+            });
+        for group in groups {
+            match DriftField::compute(&group.settings, &grid) {
+                Ok(field) => {
+                    succeeded &= group.save(|save_info, settings| field.save(save_info, settings));
+                }
+                Err(e) => {
+                    println!("{}: {}", group.filepath, e);
+                    succeeded = false;
+                }
+            }
+        }
+        return exit_code(succeeded);
+    } else if cli_arguments.potential {
+        // Compute the effective potentials of each settings file
+        let grid = PotentialGrid {
+            amplitude_limit: cli_arguments.potential_amplitude_limit,
+            ..Default::default()
+        };
+
+        let (groups, mut succeeded) =
+            load_valid_groups(&cli_arguments.settings_files, &template, |filepath| {
+                analysis_group(&cli_arguments.potential_path, "potential", filepath)
+            });
+        for group in groups {
+            match PotentialLandscape::compute(&group.settings, &grid) {
+                Ok(landscape) => {
+                    succeeded &=
+                        group.save(|save_info, settings| landscape.save(save_info, settings));
                 }
+                Err(e) => {
+                    println!("{}: {}", group.filepath, e);
+                    succeeded = false;
+                }
+            }
+        }
+        return exit_code(succeeded);
+    } else if let Some(uq_file) = &cli_arguments.uncertainty {
+        // Run the uncertainty quantification around each settings file
+        let uq = match UncertaintyQuantification::from_file(uq_file) {
+            Ok(uq) => uq,
+            Err(e) => {
+                println!("{}\ncould not load {}", e, uq_file);
+                return ExitCode::FAILURE;
+            }
+        };
+
+        let num_threads = build_rayon_pool(uq.samples);
+        println!(
+            "Running {} samples on {} threads...",
+            uq.samples, num_threads
+        );
+
+        let (groups, mut succeeded) =
+            load_valid_groups(&cli_arguments.settings_files, &template, |filepath| {
+                let mut save_info = uq.save_info.clone();
+                if cli_arguments.settings_files.len() > 1 {
+                    let group = format!("{}/{}", save_info.get_group(), filepath.replace('/', "_"));
+                    save_info.set_group(&group);
+                }
+                Some(save_info)
+            });
+        for group in groups {
+            match uq.run(&group.settings) {
+                Ok(result) => {
+                    println!("{}: {}", group.filepath, result);
+                    succeeded &= group.save(|save_info, settings| result.save(save_info, settings));
+                }
+                Err(e) => {
+                    println!("{}: {}", group.filepath, e);
+                    succeeded = false;
+                }
+            }
+        }
+        return exit_code(succeeded);
+    } else if !cli_arguments.settings_files.is_empty() {
+        // Run the simulations related to the reported experiments
+        status!("Loading the settings files...");
+
+        // Expand the mode orders and repetitions of each settings file into separate jobs
+        let jobs = expand_jobs(&cli_arguments.settings_files, &template);
+
+        // Only run a slice of the jobs when running as part of an array job
+        let jobs = match job_slice(cli_arguments.job_index, cli_arguments.job_count) {
+            Ok(Some((index, count))) => {
+                let selected = select_jobs(jobs, index, count);
+                status!(
+                    "Running {} job(s) as array job {}/{}",
+                    selected.len(),
+                    index,
+                    count
+                );
+                selected
+            }
+            Ok(None) => jobs,
+            Err(e) => {
+                status!("{}", e);
+                return ExitCode::FAILURE;
+            }
+        };
+
+        // Load the settings from file
+        let memory_budget = cli_arguments.memory_budget;
+        if let Some(threads) = cli_arguments.threads {
+            if let Err(e) = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build_global()
+            {
+                status!("could not set the number of threads: {}", e);
             }
+        }
+        // The reports of the jobs that were not run, followed by those of the jobs that were
+        let mut reports = Vec::new();
+        if cli_arguments.monitor {
+            run_monitored(
+                jobs,
+                &template,
+                memory_budget,
+                &retry_policy,
+                &cancellation,
+                &mut reports,
+            );
+        } else if jobs.len() <= 1 {
+            // Keep it a bit general to allow for disabling rayon
+            let all_settings = load_jobs(jobs, &template, &mut reports);
+            let all_settings = check_memory_budget(all_settings, memory_budget, &mut reports);
+
+            let start_time = SystemTime::now();
+            let runs: Vec<JobRun> = all_settings
+                .into_iter()
+                .map(|(filepath, settings)| run_job(filepath, settings, &cancellation))
+                .collect();
+
+            reports.extend(save(runs, &retry_policy, start_time));
         } else {
+            // Check all the save targets and memory estimates before starting any of the simulations
+            let all_settings = load_jobs(jobs, &template, &mut reports);
+            let all_settings = check_save_conflicts(all_settings, &mut reports);
+            let all_settings = check_memory_budget(all_settings, memory_budget, &mut reports);
+
             let start_time = SystemTime::now();
-            let save_data: Vec<Option<SaveData>> = cli_arguments
-                .settings_files
-                .into_par_iter()
-                .map(|filepath| {
-                    println!("Loading settings from: {}", filepath);
-
-                    match Settings::from_file(&filepath) {
-                        Ok(settings) => run_settings(settings),
-                        Err(e) => {
-                            println!(
-                                "{}\nCould not load {}, the simulation will be skipped",
-                                e, filepath
-                            );
-                            None
-                        }
-                    }
-                })
+            let runs: Vec<JobRun> = longest_first(all_settings)
+                .into_iter()
+                .par_bridge()
+                .map(|(filepath, settings)| run_job(filepath, settings, &cancellation))
                 .collect();
 
             // Save the data outside of the parallel for-loop
-            save(save_data, start_time);
+            reports.extend(save(runs, &retry_policy, start_time));
+        }
+
+        print_status_table(&reports);
+        if let Some(path) = &cli_arguments.summary_json {
+            if let Err(e) = write_summary(path, &reports) {
+                status!("could not write the summary: {}", e);
+                return ExitCode::FAILURE;
+            }
         }
+
+        return batch_exit_code(&reports);
     } else {
         // If no arguments are provided print the help information
         let mut cmd = CliParser::command();
         cmd.print_help().unwrap_or_default()
     }
+
+    ExitCode::SUCCESS
 }
 
 /// Shorthand for checking whether there is a save conflict and run the simulation.
+///
+/// Returns the reason if the simulation is skipped.
 #[inline]
-fn run_settings(mut settings: Settings) -> Option<SaveData> {
-    match settings.observer.save_info().is_valid() {
-        Ok(_) => {
-            println!("Results will be saved to {}", settings.observer.save_info())
+fn run_settings(
+    settings: Settings,
+    cancellation: &Option<CancellationToken>,
+) -> Result<SaveData, String> {
+    // Clean up after saves interrupted by earlier crashes
+    match settings.observer.save_info().remove_stale_temporary_files() {
+        Ok(removed) => {
+            for path in removed {
+                status!("Removed stale temporary file: {}", path.to_string_lossy());
+            }
+        }
+        Err(e) => status!("could not check for stale temporary files: {}", e),
+    }
+
+    match settings.observer.valid_path() {
+        Ok(_) => status!("Starting simulation\n{}", settings.summary()),
+        Err(e) => {
+            status! {"{}\nSave conflict, skipping simulation {}", e, settings.observer.save_info().get_group()}
+            return Err(format!("save conflict: {}", e));
         }
+    }
+
+    // Continue the saved time series when appending to an existing group
+    let group = settings.observer.save_info().get_group().to_owned();
+    let mut settings = match settings.resumed() {
+        Ok(settings) => settings,
         Err(e) => {
-            println! {"{}\nSave conflict, skipping simulation {}", e, settings.observer.save_info().get_group()}
-            return None;
+            status!("{}\ncould not resume simulation {}, skipping it", e, group);
+            return Err(format!("could not resume: {}", e));
+        }
+    };
+
+    // Do not start new simulations after the wall-clock budget is exceeded
+    if let Some(cancellation) = cancellation {
+        if cancellation.is_cancelled() {
+            status!(
+                "Wall-time budget exceeded, skipping simulation {}",
+                settings.observer.save_info().get_group()
+            );
+            return Err("wall-time budget exceeded".to_owned());
         }
     }
+    settings.set_cancellation(cancellation.clone());
 
+    let run_start = SystemTime::now();
     settings.run();
 
     let mut save_data = SaveData::from(settings);
     save_data.finish_time = SystemTime::now();
+    if let Ok(run_time) = save_data.finish_time.duration_since(run_start) {
+        save_data.set_run_time(run_time.as_secs_f64());
+    }
+
+    Ok(save_data)
+}
+
+/// Run the simulation of the job `filepath`, keeping where its results are saved for the reports.
+fn run_job(
+    filepath: String,
+    settings: Settings,
+    cancellation: &Option<CancellationToken>,
+) -> JobRun {
+    let target = settings.observer.save_info().to_string();
+    let result = run_settings(settings, cancellation);
+
+    JobRun {
+        filepath,
+        target,
+        result,
+    }
+}
+
+/// Load the settings of the jobs, skipping (and reporting) the files that cannot be loaded.
+fn load_jobs(
+    jobs: Vec<Job>,
+    template: &SettingsTemplate,
+    reports: &mut Vec<JobReport>,
+) -> Vec<(String, Settings)> {
+    let mut all_settings = Vec::new();
+    for job in jobs {
+        let filepath = job.filepath;
+        status!("Loading settings from: {}", filepath);
+        let settings = match job.mode_order {
+            Some(mode_order) => {
+                Settings::from_template_with_mode_order(&filepath, template, mode_order)
+            }
+            None => Settings::from_template(&filepath, template),
+        };
+        match settings {
+            Ok(mut settings) => {
+                if let Some(index) = job.repetition {
+                    settings.set_repetition(index);
+                }
+                all_settings.push((filepath, settings));
+            }
+            Err(e) => {
+                status!(
+                    "{}\ncould not load settings {}, skipping simulation",
+                    e,
+                    filepath
+                );
+                reports.push(JobReport::failed(
+                    filepath,
+                    None,
+                    JobStatus::NotLoaded,
+                    e.to_string(),
+                ));
+            }
+        }
+    }
 
-    Some(save_data)
+    all_settings
+}
+
+/// Settings file of an analysis mode, with where its results are saved (if they are).
+struct ValidGroup {
+    filepath: String,
+    settings: Settings,
+    save_info: Option<SaveInfo>,
+}
+
+impl ValidGroup {
+    /// Save the results with `save` (if they are saved), and report the outcome.
+    ///
+    /// Returns whether the results were saved.
+    fn save<E: std::fmt::Display>(
+        &self,
+        save: impl FnOnce(&SaveInfo, &Settings) -> Result<(), E>,
+    ) -> bool {
+        let Some(save_info) = &self.save_info else {
+            return true;
+        };
+        match save(save_info, &self.settings) {
+            Ok(_) => {
+                println!("Results were successfully saved to: {}", save_info);
+                true
+            }
+            Err(e) => {
+                println!("could not save: {}", e);
+                false
+            }
+        }
+    }
+}
+
+/// Load the settings files of an analysis mode, skipping (and reporting) the files that
+/// cannot be loaded or whose results cannot be saved to the group given by `save_info`.
+///
+/// Returns the remaining files, and whether all the files could be used.
+fn load_valid_groups(
+    paths: &[String],
+    template: &SettingsTemplate,
+    save_info: impl Fn(&str) -> Option<SaveInfo>,
+) -> (Vec<ValidGroup>, bool) {
+    let mut groups = Vec::new();
+    for filepath in paths {
+        println!("Loading settings from: {}", filepath);
+        let settings = match Settings::from_template(filepath, template) {
+            Ok(settings) => settings,
+            Err(e) => {
+                println!("{}\ncould not load settings {}, skipping", e, filepath);
+                continue;
+            }
+        };
+
+        let save_info = save_info(filepath);
+        if let Some(Err(e)) = save_info.as_ref().map(SaveInfo::is_valid) {
+            println!("{}\nSave conflict, skipping {}", e, filepath);
+            continue;
+        }
+
+        groups.push(ValidGroup {
+            filepath: filepath.clone(),
+            settings,
+            save_info,
+        });
+    }
+
+    let all_valid = groups.len() == paths.len();
+    (groups, all_valid)
+}
+
+/// Group `<prefix>/<settings file>` of the file `path`, for the results of an analysis mode.
+fn analysis_group(path: &str, prefix: &str, filepath: &str) -> Option<SaveInfo> {
+    let group = format!("{}/{}", prefix, filepath.replace('/', "_"));
+    Some(SaveInfo::new(&PathBuf::from(path), &group))
+}
+
+/// Check the save targets of all the jobs together, and skip the conflicting jobs.
+///
+/// The conflicts are reported together before any simulation is started.
+fn check_save_conflicts(
+    all_settings: Vec<(String, Settings)>,
+    reports: &mut Vec<JobReport>,
+) -> Vec<(String, Settings)> {
+    let save_infos: Vec<SaveInfo> = all_settings
+        .iter()
+        .map(|(_, settings)| settings.observer.save_info())
+        .collect();
+    let checks = SaveInfo::check_batch(&save_infos);
+
+    let conflicts: Vec<String> = all_settings
+        .iter()
+        .zip(&checks)
+        .filter_map(|((filepath, _), check)| {
+            check
+                .as_ref()
+                .err()
+                .map(|e| format!("  {}: {}", filepath, e))
+        })
+        .collect();
+    if !conflicts.is_empty() {
+        status!(
+            "Save conflicts, skipping {} simulation(s):\n{}",
+            conflicts.len(),
+            conflicts.join("\n")
+        );
+    }
+
+    all_settings
+        .into_iter()
+        .zip(checks)
+        .filter_map(|((filepath, settings), check)| match check {
+            Ok(_) => Some((filepath, settings)),
+            Err(e) => {
+                reports.push(JobReport::failed(
+                    filepath,
+                    Some(&settings),
+                    JobStatus::Skipped,
+                    format!("save conflict: {}", e),
+                ));
+                None
+            }
+        })
+        .collect()
+}
+
+/// Skip the jobs whose logged values are estimated to exceed the memory budget (in megabytes).
+///
+/// The parallel jobs then take at most the budget times the number of
+/// threads. The skipped jobs are reported together before any simulation is
+/// started.
+fn check_memory_budget(
+    all_settings: Vec<(String, Settings)>,
+    memory_budget: Option<f64>,
+    reports: &mut Vec<JobReport>,
+) -> Vec<(String, Settings)> {
+    let budget = match memory_budget {
+        Some(megabytes) => megabytes * 1e6,
+        None => return all_settings,
+    };
+
+    let estimated_bytes =
+        |settings: &Settings| settings.observer.estimated_bytes(&settings.parameters) as f64;
+    let (within, exceeding): (Vec<_>, Vec<_>) = all_settings
+        .into_iter()
+        .partition(|(_, settings)| estimated_bytes(settings) <= budget);
+    if !exceeding.is_empty() {
+        let jobs: Vec<String> = exceeding
+            .iter()
+            .map(|(filepath, settings)| {
+                format!("  {}: {:.1} MB", filepath, estimated_bytes(settings) / 1e6)
+            })
+            .collect();
+        status!(
+            "Memory budget of {:.1} MB per job ({:.1} MB for {} parallel jobs) exceeded, skipping {} simulation(s):\n{}",
+            budget / 1e6,
+            budget / 1e6 * rayon::current_num_threads() as f64,
+            rayon::current_num_threads(),
+            jobs.len(),
+            jobs.join("\n")
+        );
+    }
+    for (filepath, settings) in exceeding {
+        let message = format!(
+            "estimated {:.1} MB exceed the memory budget",
+            estimated_bytes(&settings) / 1e6
+        );
+        reports.push(JobReport::failed(
+            filepath,
+            Some(&settings),
+            JobStatus::Skipped,
+            message,
+        ));
+    }
+
+    within
+}
+
+/// Order the jobs by their estimated cost, longest first.
+///
+/// Run through [`ParallelBridge::par_bridge`], each thread takes the next
+/// job in this order when it is done with its current one, such that the
+/// longest jobs are not left to the end of the batch.
+fn longest_first(mut all_settings: Vec<(String, Settings)>) -> Vec<(String, Settings)> {
+    all_settings.sort_by(|(_, a), (_, b)| b.estimated_cost().total_cmp(&a.estimated_cost()));
+
+    all_settings
+}
+
+/// Run the jobs in parallel while showing the terminal dashboard of their progress.
+#[cfg(feature = "monitor")]
+fn run_monitored(
+    jobs: Vec<Job>,
+    template: &SettingsTemplate,
+    memory_budget: Option<f64>,
+    retry_policy: &RetryPolicy,
+    cancellation: &Option<CancellationToken>,
+    reports: &mut Vec<JobReport>,
+) {
+    use azimuthal_fdf::monitor::{self, JobMonitor};
+
+    let mut all_settings = Vec::new();
+    let mut monitors = Vec::new();
+    let jobs = check_save_conflicts(load_jobs(jobs, template, reports), reports);
+    let jobs = check_memory_budget(jobs, memory_budget, reports);
+    for (filepath, mut settings) in longest_first(jobs) {
+        let name = format!(
+            "{}: {}",
+            filepath,
+            settings.observer.save_info().get_group()
+        );
+        monitors.push(JobMonitor::attach(&name, &mut settings));
+        all_settings.push((filepath, settings));
+    }
+
+    let start_time = SystemTime::now();
+    let cancellation = cancellation.clone();
+    let simulations = std::thread::spawn(move || {
+        all_settings
+            .into_iter()
+            .par_bridge()
+            .map(|(filepath, settings)| run_job(filepath, settings, &cancellation))
+            .collect::<Vec<JobRun>>()
+    });
+
+    if let Err(e) = monitor::run(&monitors, || simulations.is_finished()) {
+        status!("could not show the monitor: {}", e);
+    }
+
+    // Save the data after all the simulations have finished
+    match simulations.join() {
+        Ok(runs) => reports.extend(save(runs, retry_policy, start_time)),
+        Err(_) => status!("the simulations did not finish, nothing was saved"),
+    }
+}
+
+#[cfg(not(feature = "monitor"))]
+fn run_monitored(
+    jobs: Vec<Job>,
+    _template: &SettingsTemplate,
+    _memory_budget: Option<f64>,
+    _retry_policy: &RetryPolicy,
+    _cancellation: &Option<CancellationToken>,
+    reports: &mut Vec<JobReport>,
+) {
+    let message = "the '--monitor' option requires compiling with the 'monitor' feature";
+    status!("{}", message);
+    reports.extend(
+        jobs.into_iter()
+            .map(|job| JobReport::failed(job.filepath, None, JobStatus::Skipped, message)),
+    );
+}
+
+/// Plot the group `group` of the results file `path` to the image `output`.
+#[cfg(feature = "plot")]
+fn plot(path: &Path, group: &str, output: &Path) -> ExitCode {
+    match azimuthal_fdf::plot(path, group, output) {
+        Ok(_) => {
+            println!("Plot was saved to: {}", output.to_string_lossy());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            println!("could not plot {}: {}", path.to_string_lossy(), e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(not(feature = "plot"))]
+fn plot(_path: &Path, _group: &str, _output: &Path) -> ExitCode {
+    println!("the '--plot' option requires compiling with the 'plot' feature");
+    ExitCode::FAILURE
 }
 
 /// Shorthand for saving the [`SaveData`] from the different simulations
+///
+/// The failed saves are retried by `retry_policy`, and the results rescued
+/// to a local file if they cannot be saved. Returns the reports of the
+/// simulations, with their summaries (see [`SaveData::summary_json`]) if
+/// they were run.
 #[inline]
-fn save(save_data: Vec<Option<SaveData>>, start_time: SystemTime) {
-    for sd in save_data {
-        if let Some(sd) = sd {
-            match sd.save() {
-                Ok(_) => println!("Results were successfully saved to: {}", sd.get_save_info()),
-                Err(e) => println!("could not save: {}", e),
-            };
+fn save(runs: Vec<JobRun>, retry_policy: &RetryPolicy, start_time: SystemTime) -> Vec<JobReport> {
+    let mut reports = Vec::new();
+    for run in runs {
+        let mut sd = match run.result {
+            Ok(sd) => sd,
+            Err(message) => {
+                reports.push(JobReport {
+                    filepath: run.filepath,
+                    target: Some(run.target),
+                    status: JobStatus::Skipped,
+                    message: Some(message),
+                    summary: None,
+                });
+                continue;
+            }
+        };
 
-            if let Ok(elapsed_time) = sd.finish_time.duration_since(start_time) {
-                let si = sd.get_save_info();
-                println!(
-                    "{}: {} took {} seconds",
-                    si.get_path().to_string_lossy(),
-                    si.get_group(),
-                    elapsed_time.as_secs()
+        let (status, message) = match sd.save_or_rescue(retry_policy) {
+            Ok(SaveOutcome::Rescued { path, error }) => {
+                let path = path.to_string_lossy();
+                status!(
+                    "could not save: {}\nResults were rescued to: {}",
+                    error,
+                    path
                 );
+                let message = format!("{}, rescued to {}", error, path);
+                (JobStatus::Rescued, Some(message))
+            }
+            Ok(SaveOutcome::Saved { failed_attempts }) => {
+                if failed_attempts > 0 {
+                    status!(
+                        "Saving succeeded after {} failed attempt(s)",
+                        failed_attempts
+                    );
+                }
+                if let Some(time) = sd.get_diverged_at() {
+                    status!("Partial results were saved to: {}", sd.get_save_info());
+                    let message = format!("diverged to a non-finite state at t = {}", time);
+                    (JobStatus::Diverged, Some(message))
+                } else if sd.is_partial() {
+                    status!("Partial results were saved to: {}", sd.get_save_info());
+                    (JobStatus::Partial, None)
+                } else {
+                    status!("Results were successfully saved to: {}", sd.get_save_info());
+                    (JobStatus::Completed, None)
+                }
+            }
+            Err(e) => {
+                status!("could not save: {}", e);
+                (JobStatus::NotSaved, Some(e.to_string()))
+            }
+        };
+        reports.push(JobReport {
+            filepath: run.filepath,
+            target: Some(run.target),
+            status,
+            message,
+            summary: Some(sd.summary_json()),
+        });
+
+        if let Some(exponents) = sd.get_lyapunov_exponents() {
+            status!(
+                "{}: mean finite-time Lyapunov exponent = {}",
+                sd.get_save_info(),
+                exponents.mean()
+            );
+            if let Some(units) = sd.get_parameters().get_physical_units() {
+                status!("    ({} 1/s)", units.per_second(exponents.mean()));
             }
         }
+
+        if let Ok(elapsed_time) = sd.finish_time.duration_since(start_time) {
+            let si = sd.get_save_info();
+            status!(
+                "{}: {} took {} seconds",
+                si.get_path().to_string_lossy(),
+                si.get_group(),
+                elapsed_time.as_secs()
+            );
+        }
+    }
+
+    reports
+}
+
+/// Final status of a job.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum JobStatus {
+    /// Run to the end, with the results saved.
+    Completed,
+    /// Stopped early by the wall-time budget, with the partial results saved.
+    Partial,
+    /// Stopped when the mode diverged to a non-finite state, with the results until then saved.
+    Diverged,
+    /// Run, but the results could only be saved to a local temporary file.
+    Rescued,
+    /// Run, but the results could not be saved.
+    NotSaved,
+    /// Not run, e.g. because of a save conflict or the memory or wall-time budget.
+    Skipped,
+    /// The settings could not be loaded.
+    NotLoaded,
+}
+
+impl JobStatus {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Completed => "completed",
+            Self::Partial => "partial",
+            Self::Diverged => "diverged",
+            Self::Rescued => "rescued",
+            Self::NotSaved => "not saved",
+            Self::Skipped => "skipped",
+            Self::NotLoaded => "not loaded",
+        }
+    }
+}
+
+/// Outcome of a job, for the status table, the summary, and the exit code.
+struct JobReport {
+    filepath: String,
+    /// Where the results are saved, if the settings were loaded.
+    target: Option<String>,
+    status: JobStatus,
+    /// Why the job failed, if it did.
+    message: Option<String>,
+    /// Summary of the simulation, if it was run.
+    summary: Option<serde_json::Value>,
+}
+
+impl JobReport {
+    /// Report of a job of `filepath` (with the `settings`, if loaded) which was not run.
+    fn failed(
+        filepath: String,
+        settings: Option<&Settings>,
+        status: JobStatus,
+        message: impl ToString,
+    ) -> Self {
+        Self {
+            filepath,
+            target: settings.map(|settings| settings.observer.save_info().to_string()),
+            status,
+            message: Some(message.to_string()),
+            summary: None,
+        }
+    }
+
+    /// The report as JSON, with the summary of the simulation if it was run.
+    fn to_json(&self) -> serde_json::Value {
+        let mut json = self
+            .summary
+            .clone()
+            .unwrap_or_else(|| serde_json::json!({}));
+        json["settings_file"] = self.filepath.clone().into();
+        json["status"] = self.status.name().into();
+        if let Some(message) = &self.message {
+            json["message"] = message.clone().into();
+        }
+
+        json
+    }
+}
+
+/// A simulation of a job, run or skipped, before its results are saved.
+struct JobRun {
+    filepath: String,
+    /// Where the results are saved.
+    target: String,
+    /// The results, or why the simulation was skipped.
+    result: Result<SaveData, String>,
+}
+
+/// Print the final status of each of the jobs.
+fn print_status_table(reports: &[JobReport]) {
+    let completed = reports
+        .iter()
+        .filter(|report| report.status == JobStatus::Completed)
+        .count();
+    status!(
+        "Status of the jobs ({} of {} completed):",
+        completed,
+        reports.len()
+    );
+    for report in reports {
+        let mut line = format!("  {:<10}  {}", report.status.name(), report.filepath);
+        if let Some(target) = &report.target {
+            line.push_str(&format!(" ({})", target));
+        }
+        if let Some(message) = &report.message {
+            line.push_str(&format!(": {}", message));
+        }
+        status!("{}", line);
     }
 }
 
+/// Exit code of a batch of jobs.
+///
+/// Zero if all the jobs completed, [`ExitCode::FAILURE`] (one) if none of
+/// them did, and [`EXIT_PARTIAL_FAILURE`] otherwise. Interrupted and diverged
+/// jobs, with partial results, do not count as completed.
+fn batch_exit_code(reports: &[JobReport]) -> ExitCode {
+    let completed = reports
+        .iter()
+        .filter(|report| report.status == JobStatus::Completed)
+        .count();
+    if completed == reports.len() {
+        ExitCode::SUCCESS
+    } else if completed == 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::from(EXIT_PARTIAL_FAILURE)
+    }
+}
+
+/// Exit code of an option other than running simulations, which fails on any error.
+fn exit_code(succeeded: bool) -> ExitCode {
+    if succeeded {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Write the reports of the jobs as JSON to the file `path`, or the standard output for `-`.
+fn write_summary(path: &str, reports: &[JobReport]) -> std::io::Result<()> {
+    let simulations: Vec<serde_json::Value> = reports.iter().map(JobReport::to_json).collect();
+    let summary = serde_json::json!({ "simulations": simulations });
+    if path == STDOUT_PATH {
+        let mut stdout = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut stdout, &summary)?;
+        writeln!(stdout)
+    } else {
+        serde_json::to_writer_pretty(std::fs::File::create(path)?, &summary)?;
+        Ok(())
+    }
+}
+
+/// A single simulation of a settings file.
+struct Job {
+    filepath: String,
+    /// One of the mode orders listed in the file, if it lists several.
+    mode_order: Option<u32>,
+    /// Index of the realization, if the file requests several.
+    repetition: Option<usize>,
+}
+
+/// List the jobs of the settings files, with one job per mode order and repetition.
+///
+/// Files requesting a single realization of a single mode order are listed
+/// without a mode order and repetition index. Files that cannot be loaded
+/// are kept as a single job, such that the error is reported when the job
+/// is run.
+fn expand_jobs(settings_files: &[String], template: &SettingsTemplate) -> Vec<Job> {
+    let mut jobs = Vec::new();
+    for filepath in settings_files {
+        // Only read the mode orders and number of repetitions, to avoid allocating the observers
+        let json = template.load(filepath).unwrap_or_default();
+        let repetitions = json["repetitions"].as_u64().unwrap_or(1);
+        let mode_orders: Vec<Option<u32>> = match json["parameters"]["mode_order"]
+            .as_array()
+            .filter(|orders| !orders.is_empty())
+        {
+            Some(orders) => orders
+                .iter()
+                .map(|order| order.as_u64().map(|order| order as u32))
+                .collect(),
+            None => vec![None],
+        };
+
+        for mode_order in mode_orders {
+            let job = |repetition| Job {
+                filepath: filepath.clone(),
+                mode_order,
+                repetition,
+            };
+            if repetitions > 1 {
+                jobs.extend((0..repetitions as usize).map(|index| job(Some(index))));
+            } else {
+                jobs.push(job(None));
+            }
+        }
+    }
+
+    jobs
+}
+
+/// Find which slice of the jobs to run, as `(job_index, job_count)`.
+///
+/// Uses the `--job-index` and `--job-count` options if given, otherwise the
+/// SLURM array job environment variables (`SLURM_ARRAY_TASK_ID`,
+/// `SLURM_ARRAY_TASK_MIN`, and `SLURM_ARRAY_TASK_COUNT`). Returns `None`
+/// when all the jobs should be run.
+fn job_slice(
+    job_index: Option<usize>,
+    job_count: Option<usize>,
+) -> Result<Option<(usize, usize)>, String> {
+    let read_env = |name: &str| -> Option<usize> {
+        std::env::var(name)
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+    };
+
+    let (index, count) = match (job_index, job_count) {
+        (Some(index), Some(count)) => (index, count),
+        (None, None) => {
+            match (
+                read_env("SLURM_ARRAY_TASK_ID"),
+                read_env("SLURM_ARRAY_TASK_COUNT"),
+            ) {
+                (Some(id), Some(count)) => {
+                    // The task IDs do not necessarily start at zero
+                    let min = read_env("SLURM_ARRAY_TASK_MIN").unwrap_or(0);
+                    (id.saturating_sub(min), count)
+                }
+                _ => return Ok(None),
+            }
+        }
+        _ => return Err("'--job-index' and '--job-count' must be used together".to_owned()),
+    };
+
+    if index >= count {
+        return Err(format!(
+            "the job index {} has to be smaller than the job count {}",
+            index, count
+        ));
+    }
+
+    Ok(Some((index, count)))
+}
+
+/// Select the jobs of array job `index` out of `count` (every `count`-th job).
+fn select_jobs<T>(jobs: Vec<T>, index: usize, count: usize) -> Vec<T> {
+    jobs.into_iter()
+        .enumerate()
+        .filter(|(ind, _)| ind % count == index)
+        .map(|(_, job)| job)
+        .collect()
+}
+
 #[inline]
 fn build_rayon_pool(number_of_jobs: usize) -> usize {
     let max_threads = num_cpus::get_physical() - 1;
@@ -261,6 +1431,14 @@ pub struct CliParser {
     #[arg(long, action)]
     experiment: bool,
 
+    /// Run the simulations of the given preset(s), see '--list-presets'
+    #[arg(long, num_args(1..))]
+    preset: Vec<String>,
+
+    /// List the available presets with their versions
+    #[arg(long, action)]
+    list_presets: bool,
+
     /// Export the default settings to JSON file.
     #[arg(short, long, action)]
     export_default_settings: bool,
@@ -279,7 +1457,237 @@ pub struct CliParser {
     #[arg(long, default_value_t = String::from("default_settings.json"))]
     export_path: String,
 
-    /// Path to the settings file(s) to run simulations for
+    /// Include the read-only 'derived' section (the number of time steps,
+    /// burner locations, ...) with the '--export-default-settings' option
+    #[arg(long, action)]
+    export_derived: bool,
+
+    /// Path to the settings file(s) to run simulations for ('-' reads the
+    /// settings from the standard input)
     #[arg(short, long, num_args(0..))]
     settings_files: Vec<String>,
+
+    /// Write a JSON summary of the simulations of the '--settings-files' to the
+    /// given file ('-' writes it to the standard output, and the messages to the
+    /// standard error)
+    #[arg(long, value_name = "PATH")]
+    summary_json: Option<String>,
+
+    /// Set the variable KEY of the '${KEY}' placeholders in the settings files,
+    /// or override the value at the dotted path KEY (e.g. 'parameters.gain=0.06')
+    #[arg(long, value_name = "KEY=VALUE", num_args(1..))]
+    set: Vec<String>,
+
+    /// Calculate the sensitivity of the mean amplitude and spinning probability
+    /// to the given parameter (damping, gain, noise, or gain_ratio_r) for the
+    /// '--settings-files' using common random numbers
+    #[arg(long)]
+    sensitivity: Option<String>,
+
+    /// Relative step size used by the '--sensitivity' option
+    #[arg(long, default_value_t = 0.01)]
+    sensitivity_step: f64,
+
+    /// Run the '--settings-files' at several time steps with the same Brownian
+    /// paths, and report the strong and weak convergence orders of the integrator
+    #[arg(long, action)]
+    convergence: bool,
+
+    /// Number of time steps tested by the '--convergence' option, the time
+    /// step of the settings halved repeatedly
+    #[arg(long, default_value_t = 4)]
+    convergence_levels: usize,
+
+    /// Number of realizations used by the '--convergence' option
+    #[arg(long, default_value_t = 20)]
+    convergence_samples: usize,
+
+    /// Set the output path for the '--convergence' option
+    #[arg(long, default_value_t = String::from("convergence.hdf5"))]
+    convergence_path: String,
+
+    /// Check the invariants of the model (the range of the nature angle, the
+    /// Fourier round trip, and the rotation invariance of the describing
+    /// function) for random inputs around the '--settings-files', or around
+    /// the default settings if none are given
+    #[arg(long, action)]
+    verify: bool,
+
+    /// Number of random inputs of each invariant checked by the '--verify' option
+    #[arg(long, default_value_t = 20)]
+    verify_cases: usize,
+
+    /// Run the '--settings-files' (or the default settings) and their mirror
+    /// images (gain ratio r -> 1/r, chi -> -chi) with mirrored noise, and
+    /// check that the results match the parity-transformed originals
+    #[arg(long, action)]
+    reflection: bool,
+
+    /// Largest deviation considered consistent by the '--reflection' option
+    #[arg(long, default_value_t = 1e-8)]
+    reflection_tolerance: f64,
+
+    /// Path to a JSON file describing the distributions of the uncertain
+    /// parameters, to run an uncertainty quantification ensemble around
+    /// each of the '--settings-files'
+    #[arg(long)]
+    uncertainty: Option<String>,
+
+    /// Run the two '--settings-files' with identical noise realizations and
+    /// report the paired differences
+    #[arg(long, action)]
+    paired: bool,
+
+    /// Set the output path for the '--paired' option
+    #[arg(long, default_value_t = String::from("paired_comparison.hdf5"))]
+    paired_path: String,
+
+    /// Ramp the given parameter (damping, gain, noise, or gain_ratio_r) up and
+    /// back down within each of the '--settings-files' simulations, and report
+    /// the amplitude branches of the up and down sweeps
+    #[arg(long, requires = "hysteresis_range")]
+    hysteresis: Option<String>,
+
+    /// Lower and upper value of the parameter ramped by the '--hysteresis' option
+    #[arg(long, num_args(2), value_names = ["LOWER", "UPPER"])]
+    hysteresis_range: Vec<f64>,
+
+    /// Number of parameter bins of the amplitude branches of the '--hysteresis' option
+    #[arg(long, default_value_t = 50)]
+    hysteresis_bins: usize,
+
+    /// Set the output path for the '--hysteresis' option
+    #[arg(long, default_value_t = String::from("hysteresis.hdf5"))]
+    hysteresis_path: String,
+
+    /// Run the '--settings-files' and estimate the mean first-passage times
+    /// between the clockwise spinning, standing, and anti-clockwise spinning states
+    #[arg(long, action)]
+    first_passage: bool,
+
+    /// Set the output path for the '--first-passage' option
+    #[arg(long, default_value_t = String::from("first_passage.hdf5"))]
+    first_passage_path: String,
+
+    /// Evaluate the deterministic drift of the amplitude and nature angle of
+    /// the '--settings-files' on a grid, for phase portraits
+    #[arg(long, action)]
+    drift_field: bool,
+
+    /// Set the output path for the '--drift-field' option
+    #[arg(long, default_value_t = String::from("drift_field.hdf5"))]
+    drift_field_path: String,
+
+    /// Largest amplitude of the grid of the '--drift-field' option
+    #[arg(long, default_value_t = 5.0)]
+    drift_field_amplitude_limit: f64,
+
+    /// Compute the effective potentials of the amplitude and nature angle of
+    /// the '--settings-files' from the drift and diffusion terms
+    #[arg(long, action)]
+    potential: bool,
+
+    /// Set the output path for the '--potential' option
+    #[arg(long, default_value_t = String::from("potential.hdf5"))]
+    potential_path: String,
+
+    /// Largest amplitude of the grid of the '--potential' option
+    #[arg(long, default_value_t = 5.0)]
+    potential_amplitude_limit: f64,
+
+    /// Wall-clock budget (in seconds) for all the simulations, after which
+    /// running simulations are stopped and saved as partial results
+    #[arg(long)]
+    wall_time: Option<f64>,
+
+    /// Memory budget (in megabytes) of the logged values of each of the
+    /// '--settings-files' simulations, where the simulations estimated to
+    /// exceed it are skipped before any simulation is started
+    #[arg(long)]
+    memory_budget: Option<f64>,
+
+    /// Number of times a failed save is retried, after which the results
+    /// are rescued to a file in the temporary directory
+    #[arg(long, default_value_t = 3)]
+    save_retries: usize,
+
+    /// Delay (in seconds) before the first retry of a failed save, which
+    /// doubles for each further retry
+    #[arg(long, default_value_t = 1.0)]
+    save_retry_delay: f64,
+
+    /// Number of threads running the '--settings-files' simulations in
+    /// parallel (defaults to the number of logical cores), where the
+    /// simulations are started longest first by their estimated cost, and
+    /// the maximum number of jobs running at the same time in the '--serve'
+    /// service
+    #[arg(long)]
+    threads: Option<usize>,
+
+    /// Index of this process when splitting the '--settings-files' (including
+    /// repetitions) over an array job, defaults to SLURM_ARRAY_TASK_ID
+    #[arg(long, requires = "job_count")]
+    job_index: Option<usize>,
+
+    /// Number of processes in the array job, defaults to SLURM_ARRAY_TASK_COUNT
+    #[arg(long, requires = "job_index")]
+    job_count: Option<usize>,
+
+    /// Show a terminal dashboard with the progress and the recent amplitude
+    /// and nature angle of the '--settings-files' simulations (requires the
+    /// 'monitor' feature)
+    #[arg(long, action)]
+    monitor: bool,
+
+    /// Measure the steps per second of a short standardized simulation on
+    /// '--threads' threads, and estimate the wall time of the
+    /// '--settings-files' (if given) on this machine
+    #[arg(long, action)]
+    bench: bool,
+
+    /// Convert the groups of an HDF5 results file to CSV or NPZ, with the
+    /// attributes saved in a sidecar JSON file
+    #[arg(long)]
+    convert: Option<String>,
+
+    /// Groups converted by the '--convert' option (all groups if not given)
+    #[arg(long, num_args(1..))]
+    convert_groups: Vec<String>,
+
+    /// Format of the '--convert' option: csv (a directory of files) or npz
+    #[arg(long, default_value_t = String::from("csv"))]
+    convert_format: String,
+
+    /// Set the output path for the '--convert' option
+    #[arg(long, default_value_t = String::from("converted"))]
+    convert_output: String,
+
+    /// List the groups, datasets, and key attributes (parameters, seeds, run
+    /// times) of an HDF5 results file
+    #[arg(long)]
+    inspect: Option<String>,
+
+    /// Plot the amplitude and nature angle traces (of a time series group) or
+    /// probability densities (of a histogram group) of an HDF5 results file
+    /// (requires the 'plot' feature)
+    #[arg(long)]
+    plot: Option<String>,
+
+    /// Group plotted by the '--plot' option
+    #[arg(long, default_value_t = String::from("data"))]
+    plot_group: String,
+
+    /// Set the output path for the '--plot' option, a PNG image or an SVG image if ending in .svg
+    #[arg(long, default_value_t = String::from("plot.png"))]
+    plot_output: String,
+
+    /// Run an HTTP service on the given address (e.g. 127.0.0.1:8080) for
+    /// submitting, monitoring, and cancelling simulations
+    #[arg(long)]
+    serve: Option<String>,
+
+    /// Directory of the results of the '--serve' service, where the paths
+    /// of the submitted jobs are relative to this directory
+    #[arg(long, default_value_t = String::from("service_results"))]
+    serve_directory: String,
 }
@@ -0,0 +1,240 @@
+//! Terminal dashboard of running simulations.
+//!
+//! A [`JobMonitor`] is attached to the [`Settings`] of each simulation, and
+//! collects its progress and the recent amplitude and nature angle through the
+//! [`crate::SimulationHooks`]. [`run`] renders the traces of all the running
+//! jobs, such that divergence or mis-set parameters can be spotted early in
+//! long batches instead of after they have finished.
+use std::collections::VecDeque;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ratatui::crossterm::event::{self, Event, KeyCode};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style};
+use ratatui::symbols::Marker;
+use ratatui::text::Line;
+use ratatui::widgets::{Axis, Block, Chart, Dataset, Gauge, GraphType};
+use ratatui::Frame;
+
+use crate::{Float, Settings};
+
+/// Number of logged values kept for the traces of each job.
+const TRACE_LENGTH: usize = 2000;
+
+/// Height (in lines) of the panel of each job.
+const PANEL_HEIGHT: u16 = 12;
+
+/// Time between the redraws of the dashboard.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Time between the full redraws, which remove the output printed by the simulations.
+const CLEAR_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Recent values of the amplitude and nature angle.
+#[derive(Debug, Default)]
+struct Trace {
+    amplitude: VecDeque<(Float, Float)>,
+    chi: VecDeque<(Float, Float)>,
+}
+
+impl Trace {
+    fn push(&mut self, time: Float, amplitude: Float, chi: Float) {
+        if self.amplitude.len() == TRACE_LENGTH {
+            self.amplitude.pop_front();
+            self.chi.pop_front();
+        }
+        self.amplitude.push_back((time, amplitude));
+        self.chi.push_back((time, chi));
+    }
+}
+
+/// Progress and recent traces of a single simulation.
+#[derive(Clone, Debug)]
+pub struct JobMonitor {
+    name: String,
+    total_steps: usize,
+    progress: Arc<AtomicUsize>,
+    trace: Arc<Mutex<Trace>>,
+    finished: Arc<AtomicBool>,
+}
+
+impl JobMonitor {
+    /// Attach a monitor to the settings, using their progress counter and hooks.
+    pub fn attach(name: &str, settings: &mut Settings) -> Self {
+        let progress = Arc::new(AtomicUsize::new(0));
+        settings.progress = Some(progress.clone());
+
+        let trace = Arc::new(Mutex::new(Trace::default()));
+        let save_trace = trace.clone();
+        settings.hooks.on_save(move |state| {
+            if let Ok(mut trace) = save_trace.lock() {
+                trace.push(state.time, state.mode.a(), state.mode.chi());
+            }
+        });
+
+        let finished = Arc::new(AtomicBool::new(false));
+        let finish_flag = finished.clone();
+        settings.hooks.on_finish(move |_| {
+            finish_flag.store(true, Ordering::Relaxed);
+        });
+
+        Self {
+            name: name.to_string(),
+            total_steps: settings.parameters.get_total_steps(),
+            progress,
+            trace,
+            finished,
+        }
+    }
+
+    /// Check whether the simulation has finished.
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Relaxed)
+    }
+
+    /// Fraction of the time steps completed.
+    pub fn ratio(&self) -> Float {
+        if self.is_finished() {
+            return 1.0;
+        }
+        let steps = self.progress.load(Ordering::Relaxed);
+
+        (steps as Float / self.total_steps.max(1) as Float).min(1.0)
+    }
+}
+
+/// Render the dashboard of the jobs until `is_done` returns true or `q` is pressed.
+///
+/// Closing the dashboard early does not stop the simulations.
+pub fn run(jobs: &[JobMonitor], is_done: impl Fn() -> bool) -> io::Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let mut last_clear = Instant::now();
+
+    let result = loop {
+        if is_done() {
+            break Ok(());
+        }
+
+        if last_clear.elapsed() >= CLEAR_INTERVAL {
+            if let Err(e) = terminal.clear() {
+                break Err(e);
+            }
+            last_clear = Instant::now();
+        }
+        if let Err(e) = terminal.draw(|frame| draw(frame, jobs)) {
+            break Err(e);
+        }
+
+        match event::poll(REFRESH_INTERVAL) {
+            Ok(true) => match event::read() {
+                Ok(Event::Key(key)) if key.code == KeyCode::Char('q') => break Ok(()),
+                Ok(_) => {}
+                Err(e) => break Err(e),
+            },
+            Ok(false) => {}
+            Err(e) => break Err(e),
+        }
+    };
+
+    ratatui::try_restore()?;
+    result
+}
+
+fn draw(frame: &mut Frame, jobs: &[JobMonitor]) {
+    let [main, footer] =
+        Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(frame.area());
+
+    // Only show the running jobs that fit on the screen
+    let max_panels = (main.height / PANEL_HEIGHT).max(1) as usize;
+    let running: Vec<&JobMonitor> = jobs
+        .iter()
+        .filter(|job| !job.is_finished())
+        .take(max_panels)
+        .collect();
+
+    let panels =
+        Layout::vertical(vec![Constraint::Length(PANEL_HEIGHT); running.len()]).split(main);
+    for (job, area) in running.into_iter().zip(panels.iter()) {
+        draw_job(frame, job, *area);
+    }
+
+    let finished = jobs.iter().filter(|job| job.is_finished()).count();
+    let status = format!(
+        " {}/{} simulations finished, press 'q' to close the monitor",
+        finished,
+        jobs.len()
+    );
+    frame.render_widget(Line::from(status), footer);
+}
+
+fn draw_job(frame: &mut Frame, job: &JobMonitor, area: Rect) {
+    let block = Block::bordered().title(job.name.as_str());
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let [progress, charts] =
+        Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(inner);
+    let ratio = job.ratio();
+    let gauge = Gauge::default()
+        .gauge_style(Style::default().fg(Color::Green))
+        .ratio(ratio)
+        .label(format!("{:.1} %", 100.0 * ratio));
+    frame.render_widget(gauge, progress);
+
+    // Copy the traces to release the lock before rendering
+    let (amplitude, chi) = match job.trace.lock() {
+        Ok(trace) => (
+            trace.amplitude.iter().copied().collect::<Vec<_>>(),
+            trace.chi.iter().copied().collect::<Vec<_>>(),
+        ),
+        Err(_) => return,
+    };
+
+    let [left, right] =
+        Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(charts);
+    frame.render_widget(trace_chart("amplitude", &amplitude, Color::Cyan), left);
+    frame.render_widget(trace_chart("nature angle", &chi, Color::Yellow), right);
+}
+
+/// Line chart of a trace, with the axes fitted to the values.
+fn trace_chart<'a>(name: &'a str, data: &'a [(Float, Float)], color: Color) -> Chart<'a> {
+    let (t_min, t_max) = bounds(data.iter().map(|(t, _)| *t));
+    let (y_min, y_max) = bounds(data.iter().map(|(_, y)| *y));
+
+    let dataset = Dataset::default()
+        .name(name)
+        .marker(Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(color))
+        .data(data);
+
+    Chart::new(vec![dataset])
+        .x_axis(
+            Axis::default()
+                .bounds([t_min, t_max])
+                .labels([format!("{:.0}", t_min), format!("{:.0}", t_max)]),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([y_min, y_max])
+                .labels([format!("{:.3}", y_min), format!("{:.3}", y_max)]),
+        )
+}
+
+/// Minimum and maximum of the values, widened if they are (nearly) equal.
+fn bounds(values: impl Iterator<Item = Float>) -> (Float, Float) {
+    let (min, max) = values.fold((Float::INFINITY, Float::NEG_INFINITY), |(min, max), v| {
+        (min.min(v), max.max(v))
+    });
+    if !min.is_finite() || !max.is_finite() {
+        return (0.0, 1.0);
+    }
+    if max - min < 1e-12 {
+        return (min - 0.5, max + 0.5);
+    }
+
+    (min, max)
+}
@@ -0,0 +1,143 @@
+//! Noise intensity following a schedule over the simulation.
+//!
+//! The intensity of the additive noise is interpolated linearly between the
+//! points of the schedule, and is constant before the first and after the
+//! last point. Two points at the same time give a step change, e.g. a sudden
+//! drop of the noise (a quench) to observe the relaxation of the mode
+//! towards the attractors of the deterministic system.
+use serde::{Deserialize, Serialize};
+
+use crate::Float;
+
+/// Possible errors for [`NoiseSchedule`].
+#[derive(Clone, Copy, Debug)]
+pub enum NoiseScheduleError {
+    Points,
+    Time,
+    Noise,
+    Ramp,
+}
+
+impl std::error::Error for NoiseScheduleError {}
+
+impl std::fmt::Display for NoiseScheduleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Points => "at least one point is required",
+            Self::Time => "the times have to be finite and non-decreasing",
+            Self::Noise => "the noise intensities have to be non-negative",
+            Self::Ramp => "the noise cannot be ramped and scheduled at the same time",
+        };
+
+        write!(f, "error in the noise schedule: {}", msg)
+    }
+}
+
+/// Noise intensity `noise` from the `time` (in cycles) of a [`NoiseSchedule`].
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct SchedulePoint {
+    pub time: Float,
+    pub noise: Float,
+}
+
+/// Piecewise linear schedule of the noise intensity.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct NoiseSchedule {
+    pub points: Vec<SchedulePoint>,
+}
+
+impl NoiseSchedule {
+    pub fn new(points: Vec<SchedulePoint>) -> Result<Self, NoiseScheduleError> {
+        let schedule = Self { points };
+        schedule.validate()?;
+
+        Ok(schedule)
+    }
+
+    /// Noise quench: the intensity `before` until `time`, and `after` from then on.
+    pub fn step(time: Float, before: Float, after: Float) -> Result<Self, NoiseScheduleError> {
+        Self::new(vec![
+            SchedulePoint {
+                time,
+                noise: before,
+            },
+            SchedulePoint { time, noise: after },
+        ])
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), NoiseScheduleError> {
+        if self.points.is_empty() {
+            return Err(NoiseScheduleError::Points);
+        }
+        if self.points.iter().any(|p| !p.time.is_finite())
+            || self.points.windows(2).any(|w| w[0].time > w[1].time)
+        {
+            return Err(NoiseScheduleError::Time);
+        }
+        if self
+            .points
+            .iter()
+            .any(|p| p.noise.is_nan() || p.noise < 0.0)
+        {
+            return Err(NoiseScheduleError::Noise);
+        }
+
+        Ok(())
+    }
+
+    /// Noise intensity at `time`.
+    ///
+    /// At the time of a step, the intensity after the step is used.
+    pub fn noise(&self, time: Float) -> Float {
+        let upper = self.points.partition_point(|p| p.time <= time);
+        if upper == 0 {
+            return self.points[0].noise;
+        }
+        let lower = &self.points[upper - 1];
+        match self.points.get(upper) {
+            Some(upper) => {
+                let fraction = (time - lower.time) / (upper.time - lower.time);
+                lower.noise + fraction * (upper.noise - lower.noise)
+            }
+            None => lower.noise,
+        }
+    }
+
+    /// Times and noise intensities of the points, for saving the schedule.
+    #[cfg(any(feature = "hdf5", feature = "npz"))]
+    pub(crate) fn columns(&self) -> (Vec<Float>, Vec<Float>) {
+        self.points.iter().map(|p| (p.time, p.noise)).unzip()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_and_ramps() {
+        let quench = NoiseSchedule::step(10.0, 0.1, 0.0).unwrap();
+        assert_eq!(quench.noise(0.0), 0.1);
+        assert_eq!(quench.noise(9.99), 0.1);
+        assert_eq!(quench.noise(10.0), 0.0);
+        assert_eq!(quench.noise(20.0), 0.0);
+
+        let ramp = NoiseSchedule::new(vec![
+            SchedulePoint {
+                time: 0.0,
+                noise: 0.0,
+            },
+            SchedulePoint {
+                time: 4.0,
+                noise: 0.2,
+            },
+        ])
+        .unwrap();
+        assert!((ramp.noise(1.0) - 0.05).abs() < 1e-12);
+        assert_eq!(ramp.noise(5.0), 0.2);
+
+        let mut reversed = ramp.clone();
+        reversed.points.reverse();
+        assert!(reversed.validate().is_err());
+    }
+}
@@ -0,0 +1,152 @@
+//! Atomic writes of the result files.
+//!
+//! The results are written to a temporary copy of the file next to it, which
+//! replaces the file (by renaming) only when everything has been written. A
+//! crash while saving thereby leaves the original file intact, and at most a
+//! temporary file behind, which is removed by
+//! [`SaveInfo::remove_stale_temporary_files`].
+//!
+//! The file is copied and replaced as a whole, such that several processes
+//! must not save to the same file at the same time.
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use super::SaveInfo;
+
+/// Extension of the temporary files.
+const TEMPORARY_EXTENSION: &str = "tmp";
+/// Age after which the temporary files of other processes are considered stale.
+const STALE_AGE: Duration = Duration::from_secs(3600);
+
+/// Counter distinguishing the temporary files of a process.
+static TEMPORARY_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// File name of `path`, or an empty string if there is none.
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// New temporary sibling of `path`, named `.<file name>.<process id>-<counter>.tmp`.
+fn temporary_path(path: &Path) -> PathBuf {
+    let counter = TEMPORARY_FILES.fetch_add(1, Ordering::Relaxed);
+    let name = format!(
+        ".{}.{}-{}.{}",
+        file_name(path),
+        std::process::id(),
+        counter,
+        TEMPORARY_EXTENSION
+    );
+
+    path.with_file_name(name)
+}
+
+/// Process id of the temporary sibling `candidate` of `path` (if it is one).
+fn temporary_process_id(path: &Path, candidate: &Path) -> Option<u32> {
+    let prefix = format!(".{}.", file_name(path));
+    let suffix = format!(".{}", TEMPORARY_EXTENSION);
+    let name = file_name(candidate);
+    let (process_id, _counter) = name
+        .strip_prefix(&prefix)?
+        .strip_suffix(&suffix)?
+        .split_once('-')?;
+
+    process_id.parse().ok()
+}
+
+/// Whether a file can be created next to `path`, tested with a temporary file.
+pub(super) fn directory_is_writable(path: &Path) -> bool {
+    let temporary = temporary_path(path);
+    let created = std::fs::File::create(&temporary).is_ok();
+    let _ = std::fs::remove_file(&temporary);
+
+    created
+}
+
+/// Call `write` with the save information of a temporary copy of the file, and
+/// replace the file with the copy if `write` succeeds.
+///
+/// The temporary copy is removed if `write` fails.
+#[cfg(feature = "hdf5")]
+pub(crate) fn write_atomically<T>(
+    save_info: &SaveInfo,
+    write: impl FnOnce(&SaveInfo) -> hdf5::Result<T>,
+) -> hdf5::Result<T> {
+    save_info.create_directory().map_err(|e| e.to_string())?;
+    let path = save_info.get_path();
+    let temporary = temporary_path(path);
+    if path.exists() {
+        std::fs::copy(path, &temporary).map_err(|e| e.to_string())?;
+    }
+
+    let mut temporary_info = save_info.clone();
+    temporary_info.set_path(&temporary);
+    let result = write(&temporary_info).and_then(|value| {
+        std::fs::rename(&temporary, path).map_err(|e| e.to_string())?;
+        Ok(value)
+    });
+    if result.is_err() {
+        // The file may not have been created at all
+        let _ = std::fs::remove_file(&temporary);
+    }
+
+    result
+}
+
+impl SaveInfo {
+    /// Remove the temporary files left next to the file by interrupted saves.
+    ///
+    /// Only the temporary files of other processes that have not been
+    /// modified for an hour are removed, to spare the saves in progress.
+    /// Returns the paths of the removed files.
+    pub fn remove_stale_temporary_files(&self) -> std::io::Result<Vec<PathBuf>> {
+        let directory = self.directory();
+        if !directory.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut removed = Vec::new();
+        for entry in std::fs::read_dir(&directory)? {
+            let candidate = entry?.path();
+            match temporary_process_id(&self.path, &candidate) {
+                Some(process_id) if process_id != std::process::id() => {}
+                _ => continue,
+            }
+
+            let age = std::fs::metadata(&candidate)?
+                .modified()?
+                .elapsed()
+                .unwrap_or_default();
+            if age > STALE_AGE {
+                std::fs::remove_file(&candidate)?;
+                removed.push(candidate);
+            }
+        }
+
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temporary_files_are_recognized() {
+        let path = PathBuf::from("results/run.h5");
+        let temporary = temporary_path(&path);
+        assert_eq!(temporary.parent(), path.parent());
+        assert_eq!(
+            temporary_process_id(&path, &temporary),
+            Some(std::process::id())
+        );
+
+        assert_eq!(temporary_process_id(&path, &path), None);
+        let other = PathBuf::from("results/.run.h5.12-3.tmp");
+        assert_eq!(temporary_process_id(&path, &other), Some(12));
+        let other_file = PathBuf::from("results/.other.h5.12-3.tmp");
+        assert_eq!(temporary_process_id(&path, &other_file), None);
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+use super::{LogSettings, ObserverTrait, SaveInfo};
+use crate::azimuthal_mode::SystemMode;
+use crate::hrr_integral::DescribingFunction;
+use crate::Float;
+#[cfg(feature = "hdf5")]
+use crate::Parameters;
+#[cfg(feature = "hdf5")]
+use ndarray;
+use serde::{Deserialize, Serialize};
+
+/// Possible errors for [`AutocorrelationObserver`].
+#[derive(Clone, Copy, Debug)]
+pub enum AutocorrelationError {
+    NotEmpty,
+}
+
+impl std::error::Error for AutocorrelationError {}
+
+impl std::fmt::Display for AutocorrelationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::NotEmpty => "the maximum lag cannot be changed after logging values",
+        };
+
+        write!(f, "error in the autocorrelation observer: {}", msg)
+    }
+}
+
+/// Autocorrelation observer.
+///
+/// Estimates the autocorrelation function of the amplitude and the nature
+/// angle up to `max_lag` logged samples with a running estimator, together
+/// with the integrated autocorrelation time. The latter is used to find the
+/// effective number of independent samples of the logged statistics.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AutocorrelationObserver {
+    pub save_info: SaveInfo,
+
+    /// When and how the mode is logged.
+    #[serde(flatten)]
+    pub logging: LogSettings,
+
+    max_lag: usize,
+
+    #[serde(skip)]
+    amplitude: RunningAutocorrelation,
+
+    #[serde(skip)]
+    chi: RunningAutocorrelation,
+}
+
+impl AutocorrelationObserver {
+    pub fn new(
+        output_filepath: &PathBuf,
+        group_name: Option<&str>,
+        max_lag: usize,
+    ) -> AutocorrelationObserver {
+        let save_info = SaveInfo::for_observer(output_filepath, group_name);
+        AutocorrelationObserver {
+            save_info,
+            logging: LogSettings::default(),
+            max_lag,
+            amplitude: RunningAutocorrelation::new(max_lag),
+            chi: RunningAutocorrelation::new(max_lag),
+        }
+    }
+
+    /// Set the maximum lag (in number of logged samples).
+    ///
+    /// Only possible before any values are logged.
+    pub fn set_max_lag(&mut self, max_lag: usize) -> Result<(), AutocorrelationError> {
+        if self.amplitude.num_values != 0 {
+            return Err(AutocorrelationError::NotEmpty);
+        }
+        self.max_lag = max_lag;
+        self.create_estimators();
+
+        Ok(())
+    }
+
+    fn create_estimators(&mut self) {
+        self.amplitude = RunningAutocorrelation::new(self.max_lag);
+        self.chi = RunningAutocorrelation::new(self.max_lag);
+    }
+
+    /// Clear the logged values, keeping the maximum lag and the allocated sums.
+    pub fn reset(&mut self) {
+        self.amplitude.clear();
+        self.chi.clear();
+    }
+}
+
+impl Default for AutocorrelationObserver {
+    fn default() -> Self {
+        let output_filepath = PathBuf::from("simulation_autocorrelation.hdf5");
+
+        Self::new(&output_filepath, None, 1000)
+    }
+}
+
+impl_observer_conversions!(AutocorrelationObserver);
+
+impl ObserverTrait for AutocorrelationObserver {
+    #[inline]
+    fn log(&mut self, acoustic_mode: &SystemMode, _hrr_mode: &SystemMode, _time: Float) {
+        // The storage is skipped when loading from JSON
+        if self.amplitude.lags.len() != self.max_lag + 1 {
+            self.create_estimators();
+        }
+
+        self.amplitude.push(acoustic_mode.a());
+        self.chi.push(acoustic_mode.chi());
+    }
+
+    #[cfg(feature = "hdf5")]
+    fn save(
+        &self,
+        parameters: &Parameters,
+        _describing_function: &DescribingFunction,
+    ) -> hdf5::Result<()> {
+        let file = hdf5::File::append(&self.save_info.path)?;
+        let group = file.create_group(&self.save_info.group)?;
+
+        let lags: Vec<usize> = (0..=self.max_lag).collect();
+        super::write_dataset(&group, &lags, "lag")?;
+
+        for (name, estimator) in [("amplitude", &self.amplitude), ("chi", &self.chi)] {
+            let subgroup = group.create_group(name)?;
+            let acf = estimator.autocorrelation();
+            let tau = integrated_autocorrelation_time(&acf);
+            let effective_sample_size = estimator.num_values as Float / tau;
+
+            super::write_dataset(&subgroup, &acf, "autocorrelation")?;
+            super::save_attr(&subgroup, &ndarray::arr0(tau), "integrated_time")?;
+            super::save_attr(
+                &subgroup,
+                &ndarray::arr0(effective_sample_size),
+                "effective_sample_size",
+            )?;
+        }
+
+        super::save_parameters_as_attribute_json(&group, parameters)
+    }
+
+    fn results_json(&self, _describing_function: &DescribingFunction) -> serde_json::Value {
+        let mut results = serde_json::json!({ "lag": (0..=self.max_lag).collect::<Vec<_>>() });
+        for (name, estimator) in [("amplitude", &self.amplitude), ("chi", &self.chi)] {
+            let acf = estimator.autocorrelation();
+            let tau = integrated_autocorrelation_time(&acf);
+            results[name] = serde_json::json!({
+                "autocorrelation": acf,
+                "integrated_time": tau,
+                "effective_sample_size": estimator.num_values as Float / tau,
+            });
+        }
+
+        results
+    }
+}
+
+/// Running co-moment of the pairs of values separated by a given lag.
+#[derive(Clone, Copy, Debug, Default)]
+struct LagComoment {
+    count: usize,
+    /// Means of the newer and older values of the pairs.
+    mean_x: Float,
+    mean_y: Float,
+    /// Sum of the products of the deviations from the means.
+    c_xy: Float,
+}
+
+impl LagComoment {
+    /// Add a pair of values (using Welford's algorithm).
+    #[inline]
+    fn push(&mut self, x: Float, y: Float) {
+        self.count += 1;
+        let n = self.count as Float;
+
+        let dx = x - self.mean_x;
+        self.mean_x += dx / n;
+        self.mean_y += (y - self.mean_y) / n;
+        self.c_xy += dx * (y - self.mean_y);
+    }
+
+    /// Covariance of the pairs about the global `mean`.
+    fn covariance(&self, mean: Float) -> Float {
+        match self.count {
+            0 => 0.0,
+            n => self.c_xy / n as Float + (self.mean_x - mean) * (self.mean_y - mean),
+        }
+    }
+}
+
+/// Running estimator of the autocorrelation function of a scalar signal.
+///
+/// The sums are centred, which avoids the cancellation of the raw moments
+/// when the mean is large compared to the fluctuations.
+#[derive(Clone, Debug, Default)]
+struct RunningAutocorrelation {
+    history: VecDeque<Float>,
+    lags: Vec<LagComoment>,
+    mean: Float,
+    num_values: usize,
+}
+
+impl RunningAutocorrelation {
+    fn new(max_lag: usize) -> Self {
+        Self {
+            history: VecDeque::with_capacity(max_lag + 1),
+            lags: vec![LagComoment::default(); max_lag + 1],
+            mean: 0.0,
+            num_values: 0,
+        }
+    }
+
+    /// Clear the values, keeping the allocated history and sums.
+    fn clear(&mut self) {
+        self.history.clear();
+        self.lags.fill(LagComoment::default());
+        self.mean = 0.0;
+        self.num_values = 0;
+    }
+
+    #[inline]
+    fn push(&mut self, value: Float) {
+        // Keep the `max_lag` previous values, with the newest first
+        if self.history.len() == self.lags.len() {
+            self.history.pop_back();
+        }
+        self.history.push_front(value);
+
+        for (comoment, &previous) in self.lags.iter_mut().zip(&self.history) {
+            comoment.push(value, previous);
+        }
+
+        self.num_values += 1;
+        self.mean += (value - self.mean) / self.num_values as Float;
+    }
+
+    /// Normalized autocorrelation function, based on the global mean.
+    fn autocorrelation(&self) -> Vec<Float> {
+        if self.num_values == 0 {
+            return vec![0.0; self.lags.len()];
+        }
+
+        let autocovariance: Vec<Float> = self
+            .lags
+            .iter()
+            .map(|comoment| comoment.covariance(self.mean))
+            .collect();
+
+        let variance = autocovariance[0];
+        if variance <= 0.0 {
+            return vec![0.0; autocovariance.len()];
+        }
+
+        autocovariance.iter().map(|c| c / variance).collect()
+    }
+}
+
+/// Integrated autocorrelation time (in number of samples).
+///
+/// Uses the self-consistent window of Sokal, summing the autocorrelation
+/// function up to the first lag `M` satisfying `M >= 5 * tau(M)`.
+fn integrated_autocorrelation_time(acf: &[Float]) -> Float {
+    let mut tau = 1.0;
+    for (lag, rho) in acf.iter().enumerate().skip(1) {
+        tau += 2.0 * rho;
+        if lag as Float >= 5.0 * tau {
+            break;
+        }
+    }
+
+    tau.max(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alternating_signal() {
+        let mut estimator = RunningAutocorrelation::new(2);
+        for ind in 0..1000 {
+            estimator.push(if ind % 2 == 0 { 1.0 } else { -1.0 });
+        }
+
+        let acf = estimator.autocorrelation();
+        assert!((acf[0] - 1.0).abs() < 1e-12);
+        assert!((acf[1] + 1.0).abs() < 1e-12);
+        assert!((acf[2] - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn large_mean() {
+        // The raw moments would lose the fluctuations to cancellation
+        let mut estimator = RunningAutocorrelation::new(2);
+        for ind in 0..1000 {
+            estimator.push(1e8 + if ind % 2 == 0 { 1.0 } else { -1.0 });
+        }
+
+        let acf = estimator.autocorrelation();
+        assert!((acf[0] - 1.0).abs() < 1e-9);
+        assert!((acf[1] + 1.0).abs() < 1e-6);
+        assert!((acf[2] - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn max_lag_is_fixed_after_logging() {
+        let mut observer = AutocorrelationObserver::default();
+        assert!(observer.set_max_lag(10).is_ok());
+
+        let mode = SystemMode::default();
+        observer.log(&mode, &mode, 0.0);
+        assert!(observer.set_max_lag(20).is_err());
+        assert_eq!(observer.max_lag, 10);
+    }
+}
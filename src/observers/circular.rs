@@ -0,0 +1,90 @@
+use crate::Float;
+
+/// Directional statistics of a periodic variable (with period `2 pi`).
+///
+/// The orientation angle and temporal phase are periodic, such that their
+/// (linear) means and variances depend on where the angles are wrapped. The
+/// circular statistics are instead computed from the mean resultant vector
+/// of the unit vectors `(cos x, sin x)`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CircularStatistics {
+    sum_cos: Float,
+    sum_sin: Float,
+    total_weight: Float,
+}
+
+impl CircularStatistics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an angle with the given statistical weight.
+    #[inline]
+    pub fn push(&mut self, angle: Float, weight: Float) {
+        self.sum_cos += weight * angle.cos();
+        self.sum_sin += weight * angle.sin();
+        self.total_weight += weight;
+    }
+
+    /// Circular mean, in `(-pi, pi]`.
+    pub fn mean(&self) -> Float {
+        self.sum_sin.atan2(self.sum_cos)
+    }
+
+    /// Mean of `cos x`, i.e. the projection of the mean resultant vector on `x = 0`.
+    pub fn mean_cos(&self) -> Float {
+        if self.total_weight <= 0.0 {
+            return 0.0;
+        }
+
+        self.sum_cos / self.total_weight
+    }
+
+    /// Mean resultant length, from 0 (uniformly spread) to 1 (concentrated).
+    pub fn resultant_length(&self) -> Float {
+        if self.total_weight <= 0.0 {
+            return 0.0;
+        }
+
+        self.sum_cos.hypot(self.sum_sin) / self.total_weight
+    }
+
+    /// Circular standard deviation `sqrt(-2 ln R)`.
+    pub fn standard_deviation(&self) -> Float {
+        (-2.0 * self.resultant_length().ln()).sqrt()
+    }
+
+    /// Maximum likelihood estimate of the concentration of a von Mises fit.
+    ///
+    /// Uses the approximation of the inverse of `A1(kappa) = I1(kappa) / I0(kappa)`
+    /// by Fisher (1993), where the location of the fit is the [`Self::mean`].
+    pub fn von_mises_concentration(&self) -> Float {
+        let r = self.resultant_length();
+        if r < 0.53 {
+            2.0 * r + r.powi(3) + 5.0 * r.powi(5) / 6.0
+        } else if r < 0.85 {
+            -0.4 + 1.39 * r + 0.43 / (1.0 - r)
+        } else {
+            1.0 / (r.powi(3) - 4.0 * r.powi(2) + 3.0 * r)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PI;
+
+    #[test]
+    fn mean_across_the_wrap() {
+        let mut statistics = CircularStatistics::new();
+        for angle in [PI - 0.1, -PI + 0.1, PI - 0.05, -PI + 0.05] {
+            statistics.push(angle, 1.0);
+        }
+
+        // The linear mean would be zero, on the opposite side of the circle
+        assert!((statistics.mean().abs() - PI).abs() < 1e-9);
+        assert!(statistics.resultant_length() > 0.99);
+        assert!(statistics.von_mises_concentration() > 50.0);
+    }
+}
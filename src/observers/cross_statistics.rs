@@ -0,0 +1,258 @@
+use std::path::PathBuf;
+
+#[cfg(feature = "hdf5")]
+use super::metadata::Content;
+use super::transition_matrix::{
+    bin, bin_edges, check_amplitude_limit, check_bins, deserialize_amplitude_limit,
+    deserialize_bins, GridError,
+};
+use super::{LogSettings, ObserverTrait, SaveInfo};
+use crate::azimuthal_mode::SystemMode;
+use crate::hrr_integral::DescribingFunction;
+#[cfg(feature = "hdf5")]
+use crate::Parameters;
+use crate::{Float, FRAC_PI_4};
+#[cfg(feature = "hdf5")]
+use ndarray;
+use serde::{Deserialize, Serialize};
+
+/// Running (Pearson) correlation between two variables.
+#[derive(Clone, Copy, Debug, Default)]
+struct Correlation {
+    count: usize,
+    mean_x: Float,
+    mean_y: Float,
+    /// Sums of the squared deviations from the means.
+    m2_x: Float,
+    m2_y: Float,
+    /// Sum of the products of the deviations from the means.
+    c_xy: Float,
+}
+
+impl Correlation {
+    /// Add a pair of values (using Welford's algorithm).
+    #[inline]
+    fn push(&mut self, x: Float, y: Float) {
+        self.count += 1;
+        let n = self.count as Float;
+
+        let dx = x - self.mean_x;
+        let dy = y - self.mean_y;
+        self.mean_x += dx / n;
+        self.mean_y += dy / n;
+
+        self.m2_x += dx * (x - self.mean_x);
+        self.m2_y += dy * (y - self.mean_y);
+        self.c_xy += dx * (y - self.mean_y);
+    }
+
+    /// Correlation coefficient, which is NaN if either variable is constant.
+    fn coefficient(&self) -> Float {
+        self.c_xy / (self.m2_x * self.m2_y).sqrt()
+    }
+}
+
+/// Cross-statistics observer of the acoustic and heat release rate modes.
+///
+/// Computes the correlation coefficients and joint histograms of the
+/// amplitudes (`A` vs `A_q`) and nature angles (`chi` vs `chi_q`) of the
+/// acoustic and heat release rate modes. Element `(i, j)` of a saved joint
+/// histogram counts the values in bin `i` of the acoustic mode and bin `j`
+/// of the heat release rate mode.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CrossStatisticsObserver {
+    pub save_info: SaveInfo,
+
+    /// When and how the mode is logged.
+    #[serde(flatten)]
+    pub logging: LogSettings,
+
+    #[serde(deserialize_with = "deserialize_amplitude_limit")]
+    amplitude_limit: Float,
+    #[serde(deserialize_with = "deserialize_bins")]
+    amplitude_bins: usize,
+    #[serde(deserialize_with = "deserialize_bins")]
+    chi_bins: usize,
+
+    #[serde(skip)]
+    amplitude_counts: Vec<usize>,
+
+    #[serde(skip)]
+    chi_counts: Vec<usize>,
+
+    #[serde(skip)]
+    amplitude_correlation: Correlation,
+
+    #[serde(skip)]
+    chi_correlation: Correlation,
+}
+
+impl CrossStatisticsObserver {
+    pub fn new(
+        output_filepath: &PathBuf,
+        group_name: Option<&str>,
+        amplitude_limit: Float,
+        amplitude_bins: usize,
+        chi_bins: usize,
+    ) -> Result<CrossStatisticsObserver, GridError> {
+        check_amplitude_limit(amplitude_limit)?;
+        check_bins(amplitude_bins)?;
+        check_bins(chi_bins)?;
+
+        let save_info = SaveInfo::for_observer(output_filepath, group_name);
+        let mut observer = CrossStatisticsObserver {
+            save_info,
+            logging: LogSettings::default(),
+            amplitude_limit,
+            amplitude_bins,
+            chi_bins,
+            amplitude_counts: Vec::new(),
+            chi_counts: Vec::new(),
+            amplitude_correlation: Correlation::default(),
+            chi_correlation: Correlation::default(),
+        };
+        observer.allocate();
+
+        Ok(observer)
+    }
+
+    /// Allocate the storage for the joint histograms.
+    ///
+    /// Required after loading the observer from JSON.
+    fn allocate(&mut self) {
+        self.amplitude_counts = vec![0; self.amplitude_bins * self.amplitude_bins];
+        self.chi_counts = vec![0; self.chi_bins * self.chi_bins];
+    }
+
+    /// Correlation coefficient of the acoustic and heat release rate amplitudes.
+    pub fn get_amplitude_correlation(&self) -> Float {
+        self.amplitude_correlation.coefficient()
+    }
+
+    /// Correlation coefficient of the acoustic and heat release rate nature angles.
+    pub fn get_chi_correlation(&self) -> Float {
+        self.chi_correlation.coefficient()
+    }
+
+    /// Clear the joint histograms and correlations, keeping the bins.
+    pub fn reset(&mut self) {
+        self.amplitude_counts.fill(0);
+        self.chi_counts.fill(0);
+        self.amplitude_correlation = Correlation::default();
+        self.chi_correlation = Correlation::default();
+    }
+}
+
+impl Default for CrossStatisticsObserver {
+    fn default() -> Self {
+        let output_filepath = PathBuf::from("simulation_cross_statistics.hdf5");
+
+        // The default grid is valid
+        Self::new(&output_filepath, None, 5.0, 50, 50).unwrap()
+    }
+}
+
+impl_observer_conversions!(CrossStatisticsObserver);
+
+impl ObserverTrait for CrossStatisticsObserver {
+    #[inline]
+    fn log(&mut self, acoustic_mode: &SystemMode, hrr_mode: &SystemMode, _time: Float) {
+        // The storage is skipped when loading from JSON
+        if self.amplitude_counts.is_empty() {
+            self.allocate();
+        }
+
+        let (a, a_q) = (acoustic_mode.a(), hrr_mode.a());
+        self.amplitude_correlation.push(a, a_q);
+        let a_bin = bin(a, 0.0, self.amplitude_limit, self.amplitude_bins);
+        let a_q_bin = bin(a_q, 0.0, self.amplitude_limit, self.amplitude_bins);
+        self.amplitude_counts[a_bin * self.amplitude_bins + a_q_bin] += 1;
+
+        let (chi, chi_q) = (acoustic_mode.chi(), hrr_mode.chi());
+        self.chi_correlation.push(chi, chi_q);
+        let chi_bin = bin(chi, -FRAC_PI_4, FRAC_PI_4, self.chi_bins);
+        let chi_q_bin = bin(chi_q, -FRAC_PI_4, FRAC_PI_4, self.chi_bins);
+        self.chi_counts[chi_bin * self.chi_bins + chi_q_bin] += 1;
+    }
+
+    #[cfg(feature = "hdf5")]
+    fn save(
+        &self,
+        parameters: &Parameters,
+        _describing_function: &DescribingFunction,
+    ) -> hdf5::Result<()> {
+        let file = hdf5::File::append(&self.save_info.path)?;
+        let group = file.create_group(&self.save_info.group)?;
+
+        // Save the joint histograms as (acoustic, heat release rate) matrices
+        let joint_histograms = [
+            ("amplitude", self.amplitude_bins, &self.amplitude_counts),
+            ("chi", self.chi_bins, &self.chi_counts),
+        ];
+        for (name, nbins, counts) in joint_histograms {
+            let counts = ndarray::Array2::from_shape_vec((nbins, nbins), counts.clone())
+                .map_err(|e| e.to_string())?;
+            group
+                .new_dataset_builder()
+                .with_data(&counts)
+                .create(name)?;
+        }
+
+        // Save the bin edges in a subgroup
+        let edge_group = group.create_group("bin_edges")?;
+        let a_edges = bin_edges(0.0, self.amplitude_limit, self.amplitude_bins);
+        let chi_edges = bin_edges(-FRAC_PI_4, FRAC_PI_4, self.chi_bins);
+        super::write_described_dataset(&edge_group, &a_edges, "amplitude", Content::BinEdges)?;
+        super::write_described_dataset(&edge_group, &chi_edges, "chi", Content::BinEdges)?;
+
+        let amplitude_correlation = self.get_amplitude_correlation();
+        super::save_attr(
+            &group,
+            &ndarray::arr0(amplitude_correlation),
+            "amplitude_correlation",
+        )?;
+        let chi_correlation = self.get_chi_correlation();
+        super::save_attr(&group, &ndarray::arr0(chi_correlation), "chi_correlation")?;
+        super::save_attr(
+            &group,
+            &ndarray::arr0(self.amplitude_correlation.count),
+            "number_of_values",
+        )?;
+        super::save_parameters_as_attribute_json(&group, parameters)
+    }
+
+    fn results_json(&self, _describing_function: &DescribingFunction) -> serde_json::Value {
+        serde_json::json!({
+            "amplitude": self.amplitude_counts.chunks(self.amplitude_bins).collect::<Vec<_>>(),
+            "chi": self.chi_counts.chunks(self.chi_bins).collect::<Vec<_>>(),
+            "bin_edges": {
+                "amplitude": bin_edges(0.0, self.amplitude_limit, self.amplitude_bins),
+                "chi": bin_edges(-FRAC_PI_4, FRAC_PI_4, self.chi_bins),
+            },
+            "amplitude_correlation": self.get_amplitude_correlation(),
+            "chi_correlation": self.get_chi_correlation(),
+            "number_of_values": self.amplitude_correlation.count,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn correlation_of_modes() {
+        let mut observer = CrossStatisticsObserver::default();
+        for ind in 0..10 {
+            let x = 0.1 * ind as Float;
+            let acoustic_mode = SystemMode::new(1.0 + x, 0.0, 0.0, 0.5 * x - 0.2);
+            // Linearly related amplitudes, and opposite nature angles
+            let hrr_mode = SystemMode::new(0.5 + 2.0 * x, 0.0, 0.0, 0.2 - 0.5 * x);
+            observer.log(&acoustic_mode, &hrr_mode, 0.0);
+        }
+
+        assert!((observer.get_amplitude_correlation() - 1.0).abs() < 1e-9);
+        assert!((observer.get_chi_correlation() + 1.0).abs() < 1e-9);
+        assert_eq!(observer.chi_counts.iter().sum::<usize>(), 10);
+    }
+}
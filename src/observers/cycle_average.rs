@@ -0,0 +1,200 @@
+use std::path::PathBuf;
+
+use super::{LogSettings, ObserverTrait, SaveInfo};
+use crate::azimuthal_mode::SystemMode;
+use crate::hrr_integral::DescribingFunction;
+#[cfg(feature = "hdf5")]
+use crate::Parameters;
+use crate::{Float, PI};
+use serde::{Deserialize, Serialize};
+
+/// Averages of the mode over one cycle of the oscillation.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CycleAverage {
+    /// Start time of the cycle.
+    pub time: Float,
+    /// Mean amplitude.
+    pub amplitude: Float,
+    /// Mean nature angle.
+    pub chi: Float,
+    /// Net change of the orientation angle `n theta_0` over the cycle.
+    pub ntheta_0_drift: Float,
+    /// Number of logged values in the cycle.
+    pub number_of_values: usize,
+}
+
+/// Cycle-averaged observer.
+///
+/// Accumulates the averages over each cycle of the oscillation instead of the
+/// raw samples, shrinking the output by the number of saves per cycle while
+/// retaining the slow dynamics of the amplitude, nature angle, and orientation.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CycleAverageObserver {
+    pub save_info: SaveInfo,
+
+    /// When and how the mode is logged.
+    #[serde(flatten)]
+    pub logging: LogSettings,
+
+    #[serde(skip)]
+    averages: Vec<CycleAverage>,
+
+    /// Sums over the current cycle (divided by the count when finished).
+    #[serde(skip)]
+    current: Option<CycleAverage>,
+
+    #[serde(skip)]
+    previous_nth0: Option<Float>,
+}
+
+impl CycleAverageObserver {
+    pub fn new(output_filepath: &PathBuf, group_name: Option<&str>) -> CycleAverageObserver {
+        let save_info = SaveInfo::for_observer(output_filepath, group_name);
+        CycleAverageObserver {
+            save_info,
+            logging: LogSettings::default(),
+            averages: Vec::new(),
+            current: None,
+            previous_nth0: None,
+        }
+    }
+
+    /// Reserves capacity for the averages of `additional` cycles.
+    pub fn reserve(&mut self, additional: usize) {
+        self.averages.reserve(additional);
+    }
+
+    /// Get the averages of all the cycles, including the (possibly incomplete) last one.
+    pub fn get_averages(&self) -> Vec<CycleAverage> {
+        let mut averages = self.averages.clone();
+        averages.extend(self.current.map(finish));
+        averages
+    }
+
+    /// Clear the averages, including the current cycle, keeping the reserved capacity.
+    pub fn reset(&mut self) {
+        self.averages.clear();
+        self.current = None;
+        self.previous_nth0 = None;
+    }
+}
+
+/// Turn the sums over a cycle into averages.
+fn finish(sums: CycleAverage) -> CycleAverage {
+    let count = sums.number_of_values as Float;
+
+    CycleAverage {
+        amplitude: sums.amplitude / count,
+        chi: sums.chi / count,
+        ..sums
+    }
+}
+
+impl Default for CycleAverageObserver {
+    fn default() -> Self {
+        let output_filepath = PathBuf::from("simulation_cycle_average.hdf5");
+
+        Self::new(&output_filepath, None)
+    }
+}
+
+impl_observer_conversions!(CycleAverageObserver);
+
+impl ObserverTrait for CycleAverageObserver {
+    #[inline]
+    fn log(&mut self, acoustic_mode: &SystemMode, _hrr_mode: &SystemMode, time: Float) {
+        // The time is measured in cycles of the oscillation
+        let start = time.floor();
+        match self.current {
+            Some(sums) if sums.time == start => {}
+            _ => {
+                self.averages.extend(self.current.map(finish));
+                self.current = Some(CycleAverage {
+                    time: start,
+                    ..CycleAverage::default()
+                });
+            }
+        }
+
+        // Change of the orientation angle since the last value, wrapped to (-pi, pi]
+        let nth0 = acoustic_mode.nth0();
+        let dnth0 = match self.previous_nth0 {
+            Some(previous) => {
+                let dnth0 = nth0 - previous;
+                dnth0 - 2.0 * PI * (dnth0 / (2.0 * PI)).round()
+            }
+            None => 0.0,
+        };
+        self.previous_nth0 = Some(nth0);
+
+        if let Some(sums) = &mut self.current {
+            sums.amplitude += acoustic_mode.a();
+            sums.chi += acoustic_mode.chi();
+            sums.ntheta_0_drift += dnth0;
+            sums.number_of_values += 1;
+        }
+    }
+
+    #[cfg(feature = "hdf5")]
+    fn save(
+        &self,
+        parameters: &Parameters,
+        _describing_function: &DescribingFunction,
+    ) -> hdf5::Result<()> {
+        let file = hdf5::File::append(&self.save_info.path)?;
+        let group = file.create_group(&self.save_info.group)?;
+
+        let averages = self.get_averages();
+
+        let time: Vec<Float> = averages.iter().map(|c| c.time).collect();
+        super::write_dataset(&group, &time, "time")?;
+
+        let amplitude: Vec<Float> = averages.iter().map(|c| c.amplitude).collect();
+        super::write_dataset(&group, &amplitude, "amplitude")?;
+
+        let chi: Vec<Float> = averages.iter().map(|c| c.chi).collect();
+        super::write_dataset(&group, &chi, "chi")?;
+
+        let drift: Vec<Float> = averages.iter().map(|c| c.ntheta_0_drift).collect();
+        super::write_dataset(&group, &drift, "ntheta_0_drift")?;
+
+        let count: Vec<usize> = averages.iter().map(|c| c.number_of_values).collect();
+        super::write_dataset(&group, &count, "number_of_values")?;
+
+        super::save_parameters_as_attribute_json(&group, parameters)
+    }
+
+    fn results_json(&self, _describing_function: &DescribingFunction) -> serde_json::Value {
+        let averages = self.get_averages();
+
+        serde_json::json!({
+            "time": averages.iter().map(|c| c.time).collect::<Vec<_>>(),
+            "amplitude": averages.iter().map(|c| c.amplitude).collect::<Vec<_>>(),
+            "chi": averages.iter().map(|c| c.chi).collect::<Vec<_>>(),
+            "ntheta_0_drift": averages.iter().map(|c| c.ntheta_0_drift).collect::<Vec<_>>(),
+            "number_of_values": averages.iter().map(|c| c.number_of_values).collect::<Vec<_>>(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn averages_per_cycle() {
+        let mut observer = CycleAverageObserver::default();
+        for ind in 0..8 {
+            // The orientation angle wraps around pi during the second cycle
+            let nth0 = 2.5 + 0.25 * ind as Float;
+            let mode = SystemMode::new(1.0 + ind as Float, nth0, 0.0, 0.0);
+            observer.log(&mode, &mode, 0.25 * ind as Float);
+        }
+
+        let averages = observer.get_averages();
+        assert_eq!(averages.len(), 2);
+        assert!((averages[0].amplitude - 2.5).abs() < 1e-9);
+        assert!((averages[1].amplitude - 6.5).abs() < 1e-9);
+        assert!((averages[1].ntheta_0_drift - 1.0).abs() < 1e-9);
+    }
+}
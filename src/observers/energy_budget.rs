@@ -0,0 +1,139 @@
+use std::path::PathBuf;
+
+#[cfg(feature = "hdf5")]
+use super::metadata::Content;
+use super::{LogSettings, ObserverTrait, SaveInfo};
+use crate::azimuthal_mode::SystemMode;
+use crate::hrr_integral::DescribingFunction;
+use crate::Float;
+#[cfg(feature = "hdf5")]
+use crate::Parameters;
+use serde::{Deserialize, Serialize};
+
+/// Instantaneous contributions to the amplitude equation.
+///
+/// Decomposition of the real part of the right hand side, which governs
+/// the evolution of the natural logarithm of the amplitude. All terms are
+/// expressed as rates of change of ln(A), with the stochastic term given
+/// as its increment over the time step divided by the time step.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EnergyBudget {
+    /// Work done by the (saturated) flame response.
+    pub gain: Float,
+    /// Acoustic damping loss.
+    pub damping: Float,
+    /// Deterministic drift induced by the noise.
+    pub noise_drift: Float,
+    /// Stochastic noise input.
+    pub noise: Float,
+}
+
+/// Energy budget observer.
+///
+/// Logs the time series of the individual terms of the amplitude equation,
+/// showing which terms dominate the amplitude dynamics.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct EnergyBudgetObserver {
+    pub save_info: SaveInfo,
+
+    /// When and how the mode is logged.
+    #[serde(flatten)]
+    pub logging: LogSettings,
+
+    #[serde(skip)]
+    latest: Option<EnergyBudget>,
+
+    #[serde(skip)]
+    budgets: Vec<EnergyBudget>,
+
+    #[serde(skip)]
+    time: Vec<Float>,
+}
+
+impl EnergyBudgetObserver {
+    pub fn new(output_filepath: &PathBuf, group_name: Option<&str>) -> EnergyBudgetObserver {
+        let save_info = SaveInfo::for_observer(output_filepath, group_name);
+        EnergyBudgetObserver {
+            save_info,
+            logging: LogSettings::default(),
+            latest: None,
+            budgets: Vec::new(),
+            time: Vec::new(),
+        }
+    }
+
+    /// Reserves capacity for storing the time series.
+    pub fn reserve(&mut self, additional: usize) {
+        self.budgets.reserve(additional);
+        self.time.reserve(additional);
+    }
+
+    /// Clear the logged budgets, keeping the settings and the reserved capacity.
+    pub fn reset(&mut self) {
+        self.latest = None;
+        self.budgets.clear();
+        self.time.clear();
+    }
+}
+
+impl Default for EnergyBudgetObserver {
+    fn default() -> Self {
+        let output_filepath = PathBuf::from("simulation_energy_budget.hdf5");
+
+        Self::new(&output_filepath, None)
+    }
+}
+
+impl_observer_conversions!(EnergyBudgetObserver);
+
+impl ObserverTrait for EnergyBudgetObserver {
+    #[inline]
+    fn log(&mut self, _acoustic_mode: &SystemMode, _hrr_mode: &SystemMode, time: Float) {
+        // The initial state has no right hand side associated with it
+        if let Some(budget) = self.latest.take() {
+            self.budgets.push(budget);
+            self.time.push(time);
+        }
+    }
+
+    #[inline]
+    fn log_energy_budget(&mut self, budget: &EnergyBudget) {
+        self.latest = Some(*budget);
+    }
+
+    #[cfg(feature = "hdf5")]
+    fn save(
+        &self,
+        parameters: &Parameters,
+        _describing_function: &DescribingFunction,
+    ) -> hdf5::Result<()> {
+        let file = hdf5::File::append(&self.save_info.path)?;
+        let group = file.create_group(&self.save_info.group)?;
+
+        super::write_described_dataset(&group, &self.time, "time", Content::Values)?;
+
+        let gain: Vec<Float> = self.budgets.iter().map(|b| b.gain).collect();
+        super::write_dataset(&group, &gain, "gain")?;
+
+        let damping: Vec<Float> = self.budgets.iter().map(|b| b.damping).collect();
+        super::write_dataset(&group, &damping, "damping")?;
+
+        let noise_drift: Vec<Float> = self.budgets.iter().map(|b| b.noise_drift).collect();
+        super::write_dataset(&group, &noise_drift, "noise_drift")?;
+
+        let noise: Vec<Float> = self.budgets.iter().map(|b| b.noise).collect();
+        super::write_dataset(&group, &noise, "noise")?;
+
+        super::save_parameters_as_attribute_json(&group, parameters)
+    }
+
+    fn results_json(&self, _describing_function: &DescribingFunction) -> serde_json::Value {
+        serde_json::json!({
+            "time": self.time,
+            "gain": self.budgets.iter().map(|b| b.gain).collect::<Vec<_>>(),
+            "damping": self.budgets.iter().map(|b| b.damping).collect::<Vec<_>>(),
+            "noise_drift": self.budgets.iter().map(|b| b.noise_drift).collect::<Vec<_>>(),
+            "noise": self.budgets.iter().map(|b| b.noise).collect::<Vec<_>>(),
+        })
+    }
+}
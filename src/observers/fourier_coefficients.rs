@@ -0,0 +1,127 @@
+use std::path::PathBuf;
+
+#[cfg(feature = "hdf5")]
+use super::metadata::Content;
+use super::{LogSettings, ObserverTrait, SaveInfo};
+use crate::azimuthal_mode::SystemMode;
+use crate::hrr_integral::{DescribingFunction, GainCoefficients};
+use crate::Float;
+#[cfg(feature = "hdf5")]
+use crate::Parameters;
+use serde::{Deserialize, Serialize};
+
+/// Fourier coefficients observer.
+///
+/// Logs the time series of the Fourier coefficients `n0` and `n2n` (amplitude
+/// and phase `theta2n`) of the saturated gain distribution around the annulus,
+/// which couple the acoustic mode to the heat release rate. Shows how the
+/// nonlinearity of the flames shapes the heat release rate mode during
+/// transients. The coefficients are computed from the instantaneous mode,
+/// also when the logged mode is prefiltered, and are not available for all
+/// describing functions.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FourierCoefficientsObserver {
+    pub save_info: SaveInfo,
+
+    /// When and how the mode is logged.
+    #[serde(flatten)]
+    pub logging: LogSettings,
+
+    #[serde(skip)]
+    latest: Option<GainCoefficients>,
+
+    #[serde(skip)]
+    coefficients: Vec<GainCoefficients>,
+
+    #[serde(skip)]
+    time: Vec<Float>,
+}
+
+impl FourierCoefficientsObserver {
+    pub fn new(output_filepath: &PathBuf, group_name: Option<&str>) -> FourierCoefficientsObserver {
+        let save_info = SaveInfo::for_observer(output_filepath, group_name);
+        FourierCoefficientsObserver {
+            save_info,
+            logging: LogSettings::default(),
+            latest: None,
+            coefficients: Vec::new(),
+            time: Vec::new(),
+        }
+    }
+
+    /// Reserves capacity for storing the time series.
+    pub fn reserve(&mut self, additional: usize) {
+        self.coefficients.reserve(additional);
+        self.time.reserve(additional);
+    }
+
+    /// Logged Fourier coefficients.
+    pub fn get_coefficients(&self) -> &[GainCoefficients] {
+        &self.coefficients
+    }
+
+    /// Clear the logged coefficients, keeping the settings and the reserved capacity.
+    pub fn reset(&mut self) {
+        self.latest = None;
+        self.coefficients.clear();
+        self.time.clear();
+    }
+}
+
+impl Default for FourierCoefficientsObserver {
+    fn default() -> Self {
+        let output_filepath = PathBuf::from("simulation_fourier_coefficients.hdf5");
+
+        Self::new(&output_filepath, None)
+    }
+}
+
+impl_observer_conversions!(FourierCoefficientsObserver);
+
+impl ObserverTrait for FourierCoefficientsObserver {
+    #[inline]
+    fn log(&mut self, _acoustic_mode: &SystemMode, _hrr_mode: &SystemMode, time: Float) {
+        // Nothing is logged if the describing function does not provide the coefficients
+        if let Some(coefficients) = self.latest.take() {
+            self.coefficients.push(coefficients);
+            self.time.push(time);
+        }
+    }
+
+    #[inline]
+    fn log_gain_coefficients(&mut self, coefficients: &GainCoefficients) {
+        self.latest = Some(*coefficients);
+    }
+
+    #[cfg(feature = "hdf5")]
+    fn save(
+        &self,
+        parameters: &Parameters,
+        _describing_function: &DescribingFunction,
+    ) -> hdf5::Result<()> {
+        let file = hdf5::File::append(&self.save_info.path)?;
+        let group = file.create_group(&self.save_info.group)?;
+
+        super::write_described_dataset(&group, &self.time, "time", Content::Values)?;
+
+        let n0: Vec<Float> = self.coefficients.iter().map(|c| c.n0).collect();
+        super::write_dataset(&group, &n0, "n0")?;
+
+        let n2n: Vec<Float> = self.coefficients.iter().map(|c| c.n2n).collect();
+        super::write_dataset(&group, &n2n, "n2n")?;
+
+        let theta2n: Vec<Float> = self.coefficients.iter().map(|c| c.theta2n).collect();
+        super::write_dataset(&group, &theta2n, "theta2n")?;
+
+        super::save_parameters_as_attribute_json(&group, parameters)
+    }
+
+    fn results_json(&self, _describing_function: &DescribingFunction) -> serde_json::Value {
+        serde_json::json!({
+            "time": self.time,
+            "n0": self.coefficients.iter().map(|c| c.n0).collect::<Vec<_>>(),
+            "n2n": self.coefficients.iter().map(|c| c.n2n).collect::<Vec<_>>(),
+            "theta2n": self.coefficients.iter().map(|c| c.theta2n).collect::<Vec<_>>(),
+        })
+    }
+}
@@ -1,10 +1,20 @@
 use std::path::PathBuf;
 
-use super::{ObserverTrait, SaveInfo};
+use super::circular::CircularStatistics;
+use super::kde::KernelDensity;
+#[cfg(feature = "hdf5")]
+use super::kde::{self, Boundary};
+#[cfg(feature = "hdf5")]
+use super::metadata::Content;
+#[cfg(feature = "npz")]
+use super::npz::NpzWriter;
+use super::{LogSettings, ObserverTrait, SaveInfo};
 use crate::azimuthal_mode::SystemMode;
 use crate::hrr_integral::DescribingFunction;
-use crate::{Float, Parameters, PI};
-use hdf5;
+#[cfg(any(feature = "hdf5", feature = "npz"))]
+use crate::Parameters;
+use crate::{Float, PI};
+#[cfg(feature = "hdf5")]
 use ndarray;
 use serde::{Deserialize, Serialize};
 use serde_json;
@@ -13,27 +23,100 @@ use serde_json;
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct HistogramObserver {
     pub save_info: SaveInfo,
+
+    /// When and how the mode is logged.
+    #[serde(flatten)]
+    pub logging: LogSettings,
+
     amplitude_limit: Float,
     max_amplitude_limit: Float,
     nbins: usize,
 
+    /// Additionally save kernel density estimates of the histograms.
+    #[serde(default)]
+    pub kde: Option<KernelDensity>,
+
+    /// Additionally bin the amplitude, orientation angle, and temporal phase
+    /// of the heat release rate mode (its nature angle is always binned).
+    #[serde(default)]
+    pub hrr_mode: bool,
+
+    #[serde(skip)]
+    a: Bins,
+
+    #[serde(skip)]
+    nth0: Bins,
+
+    #[serde(skip)]
+    phi: Bins,
+
+    #[serde(skip)]
+    chi: Bins,
+
+    #[serde(skip)]
+    chi_q: Bins,
+
     #[serde(skip)]
-    a: Vec<usize>,
+    a_q: Bins,
 
     #[serde(skip)]
-    nth0: Vec<usize>,
+    nth0_q: Bins,
 
     #[serde(skip)]
-    phi: Vec<usize>,
+    phi_q: Bins,
 
+    /// Circular statistics of `ntheta_0`, `phi`, `ntheta_0_q`, and `phi_q`.
     #[serde(skip)]
-    chi: Vec<usize>,
+    circular: [CircularStatistics; 4],
 
+    /// Circular statistics of `2 ntheta_0`, measuring the orientation locking.
     #[serde(skip)]
-    chi_q: Vec<usize>,
+    locking: CircularStatistics,
 
     #[serde(skip)]
     num_values: usize,
+
+    #[serde(skip)]
+    total_weight: Float,
+
+    /// Sum of the squared weights, giving the effective sample size of the weighted values.
+    #[serde(skip)]
+    sum_squared_weight: Float,
+
+    #[serde(skip)]
+    weighted: bool,
+}
+
+/// Counts and accumulated weights of a single histogram.
+#[derive(Clone, Debug, Default)]
+struct Bins {
+    counts: Vec<usize>,
+    weights: Vec<Float>,
+}
+
+impl Bins {
+    fn new(nbins: usize) -> Self {
+        Self {
+            counts: vec![0; nbins],
+            weights: vec![0.0; nbins],
+        }
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    #[inline]
+    fn add(&mut self, index: usize, weight: Float) {
+        self.counts[index] += 1;
+        self.weights[index] += weight;
+    }
+
+    fn resize(&mut self, nbins: usize) {
+        self.counts.resize(nbins, 0);
+        self.weights.resize(nbins, 0.0);
+    }
 }
 
 impl HistogramObserver {
@@ -43,24 +126,30 @@ impl HistogramObserver {
         nbins: usize,
         a_lim: Float,
     ) -> HistogramObserver {
-        // Set up the save info
-        let mut save_info = SaveInfo::default();
-        save_info.set_path(output_filepath);
-        if let Some(group) = group_name {
-            save_info.set_group(group);
-        }
+        let save_info = SaveInfo::for_observer(output_filepath, group_name);
 
         HistogramObserver {
             save_info,
+            logging: LogSettings::default(),
             amplitude_limit: a_lim,
             max_amplitude_limit: 10.0 * a_lim,
             nbins,
-            a: vec![0; nbins],
-            nth0: vec![0; nbins],
-            phi: vec![0; nbins],
-            chi: vec![0; nbins],
-            chi_q: vec![0; nbins],
+            kde: None,
+            hrr_mode: false,
+            a: Bins::new(nbins),
+            nth0: Bins::new(nbins),
+            phi: Bins::new(nbins),
+            chi: Bins::new(nbins),
+            chi_q: Bins::new(nbins),
+            a_q: Bins::new(nbins),
+            nth0_q: Bins::new(nbins),
+            phi_q: Bins::new(nbins),
+            circular: [CircularStatistics::default(); 4],
+            locking: CircularStatistics::default(),
             num_values: 0,
+            total_weight: 0.0,
+            sum_squared_weight: 0.0,
+            weighted: false,
         }
     }
 
@@ -75,11 +164,14 @@ impl HistogramObserver {
 
         self.nbins = nbins;
 
-        self.a.resize(nbins, 0);
-        self.nth0.resize(nbins, 0);
-        self.phi.resize(nbins, 0);
-        self.chi.resize(nbins, 0);
-        self.chi_q.resize(nbins, 0);
+        self.a.resize(nbins);
+        self.nth0.resize(nbins);
+        self.phi.resize(nbins);
+        self.chi.resize(nbins);
+        self.chi_q.resize(nbins);
+        self.a_q.resize(nbins);
+        self.nth0_q.resize(nbins);
+        self.phi_q.resize(nbins);
     }
 
     /// Create [`HistogramObserver`] observer from a JSON string.
@@ -105,39 +197,329 @@ impl HistogramObserver {
             )
         }
 
-        self.a.resize((1 + extension_factor) * self.a.len(), 0);
+        // The amplitudes of both modes share the bin edges
+        self.a.resize((1 + extension_factor) * self.a.len());
+        self.a_q.resize((1 + extension_factor) * self.a_q.len());
         self.amplitude_limit *= (1 + extension_factor) as Float;
     }
-}
 
-impl ObserverTrait for HistogramObserver {
+    /// Circular statistics of the periodic variables, with their dataset names.
+    ///
+    /// The statistics of the heat release rate mode are only included with `hrr_mode`.
+    pub fn circular_statistics(&self) -> Vec<(&'static str, CircularStatistics)> {
+        let names = ["ntheta_0", "phi", "ntheta_0_q", "phi_q"];
+        let count = if self.hrr_mode { 4 } else { 2 };
+
+        names.into_iter().zip(self.circular).take(count).collect()
+    }
+
+    /// Strength of the locking of the orientation to the reference angle.
+    ///
+    /// Given by the mean of `cos(2 ntheta_0)`, as the orientation angles `ntheta_0`
+    /// and `ntheta_0 + pi` describe the same standing mode. It is 1 when the
+    /// anti-nodes are locked to the reference, -1 when the nodes are locked to
+    /// it, and 0 without a preferred orientation.
+    pub fn orientation_locking(&self) -> Float {
+        self.locking.mean_cos()
+    }
+
+    /// Bin centres and counts of the amplitude histogram.
+    pub fn amplitude_histogram(&self) -> (Vec<Float>, &[usize]) {
+        let centers = bin_centers(self.amplitude_limit, self.a.len(), 0.0);
+        (centers, &self.a.counts)
+    }
+
+    /// Bin centres and accumulated weights of the amplitude histogram.
+    ///
+    /// The weights are only accumulated with importance sampling, where the
+    /// weights divided by [`HistogramObserver::number_of_values`] are unbiased
+    /// estimates of the probabilities of the bins.
+    pub fn weighted_amplitude_histogram(&self) -> (Vec<Float>, &[Float]) {
+        let centers = bin_centers(self.amplitude_limit, self.a.len(), 0.0);
+        (centers, &self.a.weights)
+    }
+
+    /// Number of binned values.
+    pub fn number_of_values(&self) -> usize {
+        self.num_values
+    }
+
+    /// Bin centres (in `[-pi/4, pi/4)`) and counts of the nature angle histogram.
+    pub fn nature_angle_histogram(&self) -> (Vec<Float>, &[usize]) {
+        let centers = bin_centers(PI / 2.0, self.chi.len(), PI / 4.0);
+        (centers, &self.chi.counts)
+    }
+
+    /// Save the histograms to the `.npz` file given by the save information.
+    ///
+    /// The arrays are named as the datasets of the HDF5 output. Kernel
+    /// density estimates are not included.
+    #[cfg(feature = "npz")]
+    pub fn save_npz(&self, parameters: &Parameters) -> std::io::Result<()> {
+        let mut writer = NpzWriter::open(&self.save_info)?;
+
+        let mut variables = vec![
+            (
+                "amplitude",
+                &self.a,
+                get_bin_edges(0.0, self.amplitude_limit, self.a.len()),
+            ),
+            (
+                "ntheta_0",
+                &self.nth0,
+                get_bin_edges(-PI, PI, self.nth0.len()),
+            ),
+            ("phi", &self.phi, get_bin_edges(-PI, PI, self.phi.len())),
+            (
+                "chi",
+                &self.chi,
+                get_bin_edges(-PI / 4.0, PI / 4.0, self.chi.len()),
+            ),
+            (
+                "chi_q",
+                &self.chi_q,
+                get_bin_edges(-PI / 4.0, PI / 4.0, self.chi_q.len()),
+            ),
+        ];
+        if self.hrr_mode {
+            variables.extend([
+                (
+                    "amplitude_q",
+                    &self.a_q,
+                    get_bin_edges(0.0, self.amplitude_limit, self.a_q.len()),
+                ),
+                (
+                    "ntheta_0_q",
+                    &self.nth0_q,
+                    get_bin_edges(-PI, PI, self.nth0_q.len()),
+                ),
+                (
+                    "phi_q",
+                    &self.phi_q,
+                    get_bin_edges(-PI, PI, self.phi_q.len()),
+                ),
+            ]);
+        }
+        for (name, bins, edges) in variables {
+            writer.write(name, &bins.counts)?;
+            writer.write(&format!("bin_edges/{}", name), &edges)?;
+            if self.weighted {
+                writer.write(&format!("weighted/{}", name), &bins.weights)?;
+            }
+        }
+
+        if self.weighted {
+            writer.write_scalar("weighted/total_weight", self.total_weight)?;
+        }
+        for (name, statistics) in self.circular_statistics() {
+            let prefix = format!("circular/{}", name);
+            writer.write_scalar(&format!("{}/mean", prefix), statistics.mean())?;
+            let length = statistics.resultant_length();
+            writer.write_scalar(&format!("{}/resultant_length", prefix), length)?;
+            let kappa = statistics.von_mises_concentration();
+            writer.write_scalar(&format!("{}/von_mises_kappa", prefix), kappa)?;
+        }
+        writer.write_scalar("orientation_locking", self.orientation_locking())?;
+        if let Some(reference_angle) = self.logging.reference_angle {
+            writer.write_scalar("reference_angle", reference_angle)?;
+        }
+        writer.write_scalar("number_of_values", self.num_values)?;
+        writer.write_str("parameters", &parameters.to_string())?;
+        writer.finish()
+    }
+
+    /// Save the kernel density estimates of the histograms in a subgroup.
+    #[cfg(feature = "hdf5")]
+    fn save_kde(&self, group: &hdf5::Group, settings: &KernelDensity) -> hdf5::Result<()> {
+        let kde_group = group.create_group("kde")?;
+
+        // The periodic and nature angle histograms are binned using the modulo of the limit
+        let nth0_boundary = Boundary::Periodic { period: 2.0 * PI };
+        let chi_boundary = Boundary::Reflect {
+            lower: -PI / 4.0,
+            upper: PI / 4.0,
+        };
+        // Only the lower boundary is physical for the amplitude
+        let a_boundary = Boundary::Reflect {
+            lower: 0.0,
+            upper: Float::INFINITY,
+        };
+        let mut variables = vec![
+            (
+                "amplitude",
+                &self.a,
+                bin_centers(self.amplitude_limit, self.a.len(), 0.0),
+                a_boundary,
+            ),
+            (
+                "ntheta_0",
+                &self.nth0,
+                bin_centers(2.0 * PI, self.nth0.len(), PI),
+                nth0_boundary,
+            ),
+            (
+                "phi",
+                &self.phi,
+                bin_centers(2.0 * PI, self.phi.len(), PI),
+                nth0_boundary,
+            ),
+            (
+                "chi",
+                &self.chi,
+                bin_centers(PI / 2.0, self.chi.len(), PI / 4.0),
+                chi_boundary,
+            ),
+            (
+                "chi_q",
+                &self.chi_q,
+                bin_centers(PI / 2.0, self.chi_q.len(), PI / 4.0),
+                chi_boundary,
+            ),
+        ];
+        if self.hrr_mode {
+            variables.extend([
+                (
+                    "amplitude_q",
+                    &self.a_q,
+                    bin_centers(self.amplitude_limit, self.a_q.len(), 0.0),
+                    a_boundary,
+                ),
+                (
+                    "ntheta_0_q",
+                    &self.nth0_q,
+                    bin_centers(2.0 * PI, self.nth0_q.len(), PI),
+                    nth0_boundary,
+                ),
+                (
+                    "phi_q",
+                    &self.phi_q,
+                    bin_centers(2.0 * PI, self.phi_q.len(), PI),
+                    nth0_boundary,
+                ),
+            ]);
+        }
+
+        let effective_sample_size =
+            kde::effective_sample_size(self.total_weight, self.sum_squared_weight);
+        for (name, bins, centers, boundary) in variables {
+            let weights: Vec<Float> = if self.weighted {
+                bins.weights.clone()
+            } else {
+                bins.counts.iter().map(|&c| c as Float).collect()
+            };
+            let (grid, density) = kde::binned_kde(
+                &centers,
+                &weights,
+                effective_sample_size,
+                boundary,
+                settings,
+            );
+
+            let variable_group = kde_group.create_group(name)?;
+            super::write_dataset(&variable_group, &grid, "grid")?;
+            super::write_dataset(&variable_group, &density, "pdf")?;
+        }
+
+        Ok(())
+    }
+
+    /// Bin the state space parameters with the given statistical weight.
     #[inline]
-    fn log(&mut self, acoustic_mode: &SystemMode, hrr_mode: &SystemMode, _time: Float) {
+    fn bin(&mut self, acoustic_mode: &SystemMode, hrr_mode: &SystemMode, weight: Float) {
         // Check if the amplitude range needs to be expanded
-        if acoustic_mode.a() >= self.amplitude_limit {
-            self.expand_amplitude_range(acoustic_mode.a());
+        let max_amplitude = if self.hrr_mode {
+            acoustic_mode.a().max(hrr_mode.a())
+        } else {
+            acoustic_mode.a()
+        };
+        if max_amplitude >= self.amplitude_limit {
+            self.expand_amplitude_range(max_amplitude);
         }
 
         // Calculate the bin index for each state space parameter and then
-        let a_bin = get_index(acoustic_mode.a(), self.amplitude_limit, &self.a);
-        self.a[a_bin] += 1;
+        let a_bin = get_index(acoustic_mode.a(), self.amplitude_limit, self.a.len());
+        self.a.add(a_bin, weight);
 
-        let nth0_bin = get_index(acoustic_mode.nth0(), 2.0 * PI, &self.nth0);
-        self.nth0[nth0_bin] += 1;
+        let nth0_bin = get_index(acoustic_mode.nth0(), 2.0 * PI, self.nth0.len());
+        self.nth0.add(nth0_bin, weight);
 
-        let phi_bin = get_index(acoustic_mode.phi(), 2.0 * PI, &self.phi);
-        self.phi[phi_bin] += 1;
+        let phi_bin = get_index(acoustic_mode.phi(), 2.0 * PI, self.phi.len());
+        self.phi.add(phi_bin, weight);
 
-        let chi_bin = get_index(acoustic_mode.chi(), PI / 2.0, &self.chi);
-        self.chi[chi_bin] += 1;
+        let chi_bin = get_index(acoustic_mode.chi(), PI / 2.0, self.chi.len());
+        self.chi.add(chi_bin, weight);
 
-        let chi_q_bin = get_index(hrr_mode.chi(), PI / 2.0, &self.chi_q);
-        self.chi_q[chi_q_bin] += 1;
+        let chi_q_bin = get_index(hrr_mode.chi(), PI / 2.0, self.chi_q.len());
+        self.chi_q.add(chi_q_bin, weight);
 
-        // Update the total number of values
+        if self.hrr_mode {
+            let a_q_bin = get_index(hrr_mode.a(), self.amplitude_limit, self.a_q.len());
+            self.a_q.add(a_q_bin, weight);
+
+            let nth0_q_bin = get_index(hrr_mode.nth0(), 2.0 * PI, self.nth0_q.len());
+            self.nth0_q.add(nth0_q_bin, weight);
+
+            let phi_q_bin = get_index(hrr_mode.phi(), 2.0 * PI, self.phi_q.len());
+            self.phi_q.add(phi_q_bin, weight);
+        }
+
+        self.circular[0].push(acoustic_mode.nth0(), weight);
+        self.circular[1].push(acoustic_mode.phi(), weight);
+        self.locking.push(2.0 * acoustic_mode.nth0(), weight);
+        if self.hrr_mode {
+            self.circular[2].push(hrr_mode.nth0(), weight);
+            self.circular[3].push(hrr_mode.phi(), weight);
+        }
+
+        // Update the total number of values and their weight
         self.num_values += 1;
+        self.total_weight += weight;
+        self.sum_squared_weight += weight * weight;
+    }
+
+    /// Clear the counts, weights, and statistics, keeping the bins and the settings.
+    pub fn reset(&mut self) {
+        for bins in [
+            &mut self.a,
+            &mut self.nth0,
+            &mut self.phi,
+            &mut self.chi,
+            &mut self.chi_q,
+            &mut self.a_q,
+            &mut self.nth0_q,
+            &mut self.phi_q,
+        ] {
+            bins.counts.fill(0);
+            bins.weights.fill(0.0);
+        }
+        self.circular = [CircularStatistics::default(); 4];
+        self.locking = CircularStatistics::default();
+        self.num_values = 0;
+        self.total_weight = 0.0;
+        self.sum_squared_weight = 0.0;
+        self.weighted = false;
+    }
+}
+
+impl ObserverTrait for HistogramObserver {
+    #[inline]
+    fn log(&mut self, acoustic_mode: &SystemMode, hrr_mode: &SystemMode, _time: Float) {
+        self.bin(acoustic_mode, hrr_mode, 1.0);
+    }
+
+    #[inline]
+    fn log_weighted(
+        &mut self,
+        acoustic_mode: &SystemMode,
+        hrr_mode: &SystemMode,
+        _time: Float,
+        weight: Float,
+    ) {
+        self.weighted = true;
+        self.bin(acoustic_mode, hrr_mode, weight);
     }
 
+    #[cfg(feature = "hdf5")]
     fn save(
         &self,
         setup: &Parameters,
@@ -151,11 +533,86 @@ impl ObserverTrait for HistogramObserver {
         let group = file.create_group(group_name)?;
 
         // Save the actual (non-normalised) histograms
-        super::write_dataset(&group, &self.a, "amplitude")?;
-        super::write_dataset(&group, &self.nth0, "ntheta_0")?;
-        super::write_dataset(&group, &self.phi, "phi")?;
-        super::write_dataset(&group, &self.chi, "chi")?;
-        super::write_dataset(&group, &self.chi_q, "chi_q")?;
+        super::write_described_dataset(&group, &self.a.counts, "amplitude", Content::Counts)?;
+        super::write_described_dataset(&group, &self.nth0.counts, "ntheta_0", Content::Counts)?;
+        super::write_described_dataset(&group, &self.phi.counts, "phi", Content::Counts)?;
+        super::write_described_dataset(&group, &self.chi.counts, "chi", Content::Counts)?;
+        super::write_described_dataset(&group, &self.chi_q.counts, "chi_q", Content::Counts)?;
+        if self.hrr_mode {
+            super::write_described_dataset(
+                &group,
+                &self.a_q.counts,
+                "amplitude_q",
+                Content::Counts,
+            )?;
+            super::write_described_dataset(
+                &group,
+                &self.nth0_q.counts,
+                "ntheta_0_q",
+                Content::Counts,
+            )?;
+            super::write_described_dataset(&group, &self.phi_q.counts, "phi_q", Content::Counts)?;
+        }
+
+        // Save the weighted histograms if importance sampling was used
+        if self.weighted {
+            let weight_group = group.create_group("weighted")?;
+            super::write_described_dataset(
+                &weight_group,
+                &self.a.weights,
+                "amplitude",
+                Content::Weights,
+            )?;
+            super::write_described_dataset(
+                &weight_group,
+                &self.nth0.weights,
+                "ntheta_0",
+                Content::Weights,
+            )?;
+            super::write_described_dataset(
+                &weight_group,
+                &self.phi.weights,
+                "phi",
+                Content::Weights,
+            )?;
+            super::write_described_dataset(
+                &weight_group,
+                &self.chi.weights,
+                "chi",
+                Content::Weights,
+            )?;
+            super::write_described_dataset(
+                &weight_group,
+                &self.chi_q.weights,
+                "chi_q",
+                Content::Weights,
+            )?;
+            if self.hrr_mode {
+                super::write_described_dataset(
+                    &weight_group,
+                    &self.a_q.weights,
+                    "amplitude_q",
+                    Content::Weights,
+                )?;
+                super::write_described_dataset(
+                    &weight_group,
+                    &self.nth0_q.weights,
+                    "ntheta_0_q",
+                    Content::Weights,
+                )?;
+                super::write_described_dataset(
+                    &weight_group,
+                    &self.phi_q.weights,
+                    "phi_q",
+                    Content::Weights,
+                )?;
+            }
+            super::save_attr(
+                &weight_group,
+                &ndarray::arr0(self.total_weight),
+                "total_weight",
+            )?;
+        }
 
         // Calculate the bin edges
         let a_edges = get_bin_edges(0.0, self.amplitude_limit, self.a.len());
@@ -166,26 +623,121 @@ impl ObserverTrait for HistogramObserver {
 
         // Save the bin edges in a subgroup
         let edge_group = group.create_group("bin_edges")?;
-        super::write_dataset(&edge_group, &a_edges, "amplitude")?;
-        super::write_dataset(&edge_group, &nth0_edges, "ntheta_0")?;
-        super::write_dataset(&edge_group, &phi_edges, "phi")?;
-        super::write_dataset(&edge_group, &chi_edges, "chi")?;
-        super::write_dataset(&edge_group, &chi_q_edges, "chi_q")?;
+        super::write_described_dataset(&edge_group, &a_edges, "amplitude", Content::BinEdges)?;
+        super::write_described_dataset(&edge_group, &nth0_edges, "ntheta_0", Content::BinEdges)?;
+        super::write_described_dataset(&edge_group, &phi_edges, "phi", Content::BinEdges)?;
+        super::write_described_dataset(&edge_group, &chi_edges, "chi", Content::BinEdges)?;
+        super::write_described_dataset(&edge_group, &chi_q_edges, "chi_q", Content::BinEdges)?;
+        if self.hrr_mode {
+            // The bins of the heat release rate mode match the acoustic mode
+            super::write_described_dataset(
+                &edge_group,
+                &a_edges,
+                "amplitude_q",
+                Content::BinEdges,
+            )?;
+            super::write_described_dataset(
+                &edge_group,
+                &nth0_edges,
+                "ntheta_0_q",
+                Content::BinEdges,
+            )?;
+            super::write_described_dataset(&edge_group, &phi_edges, "phi_q", Content::BinEdges)?;
+        }
+
+        // Save the circular statistics of the periodic variables
+        let circular_group = group.create_group("circular")?;
+        for (name, statistics) in self.circular_statistics() {
+            let variable_group = circular_group.create_group(name)?;
+            super::save_attr(&variable_group, &ndarray::arr0(statistics.mean()), "mean")?;
+            super::save_attr(
+                &variable_group,
+                &ndarray::arr0(statistics.resultant_length()),
+                "resultant_length",
+            )?;
+            super::save_attr(
+                &variable_group,
+                &ndarray::arr0(statistics.von_mises_concentration()),
+                "von_mises_kappa",
+            )?;
+        }
+
+        // Save the smoothed probability density functions
+        if let Some(settings) = &self.kde {
+            self.save_kde(&group, settings)?;
+        }
+
+        // Save the orientation locking relative to the reference angle (or to zero)
+        let locking = self.orientation_locking();
+        super::save_attr(&group, &ndarray::arr0(locking), "orientation_locking")?;
+        if let Some(reference_angle) = self.logging.reference_angle {
+            super::save_attr(&group, &ndarray::arr0(reference_angle), "reference_angle")?;
+        }
 
         // Save the number of values
         super::save_attr(&group, &ndarray::arr0(self.num_values), "number_of_values")?;
         // Save the setup as an attribute
         super::save_parameters_as_attribute_json(&group, setup)
     }
-}
 
-impl std::fmt::Display for HistogramObserver {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let data_string = serde_json::to_string_pretty(self).unwrap_or_default();
-        write!(f, "HistogramObserver: {}", data_string)
+    fn results_json(&self, _describing_function: &DescribingFunction) -> serde_json::Value {
+        let mut bins = vec![
+            ("amplitude", &self.a, 0.0, self.amplitude_limit),
+            ("ntheta_0", &self.nth0, -PI, PI),
+            ("phi", &self.phi, -PI, PI),
+            ("chi", &self.chi, -PI / 4.0, PI / 4.0),
+            ("chi_q", &self.chi_q, -PI / 4.0, PI / 4.0),
+        ];
+        if self.hrr_mode {
+            bins.push(("amplitude_q", &self.a_q, 0.0, self.amplitude_limit));
+            bins.push(("ntheta_0_q", &self.nth0_q, -PI, PI));
+            bins.push(("phi_q", &self.phi_q, -PI, PI));
+        }
+
+        let mut results = serde_json::Map::new();
+        let mut weighted = serde_json::Map::new();
+        let mut edges = serde_json::Map::new();
+        for (name, histogram, min, max) in bins {
+            results.insert(name.to_owned(), serde_json::json!(histogram.counts));
+            weighted.insert(name.to_owned(), serde_json::json!(histogram.weights));
+            let bin_edges = get_bin_edges(min, max, histogram.len());
+            edges.insert(name.to_owned(), serde_json::json!(bin_edges));
+        }
+        if self.weighted {
+            weighted.insert("total_weight".to_owned(), self.total_weight.into());
+            results.insert("weighted".to_owned(), weighted.into());
+        }
+        results.insert("bin_edges".to_owned(), edges.into());
+
+        let circular: serde_json::Map<_, _> = self
+            .circular_statistics()
+            .into_iter()
+            .map(|(name, statistics)| {
+                let statistics = serde_json::json!({
+                    "mean": statistics.mean(),
+                    "resultant_length": statistics.resultant_length(),
+                    "von_mises_kappa": statistics.von_mises_concentration(),
+                });
+                (name.to_owned(), statistics)
+            })
+            .collect();
+        results.insert("circular".to_owned(), circular.into());
+        results.insert(
+            "orientation_locking".to_owned(),
+            self.orientation_locking().into(),
+        );
+        results.insert(
+            "reference_angle".to_owned(),
+            self.logging.reference_angle.into(),
+        );
+        results.insert("number_of_values".to_owned(), self.num_values.into());
+
+        results.into()
     }
 }
 
+impl_observer_conversions!(HistogramObserver);
+
 impl Default for HistogramObserver {
     fn default() -> Self {
         let output_filepath = PathBuf::from("simulation_histogram.hdf5");
@@ -197,25 +749,16 @@ impl Default for HistogramObserver {
     }
 }
 
-impl From<SaveInfo> for HistogramObserver {
-    fn from(value: SaveInfo) -> Self {
-        let mut ho = Self::default();
-        ho.save_info = value;
-
-        ho
-    }
-}
-
 #[inline]
-fn get_index(num: Float, limit: Float, bin_vec: &Vec<usize>) -> usize {
+fn get_index(num: Float, limit: Float, nbins: usize) -> usize {
     if num > limit + Float::EPSILON {
         println!("Number: {}\t Limit: {}", num, limit);
     }
     if num >= limit {
-        return bin_vec.len() - 1;
+        return nbins - 1;
     }
 
-    Float::floor((modulo(num, limit) / limit) * bin_vec.len() as Float) as usize
+    Float::floor((modulo(num, limit) / limit) * nbins as Float) as usize
 }
 
 #[inline]
@@ -223,6 +766,25 @@ fn modulo(num: Float, limit: Float) -> Float {
     ((num % limit) + limit) % limit
 }
 
+/// Centers of the bins in terms of the binned quantity.
+///
+/// Bins are filled using the modulo of `limit`, meaning that values
+/// at or above `wrap` correspond to negative values of the quantity.
+fn bin_centers(limit: Float, len: usize, wrap: Float) -> Vec<Float> {
+    let bin_length = limit / len as Float;
+
+    (0..len)
+        .map(|ind| {
+            let center = bin_length * (ind as Float + 0.5);
+            if wrap > 0.0 && center >= wrap {
+                center - limit
+            } else {
+                center
+            }
+        })
+        .collect()
+}
+
 fn get_bin_edges(min: Float, max: Float, len: usize) -> Vec<Float> {
     // There are `len` number of intervals, meaning there should be
     // `len + 1` values for the edges
@@ -230,3 +792,27 @@ fn get_bin_edges(min: Float, max: Float, len: usize) -> Vec<Float> {
 
     (0..=len).map(|ind| bin_length * ind as Float).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hrr_mode_bins() {
+        let mut observer = HistogramObserver {
+            hrr_mode: true,
+            ..Default::default()
+        };
+        let acoustic_mode = SystemMode::new(1.0, 0.0, 0.0, 0.0);
+        let hrr_mode = SystemMode::new(25.0, 1.0, -1.0, 0.1);
+        observer.log(&acoustic_mode, &hrr_mode, 0.0);
+
+        // The amplitude range is expanded for the heat release rate mode as well
+        assert_eq!(observer.a.len(), observer.a_q.len());
+        assert!(observer.amplitude_limit > 25.0);
+        assert_eq!(observer.a_q.counts.iter().sum::<usize>(), 1);
+
+        let nth0_q_bin = get_index(1.0, 2.0 * PI, observer.nth0_q.len());
+        assert_eq!(observer.nth0_q.counts[nth0_q_bin], 1);
+    }
+}
@@ -0,0 +1,149 @@
+use std::path::PathBuf;
+
+#[cfg(feature = "hdf5")]
+use super::metadata::Content;
+use super::{LogSettings, ObserverTrait, SaveInfo};
+use crate::azimuthal_mode::SystemMode;
+use crate::hrr_integral::{DescribingFunction, IntegralTerms};
+#[cfg(feature = "hdf5")]
+use crate::Parameters;
+use crate::{Float, Quaternion};
+use serde::{Deserialize, Serialize};
+
+/// Integral terms observer.
+///
+/// Logs the time series of the individual contributions to the right hand
+/// side of the governing equation (the `n0` and `n2n` terms of the heat
+/// release rate integral, the damping, and the noise-induced drift), such that
+/// e.g. the drift of the nature angle can be attributed to specific physical
+/// terms. Each term is saved as a dataset with the columns `real`, `imag_i`,
+/// `imag_j`, and `imag_k`, computed from the instantaneous mode.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IntegralTermsObserver {
+    pub save_info: SaveInfo,
+
+    /// When and how the mode is logged.
+    #[serde(flatten)]
+    pub logging: LogSettings,
+
+    #[serde(skip)]
+    latest: Option<IntegralTerms>,
+
+    #[serde(skip)]
+    terms: Vec<IntegralTerms>,
+
+    #[serde(skip)]
+    time: Vec<Float>,
+}
+
+impl IntegralTermsObserver {
+    pub fn new(output_filepath: &PathBuf, group_name: Option<&str>) -> IntegralTermsObserver {
+        let save_info = SaveInfo::for_observer(output_filepath, group_name);
+        IntegralTermsObserver {
+            save_info,
+            logging: LogSettings::default(),
+            latest: None,
+            terms: Vec::new(),
+            time: Vec::new(),
+        }
+    }
+
+    /// Reserves capacity for storing the time series.
+    pub fn reserve(&mut self, additional: usize) {
+        self.terms.reserve(additional);
+        self.time.reserve(additional);
+    }
+
+    /// Logged contributions to the right hand side.
+    pub fn get_terms(&self) -> &[IntegralTerms] {
+        &self.terms
+    }
+
+    /// Clear the logged terms, keeping the settings and the reserved capacity.
+    pub fn reset(&mut self) {
+        self.latest = None;
+        self.terms.clear();
+        self.time.clear();
+    }
+}
+
+impl Default for IntegralTermsObserver {
+    fn default() -> Self {
+        let output_filepath = PathBuf::from("simulation_integral_terms.hdf5");
+
+        Self::new(&output_filepath, None)
+    }
+}
+
+impl_observer_conversions!(IntegralTermsObserver);
+
+impl ObserverTrait for IntegralTermsObserver {
+    #[inline]
+    fn log(&mut self, _acoustic_mode: &SystemMode, _hrr_mode: &SystemMode, time: Float) {
+        // Nothing is logged if the describing function does not provide the terms
+        if let Some(terms) = self.latest.take() {
+            self.terms.push(terms);
+            self.time.push(time);
+        }
+    }
+
+    #[inline]
+    fn log_integral_terms(&mut self, terms: &IntegralTerms) {
+        self.latest = Some(*terms);
+    }
+
+    #[cfg(feature = "hdf5")]
+    fn save(
+        &self,
+        parameters: &Parameters,
+        _describing_function: &DescribingFunction,
+    ) -> hdf5::Result<()> {
+        let file = hdf5::File::append(&self.save_info.path)?;
+        let group = file.create_group(&self.save_info.group)?;
+
+        super::write_described_dataset(&group, &self.time, "time", Content::Values)?;
+
+        let terms = [
+            ("n0", self.terms.iter().map(|t| t.n0).collect::<Vec<_>>()),
+            ("n2n", self.terms.iter().map(|t| t.n2n).collect()),
+            ("damping", self.terms.iter().map(|t| t.damping).collect()),
+            (
+                "noise_drift",
+                self.terms.iter().map(|t| t.noise_drift).collect(),
+            ),
+        ];
+        for (name, term) in terms {
+            let values: Vec<Float> = term
+                .iter()
+                .flat_map(|q| [q.real, q.imag_i, q.imag_j, q.imag_k])
+                .collect();
+            let values = ndarray::Array2::from_shape_vec((term.len(), 4), values)
+                .map_err(|e| e.to_string())?;
+            group
+                .new_dataset_builder()
+                .with_data(&values)
+                .create(name)?;
+        }
+
+        super::save_parameters_as_attribute_json(&group, parameters)
+    }
+
+    fn results_json(&self, _describing_function: &DescribingFunction) -> serde_json::Value {
+        // Each term as rows of (real, i, j, k) as in the saved matrices
+        let rows = |term: fn(&IntegralTerms) -> Quaternion| -> Vec<[Float; 4]> {
+            self.terms
+                .iter()
+                .map(term)
+                .map(|q| [q.real, q.imag_i, q.imag_j, q.imag_k])
+                .collect()
+        };
+
+        serde_json::json!({
+            "time": self.time,
+            "n0": rows(|t| t.n0),
+            "n2n": rows(|t| t.n2n),
+            "damping": rows(|t| t.damping),
+            "noise_drift": rows(|t| t.noise_drift),
+        })
+    }
+}
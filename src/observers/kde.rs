@@ -0,0 +1,197 @@
+//! Gaussian kernel density estimates from binned data.
+
+use crate::Float;
+#[cfg(feature = "hdf5")]
+use crate::PI;
+use serde::{Deserialize, Serialize};
+
+/// Settings for the Gaussian kernel density estimates.
+///
+/// The bandwidth is found from Silverman's rule of thumb (with Kish's
+/// effective sample size for weighted values), and can be adjusted with `bandwidth_scale`. The density is evaluated at
+/// `grid_points` equidistant points spanning the range of each variable.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct KernelDensity {
+    pub grid_points: usize,
+    pub bandwidth_scale: Float,
+}
+
+impl Default for KernelDensity {
+    fn default() -> Self {
+        Self {
+            grid_points: 200,
+            bandwidth_scale: 1.0,
+        }
+    }
+}
+
+/// Treatment of the boundaries of the domain of the density.
+#[cfg(feature = "hdf5")]
+#[derive(Clone, Copy, Debug)]
+pub(super) enum Boundary {
+    /// Reflect the kernels about the lower and upper limits, preserving the total mass.
+    Reflect { lower: Float, upper: Float },
+    /// Periodic domain of the given period.
+    Periodic { period: Float },
+}
+
+#[cfg(feature = "hdf5")]
+impl Boundary {
+    /// Range of the domain used for the evaluation grid.
+    fn range(&self, centers: &[Float]) -> (Float, Float) {
+        match *self {
+            Self::Reflect { lower, upper } if upper.is_finite() => (lower, upper),
+            Self::Reflect { lower, .. } => {
+                let max = centers.iter().cloned().fold(lower, Float::max);
+                (lower, max)
+            }
+            Self::Periodic { period } => (-0.5 * period, 0.5 * period),
+        }
+    }
+}
+
+/// Kish's effective sample size `(sum w)^2 / sum w^2` of weighted values,
+/// which is the number of values if all the weights are equal.
+#[cfg(feature = "hdf5")]
+pub(super) fn effective_sample_size(total_weight: Float, sum_squared_weight: Float) -> Float {
+    if sum_squared_weight <= 0.0 {
+        return 0.0;
+    }
+
+    total_weight.powi(2) / sum_squared_weight
+}
+
+/// Evaluate the kernel density estimate of the binned data.
+///
+/// Returns the evaluation grid and the (normalized) density. The bins are
+/// described by their centers and the accumulated weight in each bin, and
+/// `effective_sample_size` is the (effective) number of values behind the bins.
+#[cfg(feature = "hdf5")]
+pub(super) fn binned_kde(
+    centers: &[Float],
+    weights: &[Float],
+    effective_sample_size: Float,
+    boundary: Boundary,
+    settings: &KernelDensity,
+) -> (Vec<Float>, Vec<Float>) {
+    let (min, max) = boundary.range(centers);
+    let n = settings.grid_points.max(2);
+    let spacing = (max - min) / (n - 1) as Float;
+    let grid: Vec<Float> = (0..n).map(|ind| min + spacing * ind as Float).collect();
+
+    let total_weight: Float = weights.iter().sum();
+    if total_weight <= 0.0 {
+        return (grid, vec![0.0; n]);
+    }
+
+    let bandwidth =
+        settings.bandwidth_scale * silverman_bandwidth(centers, weights, effective_sample_size);
+    let kernel = |x: Float| (-0.5 * (x / bandwidth).powi(2)).exp();
+    let normalization = total_weight * bandwidth * (2.0 * PI).sqrt();
+
+    let density = grid
+        .iter()
+        .map(|&x| {
+            let sum: Float = centers
+                .iter()
+                .zip(weights)
+                .filter(|(_, &w)| w > 0.0)
+                .map(|(&c, &w)| {
+                    let images = match boundary {
+                        Boundary::Reflect { lower, upper } => {
+                            kernel(x - c)
+                                + kernel(x - (2.0 * lower - c))
+                                + kernel(x - (2.0 * upper - c))
+                        }
+                        Boundary::Periodic { period } => {
+                            kernel(x - c) + kernel(x - c - period) + kernel(x - c + period)
+                        }
+                    };
+                    w * images
+                })
+                .sum();
+            sum / normalization
+        })
+        .collect();
+
+    (grid, density)
+}
+
+/// Silverman's rule of thumb for the bandwidth of binned data.
+#[cfg(feature = "hdf5")]
+fn silverman_bandwidth(
+    centers: &[Float],
+    weights: &[Float],
+    effective_sample_size: Float,
+) -> Float {
+    let total_weight: Float = weights.iter().sum();
+    let mean = centers
+        .iter()
+        .zip(weights)
+        .map(|(c, w)| c * w)
+        .sum::<Float>()
+        / total_weight;
+    let variance = centers
+        .iter()
+        .zip(weights)
+        .map(|(c, w)| w * (c - mean).powi(2))
+        .sum::<Float>()
+        / total_weight;
+
+    // Never go below the bin width, as there is no information at smaller scales
+    let bin_width = match centers {
+        [first, second, ..] => (second - first).abs(),
+        _ => Float::EPSILON,
+    };
+    // The sum of the (e.g. importance sampling) weights is not a number of values
+    let number_of_values = effective_sample_size.max(1.0);
+
+    (1.06 * variance.sqrt() * number_of_values.powf(-0.2)).max(bin_width)
+}
+
+#[cfg(all(test, feature = "hdf5"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reflected_density_is_normalized() {
+        // All the mass close to the lower boundary
+        let centers: Vec<Float> = (0..50).map(|ind| -0.75 + 0.03 * ind as Float).collect();
+        let mut weights = vec![0.0; centers.len()];
+        weights[0] = 10.0;
+        weights[1] = 5.0;
+
+        let boundary = Boundary::Reflect {
+            lower: -0.75,
+            upper: 0.75,
+        };
+        let settings = KernelDensity::default();
+        let (grid, density) = binned_kde(&centers, &weights, 15.0, boundary, &settings);
+
+        // Trapezoidal integration over the domain
+        let integral: Float = grid
+            .windows(2)
+            .zip(density.windows(2))
+            .map(|(x, f)| 0.5 * (x[1] - x[0]) * (f[0] + f[1]))
+            .sum();
+
+        assert!((integral - 1.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn bandwidth_uses_the_effective_sample_size() {
+        // Equal weights give the number of values, independent of their scale
+        assert!((effective_sample_size(0.5 * 32.0, 0.25 * 32.0) - 32.0).abs() < 1e-12);
+        // A single dominant weight gives about one value
+        let weights = [1.0, 1e-6, 1e-6, 1e-6];
+        let sum: Float = weights.iter().sum();
+        let sum_squared: Float = weights.iter().map(|w| w * w).sum();
+        assert!((effective_sample_size(sum, sum_squared) - 1.0).abs() < 1e-3);
+
+        let centers: Vec<Float> = (0..100).map(|ind| 0.01 * ind as Float).collect();
+        let weights = vec![1e-3; centers.len()];
+        let small = silverman_bandwidth(&centers, &weights, 100.0);
+        let large = silverman_bandwidth(&centers, &weights, 3200.0);
+        assert!((small / large - 2.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,143 @@
+use std::path::PathBuf;
+
+use super::{LogSettings, ObserverTrait, SaveInfo};
+use crate::azimuthal_mode::SystemMode;
+use crate::hrr_integral::DescribingFunction;
+use crate::Float;
+#[cfg(feature = "hdf5")]
+use crate::Parameters;
+#[cfg(feature = "hdf5")]
+use ndarray;
+use serde::{Deserialize, Serialize};
+
+/// Local amplitude field observer.
+///
+/// Records the time-averaged local amplitude, and its variance, at each
+/// burner location. This corresponds to the spatial pressure pattern
+/// statistics measured with azimuthally distributed microphones.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LocalAmplitudeObserver {
+    pub save_info: SaveInfo,
+
+    /// When and how the mode is logged.
+    #[serde(flatten)]
+    pub logging: LogSettings,
+
+    #[serde(skip)]
+    thetas: Vec<Float>,
+
+    #[serde(skip)]
+    mode_order: u32,
+
+    #[serde(skip)]
+    mean: Vec<Float>,
+
+    #[serde(skip)]
+    sum_sq_diff: Vec<Float>,
+
+    #[serde(skip)]
+    num_values: usize,
+}
+
+impl LocalAmplitudeObserver {
+    pub fn new(output_filepath: &PathBuf, group_name: Option<&str>) -> LocalAmplitudeObserver {
+        let save_info = SaveInfo::for_observer(output_filepath, group_name);
+        LocalAmplitudeObserver {
+            save_info,
+            logging: LogSettings::default(),
+            thetas: Vec::new(),
+            mode_order: 1,
+            mean: Vec::new(),
+            sum_sq_diff: Vec::new(),
+            num_values: 0,
+        }
+    }
+
+    /// Set the burner locations and mode order used to evaluate the local amplitudes.
+    ///
+    /// This assumes that there are no data in the observer from before.
+    pub fn set_geometry(&mut self, thetas: &[Float], mode_order: u32) {
+        assert_eq!(self.num_values, 0);
+
+        self.thetas = thetas.to_vec();
+        self.mode_order = mode_order;
+        self.mean = vec![0.0; thetas.len()];
+        self.sum_sq_diff = vec![0.0; thetas.len()];
+    }
+
+    /// Time-averaged local amplitude at each burner.
+    pub fn mean(&self) -> &[Float] {
+        &self.mean
+    }
+
+    /// Variance of the local amplitude at each burner.
+    pub fn variance(&self) -> Vec<Float> {
+        if self.num_values < 2 {
+            return vec![0.0; self.mean.len()];
+        }
+
+        let denominator = (self.num_values - 1) as Float;
+        self.sum_sq_diff.iter().map(|m2| m2 / denominator).collect()
+    }
+
+    /// Clear the statistics, keeping the geometry set by [`LocalAmplitudeObserver::set_geometry`].
+    pub fn reset(&mut self) {
+        self.mean.fill(0.0);
+        self.sum_sq_diff.fill(0.0);
+        self.num_values = 0;
+    }
+}
+
+impl Default for LocalAmplitudeObserver {
+    fn default() -> Self {
+        let output_filepath = PathBuf::from("simulation_local_amplitude.hdf5");
+
+        Self::new(&output_filepath, None)
+    }
+}
+
+impl_observer_conversions!(LocalAmplitudeObserver);
+
+impl ObserverTrait for LocalAmplitudeObserver {
+    #[inline]
+    fn log(&mut self, acoustic_mode: &SystemMode, _hrr_mode: &SystemMode, _time: Float) {
+        self.num_values += 1;
+        let n = self.num_values as Float;
+
+        // Welford's online algorithm for the mean and variance
+        for (ind, &theta) in self.thetas.iter().enumerate() {
+            let local_amplitude = acoustic_mode.local_amplitude(theta, self.mode_order);
+
+            let delta = local_amplitude - self.mean[ind];
+            self.mean[ind] += delta / n;
+            self.sum_sq_diff[ind] += delta * (local_amplitude - self.mean[ind]);
+        }
+    }
+
+    #[cfg(feature = "hdf5")]
+    fn save(
+        &self,
+        parameters: &Parameters,
+        _describing_function: &DescribingFunction,
+    ) -> hdf5::Result<()> {
+        // Open the file if it alreay exist, or else create it
+        let file = hdf5::File::append(&self.save_info.path)?;
+        let group = file.create_group(&self.save_info.group)?;
+
+        super::write_dataset(&group, &self.thetas, "theta")?;
+        super::write_dataset(&group, &self.mean, "mean")?;
+        super::write_dataset(&group, &self.variance(), "variance")?;
+
+        super::save_attr(&group, &ndarray::arr0(self.num_values), "number_of_values")?;
+        super::save_parameters_as_attribute_json(&group, parameters)
+    }
+
+    fn results_json(&self, _describing_function: &DescribingFunction) -> serde_json::Value {
+        serde_json::json!({
+            "theta": self.thetas,
+            "mean": self.mean,
+            "variance": self.variance(),
+            "number_of_values": self.num_values,
+        })
+    }
+}
@@ -0,0 +1,39 @@
+use super::Prefilter;
+use crate::{Float, Parameters};
+use serde::{Deserialize, Serialize};
+
+/// Settings shared by all the observers, of when and how the mode is logged.
+///
+/// Flattened into the settings of each observer, such that the fields are
+/// given directly in the JSON object of the observer.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct LogSettings {
+    /// Number of time steps between the logged values (overrides `saves_per_cycle`).
+    #[serde(default)]
+    pub decimation: Option<usize>,
+
+    /// Only log when the oscillation phase `2 pi t + phi` crosses this value (Poincaré section).
+    #[serde(default)]
+    pub phase_section: Option<Float>,
+
+    /// Anti-aliasing filter applied to the mode before logging.
+    #[serde(default)]
+    pub prefilter: Option<Prefilter>,
+
+    /// Azimuthal location (in radians) of a reference, e.g. an asymmetry, which the
+    /// orientation angle is logged relative to.
+    #[serde(default)]
+    pub reference_angle: Option<Float>,
+}
+
+impl LogSettings {
+    /// Number of time steps between the logged values.
+    ///
+    /// Given by the decimation if set, and by the `saves_per_cycle` of the
+    /// `parameters` otherwise.
+    pub fn log_interval(&self, parameters: &Parameters) -> usize {
+        self.decimation
+            .unwrap_or(parameters.get_skip_per_save())
+            .max(1)
+    }
+}
@@ -0,0 +1,197 @@
+//! Metadata describing the meaning, units, and valid range of the saved datasets.
+//!
+//! The datasets are described by the string attributes `description`,
+//! `units`, and `valid_range`, such that the results can be interpreted
+//! without the code. The quantity of a dataset follows from its name (e.g.
+//! `chi_q` is the nature angle of the heat release rate mode), and what is
+//! stored about it (values, histogram counts, ...) is given by [`Content`].
+
+/// Logged quantities.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Quantity {
+    Time,
+    Amplitude,
+    OrientationAngle,
+    Phase,
+    NatureAngle,
+}
+
+/// What a dataset stores about its quantity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Content {
+    /// Logged values of the quantity.
+    Values,
+    /// Number of logged values in each bin of a histogram.
+    Counts,
+    /// Sum of the importance sampling weights in each bin of a histogram.
+    Weights,
+    /// Bin edges of a histogram.
+    BinEdges,
+}
+
+/// Attributes of a dataset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Metadata {
+    pub description: String,
+    pub units: &'static str,
+    pub valid_range: &'static str,
+}
+
+impl Quantity {
+    /// Quantity of the dataset `name`, and whether it belongs to the heat release rate mode.
+    pub(crate) fn from_name(name: &str) -> Option<(Self, bool)> {
+        let (base, hrr) = match name.strip_suffix("_q") {
+            Some(base) => (base, true),
+            None => (name, false),
+        };
+        let quantity = match base {
+            "time" if !hrr => Self::Time,
+            "amplitude" => Self::Amplitude,
+            "ntheta_0" => Self::OrientationAngle,
+            "phi" => Self::Phase,
+            "chi" => Self::NatureAngle,
+            _ => return None,
+        };
+
+        Some((quantity, hrr))
+    }
+
+    fn description(&self, hrr: bool) -> String {
+        let mode = if hrr {
+            "heat release rate mode"
+        } else {
+            "acoustic mode"
+        };
+        match self {
+            Self::Time => "time of the samples".to_owned(),
+            Self::Amplitude => format!("amplitude A of the {}", mode),
+            Self::OrientationAngle => format!(
+                "orientation angle n theta_0 of the {} (mode order n times the location of the anti-node)",
+                mode
+            ),
+            Self::Phase => format!("temporal phase phi of the {}", mode),
+            Self::NatureAngle => format!(
+                "nature angle chi of the {} (0 for standing, +-pi/4 for spinning modes)",
+                mode
+            ),
+        }
+    }
+
+    fn units(&self) -> &'static str {
+        match self {
+            Self::Time => "acoustic cycles",
+            Self::Amplitude => "dimensionless (scale of the saturation of the describing function)",
+            Self::OrientationAngle | Self::Phase | Self::NatureAngle => "radians",
+        }
+    }
+
+    /// Range of the logged values.
+    fn valid_range(&self) -> &'static str {
+        match self {
+            Self::Time => "[0, number_of_cycles]",
+            Self::Amplitude => "[0, inf)",
+            // Logged continuously, not wrapped into a period
+            Self::OrientationAngle | Self::Phase => "(-inf, inf)",
+            Self::NatureAngle => "[-pi/4, pi/4]",
+        }
+    }
+
+    /// Range covered by the bins of a histogram.
+    fn binned_range(&self) -> &'static str {
+        match self {
+            Self::Time => "[0, number_of_cycles]",
+            Self::Amplitude => "[0, amplitude_limit]",
+            Self::OrientationAngle | Self::Phase => "[-pi, pi] (wrapped)",
+            Self::NatureAngle => "[-pi/4, pi/4]",
+        }
+    }
+}
+
+impl Content {
+    /// Metadata of the dataset `name` storing this content.
+    ///
+    /// Returns `None` for names that are not a logged quantity.
+    pub(crate) fn metadata(&self, name: &str) -> Option<Metadata> {
+        let (quantity, hrr) = Quantity::from_name(name)?;
+        let description = quantity.description(hrr);
+
+        let metadata = match self {
+            Self::Values => Metadata {
+                description,
+                units: quantity.units(),
+                valid_range: quantity.valid_range(),
+            },
+            Self::Counts => Metadata {
+                description: format!(
+                    "histogram of the {}, not normalized (divide by the total count and bin widths for the probability density)",
+                    description
+                ),
+                units: "samples",
+                valid_range: "[0, inf)",
+            },
+            Self::Weights => Metadata {
+                description: format!(
+                    "importance sampling weighted histogram of the {} (divide by total_weight and bin widths for the probability density)",
+                    description
+                ),
+                units: "sum of the sample weights",
+                valid_range: "[0, inf)",
+            },
+            Self::BinEdges => Metadata {
+                description: format!("bin edges of the histogram of the {}", description),
+                units: quantity.units(),
+                valid_range: quantity.binned_range(),
+            },
+        };
+
+        Some(metadata)
+    }
+}
+
+impl Metadata {
+    /// Save the metadata as string attributes of `dataset`.
+    pub(crate) fn save(&self, dataset: &hdf5::Dataset) -> hdf5::Result<()> {
+        super::save_str_attr(dataset, &self.description, "description")?;
+        super::save_str_attr(dataset, self.units, "units")?;
+        super::save_str_attr(dataset, self.valid_range, "valid_range")
+    }
+}
+
+/// Write the dataset `name` to `group`, with the metadata of `content` if `name` is a logged quantity.
+pub(crate) fn write_described_dataset(
+    group: &hdf5::Group,
+    vec: &Vec<impl hdf5::H5Type>,
+    name: &str,
+    content: Content,
+) -> hdf5::Result<hdf5::Dataset> {
+    let dataset = super::write_dataset(group, vec, name)?;
+    if let Some(metadata) = content.metadata(name) {
+        metadata.save(&dataset)?;
+    }
+
+    Ok(dataset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantities_follow_from_names() {
+        assert_eq!(Quantity::from_name("time"), Some((Quantity::Time, false)));
+        assert_eq!(
+            Quantity::from_name("chi_q"),
+            Some((Quantity::NatureAngle, true))
+        );
+        assert_eq!(Quantity::from_name("time_q"), None);
+        assert_eq!(Quantity::from_name("total_weight"), None);
+
+        let edges = Content::BinEdges.metadata("ntheta_0_q").unwrap();
+        assert!(edges.description.contains("heat release rate mode"));
+        assert_eq!(edges.units, "radians");
+        assert_eq!(
+            Content::Values.metadata("chi").unwrap().valid_range,
+            "[-pi/4, pi/4]"
+        );
+    }
+}
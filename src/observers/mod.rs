@@ -1,25 +1,127 @@
 //! Observers used for logging the [`crate::azimuthal_mode::Mode`].
+//!
+//! The observers keep the results in memory while logging, and save them to
+//! HDF5 files with [`ObserverTrait::save`], which requires the `hdf5` feature
+//! (enabled by default). Without the feature, e.g. when compiling to
+//! `wasm32-unknown-unknown`, the results of the [`TimeSeriesObserver`] can be
+//! read from memory with [`TimeSeriesObserver::get_modes`] and
+//! [`TimeSeriesObserver::get_time`].
+//!
+//! With the `npz` feature, the time series and histograms can instead be
+//! saved as NumPy `.npz` files with [`Observer::save_npz`], which is used
+//! when the path of the [`SaveInfo`] ends with `.npz`.
+
+/// Implement `From<SaveInfo>` (the default observer saving to the given
+/// location) and `Display` (the name and JSON settings) for an observer.
+macro_rules! impl_observer_conversions {
+    ($observer:ident) => {
+        impl From<SaveInfo> for $observer {
+            fn from(value: SaveInfo) -> Self {
+                Self {
+                    save_info: value,
+                    ..Self::default()
+                }
+            }
+        }
 
-mod histogram;
-mod timeseries;
+        impl std::fmt::Display for $observer {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                let data_string = serde_json::to_string(self).unwrap_or_default();
+                write!(f, "{}: {}", stringify!($observer), data_string)
+            }
+        }
+    };
+}
 
-use std::path::PathBuf;
+/// Evaluate `$body` with `$obs` bound to the observer of any variant of [`Observer`].
+macro_rules! dispatch {
+    ($observer:expr, $obs:ident => $body:expr) => {
+        match $observer {
+            Observer::TimeSeries($obs) => $body,
+            Observer::Histogram($obs) => $body,
+            Observer::LocalAmplitude($obs) => $body,
+            Observer::EnergyBudget($obs) => $body,
+            Observer::TransitionMatrix($obs) => $body,
+            Observer::Autocorrelation($obs) => $body,
+            Observer::Quantile($obs) => $body,
+            Observer::CycleAverage($obs) => $body,
+            Observer::Stream($obs) => $body,
+            Observer::CrossStatistics($obs) => $body,
+            Observer::FourierCoefficients($obs) => $body,
+            Observer::IntegralTerms($obs) => $body,
+        }
+    };
+}
 
+mod atomic;
+mod autocorrelation;
+mod circular;
+mod cross_statistics;
+mod cycle_average;
+mod energy_budget;
+mod fourier_coefficients;
+mod histogram;
+mod integral_terms;
+mod kde;
+mod local_amplitude;
+mod log_settings;
+#[cfg(feature = "hdf5")]
+mod metadata;
+#[cfg(feature = "npz")]
+pub(crate) mod npz;
+mod prefilter;
+mod quantile;
+mod stream;
+mod timeseries;
+mod transition_matrix;
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use atomic::directory_is_writable;
+#[cfg(feature = "hdf5")]
+pub(crate) use atomic::write_atomically;
+pub use autocorrelation::{AutocorrelationError, AutocorrelationObserver};
+pub use circular::CircularStatistics;
+pub use cross_statistics::CrossStatisticsObserver;
+pub use cycle_average::{CycleAverage, CycleAverageObserver};
+pub use energy_budget::{EnergyBudget, EnergyBudgetObserver};
+pub use fourier_coefficients::FourierCoefficientsObserver;
 pub use histogram::HistogramObserver;
+pub use integral_terms::IntegralTermsObserver;
+pub use kde::KernelDensity;
+pub use local_amplitude::LocalAmplitudeObserver;
+pub use log_settings::LogSettings;
+#[cfg(feature = "hdf5")]
+use metadata::write_described_dataset;
+pub use prefilter::Prefilter;
+pub(crate) use prefilter::PrefilterState;
+pub use quantile::{QuantileError, QuantileObserver};
+pub use stream::StreamObserver;
 pub use timeseries::TimeSeriesObserver;
+pub use transition_matrix::{GridError, TransitionMatrixObserver};
 
 use crate::azimuthal_mode::SystemMode;
-use crate::hrr_integral::DescribingFunction;
+use crate::hrr_integral::{DescribingFunction, GainCoefficients, IntegralTerms};
 use crate::{Float, Parameters};
+#[cfg(feature = "hdf5")]
 use hdf5::{H5Type, Location};
+#[cfg(feature = "hdf5")]
 use ndarray::{arr0, ArrayView};
 use serde::{Deserialize, Serialize};
 
+/// Number of samples per chunk of the extendable datasets.
+#[cfg(feature = "hdf5")]
+const APPEND_CHUNK: usize = 4096;
+
 /// Possible errors for [`SaveInfo`].
 #[derive(Debug)]
 pub enum ObserverError {
     DirectoryNotFound(SaveInfo),
+    DirectoryNotWritable(SaveInfo),
     GroupAlreadyExist(SaveInfo),
+    DuplicateTarget(SaveInfo),
+    AppendNotSupported(SaveInfo),
 }
 
 impl std::error::Error for ObserverError {}
@@ -33,6 +135,12 @@ impl std::fmt::Display for ObserverError {
                     save_info.path.to_string_lossy()
                 )
             }
+            Self::DirectoryNotWritable(save_info) => {
+                format!(
+                    "Could not write to the directory of \"{}\"",
+                    save_info.path.to_string_lossy()
+                )
+            }
             Self::GroupAlreadyExist(save_info) => {
                 format!(
                     "Group \"{}\" in file \"{}\" already exists",
@@ -40,6 +148,20 @@ impl std::fmt::Display for ObserverError {
                     save_info.path.to_string_lossy()
                 )
             }
+            Self::DuplicateTarget(save_info) => {
+                format!(
+                    "Group \"{}\" in file \"{}\" is the target of several simulations",
+                    save_info.group,
+                    save_info.path.to_string_lossy()
+                )
+            }
+            Self::AppendNotSupported(save_info) => {
+                format!(
+                    "Group \"{}\" in file \"{}\" can only be appended to by a time series saved as HDF5",
+                    save_info.group,
+                    save_info.path.to_string_lossy()
+                )
+            }
         };
 
         write!(f, "ObserverError: {}", msg)
@@ -50,19 +172,65 @@ impl std::fmt::Display for ObserverError {
 pub trait ObserverTrait: std::fmt::Display {
     /// Log the current state of the system.
     fn log(&mut self, acoustic_mode: &SystemMode, hrr_mode: &SystemMode, time: Float);
+    /// Log the current state of the system with a statistical weight.
+    ///
+    /// Used for importance sampling, where `weight` is the likelihood ratio of
+    /// the current trajectory. Observers that do not accumulate statistics
+    /// ignore the weight by default.
+    fn log_weighted(
+        &mut self,
+        acoustic_mode: &SystemMode,
+        hrr_mode: &SystemMode,
+        time: Float,
+        _weight: Float,
+    ) {
+        self.log(acoustic_mode, hrr_mode, time);
+    }
+    /// Receive the decomposition of the amplitude equation at the next logging point.
+    ///
+    /// Called right before [`ObserverTrait::log`], and ignored by default.
+    fn log_energy_budget(&mut self, _budget: &EnergyBudget) {}
+    /// Receive the Fourier coefficients of the gain distribution at the next logging point.
+    ///
+    /// Called right before [`ObserverTrait::log`] if [`Observer::needs_gain_coefficients`],
+    /// and ignored by default.
+    fn log_gain_coefficients(&mut self, _coefficients: &GainCoefficients) {}
+    /// Receive the individual contributions to the right hand side at the next logging point.
+    ///
+    /// Called right before [`ObserverTrait::log`] if [`Observer::needs_integral_terms`],
+    /// and ignored by default.
+    fn log_integral_terms(&mut self, _terms: &IntegralTerms) {}
     /// Save the observed data to file.
+    #[cfg(feature = "hdf5")]
     fn save(
         &self,
         parameters: &Parameters,
         describing_function: &DescribingFunction,
     ) -> hdf5::Result<()>;
+    /// Observed data as JSON, with the names of the datasets and attributes of [`ObserverTrait::save`].
+    ///
+    /// Subgroups are nested objects, and matrices arrays of rows. Used to keep
+    /// the results in memory when they cannot be saved to file.
+    fn results_json(&self, describing_function: &DescribingFunction) -> serde_json::Value;
 }
 
 /// Wrapper for the structs implementing [`ObserverTrait`].
+// Created once per simulation, so the size of the largest variants does not matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum Observer {
     TimeSeries(TimeSeriesObserver),
     Histogram(HistogramObserver),
+    LocalAmplitude(LocalAmplitudeObserver),
+    EnergyBudget(EnergyBudgetObserver),
+    TransitionMatrix(TransitionMatrixObserver),
+    Autocorrelation(AutocorrelationObserver),
+    Quantile(QuantileObserver),
+    CycleAverage(CycleAverageObserver),
+    Stream(StreamObserver),
+    CrossStatistics(CrossStatisticsObserver),
+    FourierCoefficients(FourierCoefficientsObserver),
+    IntegralTerms(IntegralTermsObserver),
 }
 
 impl Observer {
@@ -80,52 +248,267 @@ impl Observer {
         Self::Histogram(ho)
     }
 
+    pub fn new_local_amplitude(save_info: SaveInfo, parameters: &Parameters) -> Self {
+        let mut lao = LocalAmplitudeObserver::from(save_info);
+        lao.set_geometry(parameters.get_thetas(), parameters.get_mode_order());
+
+        Self::LocalAmplitude(lao)
+    }
+
+    /// Name of the observer, as used in the settings files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::TimeSeries(_) => "TimeSeries",
+            Self::Histogram(_) => "Histogram",
+            Self::LocalAmplitude(_) => "LocalAmplitude",
+            Self::EnergyBudget(_) => "EnergyBudget",
+            Self::TransitionMatrix(_) => "TransitionMatrix",
+            Self::Autocorrelation(_) => "Autocorrelation",
+            Self::Quantile(_) => "Quantile",
+            Self::CycleAverage(_) => "CycleAverage",
+            Self::Stream(_) => "Stream",
+            Self::CrossStatistics(_) => "CrossStatistics",
+            Self::FourierCoefficients(_) => "FourierCoefficients",
+            Self::IntegralTerms(_) => "IntegralTerms",
+        }
+    }
+
+    /// Settings of when and how the mode is logged.
+    pub fn logging(&self) -> &LogSettings {
+        dispatch!(self, obs => &obs.logging)
+    }
+
+    pub fn logging_mut(&mut self) -> &mut LogSettings {
+        dispatch!(self, obs => &mut obs.logging)
+    }
+
+    /// Number of time steps between the logged values.
+    ///
+    /// Given by the decimation of the observer if set, and by the
+    /// `saves_per_cycle` of the `parameters` otherwise.
+    pub fn log_interval(&self, parameters: &Parameters) -> usize {
+        self.logging().log_interval(parameters)
+    }
+
+    /// Phase of the Poincaré section the observer logs at (if any).
+    ///
+    /// When set, the mode is logged once per cycle of the oscillation instead
+    /// of at the [`Observer::log_interval`].
+    pub fn phase_section(&self) -> Option<Float> {
+        self.logging().phase_section
+    }
+
+    /// Anti-aliasing filter applied to the mode before logging (if any).
+    pub fn prefilter(&self) -> Option<Prefilter> {
+        self.logging().prefilter
+    }
+
+    /// Azimuthal location of the reference of the orientation angle (if any).
+    pub fn reference_angle(&self) -> Option<Float> {
+        self.logging().reference_angle
+    }
+
+    /// Whether the observer logs the Fourier coefficients of the gain distribution.
+    ///
+    /// Used to skip computing them otherwise.
+    pub fn needs_gain_coefficients(&self) -> bool {
+        matches!(self, Self::FourierCoefficients(_))
+    }
+
+    /// Whether the observer logs the individual contributions to the right hand side.
+    ///
+    /// Used to skip computing them otherwise.
+    pub fn needs_integral_terms(&self) -> bool {
+        matches!(self, Self::IntegralTerms(_))
+    }
+
+    /// Number of values logged during the simulation (excluding the initial mode).
+    ///
+    /// Approximated by the number of cycles for the Poincaré sections.
+    pub fn num_logged(&self, parameters: &Parameters) -> usize {
+        match self.phase_section() {
+            Some(_) => parameters.get_number_of_cycles().ceil() as usize,
+            None => parameters.get_total_steps() / self.log_interval(parameters),
+        }
+    }
+
+    /// Estimate the memory (in bytes) taken by the logged values at the end of a simulation.
+    ///
+    /// The memory part of [`Observer::estimated_size`], used to check jobs
+    /// against a memory budget before running them.
+    pub fn estimated_bytes(&self, parameters: &Parameters) -> usize {
+        self.estimated_size(parameters).0
+    }
+
+    /// Estimate the memory and disk usage (in bytes) of the logged values.
+    ///
+    /// Only the observers whose size grows with the length of the simulation
+    /// are counted.
+    pub fn estimated_size(&self, parameters: &Parameters) -> (usize, usize) {
+        let saves = self.num_logged(parameters);
+        let float = std::mem::size_of::<Float>();
+
+        match self {
+            Self::TimeSeries(obs) => {
+                let mut datasets = 6;
+                if obs.poincare_sphere {
+                    datasets += 3;
+                }
+                if obs.spinning_waves {
+                    datasets += 3;
+                }
+                (
+                    saves * (std::mem::size_of::<SystemMode>() + float),
+                    saves * datasets * float,
+                )
+            }
+            Self::EnergyBudget(_) => {
+                let size = std::mem::size_of::<EnergyBudget>() + float;
+                (saves * size, saves * size)
+            }
+            Self::FourierCoefficients(_) => {
+                let size = std::mem::size_of::<GainCoefficients>() + float;
+                (saves * size, saves * size)
+            }
+            Self::IntegralTerms(_) => {
+                let size = std::mem::size_of::<IntegralTerms>() + float;
+                (saves * size, saves * size)
+            }
+            Self::CycleAverage(_) => {
+                let cycles = parameters.get_number_of_cycles().ceil() as usize;
+                let size = std::mem::size_of::<CycleAverage>();
+                (cycles * size, cycles * size)
+            }
+            _ => (0, 0),
+        }
+    }
+
     pub fn valid_path(&self) -> Result<(), ObserverError> {
+        // Only the time series can be continued in an existing group
+        if let Some(save_info) = self.append_not_supported() {
+            return Err(ObserverError::AppendNotSupported(save_info));
+        }
+
+        dispatch!(self, obs => obs.save_info.is_valid())
+    }
+
+    /// Save information of the observer if it appends, but cannot continue an existing group.
+    fn append_not_supported(&self) -> Option<SaveInfo> {
+        let save_info = self.save_info();
+        #[cfg(feature = "npz")]
+        let npz = npz::is_npz(&save_info.path);
+        #[cfg(not(feature = "npz"))]
+        let npz = false;
+
         match self {
-            Self::TimeSeries(obs) => obs.save_info.is_valid(),
-            Self::Histogram(obs) => obs.save_info.is_valid(),
+            Self::TimeSeries(_) if !npz => None,
+            _ if save_info.append => Some(save_info),
+            _ => None,
+        }
+    }
+
+    /// Prepare the observer for a simulation described by `parameters`.
+    ///
+    /// Reserves storage for the logged values and sets up any
+    /// information the observer needs about the system geometry.
+    pub fn init(&mut self, parameters: &Parameters) {
+        self.reserve(self.num_logged(parameters));
+
+        if let Self::LocalAmplitude(obs) = self {
+            obs.set_geometry(parameters.get_thetas(), parameters.get_mode_order());
+        }
+        if let Self::CycleAverage(obs) = self {
+            obs.reserve(parameters.get_number_of_cycles().ceil() as usize);
         }
     }
 
     pub fn reserve(&mut self, additional: usize) {
-        if let Self::TimeSeries(obs) = self {
-            obs.reserve(additional);
+        match self {
+            Self::TimeSeries(obs) => obs.reserve(additional),
+            Self::EnergyBudget(obs) => obs.reserve(additional),
+            Self::FourierCoefficients(obs) => obs.reserve(additional),
+            Self::IntegralTerms(obs) => obs.reserve(additional),
+            _ => {}
         }
     }
 
+    /// Clear the logged data, keeping the settings and the allocated storage.
+    ///
+    /// Allows one observer to be reused for repetitions or continued
+    /// simulations, without reconstructing it and reserving the storage again.
+    pub fn reset(&mut self) {
+        dispatch!(self, obs => obs.reset())
+    }
+
     pub fn set_save_info(&mut self, save_info: &SaveInfo) {
-        match self {
-            Self::TimeSeries(obs) => obs.save_info = save_info.clone(),
-            Self::Histogram(obs) => obs.save_info = save_info.clone(),
-        };
+        dispatch!(self, obs => obs.save_info = save_info.clone())
     }
 
-    pub fn save_info(&self) -> SaveInfo {
+    /// Save the results to the `.npz` file given by the save information.
+    ///
+    /// Only supported by the time series and histogram observers.
+    #[cfg(feature = "npz")]
+    pub fn save_npz(
+        &self,
+        parameters: &Parameters,
+        describing_function: &DescribingFunction,
+    ) -> std::io::Result<()> {
         match self {
-            Self::TimeSeries(obs) => obs.save_info.clone(),
-            Self::Histogram(obs) => obs.save_info.clone(),
+            Self::TimeSeries(obs) => obs.save_npz(parameters, describing_function),
+            Self::Histogram(obs) => obs.save_npz(parameters),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "only the TimeSeries and Histogram observers can be saved as .npz",
+            )),
         }
     }
+
+    pub fn save_info(&self) -> SaveInfo {
+        dispatch!(self, obs => obs.save_info.clone())
+    }
 }
 
 impl ObserverTrait for Observer {
     #[inline]
     fn log(&mut self, acoustic_mode: &SystemMode, hrr_mode: &SystemMode, time: Float) {
-        match self {
-            Self::TimeSeries(obs) => obs.log(acoustic_mode, hrr_mode, time),
-            Self::Histogram(obs) => obs.log(acoustic_mode, hrr_mode, time),
-        }
+        dispatch!(self, obs => obs.log(acoustic_mode, hrr_mode, time))
+    }
+
+    #[inline]
+    fn log_weighted(
+        &mut self,
+        acoustic_mode: &SystemMode,
+        hrr_mode: &SystemMode,
+        time: Float,
+        weight: Float,
+    ) {
+        dispatch!(self, obs => obs.log_weighted(acoustic_mode, hrr_mode, time, weight))
+    }
+
+    #[inline]
+    fn log_energy_budget(&mut self, budget: &EnergyBudget) {
+        dispatch!(self, obs => obs.log_energy_budget(budget))
+    }
+
+    fn log_gain_coefficients(&mut self, coefficients: &GainCoefficients) {
+        dispatch!(self, obs => obs.log_gain_coefficients(coefficients))
     }
 
+    fn log_integral_terms(&mut self, terms: &IntegralTerms) {
+        dispatch!(self, obs => obs.log_integral_terms(terms))
+    }
+
+    #[cfg(feature = "hdf5")]
     fn save(
         &self,
         parameters: &Parameters,
         describing_function: &DescribingFunction,
     ) -> hdf5::Result<()> {
-        match self {
-            Self::TimeSeries(obs) => obs.save(parameters, describing_function),
-            Self::Histogram(obs) => obs.save(parameters, describing_function),
-        }
+        dispatch!(self, obs => obs.save(parameters, describing_function))
+    }
+
+    fn results_json(&self, describing_function: &DescribingFunction) -> serde_json::Value {
+        dispatch!(self, obs => obs.results_json(describing_function))
     }
 }
 
@@ -148,6 +531,12 @@ impl Default for Observer {
 pub struct SaveInfo {
     path: PathBuf,
     group: String,
+    /// Create the missing directories of the path when saving.
+    #[serde(default)]
+    create_directories: bool,
+    /// Continue the time series of an existing group instead of refusing to overwrite it.
+    #[serde(default)]
+    append: bool,
 }
 
 impl SaveInfo {
@@ -155,9 +544,22 @@ impl SaveInfo {
         Self {
             path: path.to_owned(),
             group: group.to_owned(),
+            create_directories: false,
+            append: false,
         }
     }
 
+    /// Save information of an observer, with the default group if `group` is `None`.
+    pub(crate) fn for_observer(path: &PathBuf, group: Option<&str>) -> Self {
+        let mut save_info = Self::default();
+        save_info.set_path(path);
+        if let Some(group) = group {
+            save_info.set_group(group);
+        }
+
+        save_info
+    }
+
     pub fn set_path(&mut self, new_path: &PathBuf) {
         self.path = new_path.to_owned();
     }
@@ -174,29 +576,131 @@ impl SaveInfo {
         &self.group
     }
 
+    /// Create the missing directories of the path when saving (opt-in).
+    ///
+    /// Useful for sweeps saving to structured output trees, e.g.
+    /// `results/gain_0.05/run.h5`.
+    pub fn set_create_directories(&mut self, create_directories: bool) {
+        self.create_directories = create_directories;
+    }
+
+    pub fn get_create_directories(&self) -> bool {
+        self.create_directories
+    }
+
+    /// Continue the time series of an existing group when saving (opt-in).
+    ///
+    /// The datasets of the [`TimeSeriesObserver`] are extended with the
+    /// samples logged after the last saved time, e.g. when continuing an
+    /// interrupted simulation with [`crate::Settings::resume`]. Only
+    /// supported by the time series saved as HDF5.
+    pub fn set_append(&mut self, append: bool) {
+        self.append = append;
+    }
+
+    pub fn get_append(&self) -> bool {
+        self.append
+    }
+
+    /// Create the directory of the path (and its parents) if enabled and missing.
+    #[cfg(any(feature = "hdf5", feature = "npz"))]
+    pub(crate) fn create_directory(&self) -> std::io::Result<()> {
+        if self.create_directories {
+            std::fs::create_dir_all(self.directory())?;
+        }
+
+        Ok(())
+    }
+
     pub fn is_valid(&self) -> Result<(), ObserverError> {
-        // First, check if the directory exists
-        let directory = std::path::Path::new(&self.path).parent();
-        if let None = directory {
+        // First, check if the directory exists (unless it is created when saving)
+        if !self.create_directories && !self.directory().is_dir() {
             return Err(ObserverError::DirectoryNotFound(self.to_owned()));
         }
 
-        // Check if the file already exists
-        let file = hdf5::File::open(&self.path);
-        let file = match file {
-            Ok(file) => file,
-            // If the directory exist, but the file does not exist yet
-            // it should be fine to save the results in the intended location
-            Err(_) => return Ok(()),
-        };
-
         // If the file exists already, make sure the group does not already exist
-        match file.group(&self.group) {
+        // (unless continuing it)
+        if !self.append && self.group_exists() {
             // The group already exists, it will not overwrite the results
-            Ok(_) => Err(ObserverError::GroupAlreadyExist(self.to_owned())),
-            // The group does not already exist, it will write the results in the desired location
-            Err(_) => Ok(()),
+            return Err(ObserverError::GroupAlreadyExist(self.to_owned()));
         }
+
+        Ok(())
+    }
+
+    /// Check the save information of a whole batch of simulations before running it.
+    ///
+    /// Besides [`SaveInfo::is_valid`], checks that the directories are
+    /// writable (by creating and removing a temporary file), and that no two
+    /// simulations save to the same group of the same file, where the later
+    /// ones conflict with the first. Returns the result of each element of
+    /// `save_infos`.
+    pub fn check_batch(save_infos: &[SaveInfo]) -> Vec<Result<(), ObserverError>> {
+        let mut writable: HashMap<PathBuf, bool> = HashMap::new();
+        let mut targets = HashSet::new();
+
+        save_infos
+            .iter()
+            .map(|save_info| {
+                save_info.is_valid()?;
+
+                // Missing directories are created in the closest existing one
+                let directory = save_info.directory();
+                let existing = directory
+                    .ancestors()
+                    .find(|ancestor| ancestor.is_dir())
+                    .unwrap_or(Path::new("."))
+                    .to_owned();
+                let directory = directory.canonicalize().unwrap_or(directory);
+                let is_writable = *writable
+                    .entry(existing.clone())
+                    .or_insert_with(|| directory_is_writable(&existing.join("batch")));
+                if !is_writable {
+                    return Err(ObserverError::DirectoryNotWritable(save_info.to_owned()));
+                }
+
+                let file = directory.join(save_info.path.file_name().unwrap_or_default());
+                if !targets.insert((file, save_info.group.clone())) {
+                    return Err(ObserverError::DuplicateTarget(save_info.to_owned()));
+                }
+
+                Ok(())
+            })
+            .collect()
+    }
+
+    /// Directory of the file, where an empty parent is the working directory.
+    fn directory(&self) -> PathBuf {
+        match self.path.parent() {
+            Some(directory) if !directory.as_os_str().is_empty() => directory.to_owned(),
+            _ => PathBuf::from("."),
+        }
+    }
+
+    /// Check if the group already exists in the file.
+    pub(crate) fn group_exists(&self) -> bool {
+        #[cfg(feature = "npz")]
+        if npz::is_npz(&self.path) {
+            return npz::group_exists(self);
+        }
+
+        self.hdf5_group_exists()
+    }
+
+    #[cfg(feature = "hdf5")]
+    fn hdf5_group_exists(&self) -> bool {
+        match hdf5::File::open(&self.path) {
+            Ok(file) => file.group(&self.group).is_ok(),
+            // If the directory exist, but the file does not exist yet
+            // it should be fine to save the results in the intended location
+            Err(_) => false,
+        }
+    }
+
+    /// Nothing is saved to file without HDF5 support.
+    #[cfg(not(feature = "hdf5"))]
+    fn hdf5_group_exists(&self) -> bool {
+        false
     }
 }
 
@@ -222,12 +726,13 @@ impl std::fmt::Display for SaveInfo {
 
 #[allow(dead_code)]
 /// Save the [`Parameters`] as a HDF5 attribute of the group
+#[cfg(feature = "hdf5")]
 fn save_parameters_as_attribute(group: &Location, parameters: &Parameters) -> hdf5::Result<()> {
     // Save the relevant bits of the parameters
-    save_attr(group, &arr0(parameters.damping), "damping")?;
-    save_attr(group, &arr0(parameters.gain), "gain")?;
-    save_attr(group, &arr0(parameters.noise), "noise")?;
-    save_attr(group, &arr0(parameters.mode_order), "mode_order")?;
+    save_attr(group, &arr0(parameters.get_damping()), "damping")?;
+    save_attr(group, &arr0(parameters.get_gain()), "gain")?;
+    save_attr(group, &arr0(parameters.get_noise()), "noise")?;
+    save_attr(group, &arr0(parameters.get_mode_order()), "mode_order")?;
     save_attr(group, &arr0(parameters.get_timestep()), "dt")?;
     save_attr(
         group,
@@ -236,21 +741,34 @@ fn save_parameters_as_attribute(group: &Location, parameters: &Parameters) -> hd
     )?;
     save_attr(
         group,
-        &arr0(parameters.number_of_burners),
+        &arr0(parameters.get_number_of_burners()),
         "number_of_burners",
     )
 }
 
-fn save_parameters_as_attribute_json(
+/// Save the [`Parameters`] as the JSON attribute `parameters`, along with any broken symmetry of the flames.
+#[cfg(feature = "hdf5")]
+pub(crate) fn save_parameters_as_attribute_json(
     group: &Location,
     parameters: &Parameters,
 ) -> hdf5::Result<()> {
     let save_string = parameters.to_string();
-    save_str_attr(group, &save_string, "parameters")
+    save_str_attr(group, &save_string, "parameters")?;
+
+    if let Some(description) = parameters.broken_symmetry() {
+        save_str_attr(group, &description, "broken_symmetry")?;
+    }
+    let burners_disabled = parameters.get_burners_disabled();
+    if !burners_disabled.is_empty() {
+        save_attr(group, burners_disabled, "burners_disabled")?;
+    }
+
+    Ok(())
 }
 
 /// Save `data` as an HDF5 attribute.
-fn save_attr<'d, A, T, D>(group: &Location, data: A, name: &str) -> hdf5::Result<()>
+#[cfg(feature = "hdf5")]
+pub(crate) fn save_attr<'d, A, T, D>(group: &Location, data: A, name: &str) -> hdf5::Result<()>
 where
     A: Into<ArrayView<'d, T, D>>,
     T: H5Type,
@@ -263,7 +781,8 @@ where
 }
 
 /// Save string `value` as a HDF5 attribute.
-fn save_str_attr(location: &Location, value: &str, name: &str) -> hdf5::Result<()> {
+#[cfg(feature = "hdf5")]
+pub(crate) fn save_str_attr(location: &Location, value: &str, name: &str) -> hdf5::Result<()> {
     // Code found here: https://users.rust-lang.org/t/add-string-attribute-using-hdf5-rust/68744/8
     let attr = location
         .new_attr::<hdf5::types::VarLenUnicode>()
@@ -273,8 +792,59 @@ fn save_str_attr(location: &Location, value: &str, name: &str) -> hdf5::Result<(
     attr.write_scalar(&value_)
 }
 
+/// Save the string `value` as an HDF5 attribute, replacing the value of an existing attribute.
+#[cfg(feature = "hdf5")]
+pub(crate) fn replace_str_attr(location: &Location, value: &str, name: &str) -> hdf5::Result<()> {
+    match location.attr(name) {
+        Ok(attr) => attr.write_scalar(&value.parse::<hdf5::types::VarLenUnicode>().unwrap()),
+        Err(_) => save_str_attr(location, value, name),
+    }
+}
+
+/// Save the scalar `value` as an HDF5 attribute, replacing the value of an existing attribute.
+#[cfg(feature = "hdf5")]
+pub(crate) fn replace_scalar_attr<T: H5Type>(
+    location: &Location,
+    value: T,
+    name: &str,
+) -> hdf5::Result<()> {
+    match location.attr(name) {
+        Ok(attr) => attr.write_scalar(&value),
+        Err(_) => save_attr(location, &arr0(value), name),
+    }
+}
+
+/// Append `values` to the extendable dataset `name` of a [`hdf5::Group`], creating it if missing.
+///
+/// Returns the dataset and whether it was created.
+#[cfg(feature = "hdf5")]
+pub(crate) fn append_dataset<T: H5Type>(
+    group: &hdf5::Group,
+    values: &[T],
+    name: &str,
+) -> hdf5::Result<(hdf5::Dataset, bool)> {
+    let (dataset, created) = match group.dataset(name) {
+        Ok(dataset) => (dataset, false),
+        Err(_) => {
+            let dataset = group
+                .new_dataset::<T>()
+                .shape(0..)
+                .chunk(APPEND_CHUNK)
+                .create(name)?;
+            (dataset, true)
+        }
+    };
+
+    let start = dataset.size();
+    dataset.resize(start + values.len())?;
+    dataset.write_slice(values, ndarray::s![start..start + values.len()])?;
+
+    Ok((dataset, created))
+}
+
 /// Write regular dataset to a [`hdf5::Group`].
-fn write_dataset(
+#[cfg(feature = "hdf5")]
+pub(crate) fn write_dataset(
     group: &hdf5::Group,
     vec: &Vec<impl hdf5::H5Type>,
     name: &str,
@@ -284,3 +854,100 @@ fn write_dataset(
 
     Ok(ds)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_conflicts() {
+        let directory = std::env::temp_dir();
+        let path = directory.join("azimuthal_fdf_batch_conflicts.h5");
+        let save_infos = [
+            SaveInfo::new(&path, "first"),
+            SaveInfo::new(&path, "second"),
+            SaveInfo::new(
+                &directory.join(".").join(path.file_name().unwrap()),
+                "first",
+            ),
+            SaveInfo::new(&directory.join("missing").join("run.h5"), "first"),
+        ];
+
+        let checks = SaveInfo::check_batch(&save_infos);
+        assert!(checks[0].is_ok() && checks[1].is_ok());
+        assert!(matches!(checks[2], Err(ObserverError::DuplicateTarget(_))));
+        assert!(matches!(
+            checks[3],
+            Err(ObserverError::DirectoryNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn only_time_series_append() {
+        let mut save_info = SaveInfo::new(&std::env::temp_dir().join("append.h5"), "data");
+        save_info.set_append(true);
+
+        let time_series = Observer::new_timeseries(save_info.clone(), 0);
+        assert!(time_series.valid_path().is_ok());
+        let histogram = Observer::new_histogram(save_info, 1.0);
+        assert!(matches!(
+            histogram.valid_path(),
+            Err(ObserverError::AppendNotSupported(_))
+        ));
+    }
+
+    #[test]
+    fn log_settings_are_flattened() {
+        let json = r#"{"Quantile": {
+            "save_info": {"path": "quantiles.h5", "group": "data"},
+            "decimation": 5,
+            "reference_angle": 0.5,
+            "probabilities": [0.5]
+        }}"#;
+        let observer: Observer = serde_json::from_str(json).unwrap();
+        assert_eq!(observer.logging().decimation, Some(5));
+        assert_eq!(observer.reference_angle(), Some(0.5));
+        assert_eq!(observer.phase_section(), None);
+
+        let json = serde_json::to_value(&observer).unwrap();
+        assert_eq!(json["Quantile"]["decimation"], 5);
+    }
+
+    #[test]
+    fn reset_observer_matches_new_observer() {
+        let log = |observer: &mut Observer, offset: Float| {
+            for ind in 0..50 {
+                let x = offset + 0.1 * ind as Float;
+                let mode = SystemMode::new(1.0 + x.sin(), x, 0.5 * x, 0.3 * x.cos());
+                observer.log(&mode, &mode, x);
+            }
+        };
+
+        let save_info = SaveInfo::default();
+        let observers = [
+            Observer::new_timeseries(save_info.clone(), 100),
+            Observer::new_histogram(save_info, 3.0),
+            Observer::Autocorrelation(AutocorrelationObserver::default()),
+            Observer::Quantile(QuantileObserver::default()),
+            Observer::TransitionMatrix(TransitionMatrixObserver::default()),
+            Observer::CycleAverage(CycleAverageObserver::default()),
+            Observer::CrossStatistics(CrossStatisticsObserver::default()),
+        ];
+        let describing_function = DescribingFunction::default();
+        for observer in observers {
+            let mut reused = observer.clone();
+            log(&mut reused, 0.0);
+            reused.reset();
+            log(&mut reused, 2.0);
+
+            let mut new = observer;
+            log(&mut new, 2.0);
+            assert_eq!(
+                reused.results_json(&describing_function),
+                new.results_json(&describing_function),
+                "{}",
+                reused.name()
+            );
+        }
+    }
+}
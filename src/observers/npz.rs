@@ -0,0 +1,191 @@
+//! Writer for NumPy `.npz` files.
+//!
+//! An `.npz` file is a zip archive of `.npy` arrays, which can be read with
+//! `numpy.load` without the HDF5 library. The groups of the HDF5 output are
+//! mapped to prefixes of the array names, such that the dataset `time` in the
+//! group `rep_000` is loaded as `np.load(path)["rep_000/time"]`.
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use super::SaveInfo;
+use crate::Float;
+
+/// Check whether `path` should be saved as an `.npz` file.
+pub(crate) fn is_npz(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "npz")
+}
+
+/// Check whether any array of the group is already stored in the file.
+pub(crate) fn group_exists(save_info: &SaveInfo) -> bool {
+    let prefix = format!("{}/", save_info.get_group());
+
+    match File::open(save_info.get_path()).map(ZipArchive::new) {
+        Ok(Ok(archive)) => archive.file_names().any(|name| name.starts_with(&prefix)),
+        _ => false,
+    }
+}
+
+/// Element type of a `.npy` array.
+pub(crate) trait NpyElement {
+    /// NumPy type description (little endian).
+    const DESCR: &'static str;
+
+    fn write_le(&self, writer: &mut impl Write) -> io::Result<()>;
+}
+
+impl NpyElement for Float {
+    const DESCR: &'static str = "<f8";
+
+    fn write_le(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(&self.to_le_bytes())
+    }
+}
+
+impl NpyElement for usize {
+    const DESCR: &'static str = "<u8";
+
+    fn write_le(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(&(*self as u64).to_le_bytes())
+    }
+}
+
+impl NpyElement for u64 {
+    const DESCR: &'static str = "<u8";
+
+    fn write_le(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(&self.to_le_bytes())
+    }
+}
+
+impl NpyElement for bool {
+    const DESCR: &'static str = "|b1";
+
+    fn write_le(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(&[*self as u8])
+    }
+}
+
+/// Appends the arrays of one group to an `.npz` file.
+pub(crate) struct NpzWriter {
+    zip: ZipWriter<File>,
+    group: String,
+}
+
+impl NpzWriter {
+    /// Open the file given by `save_info`, creating it if it does not exist.
+    pub(crate) fn open(save_info: &SaveInfo) -> io::Result<Self> {
+        save_info.create_directory()?;
+        let path = save_info.get_path();
+        let zip = if path.exists() {
+            let file = OpenOptions::new().read(true).write(true).open(path)?;
+            ZipWriter::new_append(file)?
+        } else {
+            ZipWriter::new(File::create(path)?)
+        };
+
+        Ok(Self {
+            zip,
+            group: save_info.get_group().to_owned(),
+        })
+    }
+
+    /// Write `data` as a one dimensional array.
+    pub(crate) fn write<T: NpyElement>(&mut self, name: &str, data: &[T]) -> io::Result<()> {
+        self.write_with_shape(name, data, &[data.len()])
+    }
+
+    /// Write `data` (in row-major order) as an array of the given `shape`.
+    pub(crate) fn write_with_shape<T: NpyElement>(
+        &mut self,
+        name: &str,
+        data: &[T],
+        shape: &[usize],
+    ) -> io::Result<()> {
+        self.start_array(name, T::DESCR, shape)?;
+        for value in data {
+            value.write_le(&mut self.zip)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write a scalar, e.g. the attributes of the HDF5 output.
+    pub(crate) fn write_scalar<T: NpyElement>(&mut self, name: &str, value: T) -> io::Result<()> {
+        self.write_with_shape(name, &[value], &[])
+    }
+
+    /// Write a string as a scalar unicode array.
+    pub(crate) fn write_str(&mut self, name: &str, value: &str) -> io::Result<()> {
+        let descr = format!("<U{}", value.chars().count().max(1));
+        self.start_array(name, &descr, &[])?;
+        for c in value.chars() {
+            self.zip.write_all(&(c as u32).to_le_bytes())?;
+        }
+        if value.is_empty() {
+            self.zip.write_all(&[0; 4])?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the central directory of the archive.
+    pub(crate) fn finish(mut self) -> io::Result<()> {
+        self.zip.finish()?;
+        Ok(())
+    }
+
+    /// Start a new `.npy` file in the archive, and write its header.
+    fn start_array(&mut self, name: &str, descr: &str, shape: &[usize]) -> io::Result<()> {
+        let options = FileOptions::default()
+            .compression_method(CompressionMethod::Stored)
+            .large_file(true);
+        self.zip
+            .start_file(format!("{}/{}.npy", self.group, name), options)?;
+        self.zip.write_all(&npy_header(descr, shape))
+    }
+}
+
+/// Header of a version 1.0 `.npy` file.
+fn npy_header(descr: &str, shape: &[usize]) -> Vec<u8> {
+    let shape = match shape {
+        [] => "()".to_owned(),
+        [length] => format!("({},)", length),
+        _ => {
+            let lengths: Vec<String> = shape.iter().map(|l| l.to_string()).collect();
+            format!("({})", lengths.join(", "))
+        }
+    };
+    let mut dict = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        descr, shape
+    );
+
+    // The total header length (magic string, version, length and dict) is
+    // padded with spaces to a multiple of 64 bytes, and ends with a newline
+    let unpadded = 10 + dict.len() + 1;
+    dict.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    dict.push('\n');
+
+    let mut header = b"\x93NUMPY\x01\x00".to_vec();
+    header.extend_from_slice(&(dict.len() as u16).to_le_bytes());
+    header.extend_from_slice(dict.as_bytes());
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_alignment() {
+        for shape in [&[][..], &[10], &[3, 4]] {
+            let header = npy_header("<f8", shape);
+            assert_eq!(header.len() % 64, 0);
+            assert_eq!(header.last(), Some(&b'\n'));
+        }
+    }
+}
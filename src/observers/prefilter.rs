@@ -0,0 +1,102 @@
+use crate::azimuthal_mode::SystemMode;
+use crate::Float;
+use serde::{Deserialize, Serialize};
+
+/// Anti-aliasing filter applied to the mode before it is logged.
+///
+/// When only every `skip_per_save`-th time step is logged, fluctuations faster
+/// than the logging interval alias into the logged values. The filter is
+/// applied at every time step to the state variables of the [`SystemMode`]
+/// (`ln_a`, `nth0`, `phi`, and `tan_2chi`), which are continuous in time.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum Prefilter {
+    /// Average over the time steps since the last logged value.
+    Boxcar,
+    /// Exponential moving average with the given time constant (in cycles).
+    Exponential { time_constant: Float },
+}
+
+/// State of a [`Prefilter`] during a simulation.
+pub(crate) struct PrefilterState {
+    filter: Prefilter,
+    /// Running sum (boxcar) or average (exponential) of the states.
+    state: [Float; 4],
+    count: usize,
+    /// Weight of the newest value in the exponential moving average.
+    weight: Float,
+}
+
+impl PrefilterState {
+    pub(crate) fn new(filter: Prefilter, mode: &SystemMode, dt: Float) -> Self {
+        let (state, weight) = match filter {
+            Prefilter::Boxcar => ([0.0; 4], 1.0),
+            Prefilter::Exponential { time_constant } => {
+                (to_state(mode), 1.0 - (-dt / time_constant).exp())
+            }
+        };
+
+        Self {
+            filter,
+            state,
+            count: 0,
+            weight,
+        }
+    }
+
+    /// Add the mode of the current time step.
+    #[inline]
+    pub(crate) fn push(&mut self, mode: &SystemMode) {
+        let values = to_state(mode);
+        for (state, value) in self.state.iter_mut().zip(values) {
+            match self.filter {
+                Prefilter::Boxcar => *state += value,
+                Prefilter::Exponential { .. } => *state += self.weight * (value - *state),
+            }
+        }
+        self.count += 1;
+    }
+
+    /// Get the filtered mode, restarting the average of the boxcar filter.
+    pub(crate) fn take(&mut self) -> SystemMode {
+        let state = match self.filter {
+            Prefilter::Boxcar => {
+                let count = self.count.max(1) as Float;
+                let state = self.state.map(|value| value / count);
+                self.state = [0.0; 4];
+                state
+            }
+            Prefilter::Exponential { .. } => self.state,
+        };
+        self.count = 0;
+
+        SystemMode {
+            ln_a: state[0],
+            nth0: state[1],
+            phi: state[2],
+            tan_2chi: state[3],
+        }
+    }
+}
+
+#[inline]
+fn to_state(mode: &SystemMode) -> [Float; 4] {
+    [mode.ln_a, mode.nth0, mode.phi, mode.tan_2chi]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boxcar_restarts_after_take() {
+        let initial = SystemMode::new(1.0, 0.0, 0.0, 0.0);
+        let mut filter = PrefilterState::new(Prefilter::Boxcar, &initial, 0.1);
+        for phi in [1.0, 2.0, 3.0] {
+            filter.push(&SystemMode::new(1.0, 0.0, phi, 0.0));
+        }
+        assert!((filter.take().phi - 2.0).abs() < 1e-12);
+
+        filter.push(&SystemMode::new(1.0, 0.0, 5.0, 0.0));
+        assert!((filter.take().phi - 5.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,338 @@
+use std::path::PathBuf;
+
+use super::{LogSettings, ObserverTrait, SaveInfo};
+use crate::azimuthal_mode::SystemMode;
+use crate::hrr_integral::DescribingFunction;
+use crate::Float;
+#[cfg(feature = "hdf5")]
+use crate::Parameters;
+#[cfg(feature = "hdf5")]
+use ndarray;
+use serde::{Deserialize, Serialize};
+
+/// Possible errors for [`QuantileObserver`].
+#[derive(Clone, Copy, Debug)]
+pub enum QuantileError {
+    Probability,
+    NotEmpty,
+}
+
+impl std::error::Error for QuantileError {}
+
+impl std::fmt::Display for QuantileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Probability => "the probabilities have to be in the range (0, 1)",
+            Self::NotEmpty => "the probabilities cannot be changed after logging values",
+        };
+
+        write!(f, "error in the quantile observer: {}", msg)
+    }
+}
+
+fn check_probabilities(probabilities: &[Float]) -> Result<(), QuantileError> {
+    if !probabilities.iter().all(|&p| p > 0.0 && p < 1.0) {
+        return Err(QuantileError::Probability);
+    }
+
+    Ok(())
+}
+
+fn deserialize_probabilities<'de, D>(deserializer: D) -> Result<Vec<Float>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let probabilities = Vec::<Float>::deserialize(deserializer)?;
+    check_probabilities(&probabilities).map_err(serde::de::Error::custom)?;
+
+    Ok(probabilities)
+}
+
+/// Streaming quantile observer.
+///
+/// Tracks the given quantiles of the amplitude and the magnitude of the
+/// nature angle using the P² algorithm of Jain and Chlamtac (1985), which
+/// requires constant memory independent of the length of the simulation.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct QuantileObserver {
+    pub save_info: SaveInfo,
+
+    /// When and how the mode is logged.
+    #[serde(flatten)]
+    pub logging: LogSettings,
+
+    #[serde(deserialize_with = "deserialize_probabilities")]
+    probabilities: Vec<Float>,
+
+    #[serde(skip)]
+    amplitude: Vec<P2Quantile>,
+
+    #[serde(skip)]
+    chi_abs: Vec<P2Quantile>,
+
+    #[serde(skip)]
+    num_values: usize,
+}
+
+impl QuantileObserver {
+    /// Create a new [`QuantileObserver`] tracking the quantiles `probabilities` (in the range (0, 1)).
+    pub fn new(
+        output_filepath: &PathBuf,
+        group_name: Option<&str>,
+        probabilities: &[Float],
+    ) -> Result<QuantileObserver, QuantileError> {
+        let save_info = SaveInfo::for_observer(output_filepath, group_name);
+        let mut observer = QuantileObserver {
+            save_info,
+            logging: LogSettings::default(),
+            probabilities: probabilities.to_vec(),
+            amplitude: Vec::new(),
+            chi_abs: Vec::new(),
+            num_values: 0,
+        };
+        observer.set_probabilities(probabilities)?;
+
+        Ok(observer)
+    }
+
+    /// Set the quantiles to track, with the `probabilities` in the range (0, 1).
+    ///
+    /// Only possible before any values are logged.
+    pub fn set_probabilities(&mut self, probabilities: &[Float]) -> Result<(), QuantileError> {
+        if self.num_values != 0 {
+            return Err(QuantileError::NotEmpty);
+        }
+        check_probabilities(probabilities)?;
+        self.probabilities = probabilities.to_vec();
+        self.create_estimators();
+
+        Ok(())
+    }
+
+    fn create_estimators(&mut self) {
+        let probabilities = &self.probabilities;
+        self.amplitude = probabilities.iter().map(|&p| P2Quantile::new(p)).collect();
+        self.chi_abs = probabilities.iter().map(|&p| P2Quantile::new(p)).collect();
+    }
+
+    /// Current estimates of the amplitude quantiles.
+    pub fn amplitude_quantiles(&self) -> Vec<Float> {
+        self.amplitude.iter().map(|q| q.estimate()).collect()
+    }
+
+    /// Current estimates of the quantiles of the nature angle magnitude.
+    pub fn chi_abs_quantiles(&self) -> Vec<Float> {
+        self.chi_abs.iter().map(|q| q.estimate()).collect()
+    }
+
+    /// Restart the estimators of the quantiles, keeping the probabilities.
+    pub fn reset(&mut self) {
+        for estimator in self.amplitude.iter_mut().chain(self.chi_abs.iter_mut()) {
+            *estimator = P2Quantile::new(estimator.probability);
+        }
+        self.num_values = 0;
+    }
+}
+
+impl Default for QuantileObserver {
+    fn default() -> Self {
+        let output_filepath = PathBuf::from("simulation_quantiles.hdf5");
+
+        // The default probabilities are valid
+        Self::new(&output_filepath, None, &[0.01, 0.5, 0.99]).unwrap()
+    }
+}
+
+impl_observer_conversions!(QuantileObserver);
+
+impl ObserverTrait for QuantileObserver {
+    #[inline]
+    fn log(&mut self, acoustic_mode: &SystemMode, _hrr_mode: &SystemMode, _time: Float) {
+        // The estimators are skipped when loading from JSON
+        if self.amplitude.len() != self.probabilities.len() {
+            self.create_estimators();
+        }
+
+        let a = acoustic_mode.a();
+        let chi_abs = acoustic_mode.chi().abs();
+        self.amplitude.iter_mut().for_each(|q| q.push(a));
+        self.chi_abs.iter_mut().for_each(|q| q.push(chi_abs));
+
+        self.num_values += 1;
+    }
+
+    #[cfg(feature = "hdf5")]
+    fn save(
+        &self,
+        parameters: &Parameters,
+        _describing_function: &DescribingFunction,
+    ) -> hdf5::Result<()> {
+        let file = hdf5::File::append(&self.save_info.path)?;
+        let group = file.create_group(&self.save_info.group)?;
+
+        super::write_dataset(&group, &self.probabilities, "probability")?;
+        super::write_dataset(&group, &self.amplitude_quantiles(), "amplitude")?;
+        super::write_dataset(&group, &self.chi_abs_quantiles(), "chi_abs")?;
+
+        super::save_attr(&group, &ndarray::arr0(self.num_values), "number_of_values")?;
+        super::save_parameters_as_attribute_json(&group, parameters)
+    }
+
+    fn results_json(&self, _describing_function: &DescribingFunction) -> serde_json::Value {
+        serde_json::json!({
+            "probability": self.probabilities,
+            "amplitude": self.amplitude_quantiles(),
+            "chi_abs": self.chi_abs_quantiles(),
+            "number_of_values": self.num_values,
+        })
+    }
+}
+
+/// P² estimator of a single quantile.
+#[derive(Clone, Debug)]
+struct P2Quantile {
+    probability: Float,
+    heights: [Float; 5],
+    positions: [Float; 5],
+    desired: [Float; 5],
+    increments: [Float; 5],
+    count: usize,
+}
+
+impl P2Quantile {
+    fn new(probability: Float) -> Self {
+        let p = probability;
+
+        Self {
+            probability,
+            heights: [0.0; 5],
+            positions: [1.0, 2.0, 3.0, 4.0, 5.0],
+            desired: [1.0, 1.0 + 2.0 * p, 1.0 + 4.0 * p, 3.0 + 2.0 * p, 5.0],
+            increments: [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0],
+            count: 0,
+        }
+    }
+
+    fn push(&mut self, value: Float) {
+        // Use the first five values as the initial marker heights
+        if self.count < 5 {
+            self.heights[self.count] = value;
+            self.count += 1;
+            if self.count == 5 {
+                self.heights.sort_by(|a, b| a.total_cmp(b));
+            }
+            return;
+        }
+        self.count += 1;
+
+        // Find the cell containing the new value, adjusting the extremes
+        let q = &mut self.heights;
+        let cell = if value < q[0] {
+            q[0] = value;
+            0
+        } else if value >= q[4] {
+            q[4] = value;
+            3
+        } else {
+            (0..4).find(|&i| value < q[i + 1]).unwrap_or(3)
+        };
+
+        // Increment the positions of the markers above the cell
+        for position in self.positions.iter_mut().skip(cell + 1) {
+            *position += 1.0;
+        }
+        for (desired, increment) in self.desired.iter_mut().zip(&self.increments) {
+            *desired += increment;
+        }
+
+        // Adjust the heights of the middle markers if necessary
+        for i in 1..4 {
+            let n = &self.positions;
+            let d = self.desired[i] - n[i];
+            if (d >= 1.0 && n[i + 1] - n[i] > 1.0) || (d <= -1.0 && n[i - 1] - n[i] < -1.0) {
+                let d = d.signum();
+                let parabolic = self.parabolic(i, d);
+                self.heights[i] =
+                    if self.heights[i - 1] < parabolic && parabolic < self.heights[i + 1] {
+                        parabolic
+                    } else {
+                        self.linear(i, d)
+                    };
+                self.positions[i] += d;
+            }
+        }
+    }
+
+    #[inline]
+    fn parabolic(&self, i: usize, d: Float) -> Float {
+        let q = &self.heights;
+        let n = &self.positions;
+
+        q[i] + d / (n[i + 1] - n[i - 1])
+            * ((n[i] - n[i - 1] + d) * (q[i + 1] - q[i]) / (n[i + 1] - n[i])
+                + (n[i + 1] - n[i] - d) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]))
+    }
+
+    #[inline]
+    fn linear(&self, i: usize, d: Float) -> Float {
+        let j = if d > 0.0 { i + 1 } else { i - 1 };
+
+        self.heights[i]
+            + d * (self.heights[j] - self.heights[i]) / (self.positions[j] - self.positions[i])
+    }
+
+    /// Current estimate of the quantile.
+    fn estimate(&self) -> Float {
+        match self.count {
+            0 => Float::NAN,
+            1..=4 => {
+                // Too few values for the markers, use the sorted values directly
+                let mut values = self.heights[..self.count].to_vec();
+                values.sort_by(|a, b| a.total_cmp(b));
+                let index = (self.probability * (self.count - 1) as Float).round() as usize;
+                values[index]
+            }
+            _ => self.heights[2],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn p2_median() {
+        let mut median = P2Quantile::new(0.5);
+        let mut upper = P2Quantile::new(0.9);
+
+        // Permutation of the integers 0..1000
+        for ind in 0..1000 {
+            let value = ((ind * 7919) % 1000) as Float;
+            median.push(value);
+            upper.push(value);
+        }
+
+        assert!((median.estimate() - 500.0).abs() < 20.0);
+        assert!((upper.estimate() - 900.0).abs() < 20.0);
+    }
+
+    #[test]
+    fn invalid_probabilities_are_rejected() {
+        let mut observer = QuantileObserver::default();
+        for p in [0.0, 1.0, 1.5, -0.1, Float::NAN] {
+            assert!(observer.set_probabilities(&[0.5, p]).is_err());
+        }
+        assert_eq!(observer.probabilities, [0.01, 0.5, 0.99]);
+
+        let mut json = serde_json::to_value(&observer).unwrap();
+        assert!(serde_json::from_value::<QuantileObserver>(json.clone()).is_ok());
+        json["probabilities"] = serde_json::json!([0.5, 1.5]);
+        assert!(serde_json::from_value::<QuantileObserver>(json).is_err());
+
+        let mut observer = QuantileObserver::default();
+        let mode = SystemMode::default();
+        observer.log(&mode, &mode, 0.0);
+        assert!(observer.set_probabilities(&[0.5]).is_err());
+    }
+}
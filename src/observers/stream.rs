@@ -0,0 +1,337 @@
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use super::{LogSettings, ObserverTrait, SaveInfo};
+use crate::azimuthal_mode::{Mode, SystemMode};
+use crate::hrr_integral::DescribingFunction;
+use crate::Float;
+#[cfg(feature = "hdf5")]
+use crate::Parameters;
+use serde::{Deserialize, Serialize};
+
+/// Number of frames queued for the writer thread before new frames are dropped.
+const QUEUE_CAPACITY: usize = 4096;
+
+/// Time to wait for the server when connecting or sending, before giving up.
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Logged sample sent over the socket.
+#[derive(Debug, Serialize)]
+struct Frame {
+    time: Float,
+    acoustic: Mode,
+    hrr: Mode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    weight: Option<Float>,
+}
+
+/// Streaming observer.
+///
+/// Sends the logged samples to a TCP server while the simulation runs, as
+/// newline-delimited JSON frames with the time and the acoustic and heat
+/// release rate modes, e.g. for live visualization or coupling to other
+/// tools. The frames are written by a background thread through a bounded
+/// queue, such that a slow server never stalls the simulation: frames are
+/// dropped (and counted) when the queue is full. Nothing else is kept in
+/// memory, and only the parameters are saved to file. If the server cannot
+/// be reached, the samples are dropped after printing a warning, without
+/// stopping the simulation.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StreamObserver {
+    pub save_info: SaveInfo,
+
+    /// When and how the mode is logged.
+    #[serde(flatten)]
+    pub logging: LogSettings,
+
+    /// Address of the TCP server receiving the frames (e.g. `127.0.0.1:9000`).
+    pub address: String,
+
+    #[serde(skip)]
+    writer: Option<Writer>,
+
+    /// Whether connecting or sending has failed (and streaming is stopped).
+    #[serde(skip)]
+    failed: bool,
+
+    #[serde(skip)]
+    frames_sent: Arc<AtomicUsize>,
+
+    #[serde(skip)]
+    frames_dropped: usize,
+}
+
+/// Background thread writing the queued frames to the server.
+///
+/// Dropping the writer sends the remaining frames and waits for the thread to finish.
+#[derive(Debug)]
+struct Writer {
+    sender: Option<SyncSender<Vec<u8>>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Writer {
+    fn spawn(address: String, frames_sent: Arc<AtomicUsize>) -> Writer {
+        let (sender, receiver) = mpsc::sync_channel(QUEUE_CAPACITY);
+        let handle = thread::spawn(move || write_frames(&address, receiver, &frames_sent));
+
+        Writer {
+            sender: Some(sender),
+            handle: Some(handle),
+        }
+    }
+
+    /// Queue a frame, without waiting for the thread.
+    fn try_send(&self, buffer: Vec<u8>) -> Result<(), TrySendError<Vec<u8>>> {
+        match &self.sender {
+            Some(sender) => sender.try_send(buffer),
+            None => Err(TrySendError::Disconnected(buffer)),
+        }
+    }
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        // Closing the queue stops the thread after the remaining frames
+        self.sender = None;
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Connect to the server at `address`, with a timeout for each resolved address.
+fn connect(address: &str) -> std::io::Result<TcpStream> {
+    let mut last_error = None;
+    for socket_address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&socket_address, TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(last_error
+        .unwrap_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no address found")))
+}
+
+/// Write the frames received from the simulation until the queue is closed,
+/// or until the connection fails (which closes the queue).
+fn write_frames(address: &str, receiver: Receiver<Vec<u8>>, frames_sent: &AtomicUsize) {
+    let mut stream = match connect(address) {
+        Ok(stream) => stream,
+        Err(e) => {
+            eprintln!("could not connect to {}: {}, not streaming", address, e);
+            return;
+        }
+    };
+    // A stalled server fails the connection instead of blocking the thread forever
+    if let Err(e) = stream.set_write_timeout(Some(TIMEOUT)) {
+        eprintln!("could not configure the connection to {}: {}", address, e);
+    }
+
+    for buffer in receiver {
+        if let Err(e) = stream.write_all(&buffer) {
+            eprintln!("lost connection to {}: {}, not streaming", address, e);
+            return;
+        }
+        frames_sent.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl StreamObserver {
+    pub fn new(output_filepath: &PathBuf, group_name: Option<&str>) -> StreamObserver {
+        let save_info = SaveInfo::for_observer(output_filepath, group_name);
+        StreamObserver {
+            save_info,
+            logging: LogSettings::default(),
+            address: String::from("127.0.0.1:9000"),
+            writer: None,
+            failed: false,
+            frames_sent: Arc::new(AtomicUsize::new(0)),
+            frames_dropped: 0,
+        }
+    }
+
+    /// Number of frames sent to the server.
+    ///
+    /// Frames still in the queue of the writer thread are not included.
+    pub fn get_frames_sent(&self) -> usize {
+        self.frames_sent.load(Ordering::Relaxed)
+    }
+
+    /// Number of frames dropped because the server could not keep up.
+    pub fn get_frames_dropped(&self) -> usize {
+        self.frames_dropped
+    }
+
+    /// Queue a frame for the writer thread, which is started on the first call.
+    fn send(&mut self, frame: &Frame) {
+        if self.failed {
+            return;
+        }
+
+        // Serialize each frame at once, avoiding one system call per field
+        let mut buffer = match serde_json::to_vec(frame) {
+            Ok(buffer) => buffer,
+            Err(_) => return,
+        };
+        buffer.push(b'\n');
+
+        let writer = self
+            .writer
+            .get_or_insert_with(|| Writer::spawn(self.address.clone(), self.frames_sent.clone()));
+        match writer.try_send(buffer) {
+            Ok(_) => (),
+            Err(TrySendError::Full(_)) => self.frames_dropped += 1,
+            // The writer thread has stopped after printing the reason
+            Err(TrySendError::Disconnected(_)) => {
+                self.writer = None;
+                self.failed = true;
+            }
+        }
+    }
+
+    /// Reset the number of frames sent and dropped, keeping the connection open.
+    ///
+    /// A connection that failed is attempted again when the next frame is sent.
+    pub fn reset(&mut self) {
+        self.frames_sent.store(0, Ordering::Relaxed);
+        self.frames_dropped = 0;
+        self.failed = false;
+    }
+}
+
+impl Clone for StreamObserver {
+    /// Clone the settings, where the clone opens its own connection.
+    fn clone(&self) -> Self {
+        let mut observer = Self::new(&self.save_info.path, Some(&self.save_info.group));
+        observer.logging = self.logging;
+        observer.address = self.address.clone();
+
+        observer
+    }
+}
+
+impl Default for StreamObserver {
+    fn default() -> Self {
+        let output_filepath = PathBuf::from("simulation_stream.hdf5");
+
+        Self::new(&output_filepath, None)
+    }
+}
+
+impl_observer_conversions!(StreamObserver);
+
+impl ObserverTrait for StreamObserver {
+    #[inline]
+    fn log(&mut self, acoustic_mode: &SystemMode, hrr_mode: &SystemMode, time: Float) {
+        let frame = Frame {
+            time,
+            acoustic: Mode::from(*acoustic_mode),
+            hrr: Mode::from(*hrr_mode),
+            weight: None,
+        };
+        self.send(&frame);
+    }
+
+    #[inline]
+    fn log_weighted(
+        &mut self,
+        acoustic_mode: &SystemMode,
+        hrr_mode: &SystemMode,
+        time: Float,
+        weight: Float,
+    ) {
+        let frame = Frame {
+            time,
+            acoustic: Mode::from(*acoustic_mode),
+            hrr: Mode::from(*hrr_mode),
+            weight: Some(weight),
+        };
+        self.send(&frame);
+    }
+
+    #[cfg(feature = "hdf5")]
+    fn save(
+        &self,
+        parameters: &Parameters,
+        _describing_function: &DescribingFunction,
+    ) -> hdf5::Result<()> {
+        let file = hdf5::File::append(&self.save_info.path)?;
+        let group = file.create_group(&self.save_info.group)?;
+
+        super::save_attr(
+            &group,
+            &ndarray::arr0(self.get_frames_sent()),
+            "frames_sent",
+        )?;
+        super::save_attr(
+            &group,
+            &ndarray::arr0(self.frames_dropped),
+            "frames_dropped",
+        )?;
+        super::save_str_attr(&group, &self.address, "address")?;
+
+        super::save_parameters_as_attribute_json(&group, parameters)
+    }
+
+    fn results_json(&self, _describing_function: &DescribingFunction) -> serde_json::Value {
+        serde_json::json!({
+            "frames_sent": self.get_frames_sent(),
+            "frames_dropped": self.frames_dropped,
+            "address": self.address,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    #[test]
+    fn frames_are_received() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut observer = StreamObserver {
+            address: listener.local_addr().unwrap().to_string(),
+            ..Default::default()
+        };
+
+        let mode = SystemMode::new(2.0, 0.5, 0.0, 0.1);
+        observer.log(&mode, &mode, 0.25);
+        observer.log(&mode, &mode, 0.5);
+        drop(observer);
+
+        let (stream, _) = listener.accept().unwrap();
+        let lines: Vec<String> = BufReader::new(stream).lines().map(Result::unwrap).collect();
+        assert_eq!(lines.len(), 2);
+
+        let frame: serde_json::Value = serde_json::from_str(&lines[1]).unwrap();
+        assert_eq!(frame["time"], 0.5);
+        assert!((frame["acoustic"]["amplitude"].as_f64().unwrap() - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn stalled_server_does_not_block() {
+        // The server accepts the connection, but never reads
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut observer = StreamObserver {
+            address: listener.local_addr().unwrap().to_string(),
+            ..Default::default()
+        };
+
+        let mode = SystemMode::new(2.0, 0.5, 0.0, 0.1);
+        for ind in 0..100_000 {
+            observer.log(&mode, &mode, ind as Float);
+        }
+        assert!(observer.get_frames_dropped() > 0 || observer.failed);
+        drop(listener);
+    }
+}
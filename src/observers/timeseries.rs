@@ -1,11 +1,33 @@
+#[cfg(feature = "arrow")]
+use std::collections::HashMap;
+#[cfg(feature = "arrow")]
+use std::path::Path;
 use std::path::PathBuf;
 use std::str::FromStr;
+#[cfg(feature = "arrow")]
+use std::sync::Arc;
 
-use super::{ObserverTrait, SaveInfo};
-use crate::azimuthal_mode::SystemMode;
+#[cfg(feature = "arrow")]
+use arrow_array::{ArrayRef, Float64Array, RecordBatch};
+#[cfg(feature = "arrow")]
+use arrow_ipc::writer::FileWriter;
+#[cfg(feature = "arrow")]
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+#[cfg(feature = "arrow")]
+use parquet::arrow::ArrowWriter;
+
+#[cfg(feature = "hdf5")]
+use super::metadata::Content;
+#[cfg(feature = "npz")]
+use super::npz::NpzWriter;
+use super::{CircularStatistics, LogSettings, ObserverTrait, SaveInfo};
+#[cfg(feature = "hdf5")]
+use crate::azimuthal_mode::Mode;
+use crate::azimuthal_mode::{SpinningWaves, SystemMode};
 use crate::hrr_integral::{DescribingFunction, HeatReleaseRate};
-use crate::{Float, Parameters};
-use hdf5;
+use crate::Float;
+#[cfg(any(feature = "hdf5", feature = "npz", feature = "arrow"))]
+use crate::Parameters;
 use serde::{Deserialize, Serialize};
 
 /// Time series observer.
@@ -15,6 +37,24 @@ use serde::{Deserialize, Serialize};
 pub struct TimeSeriesObserver {
     pub save_info: SaveInfo,
 
+    /// When and how the mode is logged.
+    #[serde(flatten)]
+    pub logging: LogSettings,
+
+    /// Additionally save the trajectory on the Poincaré sphere.
+    #[serde(default)]
+    pub poincare_sphere: bool,
+
+    /// Additionally save the amplitudes of the anticlockwise and clockwise
+    /// spinning waves and their phase difference.
+    #[serde(default)]
+    pub spinning_waves: bool,
+
+    /// Additionally export the time series to an Arrow IPC (`.arrow`) or
+    /// Parquet (`.parquet`) file, requires the `arrow` feature.
+    #[serde(default)]
+    pub table_path: Option<PathBuf>,
+
     #[serde(skip)]
     modes: Vec<SystemMode>,
 
@@ -24,15 +64,13 @@ pub struct TimeSeriesObserver {
 
 impl TimeSeriesObserver {
     pub fn new(output_filepath: &PathBuf, group_name: Option<&str>) -> TimeSeriesObserver {
-        // Set up the save information (path and group name)
-        let mut save_info = SaveInfo::default();
-        save_info.set_path(output_filepath);
-        if let Some(group) = group_name {
-            save_info.set_group(group);
-        }
-
+        let save_info = SaveInfo::for_observer(output_filepath, group_name);
         TimeSeriesObserver {
             save_info,
+            logging: LogSettings::default(),
+            poincare_sphere: false,
+            spinning_waves: false,
+            table_path: None,
             modes: Vec::new(),
             time: Vec::new(),
         }
@@ -42,6 +80,10 @@ impl TimeSeriesObserver {
     pub fn with_capacity(capacity: usize) -> TimeSeriesObserver {
         TimeSeriesObserver {
             save_info: SaveInfo::default(),
+            logging: LogSettings::default(),
+            poincare_sphere: false,
+            spinning_waves: false,
+            table_path: None,
             modes: Vec::with_capacity(capacity),
             time: Vec::with_capacity(capacity),
         }
@@ -55,6 +97,193 @@ impl TimeSeriesObserver {
         self.modes.reserve(additional);
         self.time.reserve(additional);
     }
+
+    /// Get the logged modes.
+    pub fn get_modes(&self) -> &[SystemMode] {
+        &self.modes
+    }
+
+    /// Get the times of the logged modes.
+    pub fn get_time(&self) -> &[Float] {
+        &self.time
+    }
+
+    /// Strength of the locking of the orientation to the reference angle.
+    ///
+    /// See [`super::HistogramObserver::orientation_locking`].
+    pub fn orientation_locking(&self) -> Float {
+        let mut locking = CircularStatistics::new();
+        for mode in &self.modes {
+            locking.push(2.0 * mode.nth0(), 1.0);
+        }
+
+        locking.mean_cos()
+    }
+
+    /// Nature angle of the heat release rate for each of the logged modes.
+    fn hrr_nature_angles(&self, describing_function: &DescribingFunction) -> Vec<Float> {
+        self.modes
+            .iter()
+            .map(|acoustic_mode| describing_function.mode(acoustic_mode).chi())
+            .collect()
+    }
+
+    /// Convert the time series to an Arrow record batch.
+    ///
+    /// The columns are named as the datasets of the HDF5 output, and the
+    /// parameters are stored as JSON in the `parameters` field of the schema
+    /// metadata.
+    #[cfg(feature = "arrow")]
+    pub fn to_record_batch(
+        &self,
+        parameters: &Parameters,
+        describing_function: &DescribingFunction,
+    ) -> Result<RecordBatch, ArrowError> {
+        let columns: [(&str, Vec<Float>); 6] = [
+            ("time", self.time.clone()),
+            (
+                "amplitude",
+                self.modes.iter().map(|mode| mode.a()).collect(),
+            ),
+            (
+                "ntheta_0",
+                self.modes.iter().map(|mode| mode.nth0()).collect(),
+            ),
+            ("phi", self.modes.iter().map(|mode| mode.phi()).collect()),
+            ("chi", self.modes.iter().map(|mode| mode.chi()).collect()),
+            ("chi_q", self.hrr_nature_angles(describing_function)),
+        ];
+
+        let fields: Vec<Field> = columns
+            .iter()
+            .map(|(name, _)| Field::new(*name, DataType::Float64, false))
+            .collect();
+        let metadata = HashMap::from([(
+            "parameters".to_owned(),
+            serde_json::to_string(parameters).unwrap_or_default(),
+        )]);
+        let schema = Schema::new(fields).with_metadata(metadata);
+
+        let arrays: Vec<ArrayRef> = columns
+            .into_iter()
+            .map(|(_, values)| Arc::new(Float64Array::from(values)) as ArrayRef)
+            .collect();
+
+        RecordBatch::try_new(Arc::new(schema), arrays)
+    }
+
+    /// Export the time series to `path` as a Parquet file if the extension
+    /// is `.parquet`, and as an Arrow IPC file otherwise.
+    #[cfg(feature = "arrow")]
+    pub fn save_table(
+        &self,
+        path: &Path,
+        parameters: &Parameters,
+        describing_function: &DescribingFunction,
+    ) -> parquet::errors::Result<()> {
+        let batch = self.to_record_batch(parameters, describing_function)?;
+        let file = std::fs::File::create(path)?;
+
+        if path.extension().is_some_and(|ext| ext == "parquet") {
+            let mut writer = ArrowWriter::try_new(file, batch.schema(), None)?;
+            writer.write(&batch)?;
+            writer.close()?;
+        } else {
+            let mut writer = FileWriter::try_new(file, &batch.schema())?;
+            writer.write(&batch)?;
+            writer.finish()?;
+        }
+
+        Ok(())
+    }
+
+    /// Save the time series to the `.npz` file given by the save information.
+    ///
+    /// The arrays are named as the datasets of the HDF5 output.
+    #[cfg(feature = "npz")]
+    pub fn save_npz(
+        &self,
+        parameters: &Parameters,
+        describing_function: &DescribingFunction,
+    ) -> std::io::Result<()> {
+        let mut writer = NpzWriter::open(&self.save_info)?;
+
+        writer.write("time", &self.time)?;
+        let a: Vec<Float> = self.modes.iter().map(|mode| mode.a()).collect();
+        writer.write("amplitude", &a)?;
+        let nth0: Vec<Float> = self.modes.iter().map(|mode| mode.nth0()).collect();
+        writer.write("ntheta_0", &nth0)?;
+        let phi: Vec<Float> = self.modes.iter().map(|mode| mode.phi()).collect();
+        writer.write("phi", &phi)?;
+        let chi: Vec<Float> = self.modes.iter().map(|mode| mode.chi()).collect();
+        writer.write("chi", &chi)?;
+        writer.write("chi_q", &self.hrr_nature_angles(describing_function))?;
+
+        if self.poincare_sphere {
+            let coordinates: Vec<[Float; 3]> = self
+                .modes
+                .iter()
+                .map(|mode| mode.poincare_sphere())
+                .collect();
+            for (ind, name) in ["x", "y", "z"].into_iter().enumerate() {
+                let component: Vec<Float> = coordinates.iter().map(|c| c[ind]).collect();
+                writer.write(&format!("poincare_sphere/{}", name), &component)?;
+            }
+        }
+
+        if self.spinning_waves {
+            for (name, component) in spinning_wave_components(&self.modes) {
+                writer.write(&format!("spinning_waves/{}", name), &component)?;
+            }
+        }
+
+        writer.write_scalar("orientation_locking", self.orientation_locking())?;
+        if let Some(reference_angle) = self.logging.reference_angle {
+            writer.write_scalar("reference_angle", reference_angle)?;
+        }
+        writer.write_str("parameters", &parameters.to_string())?;
+        writer.finish()
+    }
+
+    /// Read the last logged [`Mode`] of a time series saved to `group` in the file `path`.
+    #[cfg(feature = "hdf5")]
+    pub fn read_final_mode(path: &PathBuf, group: &str) -> hdf5::Result<Mode> {
+        let file = hdf5::File::open(path)?;
+        let group = file.group(group)?;
+
+        // Read the last element of each of the state space variables
+        let mut values = [0.0; 4];
+        for (value, name) in values
+            .iter_mut()
+            .zip(["amplitude", "ntheta_0", "phi", "chi"])
+        {
+            *value = match read_last_value(&group, name)? {
+                Some(last) => last,
+                None => return Err(format!("the dataset \"{}\" is empty", name).into()),
+            };
+        }
+
+        let [amplitude, orientation_angle, phase, nature_angle] = values;
+        Ok(Mode::new(amplitude, orientation_angle, phase, nature_angle))
+    }
+
+    /// Read the time of the last logged mode of a time series saved to `group` in the file `path`.
+    #[cfg(feature = "hdf5")]
+    pub fn read_final_time(path: &PathBuf, group: &str) -> hdf5::Result<Float> {
+        let file = hdf5::File::open(path)?;
+        let group = file.group(group)?;
+
+        match read_last_value(&group, "time")? {
+            Some(time) => Ok(time),
+            None => Err("the dataset \"time\" is empty".into()),
+        }
+    }
+
+    /// Clear the logged modes and times, keeping the settings and the reserved capacity.
+    pub fn reset(&mut self) {
+        self.modes.clear();
+        self.time.clear();
+    }
 }
 
 impl Default for TimeSeriesObserver {
@@ -65,18 +294,7 @@ impl Default for TimeSeriesObserver {
     }
 }
 
-impl From<SaveInfo> for TimeSeriesObserver {
-    fn from(value: SaveInfo) -> Self {
-        Self::new(&value.path, Some(&value.group))
-    }
-}
-
-impl std::fmt::Display for TimeSeriesObserver {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let data_string = serde_json::to_string(self).unwrap_or_default();
-        write!(f, "TimeSeriesObserver: {}", data_string)
-    }
-}
+impl_observer_conversions!(TimeSeriesObserver);
 
 impl FromStr for TimeSeriesObserver {
     type Err = serde_json::Error;
@@ -93,6 +311,7 @@ impl ObserverTrait for TimeSeriesObserver {
         self.time.push(time);
     }
 
+    #[cfg(feature = "hdf5")]
     fn save(
         &self,
         parameters: &Parameters,
@@ -101,33 +320,246 @@ impl ObserverTrait for TimeSeriesObserver {
         // Open the file
         let file = hdf5::File::append(&self.save_info.path)?;
 
-        // Obtain the group to save the data to
-        // Load the group name, or use the default
-        let group = file.create_group(&self.save_info.group)?;
+        // Obtain the group to save the data to, continuing it if appending
+        let continued = self.save_info.get_append() && file.link_exists(&self.save_info.group);
+        let group = if continued {
+            file.group(&self.save_info.group)?
+        } else {
+            file.create_group(&self.save_info.group)?
+        };
+
+        // Skip the samples up to the last saved time, i.e. the initial mode of a resumed simulation
+        let resumed_at = if continued {
+            read_last_value(&group, "time")?
+        } else {
+            None
+        };
+        let start = match resumed_at {
+            Some(last) => self.time.iter().take_while(|&&time| time <= last).count(),
+            None => 0,
+        };
+        let modes = &self.modes[start..];
 
         // Save the time of each sample
-        super::write_dataset(&group, &self.time, "time")?;
+        append_described(&group, &self.time[start..], "time")?;
 
         // Convert the data into individual vectors
-        let a: Vec<Float> = self.modes.iter().map(|mode| mode.a()).collect();
-        super::write_dataset(&group, &a, "amplitude")?;
+        let a: Vec<Float> = modes.iter().map(|mode| mode.a()).collect();
+        append_described(&group, &a, "amplitude")?;
 
-        let nth0: Vec<Float> = self.modes.iter().map(|mode| mode.nth0()).collect();
-        super::write_dataset(&group, &nth0, "ntheta_0")?;
+        let nth0: Vec<Float> = modes.iter().map(|mode| mode.nth0()).collect();
+        append_described(&group, &nth0, "ntheta_0")?;
 
-        let phi: Vec<Float> = self.modes.iter().map(|mode| mode.phi()).collect();
-        super::write_dataset(&group, &phi, "phi")?;
+        let phi: Vec<Float> = modes.iter().map(|mode| mode.phi()).collect();
+        append_described(&group, &phi, "phi")?;
 
-        let chi: Vec<Float> = self.modes.iter().map(|mode| mode.chi()).collect();
-        super::write_dataset(&group, &chi, "chi")?;
+        let chi: Vec<Float> = modes.iter().map(|mode| mode.chi()).collect();
+        append_described(&group, &chi, "chi")?;
+
+        let chi_q = self.hrr_nature_angles(describing_function);
+        append_described(&group, &chi_q[start..], "chi_q")?;
+
+        // Save the Poincaré sphere coordinates in a subgroup
+        if self.poincare_sphere {
+            let sphere_group = if continued && group.link_exists("poincare_sphere") {
+                group.group("poincare_sphere")?
+            } else {
+                group.create_group("poincare_sphere")?
+            };
+            let coordinates: Vec<[Float; 3]> =
+                modes.iter().map(|mode| mode.poincare_sphere()).collect();
+            for (ind, name) in ["x", "y", "z"].into_iter().enumerate() {
+                let component: Vec<Float> = coordinates.iter().map(|c| c[ind]).collect();
+                super::append_dataset(&sphere_group, &component, name)?;
+            }
+        }
+
+        // Save the decomposition into spinning waves in a subgroup
+        if self.spinning_waves {
+            let waves_group = if continued && group.link_exists("spinning_waves") {
+                group.group("spinning_waves")?
+            } else {
+                group.create_group("spinning_waves")?
+            };
+            for (name, component) in spinning_wave_components(modes) {
+                super::append_dataset(&waves_group, &component, name)?;
+            }
+        }
+
+        if let Some(path) = &self.table_path {
+            #[cfg(feature = "arrow")]
+            self.save_table(path, parameters, describing_function)
+                .map_err(|e| format!("could not export \"{}\": {}", path.display(), e))?;
+
+            #[cfg(not(feature = "arrow"))]
+            return Err(format!(
+                "exporting \"{}\" requires the `arrow` feature",
+                path.display()
+            )
+            .into());
+        }
 
-        let mut chi_q = Vec::with_capacity(chi.len());
-        for acoustic_mode in &self.modes {
-            let hrr_mode = describing_function.mode(&acoustic_mode);
-            chi_q.push(hrr_mode.chi());
+        // The attributes of a continued group describe its first simulation
+        if continued {
+            return match resumed_at {
+                Some(time) => record_resume_point(&group, time),
+                None => Ok(()),
+            };
+        }
+
+        // Save the orientation locking relative to the reference angle (or to zero)
+        let locking = self.orientation_locking();
+        super::save_attr(&group, &ndarray::arr0(locking), "orientation_locking")?;
+        if let Some(reference_angle) = self.logging.reference_angle {
+            super::save_attr(&group, &ndarray::arr0(reference_angle), "reference_angle")?;
         }
-        super::write_dataset(&group, &chi_q, "chi_q")?;
 
         super::save_parameters_as_attribute_json(&group, parameters)
     }
+
+    fn results_json(&self, describing_function: &DescribingFunction) -> serde_json::Value {
+        let modes = &self.modes;
+        let mut results = serde_json::json!({
+            "time": self.time,
+            "amplitude": modes.iter().map(|mode| mode.a()).collect::<Vec<_>>(),
+            "ntheta_0": modes.iter().map(|mode| mode.nth0()).collect::<Vec<_>>(),
+            "phi": modes.iter().map(|mode| mode.phi()).collect::<Vec<_>>(),
+            "chi": modes.iter().map(|mode| mode.chi()).collect::<Vec<_>>(),
+            "chi_q": self.hrr_nature_angles(describing_function),
+            "orientation_locking": self.orientation_locking(),
+            "reference_angle": self.logging.reference_angle,
+        });
+        if self.poincare_sphere {
+            let coordinates: Vec<[Float; 3]> =
+                modes.iter().map(|mode| mode.poincare_sphere()).collect();
+            let component = |ind: usize| coordinates.iter().map(|c| c[ind]).collect::<Vec<_>>();
+            results["poincare_sphere"] = serde_json::json!({
+                "x": component(0),
+                "y": component(1),
+                "z": component(2),
+            });
+        }
+        if self.spinning_waves {
+            let components = spinning_wave_components(modes);
+            results["spinning_waves"] = components
+                .into_iter()
+                .map(|(name, component)| (name.to_owned(), component.into()))
+                .collect::<serde_json::Map<_, _>>()
+                .into();
+        }
+
+        results
+    }
+}
+
+/// Amplitudes of the anticlockwise and clockwise spinning waves and their phase difference.
+fn spinning_wave_components(modes: &[SystemMode]) -> [(&'static str, Vec<Float>); 3] {
+    let waves: Vec<SpinningWaves> = modes.iter().map(|&mode| mode.into()).collect();
+
+    [
+        (
+            "anticlockwise",
+            waves.iter().map(|w| w.anticlockwise).collect(),
+        ),
+        ("clockwise", waves.iter().map(|w| w.clockwise).collect()),
+        (
+            "phase_difference",
+            waves.iter().map(|w| w.phase_difference()).collect(),
+        ),
+    ]
+}
+
+/// Append `values` to the time series `name`, describing the dataset when it is created.
+#[cfg(feature = "hdf5")]
+fn append_described(group: &hdf5::Group, values: &[Float], name: &str) -> hdf5::Result<()> {
+    let (dataset, created) = super::append_dataset(group, values, name)?;
+    if let (true, Some(metadata)) = (created, Content::Values.metadata(name)) {
+        metadata.save(&dataset)?;
+    }
+
+    Ok(())
+}
+
+/// Last value of the time series `name` of `group`, or `None` if it is empty.
+#[cfg(feature = "hdf5")]
+fn read_last_value(group: &hdf5::Group, name: &str) -> hdf5::Result<Option<Float>> {
+    let dataset = group.dataset(name)?;
+    let samples = dataset.size();
+    if samples == 0 {
+        return Ok(None);
+    }
+
+    let last = dataset.read_slice_1d::<Float, _>(ndarray::s![samples - 1..samples])?;
+    Ok(Some(last[0]))
+}
+
+/// Add `time` to the JSON list of times the time series of `group` was resumed at.
+#[cfg(feature = "hdf5")]
+fn record_resume_point(group: &hdf5::Group, time: Float) -> hdf5::Result<()> {
+    let attr = match group.attr("resume_points") {
+        Ok(attr) => attr,
+        Err(_) => {
+            let resume_points = serde_json::to_string(&[time]).unwrap_or_default();
+            return super::save_str_attr(group, &resume_points, "resume_points");
+        }
+    };
+
+    let saved = attr.read_scalar::<hdf5::types::VarLenUnicode>()?;
+    let mut resume_points: Vec<Float> = serde_json::from_str(saved.as_str()).unwrap_or_default();
+    resume_points.push(time);
+    let value: hdf5::types::VarLenUnicode = serde_json::to_string(&resume_points)
+        .unwrap_or_default()
+        .parse()
+        .unwrap();
+
+    attr.write_scalar(&value)
+}
+
+#[cfg(all(test, feature = "arrow"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_round_trip() {
+        let mut observer = TimeSeriesObserver::default();
+        for ind in 0..10 {
+            let mode = SystemMode::new(1.0 + ind as Float, 0.1, 0.2, 0.3);
+            observer.log(&mode, &mode, ind as Float);
+        }
+
+        let parameters = Parameters::default();
+        let describing_function = DescribingFunction::default();
+        let directory = std::env::temp_dir();
+
+        let path = directory.join("afdf_table_round_trip.arrow");
+        observer
+            .save_table(&path, &parameters, &describing_function)
+            .unwrap();
+        let reader =
+            arrow_ipc::reader::FileReader::try_new(std::fs::File::open(&path).unwrap(), None)
+                .unwrap();
+        let batches: Vec<RecordBatch> = reader.map(|batch| batch.unwrap()).collect();
+        assert_eq!(batches[0].num_rows(), 10);
+        assert!(batches[0].schema().metadata().contains_key("parameters"));
+        std::fs::remove_file(&path).unwrap();
+
+        let path = directory.join("afdf_table_round_trip.parquet");
+        observer
+            .save_table(&path, &parameters, &describing_function)
+            .unwrap();
+        let reader = parquet::arrow::arrow_reader::ParquetRecordBatchReader::try_new(
+            std::fs::File::open(&path).unwrap(),
+            1024,
+        )
+        .unwrap();
+        let batch = reader.into_iter().next().unwrap().unwrap();
+        let amplitude = batch
+            .column_by_name("amplitude")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(amplitude.value(9), observer.get_modes()[9].a());
+        std::fs::remove_file(&path).unwrap();
+    }
 }
@@ -0,0 +1,346 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+#[cfg(feature = "hdf5")]
+use super::metadata::Content;
+use super::{LogSettings, ObserverTrait, SaveInfo};
+use crate::azimuthal_mode::SystemMode;
+use crate::hrr_integral::DescribingFunction;
+#[cfg(feature = "hdf5")]
+use crate::Parameters;
+use crate::{Float, FRAC_PI_4};
+#[cfg(feature = "hdf5")]
+use ndarray;
+use serde::{Deserialize, Serialize};
+
+/// Possible errors for the grids of [`TransitionMatrixObserver`] and
+/// [`CrossStatisticsObserver`](super::CrossStatisticsObserver).
+#[derive(Clone, Copy, Debug)]
+pub enum GridError {
+    AmplitudeLimit,
+    Bins,
+}
+
+impl std::error::Error for GridError {}
+
+impl std::fmt::Display for GridError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::AmplitudeLimit => "the amplitude limit has to be a positive number",
+            Self::Bins => "the number of bins has to be at least one",
+        };
+
+        write!(f, "error in the observer grid: {}", msg)
+    }
+}
+
+pub(super) fn check_amplitude_limit(amplitude_limit: Float) -> Result<(), GridError> {
+    if !(amplitude_limit > 0.0 && amplitude_limit.is_finite()) {
+        return Err(GridError::AmplitudeLimit);
+    }
+
+    Ok(())
+}
+
+pub(super) fn check_bins(nbins: usize) -> Result<(), GridError> {
+    if nbins == 0 {
+        return Err(GridError::Bins);
+    }
+
+    Ok(())
+}
+
+pub(super) fn deserialize_amplitude_limit<'de, D>(deserializer: D) -> Result<Float, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let amplitude_limit = Float::deserialize(deserializer)?;
+    check_amplitude_limit(amplitude_limit).map_err(serde::de::Error::custom)?;
+
+    Ok(amplitude_limit)
+}
+
+pub(super) fn deserialize_bins<'de, D>(deserializer: D) -> Result<usize, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let nbins = usize::deserialize(deserializer)?;
+    check_bins(nbins).map_err(serde::de::Error::custom)?;
+
+    Ok(nbins)
+}
+
+/// Transition matrix (Markov) observer.
+///
+/// Discretizes the amplitude and nature angle into a coarse grid, and
+/// counts the transitions between the grid cells separated by `lag`
+/// logged samples. The state index of a cell is given by
+/// `amplitude_bin * chi_bins + chi_bin`, and element `(i, j)` of the
+/// saved matrix counts the transitions from state `i` to state `j`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TransitionMatrixObserver {
+    pub save_info: SaveInfo,
+
+    /// When and how the mode is logged.
+    #[serde(flatten)]
+    pub logging: LogSettings,
+
+    #[serde(deserialize_with = "deserialize_amplitude_limit")]
+    amplitude_limit: Float,
+    #[serde(deserialize_with = "deserialize_bins")]
+    amplitude_bins: usize,
+    #[serde(deserialize_with = "deserialize_bins")]
+    chi_bins: usize,
+    lag: usize,
+
+    #[serde(skip)]
+    history: VecDeque<usize>,
+
+    #[serde(skip)]
+    counts: Vec<usize>,
+}
+
+impl TransitionMatrixObserver {
+    /// Set up the observer, with the amplitudes between 0 and `amplitude_limit`
+    /// discretized into `amplitude_bins` bins and the nature angles into `chi_bins` bins.
+    pub fn new(
+        output_filepath: &PathBuf,
+        group_name: Option<&str>,
+        amplitude_limit: Float,
+        amplitude_bins: usize,
+        chi_bins: usize,
+        lag: usize,
+    ) -> Result<TransitionMatrixObserver, GridError> {
+        check_amplitude_limit(amplitude_limit)?;
+        check_bins(amplitude_bins)?;
+        check_bins(chi_bins)?;
+
+        let save_info = SaveInfo::for_observer(output_filepath, group_name);
+        let mut observer = TransitionMatrixObserver {
+            save_info,
+            logging: LogSettings::default(),
+            amplitude_limit,
+            amplitude_bins,
+            chi_bins,
+            lag: lag.max(1),
+            history: VecDeque::new(),
+            counts: Vec::new(),
+        };
+        observer.allocate();
+
+        Ok(observer)
+    }
+
+    /// Set the lag (in number of logged samples) between the counted states.
+    pub fn set_lag(&mut self, lag: usize) {
+        self.lag = lag.max(1);
+        self.history.clear();
+    }
+
+    /// Allocate the storage for the transition counts.
+    ///
+    /// Required after loading the observer from JSON.
+    fn allocate(&mut self) {
+        let number_of_states = self.number_of_states();
+        self.counts = vec![0; number_of_states * number_of_states];
+        self.history = VecDeque::with_capacity(self.lag + 1);
+    }
+
+    /// Number of discrete states in the grid.
+    #[inline]
+    pub fn number_of_states(&self) -> usize {
+        self.amplitude_bins * self.chi_bins
+    }
+
+    /// Discrete state index of the given mode.
+    #[inline]
+    fn state(&self, mode: &SystemMode) -> usize {
+        let a_bin = bin(mode.a(), 0.0, self.amplitude_limit, self.amplitude_bins);
+        let chi_bin = bin(mode.chi(), -FRAC_PI_4, FRAC_PI_4, self.chi_bins);
+
+        a_bin * self.chi_bins + chi_bin
+    }
+
+    /// Transition probabilities, i.e. the transition counts normalised by the
+    /// total count of each row (in row-major order).
+    ///
+    /// The rows of states that were never left are zero.
+    pub fn transition_probabilities(&self) -> Vec<Float> {
+        let number_of_states = self.number_of_states();
+        let mut probabilities = Vec::with_capacity(self.counts.len());
+        for row in self.counts.chunks(number_of_states) {
+            let total = row.iter().sum::<usize>().max(1) as Float;
+            probabilities.extend(row.iter().map(|&count| count as Float / total));
+        }
+
+        probabilities
+    }
+
+    /// Clear the transition counts and the history of states, keeping the grid and the lag.
+    pub fn reset(&mut self) {
+        self.counts.fill(0);
+        self.history.clear();
+    }
+}
+
+impl Default for TransitionMatrixObserver {
+    fn default() -> Self {
+        let output_filepath = PathBuf::from("simulation_transition_matrix.hdf5");
+
+        // The default grid is valid
+        Self::new(&output_filepath, None, 5.0, 10, 10, 1).unwrap()
+    }
+}
+
+impl_observer_conversions!(TransitionMatrixObserver);
+
+impl ObserverTrait for TransitionMatrixObserver {
+    #[inline]
+    fn log(&mut self, acoustic_mode: &SystemMode, _hrr_mode: &SystemMode, _time: Float) {
+        // The storage is skipped when loading from JSON
+        if self.counts.is_empty() {
+            self.allocate();
+        }
+
+        let state = self.state(acoustic_mode);
+        self.history.push_back(state);
+
+        // Count the transition from the state `lag` samples ago
+        if self.history.len() > self.lag {
+            if let Some(previous_state) = self.history.pop_front() {
+                let index = previous_state * self.number_of_states() + state;
+                self.counts[index] += 1;
+            }
+        }
+    }
+
+    #[cfg(feature = "hdf5")]
+    fn save(
+        &self,
+        parameters: &Parameters,
+        _describing_function: &DescribingFunction,
+    ) -> hdf5::Result<()> {
+        let file = hdf5::File::append(&self.save_info.path)?;
+        let group = file.create_group(&self.save_info.group)?;
+
+        // Save the transition counts as a (from, to) matrix
+        let number_of_states = self.number_of_states();
+        let counts = ndarray::Array2::from_shape_vec(
+            (number_of_states, number_of_states),
+            self.counts.clone(),
+        )
+        .map_err(|e| e.to_string())?;
+        group
+            .new_dataset_builder()
+            .with_data(&counts)
+            .create("counts")?;
+        let probabilities = ndarray::Array2::from_shape_vec(
+            (number_of_states, number_of_states),
+            self.transition_probabilities(),
+        )
+        .map_err(|e| e.to_string())?;
+        group
+            .new_dataset_builder()
+            .with_data(&probabilities)
+            .create("probabilities")?;
+
+        // Save the bin edges in a subgroup
+        let edge_group = group.create_group("bin_edges")?;
+        let a_edges = bin_edges(0.0, self.amplitude_limit, self.amplitude_bins);
+        let chi_edges = bin_edges(-FRAC_PI_4, FRAC_PI_4, self.chi_bins);
+        super::write_described_dataset(&edge_group, &a_edges, "amplitude", Content::BinEdges)?;
+        super::write_described_dataset(&edge_group, &chi_edges, "chi", Content::BinEdges)?;
+
+        super::save_attr(&group, &ndarray::arr0(self.lag), "lag")?;
+        super::save_parameters_as_attribute_json(&group, parameters)
+    }
+
+    fn results_json(&self, _describing_function: &DescribingFunction) -> serde_json::Value {
+        let number_of_states = self.number_of_states();
+        let probabilities = self.transition_probabilities();
+
+        serde_json::json!({
+            "counts": self.counts.chunks(number_of_states).collect::<Vec<_>>(),
+            "probabilities": probabilities.chunks(number_of_states).collect::<Vec<_>>(),
+            "bin_edges": {
+                "amplitude": bin_edges(0.0, self.amplitude_limit, self.amplitude_bins),
+                "chi": bin_edges(-FRAC_PI_4, FRAC_PI_4, self.chi_bins),
+            },
+            "lag": self.lag,
+        })
+    }
+}
+
+/// Bin index of `value` in `nbins` equally sized bins between `min` and `max`.
+///
+/// Values outside the range are put in the first or last bin. Requires `min < max`
+/// and `nbins >= 1`.
+#[inline]
+pub(super) fn bin(value: Float, min: Float, max: Float, nbins: usize) -> usize {
+    let index = ((value - min) / (max - min) * nbins as Float).floor();
+
+    (index.max(0.0) as usize).min(nbins - 1)
+}
+
+pub(super) fn bin_edges(min: Float, max: Float, nbins: usize) -> Vec<Float> {
+    let bin_length = (max - min) / nbins as Float;
+
+    (0..=nbins)
+        .map(|ind| min + bin_length * ind as Float)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bins_and_edges() {
+        assert_eq!(bin(0.0, 0.0, 1.0, 4), 0);
+        assert_eq!(bin(0.3, 0.0, 1.0, 4), 1);
+        assert_eq!(bin(0.99, 0.0, 1.0, 4), 3);
+        // Values outside the range are clamped
+        assert_eq!(bin(-1.0, 0.0, 1.0, 4), 0);
+        assert_eq!(bin(2.0, 0.0, 1.0, 4), 3);
+        assert_eq!(bin(Float::NAN, 0.0, 1.0, 4), 0);
+
+        assert_eq!(bin_edges(-1.0, 1.0, 4), [-1.0, -0.5, 0.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn rows_are_normalised() {
+        let mut observer = TransitionMatrixObserver::default();
+        // Alternate between two states, staying in the first state every third step
+        for ind in 0..30 {
+            let a = if ind % 3 == 2 { 3.2 } else { 0.2 };
+            let mode = SystemMode::new(a, 0.0, 0.0, 0.0);
+            observer.log(&mode, &mode, 0.0);
+        }
+        assert_eq!(observer.counts.iter().sum::<usize>(), 29);
+
+        let number_of_states = observer.number_of_states();
+        let probabilities = observer.transition_probabilities();
+        for row in probabilities.chunks(number_of_states) {
+            let total: Float = row.iter().sum();
+            assert!(total == 0.0 || (total - 1.0).abs() < 1e-12);
+        }
+        let from = observer.state(&SystemMode::new(0.2, 0.0, 0.0, 0.0));
+        let to = observer.state(&SystemMode::new(3.2, 0.0, 0.0, 0.0));
+        assert!((probabilities[from * number_of_states + to] - 0.5).abs() < 0.05);
+        assert_eq!(probabilities[to * number_of_states + from], 1.0);
+    }
+
+    #[test]
+    fn invalid_grids_are_rejected() {
+        let path = PathBuf::from("transition_matrix.hdf5");
+        assert!(TransitionMatrixObserver::new(&path, None, 5.0, 0, 10, 1).is_err());
+        assert!(TransitionMatrixObserver::new(&path, None, 5.0, 10, 0, 1).is_err());
+        assert!(TransitionMatrixObserver::new(&path, None, 0.0, 10, 10, 1).is_err());
+        assert!(TransitionMatrixObserver::new(&path, None, Float::NAN, 10, 10, 1).is_err());
+
+        let mut json = serde_json::to_value(TransitionMatrixObserver::default()).unwrap();
+        assert!(serde_json::from_value::<TransitionMatrixObserver>(json.clone()).is_ok());
+        json["chi_bins"] = serde_json::json!(0);
+        assert!(serde_json::from_value::<TransitionMatrixObserver>(json).is_err());
+    }
+}
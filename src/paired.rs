@@ -0,0 +1,149 @@
+//! Paired comparison of two simulations with common random numbers.
+//!
+//! Running two settings with identical noise realizations removes most of
+//! the noise from the difference between them, such that small changes of
+//! the model (e.g. in `gain_ratio_r`) can be quantified with short simulations.
+#[cfg(feature = "hdf5")]
+use crate::observers::{self, SaveInfo};
+use crate::sensitivity::run_time_series;
+use crate::{Float, OutputStatistics, Settings};
+
+/// Possible errors for [`PairedComparison`].
+#[derive(Clone, Debug)]
+pub enum PairedComparisonError {
+    Length,
+}
+
+impl std::error::Error for PairedComparisonError {}
+
+impl std::fmt::Display for PairedComparisonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Length => "the simulations have to be saved at the same times",
+        };
+
+        write!(f, "error in the paired comparison: {}", msg)
+    }
+}
+
+/// Paired differences (second minus first) of two simulations.
+#[derive(Clone, Debug)]
+pub struct PairedComparison {
+    pub first: OutputStatistics,
+    pub second: OutputStatistics,
+    pub time: Vec<Float>,
+    pub amplitude_difference: Vec<Float>,
+    pub nature_angle_difference: Vec<Float>,
+}
+
+impl PairedComparison {
+    /// Run both settings with the same seed and compare the results.
+    ///
+    /// The seed of `first` is used, or a random seed if it is not seeded.
+    /// The noise realizations are only identical as long as both settings
+    /// draw the same number of random numbers per time step, i.e. with the
+    /// same time step and the same type of describing function.
+    pub fn run(first: &Settings, second: &Settings) -> Result<Self, PairedComparisonError> {
+        let seed = first.seed.unwrap_or_else(rand::random);
+
+        let mut first = first.clone();
+        first.set_seed(Some(seed));
+        let mut second = second.clone();
+        second.set_seed(Some(seed));
+
+        let first = run_time_series(first);
+        let second = run_time_series(second);
+        if first.get_time() != second.get_time() {
+            return Err(PairedComparisonError::Length);
+        }
+
+        let (first_modes, second_modes) = (first.get_modes(), second.get_modes());
+        let amplitude_difference = first_modes
+            .iter()
+            .zip(second_modes)
+            .map(|(m1, m2)| m2.a() - m1.a())
+            .collect();
+        let nature_angle_difference = first_modes
+            .iter()
+            .zip(second_modes)
+            .map(|(m1, m2)| m2.chi() - m1.chi())
+            .collect();
+
+        Ok(Self {
+            first: OutputStatistics::from_modes(first_modes),
+            second: OutputStatistics::from_modes(second_modes),
+            time: first.get_time().to_vec(),
+            amplitude_difference,
+            nature_angle_difference,
+        })
+    }
+
+    /// Difference of the output statistics (second minus first).
+    pub fn difference(&self) -> OutputStatistics {
+        self.second - self.first
+    }
+
+    /// Root mean square of the paired amplitude difference.
+    pub fn rms_amplitude_difference(&self) -> Float {
+        let n = self.amplitude_difference.len().max(1) as Float;
+        (self
+            .amplitude_difference
+            .iter()
+            .map(|d| d.powi(2))
+            .sum::<Float>()
+            / n)
+            .sqrt()
+    }
+
+    /// Save the paired differences of the trajectories.
+    #[cfg(feature = "hdf5")]
+    pub fn save(&self, save_info: &SaveInfo, settings: &Settings) -> hdf5::Result<()> {
+        let file = hdf5::File::append(save_info.get_path())?;
+        let group = file.create_group(save_info.get_group())?;
+
+        observers::write_dataset(&group, &self.time, "time")?;
+        observers::write_dataset(&group, &self.amplitude_difference, "amplitude_difference")?;
+        observers::write_dataset(
+            &group,
+            &self.nature_angle_difference,
+            "nature_angle_difference",
+        )?;
+
+        let difference = self.difference();
+        observers::save_attr(
+            &group,
+            &ndarray::arr0(difference.mean_amplitude),
+            "mean_amplitude_difference",
+        )?;
+        observers::save_attr(
+            &group,
+            &ndarray::arr0(difference.spinning_probability),
+            "spinning_probability_difference",
+        )?;
+
+        observers::save_parameters_as_attribute_json(&group, &settings.parameters)
+    }
+}
+
+impl std::fmt::Display for PairedComparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let difference = self.difference();
+        writeln!(
+            f,
+            "mean amplitude: {} -> {} (difference {})",
+            self.first.mean_amplitude, self.second.mean_amplitude, difference.mean_amplitude
+        )?;
+        writeln!(
+            f,
+            "spinning probability: {} -> {} (difference {})",
+            self.first.spinning_probability,
+            self.second.spinning_probability,
+            difference.spinning_probability
+        )?;
+        write!(
+            f,
+            "rms of the paired amplitude difference: {}",
+            self.rms_amplitude_difference()
+        )
+    }
+}
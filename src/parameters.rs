@@ -1,5 +1,6 @@
-use crate::azimuthal_mode::Mode;
-use crate::Float;
+use crate::azimuthal_mode::{InitialMode, Mode, ModeDistribution};
+use crate::settings::RNG;
+use crate::{FlameProfile, Float, PhysicalUnits};
 use serde::{Deserialize, Serialize};
 
 /// Possible errors for [`Parameters`].
@@ -9,6 +10,13 @@ pub enum ParametersError {
     Saving,
     NegativeNumber,
     Mode,
+    Aliasing,
+    Burners,
+    BurnerOffset,
+    Quadrature,
+    FlameProfile,
+    DisabledBurner,
+    PhysicalUnits,
 }
 
 impl std::error::Error for ParametersError {}
@@ -20,26 +28,96 @@ impl std::fmt::Display for ParametersError {
             Self::Saving => "saves_per_cycle >= 2 required to avoid undersampling".to_owned(),
             Self::NegativeNumber => "negative value where positive value was expected".to_owned(),
             Self::Mode => "invalid initial mode".to_owned(),
+            Self::Aliasing => "2 * mode_order <= number_of_burners / 2 required to avoid aliasing (see allow_aliasing)".to_owned(),
+            Self::Burners => "at least one burner is required".to_owned(),
+            Self::BurnerOffset => "the burner offset has to be a finite angle".to_owned(),
+            Self::FlameProfile => "invalid flame profile".to_owned(),
+            Self::DisabledBurner => "the disabled burners have to be indices of the (discrete) burners".to_owned(),
+            Self::PhysicalUnits => "the frequency and reference pressure have to be positive".to_owned(),
+            Self::Quadrature => "at least 8 quadrature points are required to resolve the continuous flames".to_owned(),
         };
 
         write!(f, "error setting the parameters: {}", msg)
     }
 }
 
+/// Distribution of the flames around the annulus.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum FlameDistribution {
+    /// `number_of_burners` equidistant burners, summed over.
+    #[default]
+    Discrete,
+    /// A continuous distribution of the flames, integrated around the annulus.
+    ///
+    /// The limit of infinitely many burners, which isolates the effects of
+    /// the discrete burners. The integrals are evaluated analytically for
+    /// the cubic saturation, and by the trapezoidal rule with
+    /// `quadrature_points` points otherwise, which converges exponentially
+    /// for smooth describing functions.
+    Continuous { quadrature_points: usize },
+}
+
 /// Parameters describing the system to be simulated.
 ///
 /// Parameters describing the system in terms of `damping`, `gain`,
 /// and `noise`. Additionally, the AFDF parameter `r` describes the
 /// degree of asymmetry in the system, with `r = 1.0` being the
 /// conventional FDF case (symmetric).
+///
+/// The heat release rate depends on the Fourier component of order
+/// `2 * mode_order` of the flame response over the burners, which is
+/// only resolved when `2 * mode_order <= number_of_burners / 2`. Setting
+/// `allow_aliasing` skips this check, and the aliased component is used.
+///
+/// The burners are equidistantly spaced, with the first burner at the
+/// azimuthal angle `burner_offset` (zero by default). Alternatively, the
+/// flames can be distributed continuously around the annulus (see
+/// [`FlameDistribution`]), in which case the number of burners is unused.
+/// The strength of the flames can vary around the annulus, as given by the
+/// optional [`FlameProfile`] weighting the gain, and the burners in
+/// `burners_disabled` (by index, starting from zero) are switched off.
+///
+/// Besides the additive `noise`, the optional `parametric_noise` is the
+/// relative intensity of white noise fluctuations of the gain of all the
+/// flames together (e.g. equivalence ratio fluctuations), which force the
+/// mode multiplicatively, in proportion to its amplitude.
+///
+/// The simulation is nondimensional (with the time in acoustic cycles), and
+/// the optional [`PhysicalUnits`] give the frequency and pressure scale for
+/// reporting the results in seconds and pascals.
+///
+/// The derived quantities (step counts and burner locations) are always
+/// calculated when deserializing, and invalid values give an error.
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "ParametersData")]
 pub struct Parameters {
+    #[deprecated(note = "use `get_damping` and `set_damping`")]
     pub damping: Float,
+    #[deprecated(note = "use `get_gain` and `set_gain`")]
     pub gain: Float,
+    #[deprecated(note = "use `get_noise` and `set_noise`")]
     pub noise: Float,
+    #[deprecated(note = "use `get_mode_order` and `set_mode_order`")]
     pub mode_order: u32,
+    #[deprecated(note = "use `get_number_of_burners` and `set_number_of_burners`")]
     pub number_of_burners: u32,
-    pub initial_mode: Mode,
+    #[deprecated(note = "use `get_allow_aliasing` and `set_allow_aliasing`")]
+    #[serde(default)]
+    pub allow_aliasing: bool,
+    #[serde(default)]
+    burner_offset: Float,
+    #[serde(default)]
+    flame_distribution: FlameDistribution,
+    #[serde(default)]
+    flame_profile: Option<FlameProfile>,
+    #[serde(default)]
+    burners_disabled: Vec<u32>,
+    #[serde(default)]
+    parametric_noise: Float,
+    #[serde(default)]
+    physical_units: Option<PhysicalUnits>,
+    #[deprecated(note = "use `get_initial_mode` and `set_initial_mode`")]
+    pub initial_mode: InitialMode,
     timestep: Float,
     number_of_cycles: Float,
     saves_per_cycle: usize,
@@ -58,10 +136,14 @@ pub struct Parameters {
 
     #[serde(skip)]
     thetas: Vec<Float>,
+
+    #[serde(skip)]
+    flame_weights: Vec<Float>,
 }
 
 impl Parameters {
     /// Create a new instance of [`Parameters`]
+    #[allow(deprecated)]
     pub fn new(
         damping: Float,
         gain: Float,
@@ -87,7 +169,14 @@ impl Parameters {
             noise,
             mode_order,
             number_of_burners,
-            initial_mode,
+            allow_aliasing: false,
+            burner_offset: 0.0,
+            flame_distribution: FlameDistribution::Discrete,
+            flame_profile: None,
+            burners_disabled: Vec::new(),
+            parametric_noise: 0.0,
+            physical_units: None,
+            initial_mode: InitialMode::Fixed(initial_mode),
             timestep,
             number_of_cycles,
             saves_per_cycle,
@@ -96,6 +185,7 @@ impl Parameters {
             steps_per_cycle: 0,
             num_steps_to_save: 0,
             thetas: Vec::new(),
+            flame_weights: Vec::new(),
         };
 
         // Initialize the rest of the variables
@@ -105,18 +195,76 @@ impl Parameters {
         Ok(parameters)
     }
 
-    /// Initialize the private fields in [`Parameters`].
+    /// Validate and initialize the private fields in [`Parameters`].
     ///
-    /// This is always ran as part of [`Parameters::new`], but it is also
-    /// used by [`crate::Settings`] when loading from file. It should
-    /// never be required to be used manually by the user.
-    pub fn init(&mut self) -> Result<(), ParametersError> {
+    /// This is always ran as part of [`Parameters::new`], when deserializing,
+    /// and by the setters changing the geometry.
+    fn init(&mut self) -> Result<(), ParametersError> {
+        if self.saves_per_cycle < 2 {
+            return Err(ParametersError::Saving);
+        }
+        if self.get_number_of_burners() == 0 {
+            return Err(ParametersError::Burners);
+        }
+        non_negative(self.parametric_noise)?;
+        if !self.burner_offset.is_finite() {
+            return Err(ParametersError::BurnerOffset);
+        }
+        match self.flame_distribution {
+            FlameDistribution::Discrete => {
+                let (mode_order, number_of_burners) =
+                    (self.get_mode_order(), self.get_number_of_burners());
+                if !self.get_allow_aliasing() && 2 * mode_order > number_of_burners / 2 {
+                    return Err(ParametersError::Aliasing);
+                }
+            }
+            FlameDistribution::Continuous { quadrature_points } => {
+                // The gain is of order 2 in the integration variable
+                if quadrature_points < 8 {
+                    return Err(ParametersError::Quadrature);
+                }
+            }
+        }
+
+        if let Some(profile) = &self.flame_profile {
+            if !profile.is_valid() {
+                return Err(ParametersError::FlameProfile);
+            }
+        }
+        let discrete = self.flame_distribution == FlameDistribution::Discrete;
+        if self
+            .burners_disabled
+            .iter()
+            .any(|&ind| !discrete || ind >= self.get_number_of_burners())
+        {
+            return Err(ParametersError::DisabledBurner);
+        }
+
+        if self.physical_units.is_some_and(|units| !units.is_valid()) {
+            return Err(ParametersError::PhysicalUnits);
+        }
+
+        if let InitialMode::Random { distribution, .. } = self.get_initial_mode() {
+            if !distribution.is_valid() {
+                return Err(ParametersError::Mode);
+            }
+        }
+
         // Set up the theta locations (assumed equidistantly spaced)
-        let dtheta = 2.0 * crate::PI / (self.number_of_burners as Float);
+        let number_of_burners = self.get_number_of_burners();
+        let dtheta = 2.0 * crate::PI / (number_of_burners as Float);
 
-        self.thetas = (0..self.number_of_burners)
-            .map(|ind| dtheta * (ind as Float))
+        self.thetas = (0..number_of_burners)
+            .map(|ind| self.burner_offset + dtheta * (ind as Float))
+            .collect();
+        self.flame_weights = self
+            .thetas
+            .iter()
+            .map(|&theta| self.get_flame_weight(theta))
             .collect();
+        for &ind in &self.burners_disabled {
+            self.flame_weights[ind as usize] = 0.0;
+        }
 
         self.set_timestep(self.timestep)
     }
@@ -176,9 +324,152 @@ impl Parameters {
         self.saves_per_cycle
     }
 
-    /// Set the initial [`Mode`] of the simulation.
-    pub fn set_initial_mode(&mut self, mode: Mode) {
-        self.initial_mode = mode;
+    /// Set the relative intensity of the fluctuations of the gain (non-negative).
+    pub fn set_parametric_noise(&mut self, parametric_noise: Float) -> Result<(), ParametersError> {
+        self.parametric_noise = non_negative(parametric_noise)?;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn get_parametric_noise(&self) -> Float {
+        self.parametric_noise
+    }
+
+    /// Set the physical scales of the simulation (`None` for nondimensional results only).
+    pub fn set_physical_units(
+        &mut self,
+        physical_units: Option<PhysicalUnits>,
+    ) -> Result<(), ParametersError> {
+        if physical_units.is_some_and(|units| !units.is_valid()) {
+            return Err(ParametersError::PhysicalUnits);
+        }
+
+        self.physical_units = physical_units;
+        Ok(())
+    }
+
+    #[inline]
+    pub fn get_physical_units(&self) -> Option<PhysicalUnits> {
+        self.physical_units
+    }
+
+    /// Time step in seconds, if the physical units are given.
+    pub fn get_timestep_seconds(&self) -> Option<Float> {
+        self.physical_units
+            .map(|units| units.seconds(self.timestep))
+    }
+
+    /// Simulated time in seconds, if the physical units are given.
+    pub fn get_duration_seconds(&self) -> Option<Float> {
+        self.physical_units
+            .map(|units| units.seconds(self.number_of_cycles))
+    }
+
+    /// Set the azimuthal angle of the first burner (and update the burner locations).
+    ///
+    /// The parameters are left unchanged if the new value is invalid.
+    pub fn set_burner_offset(&mut self, burner_offset: Float) -> Result<(), ParametersError> {
+        let previous = self.burner_offset;
+        self.burner_offset = burner_offset;
+        self.init().inspect_err(|_| self.burner_offset = previous)
+    }
+
+    #[inline]
+    pub fn get_burner_offset(&self) -> Float {
+        self.burner_offset
+    }
+
+    /// Set whether the flames are discrete burners or distributed continuously.
+    ///
+    /// The parameters are left unchanged if the new value is invalid.
+    pub fn set_flame_distribution(
+        &mut self,
+        flame_distribution: FlameDistribution,
+    ) -> Result<(), ParametersError> {
+        let previous = self.flame_distribution;
+        self.flame_distribution = flame_distribution;
+        self.init()
+            .inspect_err(|_| self.flame_distribution = previous)
+    }
+
+    #[inline]
+    pub fn get_flame_distribution(&self) -> FlameDistribution {
+        self.flame_distribution
+    }
+
+    /// Set the azimuthal profile weighting the strength of the flames (`None` for uniform flames).
+    ///
+    /// The parameters are left unchanged if the new value is invalid.
+    pub fn set_flame_profile(
+        &mut self,
+        flame_profile: Option<FlameProfile>,
+    ) -> Result<(), ParametersError> {
+        let previous = std::mem::replace(&mut self.flame_profile, flame_profile);
+        self.init().inspect_err(|_| self.flame_profile = previous)
+    }
+
+    #[inline]
+    pub fn get_flame_profile(&self) -> Option<&FlameProfile> {
+        self.flame_profile.as_ref()
+    }
+
+    /// Weight of the flame strength at the azimuthal angle `theta` (unity without a profile).
+    #[inline]
+    pub fn get_flame_weight(&self, theta: Float) -> Float {
+        self.flame_profile
+            .as_ref()
+            .map_or(1.0, |profile| profile.weight(theta))
+    }
+
+    /// Switch off the burners with the given indices (starting from zero), zeroing their gain.
+    ///
+    /// Only available for the discrete burners. The parameters are left
+    /// unchanged if the new value is invalid.
+    pub fn set_burners_disabled(&mut self, burners: Vec<u32>) -> Result<(), ParametersError> {
+        let previous = std::mem::replace(&mut self.burners_disabled, burners);
+        self.init()
+            .inspect_err(|_| self.burners_disabled = previous)
+    }
+
+    #[inline]
+    pub fn get_burners_disabled(&self) -> &[u32] {
+        &self.burners_disabled
+    }
+
+    /// Description of how the flames break the rotational symmetry of the annulus, if they do.
+    ///
+    /// The symmetry is broken by disabled burners and by a [`FlameProfile`].
+    pub fn broken_symmetry(&self) -> Option<String> {
+        let mut causes = Vec::new();
+        if !self.burners_disabled.is_empty() {
+            let burners: Vec<String> = self
+                .burners_disabled
+                .iter()
+                .map(|ind| ind.to_string())
+                .collect();
+            causes.push(format!(
+                "burner(s) {} of {} disabled",
+                burners.join(", "),
+                self.get_number_of_burners()
+            ));
+        }
+        if self.flame_profile.is_some() {
+            causes.push("azimuthal flame profile".to_owned());
+        }
+
+        if causes.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "{}, breaking the rotational symmetry of the annulus",
+            causes.join(" and ")
+        ))
+    }
+
+    /// Get the weight of the flame strength at each burner (zero for the disabled burners).
+    #[inline]
+    pub fn get_flame_weights(&self) -> &[Float] {
+        &self.flame_weights
     }
 
     /// Get the azimuthal locations of the burners.
@@ -208,6 +499,207 @@ impl Parameters {
     }
 }
 
+/// Accessors of the deprecated public fields, the only code (besides the
+/// constructors) using the fields directly.
+#[allow(deprecated)]
+impl Parameters {
+    /// Set the damping coefficient (non-negative).
+    pub fn set_damping(&mut self, damping: Float) -> Result<(), ParametersError> {
+        self.damping = non_negative(damping)?;
+        Ok(())
+    }
+
+    /// Set the damping coefficient without validation.
+    pub fn set_damping_unchecked(&mut self, damping: Float) {
+        self.damping = damping;
+    }
+
+    #[inline]
+    pub fn get_damping(&self) -> Float {
+        self.damping
+    }
+
+    /// Set the linear gain of the flame response (non-negative).
+    pub fn set_gain(&mut self, gain: Float) -> Result<(), ParametersError> {
+        self.gain = non_negative(gain)?;
+        Ok(())
+    }
+
+    /// Set the linear gain without validation.
+    pub fn set_gain_unchecked(&mut self, gain: Float) {
+        self.gain = gain;
+    }
+
+    #[inline]
+    pub fn get_gain(&self) -> Float {
+        self.gain
+    }
+
+    /// Set the intensity of the background noise (non-negative).
+    pub fn set_noise(&mut self, noise: Float) -> Result<(), ParametersError> {
+        self.noise = non_negative(noise)?;
+        Ok(())
+    }
+
+    /// Set the noise intensity without validation.
+    pub fn set_noise_unchecked(&mut self, noise: Float) {
+        self.noise = noise;
+    }
+
+    #[inline]
+    pub fn get_noise(&self) -> Float {
+        self.noise
+    }
+
+    /// Set the azimuthal order of the mode (and check for aliasing).
+    ///
+    /// The parameters are left unchanged if the new value is invalid.
+    pub fn set_mode_order(&mut self, mode_order: u32) -> Result<(), ParametersError> {
+        let previous = self.mode_order;
+        self.mode_order = mode_order;
+        self.init().inspect_err(|_| self.mode_order = previous)
+    }
+
+    #[inline]
+    pub fn get_mode_order(&self) -> u32 {
+        self.mode_order
+    }
+
+    /// Set the number of burners (and update the burner locations).
+    ///
+    /// The parameters are left unchanged if the new value is invalid.
+    pub fn set_number_of_burners(&mut self, number_of_burners: u32) -> Result<(), ParametersError> {
+        let previous = self.number_of_burners;
+        self.number_of_burners = number_of_burners;
+        self.init()
+            .inspect_err(|_| self.number_of_burners = previous)
+    }
+
+    #[inline]
+    pub fn get_number_of_burners(&self) -> u32 {
+        self.number_of_burners
+    }
+
+    /// Allow (or forbid) aliasing of the Fourier component of the flame response.
+    ///
+    /// The parameters are left unchanged if the new value is invalid.
+    pub fn set_allow_aliasing(&mut self, allow_aliasing: bool) -> Result<(), ParametersError> {
+        let previous = self.allow_aliasing;
+        self.allow_aliasing = allow_aliasing;
+        self.init().inspect_err(|_| self.allow_aliasing = previous)
+    }
+
+    #[inline]
+    pub fn get_allow_aliasing(&self) -> bool {
+        self.allow_aliasing
+    }
+
+    /// Set the initial [`Mode`] of the simulation.
+    pub fn set_initial_mode(&mut self, mode: Mode) {
+        self.initial_mode = InitialMode::Fixed(mode);
+    }
+
+    /// Draw the initial [`Mode`] of the simulation from a distribution.
+    pub fn set_initial_mode_distribution(
+        &mut self,
+        distribution: ModeDistribution,
+    ) -> Result<(), ParametersError> {
+        if !distribution.is_valid() {
+            return Err(ParametersError::Mode);
+        }
+
+        self.initial_mode = InitialMode::Random {
+            distribution,
+            draw: None,
+        };
+
+        Ok(())
+    }
+
+    /// Get the initial mode of the simulation, fixed or drawn from a distribution.
+    #[inline]
+    pub fn get_initial_mode(&self) -> &InitialMode {
+        &self.initial_mode
+    }
+
+    /// Get the initial [`Mode`] of a run, drawing a new one if it is random.
+    pub(crate) fn resolve_initial_mode(&mut self, rng: &mut RNG) -> Mode {
+        self.initial_mode.resolve(rng)
+    }
+}
+
+/// Serialized fields of [`Parameters`], which are finalized when deserializing.
+#[derive(Deserialize)]
+struct ParametersData {
+    damping: Float,
+    gain: Float,
+    noise: Float,
+    mode_order: u32,
+    number_of_burners: u32,
+    #[serde(default)]
+    allow_aliasing: bool,
+    #[serde(default)]
+    burner_offset: Float,
+    #[serde(default)]
+    flame_distribution: FlameDistribution,
+    #[serde(default)]
+    flame_profile: Option<FlameProfile>,
+    #[serde(default)]
+    burners_disabled: Vec<u32>,
+    #[serde(default)]
+    parametric_noise: Float,
+    #[serde(default)]
+    physical_units: Option<PhysicalUnits>,
+    initial_mode: InitialMode,
+    timestep: Float,
+    number_of_cycles: Float,
+    saves_per_cycle: usize,
+}
+
+impl TryFrom<ParametersData> for Parameters {
+    type Error = ParametersError;
+
+    #[allow(deprecated)]
+    fn try_from(data: ParametersData) -> Result<Self, Self::Error> {
+        let mut parameters = Self {
+            damping: non_negative(data.damping)?,
+            gain: non_negative(data.gain)?,
+            noise: non_negative(data.noise)?,
+            mode_order: data.mode_order,
+            number_of_burners: data.number_of_burners,
+            allow_aliasing: data.allow_aliasing,
+            burner_offset: data.burner_offset,
+            flame_distribution: data.flame_distribution,
+            flame_profile: data.flame_profile,
+            burners_disabled: data.burners_disabled,
+            parametric_noise: data.parametric_noise,
+            physical_units: data.physical_units,
+            initial_mode: data.initial_mode,
+            timestep: data.timestep,
+            number_of_cycles: data.number_of_cycles,
+            saves_per_cycle: data.saves_per_cycle,
+            skip_per_save: 0,
+            total_steps: 0,
+            steps_per_cycle: 0,
+            num_steps_to_save: 0,
+            thetas: Vec::new(),
+            flame_weights: Vec::new(),
+        };
+        parameters.init()?;
+
+        Ok(parameters)
+    }
+}
+
+/// Check that `value` is a non-negative number.
+fn non_negative(value: Float) -> Result<Float, ParametersError> {
+    if value.is_nan() || value < 0.0 {
+        return Err(ParametersError::NegativeNumber);
+    }
+
+    Ok(value)
+}
+
 impl Default for Parameters {
     fn default() -> Self {
         let gain = 0.16 / crate::PI;
@@ -242,3 +734,58 @@ impl std::fmt::Display for Parameters {
         write!(f, "{}", json_string)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_initializes_derived_fields() {
+        let parameters = Parameters::default();
+        let json = serde_json::to_string(&parameters).unwrap();
+
+        let loaded: Parameters = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.get_total_steps(), parameters.get_total_steps());
+        assert_eq!(loaded.get_thetas().len(), 12);
+
+        let invalid = json.replace("\"number_of_burners\":12", "\"number_of_burners\":2");
+        assert!(serde_json::from_str::<Parameters>(&invalid).is_err());
+        let invalid = json.replace("\"parametric_noise\":0.0", "\"parametric_noise\":-0.1");
+        assert!(serde_json::from_str::<Parameters>(&invalid).is_err());
+        let invalid = json.replace("\"gain\":", "\"gain\":-");
+        assert!(serde_json::from_str::<Parameters>(&invalid).is_err());
+        let invalid = json.replace("\"noise\":0.06", "\"noise\":-0.06");
+        assert!(serde_json::from_str::<Parameters>(&invalid).is_err());
+        let invalid = json.replace("\"damping\":", "\"damping\":-");
+        assert!(serde_json::from_str::<Parameters>(&invalid).is_err());
+        let invalid = json.replace(
+            "\"physical_units\":null",
+            "\"physical_units\":{\"frequency\":-1.0}",
+        );
+        assert!(serde_json::from_str::<Parameters>(&invalid).is_err());
+        let scaled = json.replace(
+            "\"physical_units\":null",
+            "\"physical_units\":{\"frequency\":200.0}",
+        );
+        let scaled: Parameters = serde_json::from_str(&scaled).unwrap();
+        assert_eq!(scaled.get_duration_seconds(), Some(260.0));
+    }
+
+    #[test]
+    fn disabled_burners_have_no_gain() {
+        let mut parameters = Parameters::default();
+        assert!(parameters.broken_symmetry().is_none());
+
+        parameters.set_burners_disabled(vec![3, 7]).unwrap();
+        let weights = parameters.get_flame_weights();
+        assert_eq!((weights[3], weights[7], weights[4]), (0.0, 0.0, 1.0));
+        assert!(parameters.broken_symmetry().unwrap().contains("3, 7 of 12"));
+
+        assert!(parameters.set_burners_disabled(vec![12]).is_err());
+        assert_eq!(parameters.get_burners_disabled(), &[3, 7]);
+        let continuous = FlameDistribution::Continuous {
+            quadrature_points: 64,
+        };
+        assert!(parameters.set_flame_distribution(continuous).is_err());
+    }
+}
@@ -0,0 +1,90 @@
+//! Dimensional metadata for reporting the results in physical units.
+//!
+//! The simulations are nondimensional: the time is in acoustic cycles of
+//! the mode, and the amplitude is scaled by the saturation of the
+//! describing function. Given the oscillation frequency of the mode (in Hz)
+//! and optionally the pressure amplitude corresponding to a unit amplitude
+//! (in Pa), the times, rates, and amplitudes convert as
+//! `t [s] = t [cycles] / frequency`, `nu [1/s] = nu [1/cycle] * frequency`,
+//! and `p [Pa] = A * reference_pressure`.
+use serde::{Deserialize, Serialize};
+
+use crate::Float;
+
+/// Physical scales of the nondimensional simulation.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct PhysicalUnits {
+    /// Oscillation frequency of the mode, in Hz.
+    pub frequency: Float,
+    /// Pressure amplitude of a unit amplitude `A`, in Pa.
+    #[serde(default)]
+    pub reference_pressure: Option<Float>,
+}
+
+impl PhysicalUnits {
+    /// Whether the frequency (and the reference pressure, if given) are finite and positive.
+    pub fn is_valid(&self) -> bool {
+        let positive = |value: Float| value.is_finite() && value > 0.0;
+        positive(self.frequency) && self.reference_pressure.is_none_or(positive)
+    }
+
+    /// Time in seconds of the time `cycles` in acoustic cycles.
+    #[inline]
+    pub fn seconds(&self, cycles: Float) -> Float {
+        cycles / self.frequency
+    }
+
+    /// Time in acoustic cycles of the time `seconds` in seconds.
+    #[inline]
+    pub fn cycles(&self, seconds: Float) -> Float {
+        seconds * self.frequency
+    }
+
+    /// Rate in 1/s (e.g. a growth rate or Lyapunov exponent) of the rate `per_cycle` in 1/cycle.
+    #[inline]
+    pub fn per_second(&self, per_cycle: Float) -> Float {
+        per_cycle * self.frequency
+    }
+
+    /// Pressure amplitude in Pa of the amplitude `amplitude`, if the reference pressure is given.
+    #[inline]
+    pub fn pressure(&self, amplitude: Float) -> Option<Float> {
+        self.reference_pressure.map(|p| amplitude * p)
+    }
+
+    /// Time axis in seconds of the times `cycles` in acoustic cycles.
+    pub fn time_axis(&self, cycles: &[Float]) -> Vec<Float> {
+        cycles.iter().map(|&t| self.seconds(t)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions_are_consistent() {
+        let units = PhysicalUnits {
+            frequency: 200.0,
+            reference_pressure: Some(500.0),
+        };
+        assert!(units.is_valid());
+        assert_eq!(units.seconds(100.0), 0.5);
+        assert_eq!(units.cycles(units.seconds(3.0)), 3.0);
+        // A growth rate of 0.01 per cycle is 2 per second at 200 Hz
+        assert_eq!(units.per_second(0.01), 2.0);
+        assert_eq!(units.pressure(0.2), Some(100.0));
+        assert_eq!(units.time_axis(&[0.0, 2.0]), vec![0.0, 0.01]);
+
+        let unscaled = PhysicalUnits {
+            reference_pressure: None,
+            ..units
+        };
+        assert!(unscaled.is_valid() && unscaled.pressure(1.0).is_none());
+        assert!(!PhysicalUnits {
+            frequency: 0.0,
+            ..units
+        }
+        .is_valid());
+    }
+}
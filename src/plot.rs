@@ -0,0 +1,274 @@
+//! Quick-look plots of the results, for sanity checks without Python.
+//!
+//! A group saved by the `TimeSeries` observer is plotted as the traces of
+//! the amplitude and nature angle against time, and a group saved by the
+//! `Histogram` observer as the probability densities of the amplitude and
+//! nature angle. The plots are rendered without a display (e.g. on the
+//! nodes of a cluster) to PNG, or to SVG when the output path ends in
+//! `.svg`. Requires the `plot` feature.
+use std::error::Error;
+use std::path::Path;
+
+use plotters::coord::Shift;
+use plotters::prelude::*;
+
+use crate::{Float, PI};
+
+/// Largest number of points of each plotted trace, above which the time series is decimated.
+const MAX_POINTS: usize = 5000;
+
+/// Size of the plots in pixels.
+const SIZE: (u32, u32) = (1200, 800);
+
+/// Possible errors for [`plot`].
+#[derive(Clone, Debug)]
+pub enum PlotError {
+    Group(String),
+    Empty(String),
+}
+
+impl std::error::Error for PlotError {}
+
+impl std::fmt::Display for PlotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Group(group) => format!(
+                "the group '{}' is neither a time series nor a histogram",
+                group
+            ),
+            Self::Empty(group) => format!("the group '{}' has no logged values", group),
+        };
+
+        write!(f, "error in the plot: {}", msg)
+    }
+}
+
+/// Logged quantities of a results group.
+enum PlotData {
+    Traces {
+        time: Vec<Float>,
+        amplitude: Vec<Float>,
+        chi: Vec<Float>,
+    },
+    /// Bin edges and counts of the amplitude and nature angle.
+    Histograms {
+        amplitude: (Vec<Float>, Vec<Float>),
+        chi: (Vec<Float>, Vec<Float>),
+    },
+}
+
+/// Plot the group `group` of the HDF5 file `path` to the image `output` (PNG, or SVG for `.svg`).
+pub fn plot(path: &Path, group: &str, output: &Path) -> Result<(), Box<dyn Error>> {
+    let file = hdf5::File::open(path)?;
+    let results = file.group(group)?;
+
+    let data = if results.link_exists("bin_edges") {
+        let edges = results.group("bin_edges")?;
+        let histogram = |name: &str| -> hdf5::Result<(Vec<Float>, Vec<Float>)> {
+            Ok((
+                edges.dataset(name)?.read_raw()?,
+                results.dataset(name)?.read_raw()?,
+            ))
+        };
+        PlotData::Histograms {
+            amplitude: histogram("amplitude")?,
+            chi: histogram("chi")?,
+        }
+    } else if results.link_exists("time") {
+        let stride = results.dataset("time")?.size().div_ceil(MAX_POINTS).max(1);
+        let read = |name: &str| -> hdf5::Result<Vec<Float>> {
+            let values: Vec<Float> = results.dataset(name)?.read_raw()?;
+            Ok(values.into_iter().step_by(stride).collect())
+        };
+        PlotData::Traces {
+            time: read("time")?,
+            amplitude: read("amplitude")?,
+            chi: read("chi")?,
+        }
+    } else {
+        return Err(Box::new(PlotError::Group(group.to_owned())));
+    };
+
+    let title = format!("{}: {}", path.to_string_lossy(), group);
+    if output.extension().is_some_and(|ext| ext == "svg") {
+        let root = SVGBackend::new(output, SIZE).into_drawing_area();
+        draw(&root, &data, &title, group)?;
+        root.present()?;
+    } else {
+        let root = BitMapBackend::new(output, SIZE).into_drawing_area();
+        draw(&root, &data, &title, group)?;
+        root.present()?;
+    }
+
+    Ok(())
+}
+
+/// Draw the panels of `data` on `root`.
+fn draw<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    data: &PlotData,
+    title: &str,
+    group: &str,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    root.fill(&WHITE)?;
+    let root = root.titled(title, ("sans-serif", 24))?;
+
+    match data {
+        PlotData::Traces {
+            time,
+            amplitude,
+            chi,
+        } => {
+            if time.is_empty() {
+                return Err(Box::new(PlotError::Empty(group.to_owned())));
+            }
+            let panels = root.split_evenly((2, 1));
+            let time_range = time[0]..time[time.len() - 1].max(time[0] + Float::EPSILON);
+            let upper = amplitude.iter().cloned().fold(0.0, Float::max).max(1e-12);
+            draw_trace(
+                &panels[0],
+                time,
+                amplitude,
+                time_range.clone(),
+                0.0..1.05 * upper,
+                "A",
+            )?;
+            draw_trace(
+                &panels[1],
+                time,
+                chi,
+                time_range,
+                -PI / 4.0..PI / 4.0,
+                "chi",
+            )?;
+        }
+        PlotData::Histograms { amplitude, chi } => {
+            let panels = root.split_evenly((1, 2));
+            draw_pdf(&panels[0], amplitude, "A")?;
+            draw_pdf(&panels[1], chi, "chi")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Draw `values` against `time` with the axis label `label`.
+fn draw_trace<DB: DrawingBackend>(
+    area: &DrawingArea<DB, Shift>,
+    time: &[Float],
+    values: &[Float],
+    x_range: std::ops::Range<Float>,
+    y_range: std::ops::Range<Float>,
+    label: &str,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let mut chart = ChartBuilder::on(area)
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(x_range, y_range)?;
+    chart
+        .configure_mesh()
+        .x_desc("time (cycles)")
+        .y_desc(label)
+        .draw()?;
+    chart.draw_series(LineSeries::new(
+        time.iter().cloned().zip(values.iter().cloned()),
+        &BLUE,
+    ))?;
+
+    Ok(())
+}
+
+/// Draw the probability density of the histogram `(edges, counts)` with the axis label `label`.
+fn draw_pdf<DB: DrawingBackend>(
+    area: &DrawingArea<DB, Shift>,
+    (edges, counts): &(Vec<Float>, Vec<Float>),
+    label: &str,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let pdf = probability_density(edges, counts);
+    let upper = pdf.iter().cloned().fold(0.0, Float::max).max(1e-12);
+    let (lower_edge, upper_edge) = match (edges.first(), edges.last()) {
+        (Some(&lower), Some(&upper)) if upper > lower => (lower, upper),
+        _ => (0.0, 1.0),
+    };
+
+    let mut chart = ChartBuilder::on(area)
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(lower_edge..upper_edge, 0.0..1.05 * upper)?;
+    chart
+        .configure_mesh()
+        .x_desc(label)
+        .y_desc("probability density")
+        .draw()?;
+    let style = BLUE.mix(0.6).filled();
+    chart.draw_series(
+        edges
+            .windows(2)
+            .zip(&pdf)
+            .map(|(bin, &p)| Rectangle::new([(bin[0], 0.0), (bin[1], p)], style)),
+    )?;
+
+    Ok(())
+}
+
+/// Probability density of each bin of a histogram, from the `counts` and bin `edges`.
+fn probability_density(edges: &[Float], counts: &[Float]) -> Vec<Float> {
+    let total: Float = counts.iter().sum();
+    edges
+        .windows(2)
+        .zip(counts)
+        .map(|(bin, &count)| match total * (bin[1] - bin[0]) {
+            norm if norm > 0.0 => count / norm,
+            _ => 0.0,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn densities_integrate_to_one() {
+        let edges = [0.0, 0.5, 1.0, 2.0];
+        let counts = [1.0, 3.0, 4.0];
+        let pdf = probability_density(&edges, &counts);
+        let integral: Float = pdf
+            .iter()
+            .zip(edges.windows(2))
+            .map(|(p, bin)| p * (bin[1] - bin[0]))
+            .sum();
+        assert!((integral - 1.0).abs() < 1e-12);
+        assert_eq!(pdf[2], 0.5);
+
+        let traces = PlotData::Traces {
+            time: vec![0.0, 1.0, 2.0],
+            amplitude: vec![0.1, 0.3, 0.2],
+            chi: vec![0.0, 0.5, -0.5],
+        };
+        let histograms = PlotData::Histograms {
+            amplitude: (edges.to_vec(), counts.to_vec()),
+            chi: (vec![-PI / 4.0, 0.0, PI / 4.0], vec![2.0, 1.0]),
+        };
+        for data in [traces, histograms] {
+            let mut svg = String::new();
+            {
+                let root = SVGBackend::with_string(&mut svg, SIZE).into_drawing_area();
+                draw(&root, &data, "results.hdf5: data", "data").unwrap();
+                root.present().unwrap();
+            }
+            assert!(svg.contains("probability density") || svg.contains("time (cycles)"));
+        }
+    }
+}
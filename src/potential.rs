@@ -0,0 +1,235 @@
+//! Effective potentials of the amplitude and nature angle.
+//!
+//! For a variable `y` following `dy = mu(y) dt + s(y) dW`, the stationary
+//! probability density is `p(y) ~ exp(int 2 mu / s^2 dy) / s^2`. The effective
+//! potential `U = -ln p` is computed from the drift and diffusion of the
+//! governing equations of `ln A` (at a fixed nature angle) and `tan 2chi` (at
+//! a fixed amplitude), transformed to the amplitude `A` and nature angle `chi`.
+//! The minima of the potentials are the most probable states, and the depth
+//! of the wells their stability to the noise, such that the stochastic
+//! simulations can be compared with the potential-well picture directly.
+//!
+//! Only the symmetric case is considered, where the drift does not depend on
+//! the orientation angle and temporal phase.
+use crate::azimuthal_mode::SystemMode;
+use crate::drift_field::cell_centres;
+use crate::hrr_integral::{self, HeatReleaseRate};
+#[cfg(feature = "hdf5")]
+use crate::observers::{self, SaveInfo};
+use crate::{Float, Settings, FRAC_PI_4};
+
+/// Possible errors for [`PotentialLandscape`].
+#[derive(Clone, Debug)]
+pub enum PotentialError {
+    Points,
+    AmplitudeLimit,
+    Noise,
+}
+
+impl std::error::Error for PotentialError {}
+
+impl std::fmt::Display for PotentialError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Points => "at least two grid points are required in each direction",
+            Self::AmplitudeLimit => "the amplitudes have to be positive",
+            Self::Noise => "the potentials are only defined with noise",
+        };
+
+        write!(f, "error in the potential landscape: {}", msg)
+    }
+}
+
+/// Grids and fixed values the potentials are computed for.
+///
+/// The grid points are the centres of equal intervals of `(0, amplitude_limit)`
+/// and `(-pi/4, pi/4)`. The amplitude potential is computed at the fixed
+/// `nature_angle`, and the nature angle potential at the fixed `amplitude`,
+/// which is the minimum of the amplitude potential if not given.
+#[derive(Clone, Copy, Debug)]
+pub struct PotentialGrid {
+    pub amplitude_limit: Float,
+    pub amplitude_points: usize,
+    pub chi_points: usize,
+    pub nature_angle: Float,
+    pub amplitude: Option<Float>,
+}
+
+impl Default for PotentialGrid {
+    fn default() -> Self {
+        Self {
+            amplitude_limit: 5.0,
+            amplitude_points: 500,
+            chi_points: 500,
+            nature_angle: 0.0,
+            amplitude: None,
+        }
+    }
+}
+
+/// Effective potentials of the amplitude and nature angle, with zero minima.
+#[derive(Clone, Debug)]
+pub struct PotentialLandscape {
+    pub amplitude: Vec<Float>,
+    pub amplitude_potential: Vec<Float>,
+    /// Nature angle of the amplitude potential.
+    pub fixed_nature_angle: Float,
+    pub chi: Vec<Float>,
+    pub chi_potential: Vec<Float>,
+    /// Amplitude of the nature angle potential.
+    pub fixed_amplitude: Float,
+}
+
+/// Effective potential `-ln p(z)` on the grid `z`, for `y = h(z)`.
+///
+/// Each element of `terms` is the drift `mu`, the squared diffusion `s^2`,
+/// and the derivative `dh/dz` of the transformation at the grid point.
+fn effective_potential(z: &[Float], terms: &[(Float, Float, Float)]) -> Vec<Float> {
+    // Trapezoidal integral of 2 mu / s^2 dy = 2 mu / s^2 h'(z) dz
+    let integrand: Vec<Float> = terms
+        .iter()
+        .map(|&(mu, s2, dh)| 2.0 * mu / s2 * dh)
+        .collect();
+    let mut integral = 0.0;
+    let mut potential = Vec::with_capacity(z.len());
+    for ind in 0..z.len() {
+        if ind > 0 {
+            integral += 0.5 * (integrand[ind] + integrand[ind - 1]) * (z[ind] - z[ind - 1]);
+        }
+        // The density of z is the density of y times dh/dz
+        let (_, s2, dh) = terms[ind];
+        potential.push(s2.ln() - integral - dh.ln());
+    }
+
+    let minimum = potential.iter().copied().fold(Float::INFINITY, Float::min);
+    potential.iter().map(|u| u - minimum).collect()
+}
+
+impl PotentialLandscape {
+    /// Compute the potentials of the settings on the grid.
+    pub fn compute(settings: &Settings, grid: &PotentialGrid) -> Result<Self, PotentialError> {
+        if grid.amplitude_points < 2 || grid.chi_points < 2 {
+            return Err(PotentialError::Points);
+        }
+        if grid.amplitude_limit.is_nan()
+            || grid.amplitude_limit <= 0.0
+            || grid.amplitude.is_some_and(|a| a.is_nan() || a <= 0.0)
+        {
+            return Err(PotentialError::AmplitudeLimit);
+        }
+        let noise = settings.parameters.get_noise();
+        if noise <= 0.0 {
+            return Err(PotentialError::Noise);
+        }
+
+        // Deterministic right hand side (including the noise-induced drift), and
+        // the squared noise amplitude of each component of the quaternion
+        let drift = |mode: &SystemMode| {
+            settings.describing_function.integral(mode, settings)
+                + hrr_integral::noise_drift(mode, &settings.parameters)
+        };
+        let variance = |a: Float| noise.powi(2) / (2.0 * a.powi(2));
+
+        // Amplitude, with y = ln A
+        let chi_a = grid.nature_angle.clamp(-FRAC_PI_4, FRAC_PI_4);
+        let amplitude = cell_centres(0.0, grid.amplitude_limit, grid.amplitude_points);
+        let terms: Vec<_> = amplitude
+            .iter()
+            .map(|&a| {
+                let rhs = drift(&SystemMode::new(a, 0.0, 0.0, chi_a));
+                (rhs.real, variance(a), 1.0 / a)
+            })
+            .collect();
+        let amplitude_potential = effective_potential(&amplitude, &terms);
+
+        let fixed_amplitude = grid.amplitude.unwrap_or_else(|| {
+            let minimum = amplitude_potential
+                .iter()
+                .enumerate()
+                .min_by(|a, b| a.1.total_cmp(b.1))
+                .map_or(0, |(ind, _)| ind);
+            amplitude[minimum]
+        });
+
+        // Nature angle, with y = tan 2chi changing at the rate -2 imag_k / cos^2 2chi
+        let chi = cell_centres(-FRAC_PI_4, FRAC_PI_4, grid.chi_points);
+        let terms: Vec<_> = chi
+            .iter()
+            .map(|&c| {
+                let rhs = drift(&SystemMode::new(fixed_amplitude, 0.0, 0.0, c));
+                let dh = 2.0 / (2.0 * c).cos().powi(2);
+                (-rhs.imag_k * dh, variance(fixed_amplitude) * dh.powi(2), dh)
+            })
+            .collect();
+        let chi_potential = effective_potential(&chi, &terms);
+
+        Ok(Self {
+            amplitude,
+            amplitude_potential,
+            fixed_nature_angle: chi_a,
+            chi,
+            chi_potential,
+            fixed_amplitude,
+        })
+    }
+
+    /// Save the grids and the potentials.
+    #[cfg(feature = "hdf5")]
+    pub fn save(&self, save_info: &SaveInfo, settings: &Settings) -> hdf5::Result<()> {
+        let file = hdf5::File::append(save_info.get_path())?;
+        let group = file.create_group(save_info.get_group())?;
+
+        observers::write_dataset(&group, &self.amplitude, "amplitude")?;
+        observers::write_dataset(&group, &self.amplitude_potential, "amplitude_potential")?;
+        observers::write_dataset(&group, &self.chi, "chi")?;
+        observers::write_dataset(&group, &self.chi_potential, "chi_potential")?;
+
+        observers::save_attr(
+            &group,
+            &ndarray::arr0(self.fixed_nature_angle),
+            "fixed_nature_angle",
+        )?;
+        observers::save_attr(
+            &group,
+            &ndarray::arr0(self.fixed_amplitude),
+            "fixed_amplitude",
+        )?;
+        observers::save_str_attr(
+            &group,
+            settings.describing_function.name(),
+            "describing_function",
+        )?;
+
+        observers::save_parameters_as_attribute_json(&group, &settings.parameters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linear_growth_with_cubic_saturation() {
+        // Drift nu - A^2 of ln A plus the noise-induced drift, as in the governing equations
+        let (nu, noise): (Float, Float) = (0.5, 0.2);
+        let amplitude = cell_centres(0.0, 2.0, 200);
+        let terms: Vec<_> = amplitude
+            .iter()
+            .map(|&a| {
+                let s2 = noise.powi(2) / (2.0 * a.powi(2));
+                (nu - a.powi(2) + 0.5 * s2, s2, 1.0 / a)
+            })
+            .collect();
+        let potential = effective_potential(&amplitude, &terms);
+
+        // Minimum of U = -2 ln A - (nu A^2 - A^4 / 2) / s^2 with s^2 = noise^2 / 2
+        let s2 = noise.powi(2) / 2.0;
+        let expected = ((nu + (nu.powi(2) + 4.0 * s2).sqrt()) / 2.0).sqrt();
+        let (ind, _) = potential
+            .iter()
+            .enumerate()
+            .min_by(|a, b| a.1.total_cmp(b.1))
+            .unwrap();
+        assert!((amplitude[ind] - expected).abs() < 0.02);
+    }
+}
@@ -0,0 +1,5 @@
+//! Commonly used items, imported with `use azimuthal_fdf::prelude::*;`.
+pub use crate::azimuthal_mode::Mode;
+pub use crate::hrr_integral::{DescribingFunction, HeatReleaseRate};
+pub use crate::observers::{Observer, ObserverTrait, SaveInfo};
+pub use crate::{Float, Parameters, Saturation, Settings};
@@ -0,0 +1,160 @@
+//! Named configurations reproducing published simulations.
+//!
+//! Each [`Preset`] is identified by a name (prefixed by the publication it
+//! reproduces), and carries a version which is increased whenever the
+//! configuration of an existing preset changes, such that results can be
+//! traced back to the exact settings. New published cases are added to
+//! [`PRESETS`] with a new name, leaving the existing presets unchanged.
+use std::path::PathBuf;
+
+use crate::hrr_integral::{ConventionalFDF, DescribingFunction};
+use crate::observers::SaveInfo;
+use crate::{Float, Settings};
+
+/// Possible errors for [`Preset`].
+#[derive(Clone, Debug)]
+pub enum PresetError {
+    Unknown(String),
+}
+
+impl std::error::Error for PresetError {}
+
+impl std::fmt::Display for PresetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Unknown(name) => format!(
+                "unknown preset '{}' (see --list-presets for the available presets)",
+                name
+            ),
+        };
+
+        write!(f, "error in the presets: {}", msg)
+    }
+}
+
+/// Configuration of a published simulation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Preset {
+    pub name: &'static str,
+    pub version: u32,
+    pub description: &'static str,
+    /// Ratio of the gain to the damping of the default parameters.
+    gain_factor: Float,
+}
+
+/// Name of the publication of the presets of the experiment simulations.
+pub const PAPER_2023: &str = "paper2023";
+
+/// All the available presets.
+pub const PRESETS: [Preset; 4] = [
+    Preset {
+        name: "paper2023_gain5",
+        version: 1,
+        description: "experiment simulation of [1] with gain = 5 * damping",
+        gain_factor: 5.0,
+    },
+    Preset {
+        name: "paper2023_gain3.75",
+        version: 1,
+        description: "experiment simulation of [1] with gain = 3.75 * damping",
+        gain_factor: 3.75,
+    },
+    Preset {
+        name: "paper2023_gain2.5",
+        version: 1,
+        description: "experiment simulation of [1] with gain = 2.5 * damping",
+        gain_factor: 2.5,
+    },
+    Preset {
+        name: "paper2023_gain1.25",
+        version: 1,
+        description: "experiment simulation of [1] with gain = 1.25 * damping",
+        gain_factor: 1.25,
+    },
+];
+
+impl Preset {
+    /// Find the preset called `name`.
+    pub fn find(name: &str) -> Result<&'static Self, PresetError> {
+        PRESETS
+            .iter()
+            .find(|preset| preset.name == name)
+            .ok_or_else(|| PresetError::Unknown(name.to_owned()))
+    }
+
+    /// The presets reproducing the publication `publication` (the prefix of their names).
+    pub fn of_publication(publication: &str) -> impl Iterator<Item = &'static Self> + '_ {
+        PRESETS.iter().filter(move |preset| {
+            preset
+                .name
+                .strip_prefix(publication)
+                .is_some_and(|rest| rest.starts_with('_'))
+        })
+    }
+
+    /// Settings of the preset.
+    ///
+    /// The results are saved to the group `gain_factor_<gain factor>` of
+    /// `experiment_simulation.hdf5`, as by the original `--experiment` runs.
+    pub fn settings(&self) -> Settings {
+        let mut settings = Settings::default();
+
+        // The gain is scaled from the damping of the reference case
+        let damping = settings.parameters.get_damping();
+        settings
+            .parameters
+            .set_gain(self.gain_factor * damping)
+            .unwrap();
+        settings.parameters.set_noise(0.06).unwrap();
+        let timestep = settings.parameters.get_timestep() / 2.0;
+        settings.parameters.set_timestep(timestep).unwrap();
+        settings.parameters.set_number_of_cycles(170_000.0).unwrap();
+        settings.describing_function = DescribingFunction::Conventional(ConventionalFDF::new());
+
+        let path = PathBuf::from("experiment_simulation.hdf5");
+        let group = format!("gain_factor_{}", self.gain_factor);
+        settings
+            .observer
+            .set_save_info(&SaveInfo::new(&path, &group));
+
+        settings
+    }
+}
+
+impl std::fmt::Display for Preset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (v{}): {}", self.name, self.version, self.description)
+    }
+}
+
+impl Settings {
+    /// Settings of the [`Preset`] called `name`, e.g. `"paper2023_gain5"`.
+    pub fn preset(name: &str) -> Result<Self, PresetError> {
+        Ok(Preset::find(name)?.settings())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_reproduce_the_experiments() {
+        let names: Vec<&str> = Preset::of_publication(PAPER_2023)
+            .map(|preset| preset.name)
+            .collect();
+        assert_eq!(names.len(), 4);
+
+        let settings = Settings::preset("paper2023_gain5").unwrap();
+        let default = Settings::default();
+        let damping = default.parameters.get_damping();
+        assert_eq!(settings.parameters.get_gain(), 5.0 * damping);
+        assert_eq!(
+            settings.parameters.get_timestep(),
+            default.parameters.get_timestep() / 2.0
+        );
+        assert_eq!(settings.observer.save_info().get_group(), "gain_factor_5");
+
+        assert!(Settings::preset("paper2023_gain6").is_err());
+    }
+}
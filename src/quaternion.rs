@@ -5,7 +5,7 @@ use crate::Float;
 /// Quaternion number represented using four real valued numbers.
 /// Simplifies some of the calculations when calculating the
 /// values of the governing equation.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Default)]
 pub struct Quaternion {
     pub real: Float,
     pub imag_i: Float,
@@ -23,6 +23,29 @@ impl Quaternion {
             imag_k,
         }
     }
+
+    /// Exponential of the quaternion.
+    ///
+    /// The exponential of a purely imaginary quaternion is a unit quaternion,
+    /// i.e. a rotation.
+    #[inline]
+    pub fn exp(&self) -> Self {
+        let norm = (self.imag_i.powi(2) + self.imag_j.powi(2) + self.imag_k.powi(2)).sqrt();
+        let (sin, cos) = norm.sin_cos();
+        // sin(norm) / norm, continued to one at zero
+        let sinc = if norm > Float::EPSILON {
+            sin / norm
+        } else {
+            1.0
+        };
+
+        Self::new(
+            cos,
+            sinc * self.imag_i,
+            sinc * self.imag_j,
+            sinc * self.imag_k,
+        ) * self.real.exp()
+    }
 }
 
 impl std::ops::Add for Quaternion {
@@ -39,6 +62,28 @@ impl std::ops::Add for Quaternion {
     }
 }
 
+impl std::ops::Mul for Quaternion {
+    type Output = Quaternion;
+
+    /// Hamilton product of the quaternions.
+    #[inline]
+    fn mul(self, rhs: Quaternion) -> Self::Output {
+        let real = self.real * rhs.real
+            - self.imag_i * rhs.imag_i
+            - self.imag_j * rhs.imag_j
+            - self.imag_k * rhs.imag_k;
+        let imag_i = self.real * rhs.imag_i + self.imag_i * rhs.real + self.imag_j * rhs.imag_k
+            - self.imag_k * rhs.imag_j;
+        let imag_j = self.real * rhs.imag_j - self.imag_i * rhs.imag_k
+            + self.imag_j * rhs.real
+            + self.imag_k * rhs.imag_i;
+        let imag_k = self.real * rhs.imag_k + self.imag_i * rhs.imag_j - self.imag_j * rhs.imag_i
+            + self.imag_k * rhs.real;
+
+        Self::new(real, imag_i, imag_j, imag_k)
+    }
+}
+
 impl std::ops::Mul<Float> for Quaternion {
     type Output = Self;
 
@@ -0,0 +1,241 @@
+//! Reflection of the whole setup, for validating describing functions and asymmetry terms.
+//!
+//! Mirroring the annulus (`theta -> -theta`) exchanges the anticlockwise and
+//! clockwise spinning waves: the nature angle `chi` and the orientation angle
+//! `n theta_0` change sign, while the amplitude and the temporal phase are
+//! unchanged. The AFDF with the gain ratio `r` is the mirror image of the
+//! AFDF with `1 / r`, and the burners, flame profile, and disabled burners
+//! are mirrored with the annulus. With mirrored noise realizations (the
+//! components driving the orientation and nature angles change sign), the
+//! mirrored simulation reproduces the parity-transformed original, and so do
+//! its statistics. A deviation points to a describing function or asymmetry
+//! term without a consistent mirror image.
+use crate::azimuthal_mode::Mode;
+use crate::hrr_integral::DescribingFunction;
+use crate::rotation::max_abs;
+use crate::sensitivity::run_time_series;
+use crate::{Float, ParametersError, SensitivityParameter, Settings, PI, RNG};
+
+/// Possible errors for [`ReflectionTest`].
+#[derive(Clone, Debug)]
+pub enum ReflectionError {
+    Tolerance,
+    Length,
+    Mirror,
+    Parameters(ParametersError),
+}
+
+impl std::error::Error for ReflectionError {}
+
+impl std::fmt::Display for ReflectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Tolerance => "the tolerance has to be a positive number".to_owned(),
+            Self::Length => "the simulations have to be saved at the same times".to_owned(),
+            Self::Mirror => "the settings have no mirror image (the stochastic describing function, a vanishing gain ratio r, or a ramped gain ratio r)".to_owned(),
+            Self::Parameters(e) => e.to_string(),
+        };
+
+        write!(f, "error in the reflection test: {}", msg)
+    }
+}
+
+impl From<ParametersError> for ReflectionError {
+    fn from(value: ParametersError) -> Self {
+        Self::Parameters(value)
+    }
+}
+
+/// Reflection of the setup and the noise about `theta = 0`.
+#[derive(Clone, Copy, Debug)]
+pub struct ReflectionTest {
+    /// Largest deviation of the mirrored simulation considered consistent.
+    pub tolerance: Float,
+}
+
+/// Deviations of the mirrored simulation from the parity-transformed original simulation.
+#[derive(Clone, Debug)]
+pub struct ReflectionResult {
+    pub tolerance: Float,
+    pub time: Vec<Float>,
+    /// Relative difference of the amplitudes.
+    pub amplitude_deviation: Vec<Float>,
+    /// Sum of the orientation angles `n theta_0`, in `[-pi, pi)`.
+    pub orientation_deviation: Vec<Float>,
+    pub phase_deviation: Vec<Float>,
+    /// Sum of the nature angles.
+    pub nature_angle_deviation: Vec<Float>,
+}
+
+impl ReflectionTest {
+    pub fn new(tolerance: Float) -> Result<Self, ReflectionError> {
+        if tolerance.is_nan() || tolerance <= 0.0 {
+            return Err(ReflectionError::Tolerance);
+        }
+
+        Ok(Self { tolerance })
+    }
+
+    /// Mirror image of `settings`, with the initial mode `initial_mode` mirrored as well.
+    fn mirrored(settings: &Settings, initial_mode: Mode) -> Result<Settings, ReflectionError> {
+        let mut mirrored = settings.clone();
+        if let DescribingFunction::Stochastic(_) = mirrored.describing_function {
+            return Err(ReflectionError::Mirror);
+        }
+        if let Some(ramp) = &mirrored.ramp {
+            if ramp.parameter == SensitivityParameter::GainRatioR {
+                return Err(ReflectionError::Mirror);
+            }
+        }
+        // The conventional describing function has no gain ratio, and is its own mirror image
+        if let Ok(r) = SensitivityParameter::GainRatioR.get(&mirrored) {
+            if r == 0.0 {
+                return Err(ReflectionError::Mirror);
+            }
+            SensitivityParameter::GainRatioR
+                .set(&mut mirrored, 1.0 / r)
+                .expect("the gain ratio was read above");
+        }
+
+        let parameters = &mut mirrored.parameters;
+        let burners = parameters.get_number_of_burners();
+        let offset = parameters.get_burner_offset();
+        parameters.set_burner_offset(-offset)?;
+        let profile = parameters.get_flame_profile().map(|p| p.mirrored());
+        parameters.set_flame_profile(profile)?;
+        let disabled = parameters
+            .get_burners_disabled()
+            .iter()
+            .map(|&ind| (burners - ind) % burners)
+            .collect();
+        parameters.set_burners_disabled(disabled)?;
+        parameters.set_initial_mode(Mode {
+            orientation_angle: -initial_mode.orientation_angle,
+            nature_angle: -initial_mode.nature_angle,
+            ..initial_mode
+        });
+
+        if let Some(importance_sampling) = &mut mirrored.importance_sampling {
+            importance_sampling.tilt_imag_i *= -1.0;
+            importance_sampling.tilt_imag_k *= -1.0;
+        }
+
+        Ok(mirrored)
+    }
+
+    /// Run `settings` as given and mirrored with the same seed, and compare the results.
+    ///
+    /// The seed of `settings` is used, or a random seed if it is not seeded.
+    /// A random initial mode is drawn once from the seed, and used (mirrored)
+    /// by both simulations.
+    pub fn compute(&self, settings: &Settings) -> Result<ReflectionResult, ReflectionError> {
+        let seed = settings.seed.unwrap_or_else(rand::random);
+
+        let mut original = settings.clone();
+        original.set_seed(Some(seed));
+        let initial_mode = original
+            .parameters
+            .resolve_initial_mode(&mut RNG::from_seed(seed));
+        original.parameters.set_initial_mode(initial_mode);
+
+        let mut mirrored = Self::mirrored(&original, initial_mode)?;
+        mirrored.rng.set_mirrored(true);
+
+        let original = run_time_series(original);
+        let mirrored = run_time_series(mirrored);
+        if original.get_time() != mirrored.get_time() {
+            return Err(ReflectionError::Length);
+        }
+
+        let pairs: Vec<_> = original
+            .get_modes()
+            .iter()
+            .zip(mirrored.get_modes())
+            .collect();
+        let wrap = |angle: Float| (angle + PI).rem_euclid(2.0 * PI) - PI;
+        Ok(ReflectionResult {
+            tolerance: self.tolerance,
+            time: original.get_time().to_vec(),
+            amplitude_deviation: pairs.iter().map(|(m, r)| r.a() / m.a() - 1.0).collect(),
+            orientation_deviation: pairs
+                .iter()
+                .map(|(m, r)| wrap(r.nth0() + m.nth0()))
+                .collect(),
+            phase_deviation: pairs.iter().map(|(m, r)| wrap(r.phi() - m.phi())).collect(),
+            nature_angle_deviation: pairs.iter().map(|(m, r)| r.chi() + m.chi()).collect(),
+        })
+    }
+}
+
+impl ReflectionResult {
+    /// Largest deviation of the amplitude, orientation angle, temporal phase, and nature angle.
+    pub fn max_deviations(&self) -> [Float; 4] {
+        [
+            &self.amplitude_deviation,
+            &self.orientation_deviation,
+            &self.phase_deviation,
+            &self.nature_angle_deviation,
+        ]
+        .map(|deviation| max_abs(deviation))
+    }
+
+    /// Whether the mirrored simulation transforms as expected within the tolerance.
+    pub fn consistent(&self) -> bool {
+        self.max_deviations()
+            .iter()
+            .all(|&deviation| deviation <= self.tolerance)
+    }
+}
+
+impl std::fmt::Display for ReflectionResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [amplitude, orientation, phase, chi] = self.max_deviations();
+        let verdict = if self.consistent() {
+            "consistent with the reflection symmetry"
+        } else {
+            "NOT consistent with the reflection symmetry"
+        };
+
+        write!(
+            f,
+            "reflection: {} (largest deviations: amplitude {:.2e}, orientation {:.2e}, phase {:.2e}, chi {:.2e})",
+            verdict, amplitude, orientation, phase, chi
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hrr_integral::AFDFSimplified;
+    use crate::{FlameProfile, ProfileHarmonic};
+
+    #[test]
+    fn mirrored_simulation_is_consistent() {
+        let mut settings = Settings {
+            describing_function: DescribingFunction::Simplified(AFDFSimplified::new(1.6)),
+            ..Default::default()
+        };
+        settings.set_seed(Some(7));
+        let parameters = &mut settings.parameters;
+        parameters.set_number_of_cycles(2.0).unwrap();
+        parameters.set_burner_offset(0.1).unwrap();
+        parameters.set_burners_disabled(vec![0, 3]).unwrap();
+        let harmonic = ProfileHarmonic {
+            order: 1,
+            amplitude: 0.2,
+            phase: 0.5,
+        };
+        parameters
+            .set_flame_profile(Some(FlameProfile::Harmonics(vec![harmonic])))
+            .unwrap();
+        parameters.set_initial_mode(Mode::new(0.5, 0.2, 0.0, 0.3));
+
+        let result = ReflectionTest::new(1e-8)
+            .unwrap()
+            .compute(&settings)
+            .unwrap();
+        assert!(!result.time.is_empty());
+        assert!(result.consistent(), "{}", result);
+    }
+}
@@ -0,0 +1,169 @@
+//! Retries of operations which fail transiently, such as saving to shared filesystems.
+//!
+//! On shared (network) filesystems, opening the HDF5 files occasionally
+//! fails transiently. A [`RetryPolicy`] repeats such an operation after
+//! exponentially increasing delays, and is used by
+//! [`crate::SaveData::save_or_rescue`] before the results are rescued to a
+//! local file (see [`rescue_path`]).
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::Float;
+
+/// Name of the directory of the rescued results in the temporary directory.
+const RESCUE_DIRECTORY: &str = "azimuthal_fdf_rescue";
+
+/// Possible errors for [`RetryPolicy`].
+#[derive(Clone, Debug)]
+pub enum RetryError {
+    Delay,
+    Backoff,
+}
+
+impl std::error::Error for RetryError {}
+
+impl std::fmt::Display for RetryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Delay => "the delay has to be finite and non-negative",
+            Self::Backoff => "the backoff factor has to be finite and at least one",
+        };
+
+        write!(f, "error in the retry policy: {}", msg)
+    }
+}
+
+/// How often, and after which delays, a failed operation is retried.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetryPolicy {
+    retries: usize,
+    /// Delay before the first retry, in seconds.
+    delay: Float,
+    /// Factor by which the delay increases for each further retry.
+    backoff: Float,
+}
+
+impl Default for RetryPolicy {
+    /// Three retries, after 1, 2, and 4 seconds.
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            delay: 1.0,
+            backoff: 2.0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Retry up to `retries` times, waiting `delay` seconds before the first
+    /// retry and `backoff` times longer before each further retry.
+    pub fn new(retries: usize, delay: Float, backoff: Float) -> Result<Self, RetryError> {
+        if !delay.is_finite() || delay < 0.0 {
+            return Err(RetryError::Delay);
+        }
+        if !backoff.is_finite() || backoff < 1.0 {
+            return Err(RetryError::Backoff);
+        }
+
+        Ok(Self {
+            retries,
+            delay,
+            backoff,
+        })
+    }
+
+    /// Policy without any retries.
+    pub fn none() -> Self {
+        Self {
+            retries: 0,
+            ..Self::default()
+        }
+    }
+
+    pub fn get_retries(&self) -> usize {
+        self.retries
+    }
+
+    /// Delay in seconds before the retry `retry` (counted from zero).
+    pub fn delay_before(&self, retry: usize) -> Float {
+        self.delay * self.backoff.powi(retry as i32)
+    }
+
+    /// Call `attempt` until it succeeds, at most `retries + 1` times.
+    ///
+    /// Returns the result with the number of failed attempts before it, or
+    /// the error of the last attempt if all of them failed.
+    pub fn run<T, E>(&self, mut attempt: impl FnMut() -> Result<T, E>) -> Result<(T, usize), E> {
+        let mut retry = 0;
+        loop {
+            match attempt() {
+                Ok(value) => return Ok((value, retry)),
+                Err(e) if retry == self.retries => return Err(e),
+                Err(_) => {
+                    std::thread::sleep(Duration::from_secs_f64(self.delay_before(retry)));
+                    retry += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Where the results of [`crate::SaveData::save_or_rescue`] were saved to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SaveOutcome {
+    /// Saved as intended, after the given number of failed attempts.
+    Saved { failed_attempts: usize },
+    /// Rescued to the local file `path`, after all the attempts failed with the last `error`.
+    Rescued { path: PathBuf, error: String },
+}
+
+/// Path in the local temporary directory to rescue the results of `path` to.
+///
+/// The file keeps the name and format of `path`, with the process ID
+/// appended, e.g. `/tmp/azimuthal_fdf_rescue/results_1234.hdf5`.
+pub fn rescue_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "results".to_owned());
+    let mut name = format!("{}_{}", stem, std::process::id());
+    if let Some(extension) = path.extension() {
+        name = format!("{}.{}", name, extension.to_string_lossy());
+    }
+
+    std::env::temp_dir().join(RESCUE_DIRECTORY).join(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_attempts_are_retried() {
+        let policy = RetryPolicy::new(2, 0.0, 2.0).unwrap();
+        let mut attempts = 0;
+        let result = policy.run(|| {
+            attempts += 1;
+            if attempts < 3 {
+                Err("transient")
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert_eq!(result, Ok((3, 2)));
+
+        // One more failure exceeds the retries
+        let mut attempts = 0;
+        let result: Result<((), usize), usize> = policy.run(|| {
+            attempts += 1;
+            Err(attempts)
+        });
+        assert_eq!(result, Err(3));
+
+        assert_eq!(RetryPolicy::default().delay_before(2), 4.0);
+        assert!(RetryPolicy::new(1, 1.0, 0.5).is_err());
+        let rescued = rescue_path(Path::new("results/run.hdf5"));
+        assert!(rescued.starts_with(std::env::temp_dir()));
+        assert_eq!(rescued.extension().unwrap(), "hdf5");
+    }
+}
@@ -0,0 +1,198 @@
+//! Rotation of the whole setup, for debugging asymmetric setups.
+//!
+//! Rotating all the burners and the initial orientation of the mode by the
+//! same angle `alpha` is a symmetry of the model. With identical noise
+//! realizations, the amplitude, nature angle, and temporal phase are
+//! unchanged, while the orientation angle `n theta_0` is shifted by
+//! `n alpha`. A per-burner heterogeneity that rotates with its burner keeps
+//! this symmetry, such that any deviation points to a quantity tied to a
+//! fixed azimuthal location instead (e.g. a bookkeeping bug), and confirms
+//! whether the symmetry breaking of a setup is intentional.
+use crate::azimuthal_mode::Mode;
+use crate::sensitivity::run_time_series;
+use crate::{Float, ParametersError, Settings, PI, RNG};
+
+/// Possible errors for [`RotationTest`].
+#[derive(Clone, Debug)]
+pub enum RotationError {
+    Angle,
+    Tolerance,
+    Length,
+    Parameters(ParametersError),
+}
+
+impl std::error::Error for RotationError {}
+
+impl std::fmt::Display for RotationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Angle => "the rotation angle has to be finite".to_owned(),
+            Self::Tolerance => "the tolerance has to be a positive number".to_owned(),
+            Self::Length => "the simulations have to be saved at the same times".to_owned(),
+            Self::Parameters(e) => e.to_string(),
+        };
+
+        write!(f, "error in the rotation test: {}", msg)
+    }
+}
+
+impl From<ParametersError> for RotationError {
+    fn from(value: ParametersError) -> Self {
+        Self::Parameters(value)
+    }
+}
+
+/// Rotation of the burners and initial mode by `angle` (in radians).
+#[derive(Clone, Copy, Debug)]
+pub struct RotationTest {
+    pub angle: Float,
+    /// Largest deviation of the rotated simulation considered consistent.
+    pub tolerance: Float,
+}
+
+/// Deviations of the rotated simulation from the transformed original simulation.
+#[derive(Clone, Debug)]
+pub struct RotationResult {
+    pub angle: Float,
+    pub tolerance: Float,
+    pub time: Vec<Float>,
+    /// Relative difference of the amplitudes.
+    pub amplitude_deviation: Vec<Float>,
+    /// Difference of the orientation angles `n theta_0`, less `n angle`, in `[-pi, pi)`.
+    pub orientation_deviation: Vec<Float>,
+    pub phase_deviation: Vec<Float>,
+    pub nature_angle_deviation: Vec<Float>,
+}
+
+impl RotationTest {
+    pub fn new(angle: Float, tolerance: Float) -> Result<Self, RotationError> {
+        if !angle.is_finite() {
+            return Err(RotationError::Angle);
+        }
+        if tolerance.is_nan() || tolerance <= 0.0 {
+            return Err(RotationError::Tolerance);
+        }
+
+        Ok(Self { angle, tolerance })
+    }
+
+    /// Run `settings` as given and rotated with the same seed, and compare the results.
+    ///
+    /// The seed of `settings` is used, or a random seed if it is not seeded.
+    /// A random initial mode is drawn once from the seed, and used (rotated)
+    /// by both simulations.
+    pub fn compute(&self, settings: &Settings) -> Result<RotationResult, RotationError> {
+        let seed = settings.seed.unwrap_or_else(rand::random);
+        let mode_order = settings.parameters.get_mode_order() as Float;
+
+        let mut original = settings.clone();
+        original.set_seed(Some(seed));
+        let initial_mode = original
+            .parameters
+            .resolve_initial_mode(&mut RNG::from_seed(seed));
+        original.parameters.set_initial_mode(initial_mode);
+
+        let mut rotated = original.clone();
+        let offset = rotated.parameters.get_burner_offset() + self.angle;
+        rotated.parameters.set_burner_offset(offset)?;
+        rotated.parameters.set_initial_mode(Mode {
+            orientation_angle: initial_mode.orientation_angle + mode_order * self.angle,
+            ..initial_mode
+        });
+
+        let original = run_time_series(original);
+        let rotated = run_time_series(rotated);
+        if original.get_time() != rotated.get_time() {
+            return Err(RotationError::Length);
+        }
+
+        let pairs: Vec<_> = original
+            .get_modes()
+            .iter()
+            .zip(rotated.get_modes())
+            .collect();
+        let wrap = |angle: Float| (angle + PI).rem_euclid(2.0 * PI) - PI;
+        Ok(RotationResult {
+            angle: self.angle,
+            tolerance: self.tolerance,
+            time: original.get_time().to_vec(),
+            amplitude_deviation: pairs.iter().map(|(m, r)| r.a() / m.a() - 1.0).collect(),
+            orientation_deviation: pairs
+                .iter()
+                .map(|(m, r)| wrap(r.nth0() - m.nth0() - mode_order * self.angle))
+                .collect(),
+            phase_deviation: pairs.iter().map(|(m, r)| wrap(r.phi() - m.phi())).collect(),
+            nature_angle_deviation: pairs.iter().map(|(m, r)| r.chi() - m.chi()).collect(),
+        })
+    }
+}
+
+/// Largest magnitude of `values` (NaN if any value is NaN).
+pub(crate) fn max_abs(values: &[Float]) -> Float {
+    values.iter().fold(0.0, |max: Float, v| {
+        if max.is_nan() || v.is_nan() {
+            Float::NAN
+        } else {
+            max.max(v.abs())
+        }
+    })
+}
+
+impl RotationResult {
+    /// Largest deviation of the amplitude, orientation angle, temporal phase, and nature angle.
+    pub fn max_deviations(&self) -> [Float; 4] {
+        [
+            &self.amplitude_deviation,
+            &self.orientation_deviation,
+            &self.phase_deviation,
+            &self.nature_angle_deviation,
+        ]
+        .map(|deviation| max_abs(deviation))
+    }
+
+    /// Whether the rotated simulation transforms as expected within the tolerance.
+    pub fn consistent(&self) -> bool {
+        self.max_deviations()
+            .iter()
+            .all(|&deviation| deviation <= self.tolerance)
+    }
+}
+
+impl std::fmt::Display for RotationResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [amplitude, orientation, phase, chi] = self.max_deviations();
+        let verdict = if self.consistent() {
+            "consistent with the rotational symmetry"
+        } else {
+            "NOT consistent with the rotational symmetry"
+        };
+
+        write!(
+            f,
+            "rotation by {}: {} (largest deviations: amplitude {:.2e}, orientation {:.2e}, phase {:.2e}, chi {:.2e})",
+            self.angle, verdict, amplitude, orientation, phase, chi
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotated_simulation_is_consistent() {
+        let mut settings = Settings::default();
+        settings.set_seed(Some(7));
+        settings.parameters.set_number_of_cycles(2.0).unwrap();
+        settings
+            .parameters
+            .set_initial_mode(Mode::new(0.5, 0.2, 0.0, 0.3));
+
+        let result = RotationTest::new(0.4, 1e-8)
+            .unwrap()
+            .compute(&settings)
+            .unwrap();
+        assert!(!result.time.is_empty());
+        assert!(result.consistent(), "{}", result);
+    }
+}
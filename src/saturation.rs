@@ -8,6 +8,12 @@ pub enum Saturation {
     Tangent(Float),
     /// Exponential saturation.
     Exponential(Float),
+    /// Cubic saturation `1 - kappa a^2`, the leading order of the other forms.
+    ///
+    /// The factor becomes negative above `a = 1 / sqrt(kappa)`. With the
+    /// symmetric describing functions, the stationary probability density is
+    /// known in closed form, see [`crate::CubicSaturationBenchmark`].
+    Cubic(Float),
 }
 
 impl Saturation {
@@ -18,15 +24,19 @@ impl Saturation {
     /// it should approach zero in the high amplitude limit
     #[inline]
     pub fn factor(&self, local_amplitudes: &[Float]) -> Vec<Float> {
+        local_amplitudes
+            .iter()
+            .map(|&a| self.factor_at(a))
+            .collect()
+    }
+
+    /// Get the saturation factor at a single local amplitude `a`.
+    #[inline]
+    pub fn factor_at(&self, a: Float) -> Float {
         match self {
-            Self::Tangent(kappa) => local_amplitudes
-                .iter()
-                .map(|&a| 2.0 / (1.0 + (1.0 + (kappa * a).powi(2)).sqrt()))
-                .collect(),
-            Self::Exponential(kappa) => local_amplitudes
-                .iter()
-                .map(|&a| (-kappa * a).exp())
-                .collect(),
+            Self::Tangent(kappa) => 2.0 / (1.0 + (1.0 + (kappa * a).powi(2)).sqrt()),
+            Self::Exponential(kappa) => (-kappa * a).exp(),
+            Self::Cubic(kappa) => 1.0 - kappa * a.powi(2),
         }
     }
 }
@@ -0,0 +1,106 @@
+//! Derivation of independent, reproducible seeds from one master seed.
+//!
+//! Ensembles (repetitions, uncertainty quantification, convergence tests)
+//! run many simulations from the seed of one settings file. Offsetting the
+//! seed by the index of the realization (`seed + i`) makes the streams of
+//! nearby master seeds overlap, e.g. realization 1 of seed 7 repeats
+//! realization 0 of seed 8. A [`SeedSequence`] instead derives the seed of
+//! each realization by hashing the master seed together with the indices
+//! leading to the realization (its spawn key, e.g. `[1, 3]` for sample 3 of
+//! the second stream of an ensemble).
+//!
+//! The seed of the spawn key `[k_1, ..., k_m]` is `h_m`, where
+//! `h_0 = mix(master_seed)` and `h_j = mix(h_{j-1} xor mix(k_j))`, and `mix`
+//! is the output function of SplitMix64 (adding `0x9e3779b97f4a7c15`, then
+//! the two xor-shift-multiply rounds). As `mix` is a bijection, the children
+//! of a sequence have distinct seeds. A sequence without spawn key uses the
+//! master seed itself, such that a single realization keeps the seed of the
+//! settings file.
+use serde::{Deserialize, Serialize};
+
+/// Increment of the SplitMix64 generator (the golden ratio in fixed point).
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Output function of SplitMix64, a bijection on the 64-bit integers.
+#[inline]
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(GOLDEN_GAMMA);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Master seed and the path of indices (spawn key) to a realization.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SeedSequence {
+    master_seed: u64,
+    spawn_key: Vec<u64>,
+}
+
+impl SeedSequence {
+    pub fn new(master_seed: u64) -> Self {
+        Self {
+            master_seed,
+            spawn_key: Vec::new(),
+        }
+    }
+
+    /// Sequence of the child `index`, independent of the other children.
+    pub fn child(&self, index: u64) -> Self {
+        let mut spawn_key = self.spawn_key.clone();
+        spawn_key.push(index);
+
+        Self {
+            master_seed: self.master_seed,
+            spawn_key,
+        }
+    }
+
+    /// Seed of the random number generator of this realization.
+    pub fn seed(&self) -> u64 {
+        if self.spawn_key.is_empty() {
+            return self.master_seed;
+        }
+
+        self.spawn_key
+            .iter()
+            .fold(mix(self.master_seed), |state, &index| {
+                mix(state ^ mix(index))
+            })
+    }
+
+    pub fn get_master_seed(&self) -> u64 {
+        self.master_seed
+    }
+
+    pub fn get_spawn_key(&self) -> &[u64] {
+        &self.spawn_key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn children_are_distinct_and_reproducible() {
+        let master = SeedSequence::new(7);
+        assert_eq!(master.seed(), 7);
+
+        let children: Vec<u64> = (0..100).map(|ind| master.child(ind).seed()).collect();
+        let mut unique = children.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), children.len());
+        assert_eq!(master.child(3).seed(), children[3]);
+
+        // No overlap with the children of the neighbouring master seed
+        let neighbour = SeedSequence::new(8);
+        assert_ne!(master.child(1).seed(), neighbour.child(0).seed());
+        // The order of the indices matters
+        assert_ne!(
+            master.child(0).child(1).seed(),
+            master.child(1).child(0).seed()
+        );
+    }
+}
@@ -0,0 +1,303 @@
+//! Sensitivity of the output statistics to the parameters.
+//!
+//! The sensitivities are computed by central finite differences, where the
+//! simulations are run with common random numbers (the same seed), such that
+//! the difference between the runs is caused by the parameter change rather
+//! than by the noise realizations.
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::azimuthal_mode::SystemMode;
+use crate::hrr_integral::{AFDFSimplified, DescribingFunction, DescribingFunctionError};
+use crate::observers::{Observer, TimeSeriesObserver};
+use crate::{Float, ParametersError, Settings, PI};
+
+/// Possible errors for the sensitivity analysis.
+#[derive(Clone, Debug)]
+pub enum SensitivityError {
+    UnknownParameter(String),
+    NotApplicable,
+    Step,
+    Parameters(ParametersError),
+    DescribingFunction(DescribingFunctionError),
+}
+
+impl std::error::Error for SensitivityError {}
+
+impl std::fmt::Display for SensitivityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::UnknownParameter(name) => format!(
+                "unknown parameter \"{}\" (expected damping, gain, noise, or gain_ratio_r)",
+                name
+            ),
+            Self::NotApplicable => {
+                "the parameter is not used by the describing function".to_owned()
+            }
+            Self::Step => "relative_step has to satisfy 0.0 < relative_step < 1.0".to_owned(),
+            Self::Parameters(e) => e.to_string(),
+            Self::DescribingFunction(e) => e.to_string(),
+        };
+
+        write!(f, "error in the sensitivity analysis: {}", msg)
+    }
+}
+
+impl From<ParametersError> for SensitivityError {
+    fn from(value: ParametersError) -> Self {
+        Self::Parameters(value)
+    }
+}
+
+impl From<DescribingFunctionError> for SensitivityError {
+    fn from(value: DescribingFunctionError) -> Self {
+        Self::DescribingFunction(value)
+    }
+}
+
+/// Statistics of the output of a simulation.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OutputStatistics {
+    /// Time averaged amplitude.
+    pub mean_amplitude: Float,
+    /// Fraction of the time spent in spinning states.
+    pub spinning_probability: Float,
+}
+
+impl OutputStatistics {
+    /// Nature angle above which the mode is counted as spinning.
+    ///
+    /// Halfway between standing (`chi = 0`) and spinning (`|chi| = pi/4`) modes.
+    pub const SPINNING_THRESHOLD: Float = PI / 8.0;
+
+    /// Calculate the statistics of a time series of modes.
+    pub fn from_modes(modes: &[SystemMode]) -> Self {
+        if modes.is_empty() {
+            return Self::default();
+        }
+
+        let number_of_modes = modes.len() as Float;
+        let mean_amplitude = modes.iter().map(|mode| mode.a()).sum::<Float>() / number_of_modes;
+        let spinning = modes
+            .iter()
+            .filter(|mode| mode.chi().abs() > Self::SPINNING_THRESHOLD)
+            .count();
+
+        Self {
+            mean_amplitude,
+            spinning_probability: (spinning as Float) / number_of_modes,
+        }
+    }
+
+    /// Run the simulation and calculate the statistics of the result.
+    ///
+    /// The observer of `settings` is replaced by a time series kept in memory.
+    pub fn from_simulation(settings: Settings) -> Self {
+        Self::from_modes(run_time_series(settings).get_modes())
+    }
+}
+
+/// Run the simulation with the observer replaced by a time series kept in memory.
+pub(crate) fn run_time_series(mut settings: Settings) -> TimeSeriesObserver {
+    settings.set_observer(Observer::TimeSeries(TimeSeriesObserver::default()));
+    settings.run();
+
+    match settings.get_observer() {
+        Observer::TimeSeries(observer) => observer,
+        _ => unreachable!("the observer was set to a time series above"),
+    }
+}
+
+impl std::ops::Sub for OutputStatistics {
+    type Output = OutputStatistics;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            mean_amplitude: self.mean_amplitude - rhs.mean_amplitude,
+            spinning_probability: self.spinning_probability - rhs.spinning_probability,
+        }
+    }
+}
+
+impl std::ops::Div<Float> for OutputStatistics {
+    type Output = OutputStatistics;
+
+    fn div(self, rhs: Float) -> Self::Output {
+        Self {
+            mean_amplitude: self.mean_amplitude / rhs,
+            spinning_probability: self.spinning_probability / rhs,
+        }
+    }
+}
+
+/// Parameters the sensitivities can be calculated for.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensitivityParameter {
+    Damping,
+    Gain,
+    Noise,
+    GainRatioR,
+}
+
+impl SensitivityParameter {
+    /// Name of the parameter, as used in the settings files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Damping => "damping",
+            Self::Gain => "gain",
+            Self::Noise => "noise",
+            Self::GainRatioR => "gain_ratio_r",
+        }
+    }
+
+    /// Get the value of the parameter.
+    pub fn get(&self, settings: &Settings) -> Result<Float, SensitivityError> {
+        let value = match self {
+            Self::Damping => settings.parameters.get_damping(),
+            Self::Gain => settings.parameters.get_gain(),
+            Self::Noise => settings.parameters.get_noise(),
+            Self::GainRatioR => match &settings.describing_function {
+                DescribingFunction::Simplified(hrr) => hrr.gain_ratio_r,
+                DescribingFunction::Tabulated(hrr) => hrr.get_gain_ratio_r(),
+                DescribingFunction::Stochastic(hrr) => hrr.get_gain_ratio_r(),
+                DescribingFunction::Conventional(_) => return Err(SensitivityError::NotApplicable),
+            },
+        };
+
+        Ok(value)
+    }
+
+    /// Set the value of the parameter.
+    ///
+    /// The settings are left unchanged if the value is invalid, e.g. a
+    /// negative gain drawn from the tail of a normal distribution.
+    pub fn set(&self, settings: &mut Settings, value: Float) -> Result<(), SensitivityError> {
+        match self {
+            Self::Damping => settings.parameters.set_damping(value)?,
+            Self::Gain => settings.parameters.set_gain(value)?,
+            Self::Noise => settings.parameters.set_noise(value)?,
+            Self::GainRatioR => match &mut settings.describing_function {
+                DescribingFunction::Simplified(hrr) => *hrr = AFDFSimplified::try_new(value)?,
+                DescribingFunction::Tabulated(hrr) => hrr.set_gain_ratio_r(value)?,
+                DescribingFunction::Stochastic(hrr) => hrr.set_gain_ratio_r(value)?,
+                DescribingFunction::Conventional(_) => return Err(SensitivityError::NotApplicable),
+            },
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for SensitivityParameter {
+    type Err = SensitivityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "damping" => Ok(Self::Damping),
+            "gain" => Ok(Self::Gain),
+            "noise" => Ok(Self::Noise),
+            "gain_ratio_r" => Ok(Self::GainRatioR),
+            _ => Err(SensitivityError::UnknownParameter(s.to_owned())),
+        }
+    }
+}
+
+/// Finite difference sensitivity of the [`OutputStatistics`] to a parameter.
+#[derive(Clone, Copy, Debug)]
+pub struct Sensitivity {
+    pub parameter: SensitivityParameter,
+    /// Step size relative to the value of the parameter.
+    pub relative_step: Float,
+}
+
+/// Result of the sensitivity analysis.
+#[derive(Clone, Copy, Debug)]
+pub struct SensitivityResult {
+    pub parameter: SensitivityParameter,
+    pub value: Float,
+    /// Statistics at the nominal value of the parameter.
+    pub statistics: OutputStatistics,
+    /// Derivative of the statistics with respect to the parameter.
+    pub derivative: OutputStatistics,
+}
+
+impl Sensitivity {
+    pub fn new(
+        parameter: SensitivityParameter,
+        relative_step: Float,
+    ) -> Result<Self, SensitivityError> {
+        if relative_step <= 0.0 || relative_step >= 1.0 {
+            return Err(SensitivityError::Step);
+        }
+
+        Ok(Self {
+            parameter,
+            relative_step,
+        })
+    }
+
+    /// Calculate the sensitivity around `settings`.
+    ///
+    /// Runs three simulations (nominal, increased, and decreased parameter)
+    /// with the same seed. Unseeded settings are given a random seed.
+    pub fn compute(&self, settings: &Settings) -> Result<SensitivityResult, SensitivityError> {
+        let value = self.parameter.get(settings)?;
+        let step = if value == 0.0 {
+            self.relative_step
+        } else {
+            self.relative_step * value.abs()
+        };
+        let seed = settings.seed.unwrap_or_else(rand::random);
+
+        let mut statistics = [OutputStatistics::default(); 3];
+        for (stats, offset) in statistics.iter_mut().zip([0.0, step, -step]) {
+            let mut run_settings = settings.clone();
+            run_settings.set_seed(Some(seed));
+            self.parameter.set(&mut run_settings, value + offset)?;
+
+            *stats = OutputStatistics::from_simulation(run_settings);
+        }
+
+        let [nominal, forward, backward] = statistics;
+        Ok(SensitivityResult {
+            parameter: self.parameter,
+            value,
+            statistics: nominal,
+            derivative: (forward - backward) / (2.0 * step),
+        })
+    }
+}
+
+impl std::fmt::Display for SensitivityResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} = {}: mean amplitude = {} (derivative {}), spinning probability = {} (derivative {})",
+            self.parameter,
+            self.value,
+            self.statistics.mean_amplitude,
+            self.derivative.mean_amplitude,
+            self.statistics.spinning_probability,
+            self.derivative.spinning_probability,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statistics_of_modes() {
+        let modes = [
+            SystemMode::new(1.0, 0.0, 0.0, 0.0),
+            SystemMode::new(3.0, 0.0, 0.0, PI / 4.0 - 0.01),
+        ];
+        let stats = OutputStatistics::from_modes(&modes);
+
+        assert!((stats.mean_amplitude - 2.0).abs() < 1e-12);
+        assert!((stats.spinning_probability - 0.5).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,280 @@
+//! HTTP service for submitting and managing simulations.
+//!
+//! A minimal (blocking) HTTP server, such that the simulator can back a web
+//! dashboard or be orchestrated by workflow tools. The following endpoints
+//! are available:
+//! - `POST /jobs`: submit the [`Settings`] as JSON in the body, returns the job `id`.
+//! - `GET /jobs`: list the status of all the jobs.
+//! - `GET /jobs/<id>`: status and progress of the job.
+//! - `DELETE /jobs/<id>`: cancel the job (the results so far are saved as partial).
+//! - `GET /jobs/<id>/result`: download the HDF5 file of a finished job.
+//!
+//! Each job runs on its own thread, up to a maximum number of jobs running at
+//! the same time, and saves the results to the location given by its
+//! observer, as when running the settings from file. The paths are relative
+//! to the output directory of the service, and cannot leave it, such that
+//! clients cannot read or write other files on the host. Jobs running at the
+//! same time should save to different files, as the HDF5 library does not
+//! support concurrent writes to the same file.
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::observers::{Observer, SaveInfo};
+use crate::{CancellationToken, Float, SaveData, Settings};
+
+/// State of a submitted job.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Finished,
+    /// Stopped early, with the results so far saved as partial.
+    Cancelled,
+    Failed(String),
+}
+
+/// Job submitted to the service.
+#[derive(Debug)]
+struct Job {
+    save_info: SaveInfo,
+    total_steps: usize,
+    progress: Arc<AtomicUsize>,
+    cancellation: CancellationToken,
+    status: Arc<Mutex<JobStatus>>,
+}
+
+/// Status of a job as reported by the service.
+#[derive(Debug, Serialize)]
+struct JobReport {
+    id: usize,
+    status: JobStatus,
+    /// Fraction of the time steps completed.
+    progress: Float,
+    path: String,
+    group: String,
+}
+
+impl Job {
+    fn is_running(&self) -> bool {
+        matches!(*self.status.lock().unwrap(), JobStatus::Running)
+    }
+
+    fn report(&self, id: usize) -> JobReport {
+        let steps = self.progress.load(Ordering::Relaxed);
+
+        JobReport {
+            id,
+            status: self.status.lock().unwrap().clone(),
+            progress: steps as Float / self.total_steps.max(1) as Float,
+            path: self.save_info.get_path().to_string_lossy().into_owned(),
+            group: self.save_info.get_group().to_owned(),
+        }
+    }
+}
+
+/// Run the service on `address` (e.g. `127.0.0.1:8080`) until the process is stopped.
+///
+/// The results of the jobs are saved below `directory` (created if missing),
+/// and at most `max_jobs` jobs run at the same time.
+pub fn serve(
+    address: &str,
+    directory: &Path,
+    max_jobs: usize,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    std::fs::create_dir_all(directory)?;
+    let directory = directory.canonicalize()?;
+    let server = Server::http(address)?;
+    eprintln!(
+        "Listening on http://{}, saving to {}",
+        address,
+        directory.to_string_lossy()
+    );
+
+    let mut jobs: BTreeMap<usize, Job> = BTreeMap::new();
+    for mut request in server.incoming_requests() {
+        let response = handle(&mut request, &mut jobs, &directory, max_jobs);
+        if let Err(e) = request.respond(response) {
+            eprintln!("could not respond: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+type HttpResponse = Response<std::io::Cursor<Vec<u8>>>;
+
+fn handle(
+    request: &mut Request,
+    jobs: &mut BTreeMap<usize, Job>,
+    directory: &Path,
+    max_jobs: usize,
+) -> HttpResponse {
+    let url = request.url().trim_end_matches('/').to_owned();
+    let segments: Vec<&str> = url.split('/').filter(|s| !s.is_empty()).collect();
+
+    match (request.method(), segments.as_slice()) {
+        (Method::Post, ["jobs"]) => {
+            if jobs.values().filter(|job| job.is_running()).count() >= max_jobs {
+                return error(503, "too many jobs are running");
+            }
+
+            let mut body = String::new();
+            if let Err(e) = request.as_reader().read_to_string(&mut body) {
+                return error(400, &e.to_string());
+            }
+
+            match submit(&body, directory) {
+                Ok(job) => {
+                    let id = jobs.keys().next_back().map_or(0, |id| id + 1);
+                    let report = job.report(id);
+                    jobs.insert(id, job);
+                    json(201, &report)
+                }
+                Err(e) => error(400, &e.to_string()),
+            }
+        }
+        (Method::Get, ["jobs"]) => {
+            let reports: Vec<JobReport> = jobs.iter().map(|(&id, job)| job.report(id)).collect();
+            json(200, &reports)
+        }
+        (method, ["jobs", id, rest @ ..]) => {
+            let job = match id.parse().ok().and_then(|id: usize| jobs.get(&id)) {
+                Some(job) => job,
+                None => return error(404, "job not found"),
+            };
+            let id: usize = id.parse().unwrap_or_default();
+
+            match (method, rest) {
+                (Method::Get, []) => json(200, &job.report(id)),
+                (Method::Delete, []) => {
+                    job.cancellation.cancel();
+                    json(200, &job.report(id))
+                }
+                (Method::Get, ["result"]) => result(job),
+                _ => error(405, "method not allowed"),
+            }
+        }
+        _ => error(404, "not found"),
+    }
+}
+
+/// Start a new job from the JSON settings, saving below `directory`.
+fn submit(body: &str, directory: &Path) -> Result<Job, Box<dyn Error>> {
+    let mut settings = Settings::from_json(body)?;
+
+    let mut save_info = settings.observer.save_info();
+    save_info.set_path(&confine(save_info.get_path(), directory)?);
+    save_info.is_valid()?;
+    settings.observer.set_save_info(&save_info);
+    if let Observer::TimeSeries(obs) = &mut settings.observer {
+        if let Some(table_path) = &obs.table_path {
+            obs.table_path = Some(confine(table_path, directory)?);
+        }
+    }
+
+    let cancellation = CancellationToken::new();
+    let progress = Arc::new(AtomicUsize::new(0));
+    let status = Arc::new(Mutex::new(JobStatus::Running));
+    settings.set_cancellation(Some(cancellation.clone()));
+    settings.progress = Some(progress.clone());
+
+    let job = Job {
+        save_info,
+        total_steps: settings.parameters.get_total_steps(),
+        progress,
+        cancellation,
+        status: status.clone(),
+    };
+
+    std::thread::spawn(move || {
+        // A panic must not leave the job running forever
+        let outcome = panic::catch_unwind(AssertUnwindSafe(move || {
+            settings.run();
+
+            let mut save_data = SaveData::from(settings);
+            match save_data.save() {
+                Ok(_) if save_data.is_partial() => JobStatus::Cancelled,
+                Ok(_) => JobStatus::Finished,
+                Err(e) => JobStatus::Failed(e.to_string()),
+            }
+        }));
+        let new_status =
+            outcome.unwrap_or_else(|_| JobStatus::Failed(String::from("the simulation panicked")));
+        *status.lock().unwrap() = new_status;
+    });
+
+    Ok(job)
+}
+
+/// Path of the output `path` of a job below the output `directory` of the service.
+///
+/// Only relative paths staying below the directory are accepted.
+fn confine(path: &Path, directory: &Path) -> Result<PathBuf, String> {
+    let is_confined = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !is_confined || path.file_name().is_none() {
+        return Err(format!(
+            "the path \"{}\" has to be relative to the output directory, without '..'",
+            path.to_string_lossy()
+        ));
+    }
+
+    Ok(directory.join(path))
+}
+
+/// Respond with the HDF5 file of a finished (or cancelled) job.
+fn result(job: &Job) -> HttpResponse {
+    match *job.status.lock().unwrap() {
+        JobStatus::Finished | JobStatus::Cancelled => {}
+        JobStatus::Running => return error(409, "the job is still running"),
+        JobStatus::Failed(_) => return error(409, "the job failed"),
+    }
+
+    match std::fs::read(job.save_info.get_path()) {
+        Ok(data) => Response::from_data(data)
+            .with_status_code(200)
+            .with_header(header("Content-Type", "application/x-hdf5")),
+        Err(e) => error(500, &e.to_string()),
+    }
+}
+
+fn json<T: Serialize>(status_code: u16, value: &T) -> HttpResponse {
+    let body = serde_json::to_string(value).unwrap_or_default();
+
+    Response::from_string(body)
+        .with_status_code(status_code)
+        .with_header(header("Content-Type", "application/json"))
+}
+
+fn error(status_code: u16, message: &str) -> HttpResponse {
+    json(status_code, &serde_json::json!({ "error": message }))
+}
+
+fn header(name: &str, value: &str) -> Header {
+    // Only called with valid ASCII names and values
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_are_confined_to_the_directory() {
+        let directory = Path::new("/srv/results");
+        let confined = confine(Path::new("sweep/./run.h5"), directory).unwrap();
+        assert_eq!(confined, directory.join("sweep/./run.h5"));
+
+        for path in ["/etc/passwd", "../run.h5", "sweep/../../run.h5", "", "."] {
+            assert!(confine(Path::new(path), directory).is_err(), "{}", path);
+        }
+    }
+}
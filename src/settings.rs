@@ -1,20 +1,35 @@
-use rand::rngs::ThreadRng;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
 use rand_distr::StandardNormal;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
 use std::fs::File;
-use std::io::BufReader;
 use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
 use std::time;
 
-use crate::hrr_integral::DescribingFunction;
+use crate::azimuthal_mode::{InitialMode, InitialModeSampling};
+use crate::hrr_integral::{DescribingFunction, DescribingFunctionError};
+#[cfg(any(feature = "hdf5", feature = "npz"))]
+use crate::observers;
+#[cfg(feature = "hdf5")]
+use crate::observers::TimeSeriesObserver;
 use crate::observers::{Observer, ObserverTrait, SaveInfo};
-use crate::{Float, Parameters, ParametersError, Quaternion, Saturation};
+#[cfg(feature = "hdf5")]
+use crate::rescue_path;
+use crate::{
+    CancellationToken, FiniteTimeExponents, FlameDistribution, Float, ImportanceSampling,
+    Integrator, JumpNoise, Lyapunov, NoiseSchedule, NoiseScheduleError, ParameterRamp, Parameters,
+    ParametersError, Quaternion, Saturation, SeedSequence, SensitivityParameter, SettingsTemplate,
+    SimulationHooks, Sobol, StateRepresentation,
+};
+#[cfg(feature = "hdf5")]
+use crate::{RetryPolicy, SaveOutcome};
 
 /// Struct containing most of the data from [`Settings`] for saving purposes.
 ///
-/// The [`ThreadRng`] has some specific requirements for moving it in and out of
+/// The random number generator has some specific requirements for moving it in and out of
 /// different threads. Therefore, this struct is used to pass (most of) the data
 /// from the simulation out of any parallel for loops
 #[derive(Debug)]
@@ -22,52 +37,531 @@ pub struct SaveData {
     parameters: Parameters,
     observer: Observer,
     describing_function: DescribingFunction,
+    interrupted_at: Option<Float>,
+    diverged_at: Option<Float>,
+    lyapunov_exponents: Option<FiniteTimeExponents>,
+    seed: Option<u64>,
+    seed_sequence: Option<SeedSequence>,
+    #[cfg(feature = "hdf5")]
+    rng_checkpoint: RngCheckpoint,
+    #[cfg(any(feature = "hdf5", feature = "npz"))]
+    noise_schedule: Option<NoiseSchedule>,
+    /// Wall-clock time of the simulation in seconds, if measured.
+    run_time: Option<Float>,
+    /// Complete settings of the simulation as JSON, saved with the results.
+    settings_json: String,
     pub finish_time: time::SystemTime,
 }
 
 impl From<Settings> for SaveData {
     fn from(value: Settings) -> Self {
+        let settings_json = serde_json::to_string(&value).unwrap_or_default();
+        #[cfg(feature = "hdf5")]
+        let rng_checkpoint = RngCheckpoint {
+            time: value.end_time(),
+            state: value.rng.get_state(),
+        };
+
         Self {
+            settings_json,
             parameters: value.parameters,
             observer: value.observer,
             describing_function: value.describing_function,
+            interrupted_at: value.interrupted_at,
+            diverged_at: value.diverged_at,
+            lyapunov_exponents: value.lyapunov_exponents,
+            seed: value.seed,
+            seed_sequence: value.seed_sequence,
+            #[cfg(feature = "hdf5")]
+            rng_checkpoint,
+            #[cfg(any(feature = "hdf5", feature = "npz"))]
+            noise_schedule: value.noise_schedule,
+            run_time: None,
             finish_time: time::SystemTime::now(),
         }
     }
 }
 
 impl SaveData {
-    pub fn save(&self) -> hdf5::Result<()> {
+    /// Save the results, marking the group as partial if the simulation was interrupted.
+    ///
+    /// The complete settings (as JSON) and the burner locations are saved as
+    /// the attributes `settings` and `thetas` of the group, such that the
+    /// simulation can be reproduced from the file alone. The seed (if any) is
+    /// saved as the attribute `seed`, and, if it was derived from a
+    /// [`SeedSequence`], its master seed and spawn key as `master_seed` and
+    /// `seed_spawn_key`. The state of the random number generator at the end
+    /// of the simulation is saved as the attribute `rng_checkpoint` (as JSON),
+    /// such that a resumed simulation continues the same noise realization
+    /// (see [`Settings::resumed`]). A noise schedule (if any) is saved as the
+    /// attributes `noise_schedule_time` and `noise_schedule_noise`, and the
+    /// [`PhysicalUnits`](crate::PhysicalUnits) of the parameters (if any) as
+    /// `frequency` (in Hz) and `reference_pressure` (in Pa), with the mean
+    /// Lyapunov exponent also saved in 1/s as `mean_exponent_per_second`.
+    /// The wall-clock time of the simulation (if set with
+    /// [`SaveData::set_run_time`]) is saved in seconds as `run_time`, which
+    /// is the time of the last part of a continued simulation.
+    ///
+    /// The results are written to a temporary copy of the HDF5 file, which
+    /// replaces the file when all the results are written, such that a crash
+    /// while saving leaves the previous results intact.
+    #[cfg(feature = "hdf5")]
+    pub fn save(&mut self) -> hdf5::Result<()> {
+        #[cfg(feature = "npz")]
+        if observers::npz::is_npz(self.observer.save_info().get_path()) {
+            return self.save_npz().map_err(|e| e.to_string().into());
+        }
+
+        let save_info = self.observer.save_info();
+        let result = observers::write_atomically(&save_info, |temporary| {
+            self.observer.set_save_info(temporary);
+            self.save_hdf5(temporary)
+        });
+        self.observer.set_save_info(&save_info);
+
+        result
+    }
+
+    /// Save the results, retrying failed attempts, and rescue them to a local file if all attempts fail.
+    ///
+    /// The save (see [`SaveData::save`]) is retried as set by `policy`. If
+    /// the results still cannot be saved, e.g. because the shared filesystem
+    /// is unavailable, they are saved to the same group of a file of the
+    /// same format in the local temporary directory (see [`rescue_path`]),
+    /// instead of being lost. If that fails as well, the results are written
+    /// as JSON (see [`SaveData::to_bytes`]) next to it, with the extension
+    /// `.json`. Returns an error only if all of these fail.
+    #[cfg(feature = "hdf5")]
+    pub fn save_or_rescue(&mut self, policy: &RetryPolicy) -> hdf5::Result<SaveOutcome> {
+        let error = match policy.run(|| self.save()) {
+            Ok((_, failed_attempts)) => return Ok(SaveOutcome::Saved { failed_attempts }),
+            Err(e) => e,
+        };
+
+        let save_info = self.observer.save_info();
+        let path = rescue_path(save_info.get_path());
+        let mut rescue = SaveInfo::new(&path, save_info.get_group());
+        rescue.set_create_directories(true);
+        self.observer.set_save_info(&rescue);
+        let result = self.save();
+        self.observer.set_save_info(&save_info);
+
+        if result.is_ok() {
+            return Ok(SaveOutcome::Rescued {
+                path,
+                error: error.to_string(),
+            });
+        }
+
+        // Without a working backend, keep the results as JSON
+        let json_path = path.with_extension("json");
+        let written = std::fs::create_dir_all(json_path.parent().unwrap_or(&path))
+            .and_then(|_| std::fs::write(&json_path, self.to_bytes()));
+        match written {
+            Ok(_) => Ok(SaveOutcome::Rescued {
+                path: json_path,
+                error: error.to_string(),
+            }),
+            Err(e) => Err(format!(
+                "{}, and the rescue to {} failed too: {}",
+                error,
+                json_path.to_string_lossy(),
+                e
+            )
+            .into()),
+        }
+    }
+
+    /// Save the results to the HDF5 file of `save_info`, which is also the save information of the observer.
+    #[cfg(feature = "hdf5")]
+    fn save_hdf5(&self, save_info: &SaveInfo) -> hdf5::Result<()> {
         self.observer
-            .save(&self.parameters, &self.describing_function)
+            .save(&self.parameters, &self.describing_function)?;
+
+        // Make the results reproducible from the file alone
+        let file = hdf5::File::append(save_info.get_path())?;
+        let group = file.group(save_info.get_group())?;
+        // A continued group keeps the settings of its first simulation
+        if group.attr("settings").is_err() {
+            observers::save_str_attr(&group, &self.settings_json, "settings")?;
+            observers::save_attr(&group, self.parameters.get_thetas(), "thetas")?;
+            if let Some(seed) = self.seed {
+                observers::save_attr(&group, &ndarray::arr0(seed), "seed")?;
+            }
+            if let Some(sequence) = &self.seed_sequence {
+                let master_seed = ndarray::arr0(sequence.get_master_seed());
+                observers::save_attr(&group, &master_seed, "master_seed")?;
+                observers::save_attr(&group, sequence.get_spawn_key(), "seed_spawn_key")?;
+            }
+            if let Some(schedule) = &self.noise_schedule {
+                let (time, noise) = schedule.columns();
+                observers::save_attr(&group, &time, "noise_schedule_time")?;
+                observers::save_attr(&group, &noise, "noise_schedule_noise")?;
+            }
+            if let Some(units) = self.parameters.get_physical_units() {
+                observers::save_attr(&group, &ndarray::arr0(units.frequency), "frequency")?;
+                if let Some(pressure) = units.reference_pressure {
+                    observers::save_attr(&group, &ndarray::arr0(pressure), "reference_pressure")?;
+                }
+            }
+        }
+
+        let rng_checkpoint = serde_json::to_string(&self.rng_checkpoint).unwrap_or_default();
+        observers::replace_str_attr(&group, &rng_checkpoint, "rng_checkpoint")?;
+        if let Some(run_time) = self.run_time {
+            observers::replace_scalar_attr(&group, run_time, "run_time")?;
+        }
+
+        if let Some(time) = self.interrupted_at {
+            let file = hdf5::File::append(save_info.get_path())?;
+            let group = file.group(save_info.get_group())?;
+            observers::replace_scalar_attr(&group, true, "partial")?;
+            observers::replace_scalar_attr(&group, time, "interrupted_at")?;
+        } else if group.attr("partial").is_ok() {
+            // A resumed simulation completed the group
+            observers::replace_scalar_attr(&group, false, "partial")?;
+        }
+
+        // Only the exponents of the first simulation are kept in a continued group
+        if let Some(exponents) = self
+            .lyapunov_exponents
+            .as_ref()
+            .filter(|_| !group.link_exists("lyapunov"))
+        {
+            let file = hdf5::File::append(save_info.get_path())?;
+            let group = file
+                .group(save_info.get_group())?
+                .create_group("lyapunov")?;
+            observers::write_dataset(&group, &exponents.time, "time")?;
+            observers::write_dataset(&group, &exponents.exponents, "exponents")?;
+            observers::save_attr(&group, &ndarray::arr0(exponents.mean()), "mean_exponent")?;
+            if let Some(units) = self.parameters.get_physical_units() {
+                let per_second = ndarray::arr0(units.per_second(exponents.mean()));
+                observers::save_attr(&group, &per_second, "mean_exponent_per_second")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Save the results to an `.npz` file, see [`Observer::save_npz`].
+    ///
+    /// The complete `settings` (as JSON) and the burner locations `thetas`
+    /// are always added to the group, and the `seed`, `master_seed`,
+    /// `seed_spawn_key`, `noise_schedule_time`, `noise_schedule_noise`,
+    /// `frequency`, `reference_pressure`, and `run_time` when given as in
+    /// [`SaveData::save`]. The `partial` and `interrupted_at` scalars are
+    /// added to the group if the simulation was interrupted, the
+    /// `lyapunov_*` arrays if the finite-time Lyapunov exponents were
+    /// estimated, the `broken_symmetry` description if the flames break the
+    /// rotational symmetry, and the `burners_disabled` indices if any burners
+    /// are disabled.
+    #[cfg(feature = "npz")]
+    pub fn save_npz(&self) -> std::io::Result<()> {
+        self.observer
+            .save_npz(&self.parameters, &self.describing_function)?;
+
+        let mut writer = observers::npz::NpzWriter::open(&self.observer.save_info())?;
+        writer.write_str("settings", &self.settings_json)?;
+        writer.write("thetas", self.parameters.get_thetas())?;
+        if let Some(seed) = self.seed {
+            writer.write_scalar("seed", seed)?;
+        }
+        if let Some(sequence) = &self.seed_sequence {
+            writer.write_scalar("master_seed", sequence.get_master_seed())?;
+            writer.write("seed_spawn_key", sequence.get_spawn_key())?;
+        }
+        if let Some(run_time) = self.run_time {
+            writer.write_scalar("run_time", run_time)?;
+        }
+        if let Some(schedule) = &self.noise_schedule {
+            let (time, noise) = schedule.columns();
+            writer.write("noise_schedule_time", &time)?;
+            writer.write("noise_schedule_noise", &noise)?;
+        }
+        if let Some(units) = self.parameters.get_physical_units() {
+            writer.write_scalar("frequency", units.frequency)?;
+            if let Some(pressure) = units.reference_pressure {
+                writer.write_scalar("reference_pressure", pressure)?;
+            }
+        }
+        if let Some(description) = self.parameters.broken_symmetry() {
+            writer.write_str("broken_symmetry", &description)?;
+        }
+        let burners_disabled = self.parameters.get_burners_disabled();
+        if !burners_disabled.is_empty() {
+            let burners: Vec<usize> = burners_disabled.iter().map(|&ind| ind as usize).collect();
+            writer.write("burners_disabled", &burners)?;
+        }
+        writer.finish()?;
+
+        if let Some(time) = self.interrupted_at {
+            let mut writer = observers::npz::NpzWriter::open(&self.observer.save_info())?;
+            writer.write_scalar("partial", true)?;
+            writer.write_scalar("interrupted_at", time)?;
+            writer.finish()?;
+        }
+
+        if let Some(exponents) = &self.lyapunov_exponents {
+            let mut writer = observers::npz::NpzWriter::open(&self.observer.save_info())?;
+            writer.write("lyapunov_time", &exponents.time)?;
+            writer.write("lyapunov_exponents", &exponents.exponents)?;
+            writer.write_scalar("lyapunov_mean_exponent", exponents.mean())?;
+            if let Some(units) = self.parameters.get_physical_units() {
+                let per_second = units.per_second(exponents.mean());
+                writer.write_scalar("lyapunov_mean_exponent_per_second", per_second)?;
+            }
+            writer.finish()?;
+        }
+
+        Ok(())
+    }
+
+    /// Check whether the simulation was stopped before the end.
+    pub fn is_partial(&self) -> bool {
+        self.interrupted_at.is_some()
+    }
+
+    /// Get the time at which the mode diverged to a non-finite state, if it did.
+    pub fn get_diverged_at(&self) -> Option<Float> {
+        self.diverged_at
+    }
+
+    /// Get the finite-time Lyapunov exponents, if they were estimated.
+    pub fn get_lyapunov_exponents(&self) -> Option<&FiniteTimeExponents> {
+        self.lyapunov_exponents.as_ref()
     }
 
     pub fn get_save_info(&self) -> SaveInfo {
         self.observer.save_info()
     }
+
+    /// Summary of the simulation as JSON, e.g. for workflow managers.
+    ///
+    /// Lists where the results are saved, the seed, the wall-clock run time
+    /// in seconds, whether and when the simulation was interrupted, when the
+    /// mode diverged (if it did), and the mean finite-time Lyapunov exponent,
+    /// if estimated.
+    pub fn summary_json(&self) -> serde_json::Value {
+        let save_info = self.get_save_info();
+
+        serde_json::json!({
+            "path": save_info.get_path().to_string_lossy(),
+            "group": save_info.get_group(),
+            "seed": self.seed,
+            "run_time": self.run_time,
+            "partial": self.is_partial(),
+            "interrupted_at": self.interrupted_at,
+            "diverged_at": self.diverged_at,
+            "mean_lyapunov_exponent": self.lyapunov_exponents.as_ref().map(|e| e.mean()),
+        })
+    }
+
+    /// Complete results as JSON, to keep them by other means if they cannot be saved.
+    ///
+    /// Contains the `settings`, `thetas`, `seed`, `master_seed`,
+    /// `seed_spawn_key`, `run_time`, `partial`, and `interrupted_at` as saved
+    /// by [`SaveData::save`], the finite-time Lyapunov exponents (if
+    /// estimated) as `lyapunov`, and the data of the observer as `results`
+    /// (see [`ObserverTrait::results_json`]). Unlike saving, this cannot fail.
+    pub fn to_json(&self) -> serde_json::Value {
+        let settings: serde_json::Value =
+            serde_json::from_str(&self.settings_json).unwrap_or_default();
+        let lyapunov = self.lyapunov_exponents.as_ref().map(|exponents| {
+            serde_json::json!({
+                "time": exponents.time,
+                "exponents": exponents.exponents,
+                "mean_exponent": exponents.mean(),
+            })
+        });
+
+        serde_json::json!({
+            "group": self.get_save_info().get_group(),
+            "settings": settings,
+            "thetas": self.parameters.get_thetas(),
+            "seed": self.seed,
+            "master_seed": self.seed_sequence.as_ref().map(|s| s.get_master_seed()),
+            "seed_spawn_key": self.seed_sequence.as_ref().map(|s| s.get_spawn_key()),
+            "run_time": self.run_time,
+            "partial": self.is_partial(),
+            "interrupted_at": self.interrupted_at,
+            "lyapunov": lyapunov,
+            "results": self.observer.results_json(&self.describing_function),
+        })
+    }
+
+    /// Complete results as the bytes of their compact JSON, see [`SaveData::to_json`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(&self.to_json()).unwrap_or_default()
+    }
+
+    pub fn get_parameters(&self) -> &Parameters {
+        &self.parameters
+    }
+
+    /// Set the wall-clock time of the simulation (in seconds), saved with the results.
+    pub fn set_run_time(&mut self, seconds: Float) {
+        self.run_time = Some(seconds);
+    }
 }
 
 /// All the settings of the simulation.
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Settings {
     pub parameters: Parameters,
     pub saturation: Saturation,
     pub observer: Observer,
     pub describing_function: DescribingFunction,
 
+    /// Scheme used to advance the mode by one time step.
+    #[serde(default)]
+    pub integrator: Integrator,
+
+    /// Representation of the mode in which it is advanced by one time step.
+    #[serde(default)]
+    pub state_representation: StateRepresentation,
+
+    /// Optional exponential tilting of the noise for importance sampling.
+    #[serde(default)]
+    pub importance_sampling: Option<ImportanceSampling>,
+
+    /// Optional compound Poisson jumps of the acoustic state, besides the Gaussian noise.
+    #[serde(default)]
+    pub jump_noise: Option<JumpNoise>,
+
+    /// Optional twin trajectory for estimating finite-time Lyapunov exponents.
+    #[serde(default)]
+    pub lyapunov: Option<Lyapunov>,
+
+    /// Optional ramp of a parameter up and back down over the simulation.
+    #[serde(default)]
+    pub ramp: Option<ParameterRamp>,
+
+    /// Optional schedule of the noise intensity, overriding the noise of the parameters.
+    #[serde(default)]
+    pub noise_schedule: Option<NoiseSchedule>,
+
+    /// Number of parallel tasks the burner loops of each time step are split into.
+    ///
+    /// For simulations with many burners (requires the `parallel` feature).
+    /// The tasks run on the thread pool shared with the other simulations of
+    /// a batch, such that a large simulation takes over the threads left idle
+    /// by the small ones.
+    #[serde(default)]
+    pub burner_tasks: Option<usize>,
+
+    /// Number of independent realizations of the simulation.
+    #[serde(default = "default_repetitions")]
+    pub repetitions: usize,
+
+    /// How the random initial modes of the repetitions are drawn.
+    #[serde(default)]
+    pub initial_mode_sampling: InitialModeSampling,
+
+    /// Seed of the random number generator (drawn from the system if `None`).
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    /// How the standard normal samples of the noise are drawn.
+    #[serde(default)]
+    pub normal_sampling: NormalSampling,
+
+    /// Master seed and spawn key the seed was derived from (for realizations of an ensemble).
+    #[serde(skip)]
+    pub(crate) seed_sequence: Option<SeedSequence>,
+
+    /// Token used to stop the simulation early.
+    #[serde(skip)]
+    pub cancellation: Option<CancellationToken>,
+
+    /// Number of completed time steps, updated at every save step.
+    #[serde(skip)]
+    pub progress: Option<Arc<AtomicUsize>>,
+
+    /// Callbacks executed at the start, save points, and end of the simulation.
+    #[serde(skip)]
+    pub hooks: SimulationHooks,
+
+    /// Time at which the simulation was stopped early (if it was).
+    #[serde(skip)]
+    pub(crate) interrupted_at: Option<Float>,
+
+    /// Time at which the mode diverged to a non-finite state, stopping the simulation.
+    #[serde(skip)]
+    pub(crate) diverged_at: Option<Float>,
+
+    /// Time of the initial mode, later than zero when resuming a saved simulation.
+    #[serde(skip)]
+    pub(crate) start_time: Float,
+
+    /// Number of standard normal samples summed for the noise of each time step.
+    #[serde(skip)]
+    pub(crate) noise_substeps: usize,
+
+    /// Finite-time Lyapunov exponents of the last run (if estimated).
+    #[serde(skip)]
+    pub(crate) lyapunov_exponents: Option<FiniteTimeExponents>,
+
     #[serde(skip)]
     pub rng: RNG,
 }
 
+/// Format a number of bytes with a decimal (SI) prefix.
+fn format_bytes(bytes: usize) -> String {
+    let mut value = bytes as Float;
+    for unit in ["B", "kB", "MB", "GB"] {
+        if value < 1000.0 {
+            return format!("{:.1} {}", value, unit);
+        }
+        value /= 1000.0;
+    }
+
+    format!("{:.1} TB", value)
+}
+
+fn default_repetitions() -> usize {
+    1
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::new(
+            Parameters::default(),
+            Saturation::default(),
+            Observer::default(),
+            DescribingFunction::default(),
+        )
+    }
+}
+
 impl Clone for Settings {
     /// Clone everything except the RNG state(!)
+    ///
+    /// If the settings are seeded, the RNG of the clone restarts from the seed.
     fn clone(&self) -> Self {
         let parameters = self.parameters.clone();
         let saturation = self.saturation.clone();
         let observer = self.observer.clone();
         let describing_function = self.describing_function.clone();
 
-        Self::new(parameters, saturation, observer, describing_function)
+        let mut settings = Self::new(parameters, saturation, observer, describing_function);
+        settings.integrator = self.integrator;
+        settings.state_representation = self.state_representation;
+        settings.importance_sampling = self.importance_sampling;
+        settings.jump_noise = self.jump_noise;
+        settings.lyapunov = self.lyapunov;
+        settings.ramp = self.ramp;
+        settings.noise_schedule = self.noise_schedule.clone();
+        settings.burner_tasks = self.burner_tasks;
+        settings.repetitions = self.repetitions;
+        settings.initial_mode_sampling = self.initial_mode_sampling;
+        settings.normal_sampling = self.normal_sampling;
+        settings.set_seed(self.seed);
+        settings.seed_sequence = self.seed_sequence.clone();
+        settings.cancellation = self.cancellation.clone();
+        settings.hooks = self.hooks.clone();
+
+        settings
     }
 }
 
@@ -79,30 +573,200 @@ impl Settings {
         describing_function: DescribingFunction,
     ) -> Self {
         // Allocate space for the observer
-        observer.reserve(parameters.get_num_steps_to_save());
+        observer.init(&parameters);
 
         Self {
             parameters,
             observer,
             saturation,
             describing_function,
+            integrator: Integrator::default(),
+            state_representation: StateRepresentation::default(),
+            importance_sampling: None,
+            jump_noise: None,
+            lyapunov: None,
+            ramp: None,
+            noise_schedule: None,
+            burner_tasks: None,
+            repetitions: default_repetitions(),
+            initial_mode_sampling: InitialModeSampling::default(),
+            seed: None,
+            seed_sequence: None,
+            normal_sampling: NormalSampling::default(),
+            cancellation: None,
+            progress: None,
+            hooks: SimulationHooks::default(),
+            interrupted_at: None,
+            diverged_at: None,
+            start_time: 0.0,
+            noise_substeps: 1,
+            lyapunov_exponents: None,
             rng: RNG::default(),
         }
     }
 
     pub fn from_file(path: &str) -> Result<Self, Box<dyn Error>> {
-        let buffer = BufReader::new(File::open(path)?);
+        Self::from_template(path, &SettingsTemplate::default())
+    }
+
+    /// Load the settings from a JSON file with the variables and overrides of `template`.
+    ///
+    /// See [`SettingsTemplate`] for the substitution of the placeholders
+    /// `${NAME}` of the file and the overrides of the values.
+    pub fn from_template(path: &str, template: &SettingsTemplate) -> Result<Self, Box<dyn Error>> {
+        let user_settings = Self::deserialize(template.load(path)?)?;
+
+        user_settings.init()
+    }
+
+    /// Load the settings from a JSON file for one of the mode orders of a sweep.
+    ///
+    /// The parameters of the file may list several orders, e.g.
+    /// `"mode_order": [1, 2]`, which are simulated independently with the
+    /// same noise and seed, i.e. under identical forcing. The results of each
+    /// order are saved to the sibling subgroups `n_<order>` of the group given
+    /// in the file, e.g. `group/n_1` and `group/n_2`.
+    pub fn from_file_with_mode_order(path: &str, mode_order: u32) -> Result<Self, Box<dyn Error>> {
+        Self::from_template_with_mode_order(path, &SettingsTemplate::default(), mode_order)
+    }
 
-        let mut user_settings: Self = serde_json::from_reader(buffer)?;
+    /// Load the settings from a JSON file with the variables and overrides of `template`
+    /// for one of the mode orders of a sweep, see [`Settings::from_file_with_mode_order`].
+    pub fn from_template_with_mode_order(
+        path: &str,
+        template: &SettingsTemplate,
+        mode_order: u32,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut json = template.load(path)?;
+        json["parameters"]["mode_order"] = mode_order.into();
+        let mut settings = Self::deserialize(json)?.init()?;
 
-        // Calculate all the values that are not included in the JSON
-        user_settings.parameters.init()?;
+        let mut save_info = settings.observer.save_info();
+        let group = format!("{}/n_{}", save_info.get_group(), mode_order);
+        save_info.set_group(&group);
+        settings.observer.set_save_info(&save_info);
+
+        Ok(settings)
+    }
+
+    /// Load the settings from a JSON string.
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn Error>> {
+        let user_settings: Self = serde_json::from_str(json)?;
+
+        user_settings.init()
+    }
+
+    /// Calculate all the values that are not included in the JSON.
+    fn init(mut self) -> Result<Self, Box<dyn Error>> {
+        self.describing_function.init()?;
+        // The fluctuations of the stochastic describing function belong to the burners
+        if let DescribingFunction::Stochastic(_) = self.describing_function {
+            if self.parameters.get_flame_distribution() != FlameDistribution::Discrete {
+                return Err(Box::new(DescribingFunctionError::ContinuousFlames));
+            }
+        }
+        self.state_representation.check(self.integrator)?;
+        if let Some(jump_noise) = &self.jump_noise {
+            jump_noise.validate()?;
+        }
+        if let Some(importance_sampling) = &self.importance_sampling {
+            importance_sampling.validate()?;
+        }
+        // Check that the ramped parameter (if any) is used by the describing function,
+        // and valid over the whole range
+        if let Some(ramp) = &self.ramp {
+            let mut ramped = self.clone();
+            ramp.parameter.set(&mut ramped, ramp.lower)?;
+            ramp.parameter.set(&mut ramped, ramp.upper)?;
+        }
+        if let Some(schedule) = &self.noise_schedule {
+            schedule.validate()?;
+            if self
+                .ramp
+                .is_some_and(|ramp| ramp.parameter == SensitivityParameter::Noise)
+            {
+                return Err(Box::new(NoiseScheduleError::Ramp));
+            }
+        }
         // Reserve space for the observer
-        user_settings
-            .observer
-            .reserve(user_settings.parameters.get_num_steps_to_save());
+        self.observer.init(&self.parameters);
+        // Seed the random number generator
+        self.set_seed(self.seed);
 
-        Ok(user_settings)
+        Ok(self)
+    }
+
+    /// Use the final state of an earlier simulation as the initial mode.
+    ///
+    /// Reads the last logged mode of the time series saved to `group` in the
+    /// HDF5 file `path`, allowing long simulations to be chained.
+    #[cfg(feature = "hdf5")]
+    pub fn with_initial_state_from(
+        mut self,
+        path: &PathBuf,
+        group: &str,
+    ) -> Result<Self, Box<dyn Error>> {
+        let final_mode = TimeSeriesObserver::read_final_mode(path, group)?;
+        if !final_mode.is_valid() {
+            return Err(Box::new(ParametersError::Mode));
+        }
+
+        self.parameters.set_initial_mode(final_mode);
+
+        Ok(self)
+    }
+
+    /// Continue the time series saved by an earlier (e.g. interrupted) simulation.
+    ///
+    /// If the observer is a [`TimeSeriesObserver`] appending to an existing
+    /// group (see [`SaveInfo::set_append`]), the simulation starts from the
+    /// last saved mode at the last saved time, and its samples extend the
+    /// saved time series. The times the group was resumed at are saved as
+    /// the `resume_points` attribute (a JSON list) of the group. Otherwise,
+    /// the settings are returned unchanged.
+    ///
+    /// If the state of the random number generator was saved at the last
+    /// saved time (the `rng_checkpoint` attribute, see [`SaveData::save`]),
+    /// the generator continues from it, such that the resumed simulation
+    /// continues the noise realization of the interrupted one. Otherwise
+    /// (e.g. for a decimated time series), seeded settings repeat the noise of
+    /// the first simulation, so the seed should be changed between the
+    /// resumed runs.
+    #[cfg(feature = "hdf5")]
+    pub fn resumed(mut self) -> Result<Self, Box<dyn Error>> {
+        let (save_info, reference_angle) = match &self.observer {
+            Observer::TimeSeries(observer) if observer.save_info.get_append() => {
+                (observer.save_info.clone(), observer.logging.reference_angle)
+            }
+            _ => return Ok(self),
+        };
+        if !save_info.group_exists() {
+            return Ok(self);
+        }
+
+        let (path, group) = (save_info.get_path(), save_info.get_group());
+        let mut final_mode = TimeSeriesObserver::read_final_mode(path, group)?;
+        // The orientation angle is saved relative to the reference (if any)
+        if let Some(theta) = reference_angle {
+            final_mode.orientation_angle += self.parameters.get_mode_order() as Float * theta;
+        }
+        if !final_mode.is_valid() {
+            return Err(Box::new(ParametersError::Mode));
+        }
+
+        self.parameters.set_initial_mode(final_mode);
+        self.start_time = TimeSeriesObserver::read_final_time(path, group)?;
+
+        let file = hdf5::File::open(path)?;
+        if let Ok(attr) = file.group(group)?.attr("rng_checkpoint") {
+            let saved = attr.read_scalar::<hdf5::types::VarLenUnicode>()?;
+            let checkpoint: RngCheckpoint = serde_json::from_str(saved.as_str())?;
+            if checkpoint.time == self.start_time {
+                self.rng.set_state(&checkpoint.state);
+            }
+        }
+
+        Ok(self)
     }
 
     pub fn export(&self, path: &PathBuf) -> Result<(), Box<dyn Error>> {
@@ -112,12 +776,46 @@ impl Settings {
         Ok(())
     }
 
+    /// Export the settings like [`Settings::export`], with an additional `derived` section.
+    ///
+    /// The `derived` section lists the quantities calculated from the
+    /// settings when they are loaded (see [`Settings::derived`]), for
+    /// auditing the settings before running them. It is read-only, i.e. it
+    /// is ignored when the file is loaded again.
+    pub fn export_with_derived(&self, path: &PathBuf) -> Result<(), Box<dyn Error>> {
+        let mut json = serde_json::to_value(self)?;
+        json["derived"] = self.derived();
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, &json)?;
+
+        Ok(())
+    }
+
+    /// Quantities derived from the settings, such as the number of time steps and the burner locations.
+    pub fn derived(&self) -> serde_json::Value {
+        let p = &self.parameters;
+        let (memory, disk) = self.observer.estimated_size(p);
+
+        serde_json::json!({
+            "total_steps": p.get_total_steps(),
+            "steps_per_cycle": p.get_steps_per_cycle(),
+            "skip_per_save": p.get_skip_per_save(),
+            "num_steps_to_save": p.get_num_steps_to_save(),
+            "thetas": p.get_thetas(),
+            "flame_weights": p.get_flame_weights(),
+            "timestep_seconds": p.get_timestep_seconds(),
+            "duration_seconds": p.get_duration_seconds(),
+            "estimated_cost": self.estimated_cost(),
+            "estimated_memory_bytes": memory,
+            "estimated_disk_bytes": disk,
+        })
+    }
+
     /// Set the observer.
     pub fn set_observer(&mut self, observer: Observer) {
         self.observer = observer;
 
-        self.observer
-            .reserve(self.parameters.get_num_steps_to_save());
+        self.observer.init(&self.parameters);
     }
 
     /// Set the saturation model.
@@ -125,6 +823,215 @@ impl Settings {
         self.saturation = saturation;
     }
 
+    /// Set the scheme used to advance the mode by one time step.
+    pub fn set_integrator(&mut self, integrator: Integrator) {
+        self.integrator = integrator;
+    }
+
+    /// Set the representation of the mode in which it is advanced by one time step.
+    pub fn set_state_representation(&mut self, state_representation: StateRepresentation) {
+        self.state_representation = state_representation;
+    }
+
+    /// Enable (or disable with `None`) importance sampling of the noise.
+    pub fn set_importance_sampling(&mut self, importance_sampling: Option<ImportanceSampling>) {
+        self.importance_sampling = importance_sampling;
+    }
+
+    /// Enable (or disable with `None`) the compound Poisson jumps of the acoustic state.
+    pub fn set_jump_noise(&mut self, jump_noise: Option<JumpNoise>) {
+        self.jump_noise = jump_noise;
+    }
+
+    /// Enable (or disable with `None`) the estimation of finite-time Lyapunov exponents.
+    pub fn set_lyapunov(&mut self, lyapunov: Option<Lyapunov>) {
+        self.lyapunov = lyapunov;
+    }
+
+    /// Let the noise intensity follow `schedule` (or the noise of the parameters with `None`).
+    ///
+    /// The schedule is in terms of the time of the simulation, i.e. a
+    /// resumed simulation continues the schedule.
+    pub fn set_noise_schedule(&mut self, schedule: Option<NoiseSchedule>) {
+        self.noise_schedule = schedule;
+    }
+
+    /// Ramp (or stop ramping with `None`) a parameter up and back down over the simulation.
+    pub fn set_ramp(&mut self, ramp: Option<ParameterRamp>) {
+        self.ramp = ramp;
+    }
+
+    /// Seed the random number generator (or draw the seed from the system with `None`).
+    ///
+    /// Settings with the same seed use identical noise realizations.
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+        self.seed_sequence = None;
+        self.rng = match seed {
+            Some(seed) => RNG::from_seed(seed),
+            None => RNG::new(),
+        };
+        self.rng.set_normal_sampling(self.normal_sampling);
+    }
+
+    /// Set how the standard normal samples of the noise are drawn.
+    ///
+    /// Batched sampling changes the noise realization of a seed if uniform
+    /// samples are drawn as well (a random initial mode or the stochastic
+    /// describing function), as they are drawn after the whole batch.
+    pub fn set_normal_sampling(&mut self, normal_sampling: NormalSampling) {
+        self.normal_sampling = normal_sampling;
+        self.rng.set_normal_sampling(normal_sampling);
+    }
+
+    /// Seed the random number generator with the seed of `sequence`.
+    ///
+    /// The master seed and spawn key of the sequence are saved with the
+    /// results, such that the realization can be traced back to the seed of
+    /// its ensemble.
+    pub fn set_seed_sequence(&mut self, sequence: SeedSequence) {
+        self.set_seed(Some(sequence.seed()));
+        self.seed_sequence = Some(sequence);
+    }
+
+    /// Get the seed sequence the seed was derived from (if any).
+    pub fn get_seed_sequence(&self) -> Option<&SeedSequence> {
+        self.seed_sequence.as_ref()
+    }
+
+    /// Set the token used to stop the simulation early.
+    pub fn set_cancellation(&mut self, cancellation: Option<CancellationToken>) {
+        self.cancellation = cancellation;
+    }
+
+    /// Get the time at which the last run was stopped early, if it was.
+    pub fn get_interrupted_at(&self) -> Option<Float> {
+        self.interrupted_at
+    }
+
+    /// Get the time at which the mode of the last run diverged, if it did.
+    pub fn get_diverged_at(&self) -> Option<Float> {
+        self.diverged_at
+    }
+
+    /// Get the finite-time Lyapunov exponents of the last run, if they were estimated.
+    pub fn get_lyapunov_exponents(&self) -> Option<&FiniteTimeExponents> {
+        self.lyapunov_exponents.as_ref()
+    }
+
+    /// Time the last run ended at, i.e. of its last completed step.
+    #[cfg(feature = "hdf5")]
+    pub(crate) fn end_time(&self) -> Float {
+        let steps = self.parameters.get_total_steps() as Float;
+        self.interrupted_at
+            .unwrap_or(self.start_time + steps * self.parameters.get_timestep())
+    }
+
+    /// Relative cost of running the simulation, for scheduling batches of jobs.
+    ///
+    /// The number of time steps times the number of burners, which the
+    /// heat release rate integral of each step scales with, doubled by the
+    /// twin trajectory of the finite-time Lyapunov exponents (if estimated).
+    pub fn estimated_cost(&self) -> Float {
+        let steps = self.parameters.get_total_steps() as Float;
+        let burners = self.parameters.get_number_of_burners() as Float;
+        let trajectories = if self.lyapunov.is_some() { 2.0 } else { 1.0 };
+
+        steps * burners * trajectories
+    }
+
+    /// Human readable summary of the key settings of the simulation.
+    ///
+    /// Includes the derived number of time steps, where the results are saved,
+    /// and an estimate of the memory and disk usage of the logged values.
+    pub fn summary(&self) -> String {
+        let p = &self.parameters;
+        let (memory, disk) = self.observer.estimated_size(p);
+        let seed = match self.seed {
+            Some(seed) => seed.to_string(),
+            None => "random".to_owned(),
+        };
+
+        format!(
+            "Parameters:  damping = {:.4}, gain = {:.4}, noise = {:.4}\n\
+             Geometry:    mode order {}, {}\n\
+             Time:        {} cycles with timestep {:e} ({} steps, {} logged)\n\
+             Model:       {} describing function, saturation {}\n\
+             Observer:    {}, {}\n\
+             Runs:        {} repetition(s), seed {}\n\
+             Estimated:   {} memory, {} on disk",
+            p.get_damping(),
+            p.get_gain(),
+            p.get_noise(),
+            p.get_mode_order(),
+            match p.get_flame_distribution() {
+                FlameDistribution::Discrete => match p.get_burners_disabled().len() {
+                    0 => format!("{} burners", p.get_number_of_burners()),
+                    disabled => format!(
+                        "{} burners ({} disabled)",
+                        p.get_number_of_burners(),
+                        disabled
+                    ),
+                },
+                FlameDistribution::Continuous { .. } => "continuous flames".to_owned(),
+            },
+            p.get_number_of_cycles(),
+            p.get_timestep(),
+            p.get_total_steps(),
+            self.observer.num_logged(p),
+            self.describing_function.name(),
+            self.saturation,
+            self.observer.name(),
+            self.observer.save_info(),
+            self.repetitions,
+            seed,
+            format_bytes(memory),
+            format_bytes(disk),
+        )
+    }
+
+    /// Turn the settings into a single repetition `index` of a set of realizations.
+    ///
+    /// The results are saved to the subgroup `rep_<index>` of the original group,
+    /// e.g. `group/rep_000`. Seeded settings use the seed of the child `index`
+    /// of their [`SeedSequence`], such that the repetitions remain independent.
+    ///
+    /// With [`InitialModeSampling::Sobol`], a random initial mode is replaced
+    /// by the point `index` of a Sobol sequence over its distribution, which
+    /// seeded settings shift digitally with their seed. Without a seed, the
+    /// unshifted sequence is used, skipping its first point (the corner of
+    /// the distribution).
+    pub fn set_repetition(&mut self, index: usize) {
+        let mut save_info = self.observer.save_info();
+        let group = format!("{}/rep_{:03}", save_info.get_group(), index);
+        save_info.set_group(&group);
+
+        self.observer.set_save_info(&save_info);
+        self.repetitions = 1;
+        let sequence = self.seed.map(|seed| {
+            self.seed_sequence
+                .clone()
+                .unwrap_or_else(|| SeedSequence::new(seed))
+        });
+
+        if let InitialMode::Random { distribution, .. } = *self.parameters.get_initial_mode() {
+            if self.initial_mode_sampling == InitialModeSampling::Sobol {
+                // All the repetitions share the shift drawn from the seed
+                let (mut shift, point) = match &sequence {
+                    Some(sequence) => (Some(RNG::from_seed(sequence.seed())), index),
+                    None => (None, index + 1),
+                };
+                let sobol = Sobol::new(4, shift.as_mut()).expect("four dimensions are supported");
+                let mode = distribution.sobol_point(&sobol, point as u64);
+                self.parameters.set_initial_mode(mode);
+            }
+        }
+
+        if let Some(sequence) = sequence {
+            self.set_seed_sequence(sequence.child(index as u64));
+        }
+    }
+
     /// Set the time step.
     pub fn set_timestep(&mut self, dt: Float) -> Result<(), ParametersError> {
         self.parameters.set_timestep(dt)
@@ -138,34 +1045,193 @@ impl Settings {
 
 impl std::fmt::Display for Settings {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        write!(f, "{}", self.summary())
     }
 }
 
+/// How an [`RNG`] draws its standard normal samples.
+///
+/// Either way, the samples are drawn with the Ziggurat method of
+/// [`rand_distr::StandardNormal`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum NormalSampling {
+    /// Draw each sample when it is needed.
+    #[default]
+    PerSample,
+    /// Draw the samples in batches of the given size, kept in a buffer.
+    ///
+    /// Saves the call overhead of each sample, and gives the same normal
+    /// samples as drawing them one at a time.
+    Batched(usize),
+}
+
+/// State of the stream of an [`RNG`], from which it can be continued exactly.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct RngState {
+    seed: [u8; 32],
+    stream: u64,
+    /// Number of 32-bit words drawn from the stream.
+    word_pos: u128,
+    /// Normal samples drawn in the last batch, but not used yet.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    buffered: Vec<Float>,
+}
+
+/// State of the random number generator at the end of a simulation.
+#[cfg(feature = "hdf5")]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct RngCheckpoint {
+    time: Float,
+    state: RngState,
+}
+
 #[derive(Debug)]
 pub struct RNG {
-    rng: ThreadRng,
+    rng: ChaCha12Rng,
+    antithetic: bool,
+    mirrored: bool,
+    /// Size of the batches of normal samples (0 when drawing them one at a time).
+    batch: usize,
+    buffer: Vec<Float>,
+    /// Index of the next unused sample of the buffer.
+    next: usize,
 }
 
 impl RNG {
     pub fn new() -> Self {
         Self {
-            rng: rand::thread_rng(),
+            rng: ChaCha12Rng::from_entropy(),
+            antithetic: false,
+            mirrored: false,
+            batch: 0,
+            buffer: Vec::new(),
+            next: 0,
         }
     }
 
+    /// Create a reproducible random number generator from `seed`.
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            rng: ChaCha12Rng::seed_from_u64(seed),
+            antithetic: false,
+            mirrored: false,
+            batch: 0,
+            buffer: Vec::new(),
+            next: 0,
+        }
+    }
+
+    /// State of the stream, to continue it later with [`RNG::set_state`].
+    ///
+    /// The antithetic and mirrored flags, and the normal sampling, are not
+    /// part of the state.
+    pub fn get_state(&self) -> RngState {
+        RngState {
+            seed: self.rng.get_seed(),
+            stream: self.rng.get_stream(),
+            word_pos: self.rng.get_word_pos(),
+            buffered: self.buffer[self.next..].to_vec(),
+        }
+    }
+
+    /// Continue the stream from `state`.
+    pub fn set_state(&mut self, state: &RngState) {
+        self.rng = ChaCha12Rng::from_seed(state.seed);
+        self.rng.set_stream(state.stream);
+        self.rng.set_word_pos(state.word_pos);
+        self.buffer = state.buffered.clone();
+        self.next = 0;
+    }
+
+    /// Set how the standard normal samples are drawn.
+    ///
+    /// Samples left in the buffer are still used.
+    pub fn set_normal_sampling(&mut self, normal_sampling: NormalSampling) {
+        self.batch = match normal_sampling {
+            NormalSampling::PerSample => 0,
+            NormalSampling::Batched(batch) => batch,
+        };
+    }
+
+    /// Next standard normal sample of the stream, from the buffer if batched.
+    #[inline]
+    fn next_normal(&mut self) -> Float {
+        if self.next == self.buffer.len() {
+            if self.batch == 0 {
+                return self.rng.sample(StandardNormal);
+            }
+            self.buffer.resize(self.batch, 0.0);
+            for sample in self.buffer.iter_mut() {
+                *sample = self.rng.sample(StandardNormal);
+            }
+            self.next = 0;
+        }
+
+        let sample = self.buffer[self.next];
+        self.next += 1;
+        sample
+    }
+
+    /// Mirror all the samples (antithetic variates).
+    ///
+    /// Normal samples change sign and uniform samples `u` become `1 - u`,
+    /// such that two generators with the same seed produce antithetic pairs.
+    pub fn set_antithetic(&mut self, antithetic: bool) {
+        self.antithetic = antithetic;
+    }
+
+    /// Mirror the noise samples about `theta = 0` (see [`crate::ReflectionTest`]).
+    ///
+    /// The imaginary components `i` and `k` of the quaternion samples, which
+    /// drive the orientation and nature angles, change sign.
+    pub fn set_mirrored(&mut self, mirrored: bool) {
+        self.mirrored = mirrored;
+    }
+
     pub fn get_random(&mut self) -> Quaternion {
-        let real = self.rng.sample(StandardNormal);
-        let imag_i = self.rng.sample(StandardNormal);
-        let imag_j = self.rng.sample(StandardNormal);
-        let imag_k = self.rng.sample(StandardNormal);
+        let real = self.next_normal();
+        let imag_i = self.next_normal();
+        let imag_j = self.next_normal();
+        let imag_k = self.next_normal();
 
-        return Quaternion {
+        let mut sample = Quaternion {
             real,
             imag_i,
             imag_j,
             imag_k,
         };
+        if self.mirrored {
+            sample.imag_i = -sample.imag_i;
+            sample.imag_k = -sample.imag_k;
+        }
+
+        if self.antithetic {
+            sample * -1.0
+        } else {
+            sample
+        }
+    }
+
+    /// Draw a sample from the standard normal distribution.
+    pub fn get_normal(&mut self) -> Float {
+        let sample = self.next_normal();
+
+        if self.antithetic {
+            -sample
+        } else {
+            sample
+        }
+    }
+
+    /// Draw a sample from the uniform distribution on [0, 1).
+    pub fn get_uniform(&mut self) -> Float {
+        let sample: Float = self.rng.gen();
+
+        if self.antithetic {
+            1.0 - sample
+        } else {
+            sample
+        }
     }
 }
 
@@ -174,3 +1240,84 @@ impl Default for RNG {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rng_continues_from_its_state() {
+        let mut rng = RNG::from_seed(3);
+        rng.get_random();
+        rng.get_uniform();
+        let state = rng.get_state();
+        let expected = [rng.get_normal(), rng.get_uniform(), rng.get_normal()];
+
+        let json = serde_json::to_string(&state).unwrap();
+        let mut restored = RNG::new();
+        restored.set_state(&serde_json::from_str(&json).unwrap());
+        let continued = [
+            restored.get_normal(),
+            restored.get_uniform(),
+            restored.get_normal(),
+        ];
+        assert_eq!(continued, expected);
+    }
+
+    #[test]
+    fn batched_normals_match_per_sample_normals() {
+        let mut per_sample = RNG::from_seed(11);
+        let mut batched = RNG::from_seed(11);
+        batched.set_normal_sampling(NormalSampling::Batched(7));
+        let expected: Vec<Float> = (0..20).map(|_| per_sample.get_normal()).collect();
+        let drawn: Vec<Float> = (0..10).map(|_| batched.get_normal()).collect();
+        assert_eq!(drawn, expected[..10]);
+
+        // A checkpoint in the middle of a batch keeps the unused samples
+        let mut restored = RNG::new();
+        restored.set_state(&batched.get_state());
+        let continued: Vec<Float> = (0..10).map(|_| restored.get_normal()).collect();
+        assert_eq!(continued, expected[10..]);
+    }
+
+    #[test]
+    fn derived_section_is_ignored_on_load() {
+        let settings = Settings::default();
+        let path = std::env::temp_dir().join(format!("derived_test_{}.json", std::process::id()));
+        settings.export_with_derived(&path).unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let derived = &json["derived"];
+        assert_eq!(
+            derived["total_steps"],
+            settings.parameters.get_total_steps()
+        );
+        assert_eq!(
+            derived["thetas"].as_array().unwrap().len(),
+            settings.parameters.get_thetas().len()
+        );
+
+        let loaded = Settings::from_file(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.derived(), settings.derived());
+    }
+
+    #[test]
+    fn results_are_serialized_in_memory() {
+        let settings = Settings::default();
+        let num_thetas = settings.parameters.get_thetas().len();
+        let save_data = SaveData::from(settings);
+
+        let json = save_data.to_json();
+        assert_eq!(json["thetas"].as_array().unwrap().len(), num_thetas);
+        assert_eq!(json["partial"], false);
+        assert!(json["settings"]["parameters"].is_object());
+        // The default time series observer has not logged anything yet
+        assert_eq!(json["results"]["time"], serde_json::json!([]));
+        assert!(json["results"].get("poincare_sphere").is_none());
+
+        let bytes: serde_json::Value = serde_json::from_slice(&save_data.to_bytes()).unwrap();
+        assert_eq!(bytes, json);
+    }
+}
@@ -1,91 +1,424 @@
 use crate::azimuthal_mode::SystemMode;
-use crate::hrr_integral::HeatReleaseRate;
-use crate::observers::ObserverTrait;
-use crate::{Float, Quaternion, Settings};
+use crate::hrr_integral::{self, HeatReleaseRate};
+use crate::importance_sampling::LikelihoodRatio;
+use crate::integrator::{self, Integrator, StateRepresentation};
+use crate::lyapunov::LyapunovState;
+use crate::observers::{EnergyBudget, ObserverTrait, PrefilterState};
+use crate::{Float, Quaternion, Settings, SimulationState, PI};
+use std::sync::atomic::Ordering;
+
+/// Individual terms of the right hand side of the discrete equation.
+struct RhsTerms {
+    /// Heat release rate integral (including the damping).
+    hrr_integral: Quaternion,
+    /// Deterministic drift induced by the noise.
+    noise_drift: Quaternion,
+    /// Stochastic forcing (before multiplication by the square root of the time step).
+    noise: Quaternion,
+    /// Fluctuations of the flame response due to the parametric noise (likewise).
+    parametric_noise: Quaternion,
+    /// Noise amplitude relative to the amplitude of the mode.
+    relative_noise: Float,
+}
+
+impl RhsTerms {
+    /// Multiply the deterministic part by dt and the stochastic part by sqrt(dt).
+    #[inline]
+    fn total(&self, dt: Float) -> Quaternion {
+        (self.hrr_integral + self.noise_drift) * dt
+            + (self.noise + self.parametric_noise) * dt.sqrt()
+    }
+
+    /// Decompose the real part of the right hand side into its physical contributions.
+    #[inline]
+    fn energy_budget(&self, damping: Float, dt: Float) -> EnergyBudget {
+        EnergyBudget {
+            gain: self.hrr_integral.real + damping,
+            damping: -damping,
+            noise_drift: self.noise_drift.real,
+            noise: (self.noise.real + self.parametric_noise.real) / dt.sqrt(),
+        }
+    }
+}
+
+/// Detects the crossings of a Poincaré section of the oscillation phase.
+///
+/// The phase of the oscillation is `2 pi t + phi`, where the temporal phase
+/// `phi` of the mode is unwrapped to avoid detecting its jumps of `2 pi`.
+/// The crossing is detected at the first time step after the section.
+struct PhaseSection {
+    section: Float,
+    /// Unwrapped phase of the oscillation.
+    phase: Float,
+    /// Temporal phase of the mode at the previous time step.
+    phi: Float,
+}
+
+impl PhaseSection {
+    fn new(section: Float, mode: &SystemMode) -> Self {
+        Self {
+            section,
+            phase: mode.phi(),
+            phi: mode.phi(),
+        }
+    }
+
+    /// Advance the phase by one time step, and check if the section was crossed.
+    fn crossed(&mut self, mode: &SystemMode, dt: Float) -> bool {
+        // Change of phi wrapped to (-pi, pi]
+        let dphi = mode.phi() - self.phi;
+        let dphi = dphi - 2.0 * PI * (dphi / (2.0 * PI)).round();
+        self.phi = mode.phi();
+
+        let previous = ((self.phase - self.section) / (2.0 * PI)).floor();
+        self.phase += 2.0 * PI * dt + dphi;
+        let current = ((self.phase - self.section) / (2.0 * PI)).floor();
+
+        current != previous
+    }
+}
+
+/// Mode with the orientation angle relative to the `reference` orientation angle.
+#[inline]
+fn relative_orientation(mode: &SystemMode, reference: Float) -> SystemMode {
+    SystemMode {
+        nth0: mode.nth0 - reference,
+        ..*mode
+    }
+}
 
 impl Settings {
     pub fn run(&mut self) {
         // Save the initial mode
-        let initial_mode = SystemMode::from(self.parameters.initial_mode);
+        let initial_mode = self.parameters.resolve_initial_mode(&mut self.rng);
+        let initial_mode = SystemMode::from(initial_mode);
         let initial_hrr_mode = self.describing_function.mode(&initial_mode);
-        self.observer.log(&initial_mode, &initial_hrr_mode, 0.0);
+
+        // Orientation angle of the reference the modes are logged relative to
+        let reference = match self.observer.reference_angle() {
+            Some(theta) => self.parameters.get_mode_order() as Float * theta,
+            None => 0.0,
+        };
+        self.log_gain_coefficients(&initial_mode);
+        self.log_integral_terms(&initial_mode);
+        // Time of the initial mode (later than zero if resuming a saved simulation)
+        let start_time = self.start_time;
+        // The hooks get the same modes as the observer, relative to the reference
+        let logged_mode = relative_orientation(&initial_mode, reference);
+        self.observer.log(
+            &logged_mode,
+            &relative_orientation(&initial_hrr_mode, reference),
+            start_time,
+        );
+        self.hooks.start(&SimulationState {
+            step: 0,
+            time: start_time,
+            mode: &logged_mode,
+        });
 
         // Allocate variables for the mode
         let mut mode = initial_mode;
 
+        // Poincaré section of the oscillation phase (if logging at fixed phases)
+        let mut phase_section = self
+            .observer
+            .phase_section()
+            .map(|section| PhaseSection::new(section, &mode));
+
         // Make some shorthand notation
         let dt = self.parameters.get_timestep();
 
+        // Anti-aliasing filter of the logged mode (if any)
+        let mut prefilter = self
+            .observer
+            .prefilter()
+            .map(|filter| PrefilterState::new(filter, &mode, dt));
+        let log_interval = self.observer.log_interval(&self.parameters);
+
+        // Likelihood ratio of the noise since the start (only used for importance sampling)
+        let mut likelihood_ratio = LikelihoodRatio::default();
+
+        // Twin trajectory for the finite-time Lyapunov exponents (if any)
+        let lyapunov_interval = match &self.lyapunov {
+            Some(lyapunov) => {
+                let steps = lyapunov.interval * self.parameters.get_steps_per_cycle() as Float;
+                (steps.round() as usize).max(1)
+            }
+            None => 1,
+        };
+        let mut lyapunov = self
+            .lyapunov
+            .as_ref()
+            .map(|lyapunov| LyapunovState::new(lyapunov, &mode, lyapunov_interval as Float * dt));
+
+        self.interrupted_at = None;
+        self.diverged_at = None;
+        let mut completed_steps = 0;
+
+        // Duration of the simulation (for ramping a parameter)
+        let duration = self.parameters.get_total_steps() as Float * dt;
+        // Noise of the parameters, restored after following the schedule (if any)
+        let noise = self.parameters.get_noise();
+
         for ind in 1..(self.parameters.get_total_steps() + 1) {
+            // Ramp the parameter (if any) to its value at the start of the step
+            if let Some(ramp) = self.ramp {
+                let value = ramp.value((ind - 1) as Float * dt, duration);
+                // The range is checked to be valid when loading the settings
+                let _ = ramp.parameter.set(self, value);
+            }
+            if let Some(schedule) = &self.noise_schedule {
+                let noise = schedule.noise(start_time + (ind - 1) as Float * dt);
+                self.parameters.set_noise_unchecked(noise);
+            }
+
+            // Advance the internal states of the describing function (if any)
+            self.describing_function
+                .step(&self.parameters, &mut self.rng);
+
             // Find the right hand side of the discrete equation
-            let rhs = self.get_rhs(&mode);
+            let noise = self.draw_noise(&mut likelihood_ratio);
+            let parametric_noise = self.draw_parametric_noise();
+            let rhs_terms = self.get_rhs(&mode, noise, parametric_noise);
 
             // Update the solution
-            self.update_mode(&mut mode, &rhs);
+            self.update_mode(&mut mode, &rhs_terms);
+            let jump = self.draw_jump();
+            if let Some(kick) = &jump {
+                mode = integrator::jump(&mode, kick);
+            }
+            completed_steps = ind;
+
+            // Stop if the mode has diverged, as the rest of the results would not be finite
+            if !mode.is_finite() {
+                let time = start_time + (ind as Float) * dt;
+                eprintln!("Simulation diverged at t = {}", time);
+                self.diverged_at = Some(time);
+                break;
+            }
 
-            // Save the mode at set intervals
+            // Advance the twin trajectory with the same noise (if estimating Lyapunov exponents)
+            if let Some(state) = &mut lyapunov {
+                let twin_rhs_terms = self.get_rhs(&state.twin, noise, parametric_noise);
+                self.update_mode(&mut state.twin, &twin_rhs_terms);
+                if let Some(kick) = &jump {
+                    state.twin = integrator::jump(&state.twin, kick);
+                }
+                if ind % lyapunov_interval == 0 {
+                    state.renormalize(&mode, start_time + (ind as Float) * dt);
+                }
+            }
+            if let Some(filter) = &mut prefilter {
+                filter.push(&mode);
+            }
+
+            // Log the mode at the intervals (or phase section) of the observer
+            let log = match &mut phase_section {
+                Some(section) => section.crossed(&mode, dt),
+                None => (ind % log_interval) == 0,
+            };
+            if log {
+                let budget = rhs_terms.energy_budget(self.parameters.get_damping(), dt);
+                self.observer.log_energy_budget(&budget);
+                self.log_gain_coefficients(&mode);
+                self.log_integral_terms(&mode);
+
+                let time = start_time + (ind as Float) * dt;
+                let logged_mode = match &mut prefilter {
+                    Some(filter) => filter.take(),
+                    None => mode,
+                };
+                let hrr_mode = self.describing_function.mode(&logged_mode);
+                let logged_mode = relative_orientation(&logged_mode, reference);
+                let hrr_mode = relative_orientation(&hrr_mode, reference);
+                if self.importance_sampling.is_some() {
+                    let weight = likelihood_ratio.weight();
+                    self.observer
+                        .log_weighted(&logged_mode, &hrr_mode, time, weight);
+                } else {
+                    self.observer.log(&logged_mode, &hrr_mode, time);
+                }
+                self.hooks.save(&SimulationState {
+                    step: ind,
+                    time,
+                    mode: &logged_mode,
+                });
+            }
+
+            // Report the progress and check for cancellation at the save intervals
             if (ind % self.parameters.get_skip_per_save()) == 0 {
-                let time = (ind as Float) * dt;
-                let hrr_mode = self.describing_function.mode(&mode);
-                self.observer.log(&mode, &hrr_mode, time);
+                if let Some(progress) = &self.progress {
+                    progress.store(ind, Ordering::Relaxed);
+                }
+
+                // Stop cleanly (keeping the results so far) if cancelled
+                if let Some(cancellation) = &self.cancellation {
+                    if cancellation.is_cancelled() {
+                        let time = start_time + (ind as Float) * dt;
+                        eprintln!("Simulation stopped early at t = {}", time);
+                        self.interrupted_at = Some(time);
+                        break;
+                    }
+                }
             }
 
-            // Print progress to user at set intervals
+            // Print progress to user at set intervals, on the standard error
             if (ind % (1000 * self.parameters.get_steps_per_cycle())) == 0 {
-                println!(
+                eprintln!(
                     "{}/{}",
                     ((ind as Float) * dt) as usize,
                     self.parameters.get_number_of_cycles()
                 );
             }
         }
+
+        self.lyapunov_exponents = lyapunov.map(|state| state.exponents);
+        self.parameters.set_noise_unchecked(noise);
+
+        self.hooks.finish(&SimulationState {
+            step: completed_steps,
+            time: start_time + (completed_steps as Float) * dt,
+            mode: &relative_orientation(&mode, reference),
+        });
     }
 
+    /// Pass the Fourier coefficients of the gain distribution to the observer (if needed).
+    fn log_gain_coefficients(&mut self, mode: &SystemMode) {
+        if !self.observer.needs_gain_coefficients() {
+            return;
+        }
+        if let Some(coefficients) = self.describing_function.gain_coefficients(mode, self) {
+            self.observer.log_gain_coefficients(&coefficients);
+        }
+    }
+
+    /// Pass the contributions to the right hand side to the observer (if needed).
+    fn log_integral_terms(&mut self, mode: &SystemMode) {
+        if !self.observer.needs_integral_terms() {
+            return;
+        }
+        if let Some(terms) = self.describing_function.integral_terms(mode, self) {
+            self.observer.log_integral_terms(&terms);
+        }
+    }
+
+    /// Draw the standard normal noise, tilting it if importance sampling is used.
+    ///
+    /// With several noise substeps, the noise is the (normalized) sum of the
+    /// noise of the substeps, i.e. the same Brownian path as a simulation
+    /// with a time step that many times smaller.
     #[inline]
-    fn deterministic_stochastic(&self, mode: &SystemMode) -> Quaternion {
-        let nd_noise_sq = self.parameters.noise.powi(2) / (4.0 * mode.a().powi(2));
-        let real = nd_noise_sq;
-        let imag_i = 0.0;
-        let imag_j = 0.0;
-        let imag_k = nd_noise_sq * mode.tan_2chi;
-
-        Quaternion {
-            real,
-            imag_i,
-            imag_j,
-            imag_k,
+    fn draw_noise(&mut self, likelihood_ratio: &mut LikelihoodRatio) -> Quaternion {
+        let mut noise = self.rng.get_random();
+        if self.noise_substeps > 1 {
+            for _ in 1..self.noise_substeps {
+                noise = noise + self.rng.get_random();
+            }
+            noise = noise / (self.noise_substeps as Float).sqrt();
+        }
+        if let Some(importance_sampling) = &self.importance_sampling {
+            let (tilted_noise, log_ratio) = importance_sampling.tilt(noise);
+            noise = tilted_noise;
+            likelihood_ratio.add(log_ratio);
         }
+
+        noise
     }
 
+    /// Draw the standard normal fluctuation of the gain (zero without parametric noise).
+    ///
+    /// Summed over the noise substeps like [`Settings::draw_noise`]. No
+    /// samples are drawn without parametric noise, such that the noise
+    /// realizations of a seed are unchanged.
     #[inline]
-    fn get_rhs(&mut self, mode: &SystemMode) -> Quaternion {
-        let dt = self.parameters.get_timestep();
+    fn draw_parametric_noise(&mut self) -> Float {
+        if self.parameters.get_parametric_noise() == 0.0 {
+            return 0.0;
+        }
+
+        let sum: Float = (0..self.noise_substeps)
+            .map(|_| self.rng.get_normal())
+            .sum();
+        sum / (self.noise_substeps as Float).sqrt()
+    }
 
+    /// Draw the jumps of the acoustic state within the time step (if any).
+    #[inline]
+    fn draw_jump(&mut self) -> Option<Quaternion> {
+        let jump_noise = self.jump_noise?;
+        jump_noise.draw(self.parameters.get_timestep(), &mut self.rng)
+    }
+
+    /// Terms of the right hand side at `mode`, for the standard normal `noise`
+    /// and fluctuation of the gain `parametric_noise`.
+    ///
+    /// The parametric noise scales the response of the flames (the heat
+    /// release rate integral without the damping) by `1 + sigma_p xi`, i.e.
+    /// in the Itô sense for the logarithm of the amplitude.
+    #[inline]
+    fn get_rhs(&self, mode: &SystemMode, noise: Quaternion, parametric_noise: Float) -> RhsTerms {
         // Calculate the relative noise
-        let relative_noise = self.parameters.noise / (mode.a() * Float::sqrt(2.0));
+        let relative_noise = self.parameters.get_noise() / (mode.a() * Float::sqrt(2.0));
 
         // First, get the deterministic part (without dt)
         let hrr_integral = self.describing_function.integral(mode, self);
-        let rhs_deterministic = hrr_integral + self.deterministic_stochastic(mode);
+        let noise_drift = hrr_integral::noise_drift(mode, &self.parameters);
 
-        // Obtain the stochastic part
-        let rhs_stochastic = self.rng.get_random() * relative_noise;
+        let flame_response =
+            hrr_integral + Quaternion::new(self.parameters.get_damping(), 0.0, 0.0, 0.0);
+        let parametric_noise =
+            flame_response * (self.parameters.get_parametric_noise() * parametric_noise);
 
-        // Now multiply the determninistic part by dt and the stochastic part by sqrt(dt)
-        rhs_deterministic * dt + rhs_stochastic * dt.sqrt()
+        RhsTerms {
+            hrr_integral,
+            noise_drift,
+            noise: noise * relative_noise,
+            parametric_noise,
+            relative_noise,
+        }
     }
 
+    /// Advance the mode by one time step with the integrator of the settings.
     #[inline]
-    fn update_mode(&self, mode: &mut SystemMode, right_hand_side: &Quaternion) {
-        // Introduce some sharthands
-        let rhs = right_hand_side;
-        let chi = mode.chi();
-        let tan_2chi = mode.tan_2chi;
+    fn update_mode(&self, mode: &mut SystemMode, rhs_terms: &RhsTerms) {
+        let dt = self.parameters.get_timestep();
+        let right_hand_side = rhs_terms.total(dt);
+        // The transformed increments are singular at the spinning states
+        if self.state_representation == StateRepresentation::Quaternion
+            || integrator::near_spinning_state(mode)
+        {
+            *mode = integrator::quaternion_step(mode, &right_hand_side);
+            return;
+        }
+
+        let increment = match self.integrator {
+            Integrator::EulerMaruyama => integrator::state_increment(mode, &right_hand_side),
+            Integrator::SemiImplicit => {
+                let jacobian = self.describing_function.jacobian(mode, self);
+                integrator::semi_implicit_increment(mode, &right_hand_side, &jacobian, dt)
+            }
+            Integrator::Milstein => {
+                let increment = integrator::state_increment(mode, &right_hand_side);
+                let correction = integrator::milstein_correction(
+                    mode,
+                    &rhs_terms.noise,
+                    rhs_terms.relative_noise,
+                    dt,
+                );
+                std::array::from_fn(|ind| increment[ind] + correction[ind])
+            }
+        };
+
+        // Fall back to the quaternion step if the increment overflows
+        if increment.iter().any(|i| !i.is_finite()) {
+            *mode = integrator::quaternion_step(mode, &right_hand_side);
+            return;
+        }
 
         // Update the mode
-        mode.ln_a += rhs.real;
-        mode.nth0 += rhs.imag_i - tan_2chi * rhs.imag_j;
-        mode.phi += rhs.imag_j / (2.0 * chi).cos();
-        mode.tan_2chi += -2.0 * rhs.imag_k / (2.0 * chi).cos().powi(2);
+        mode.ln_a += increment[0];
+        mode.nth0 += increment[1];
+        mode.phi += increment[2];
+        mode.tan_2chi += increment[3];
     }
 }
@@ -0,0 +1,148 @@
+//! Sobol low-discrepancy sequences for quasi-Monte Carlo ensembles.
+//!
+//! The first `2^m` points of a Sobol sequence fill the unit cube evenly
+//! (each dimension has exactly one point in each of `2^m` equal intervals),
+//! such that ensemble averages of smooth observables over the points
+//! converge faster than over pseudo-random points. Point `i` is the xor of
+//! the direction numbers `v_k` of the set bits `k` of `i`, computed from the
+//! primitive polynomials and initial numbers of Joe and Kuo
+//! (`new-joe-kuo-6.21201`). A random digital shift (an xor with a random
+//! number in each dimension) keeps the even filling, while making each
+//! point uniformly distributed, such that the averages remain unbiased.
+use crate::{Float, RNG};
+
+/// Number of bits of the points.
+const BITS: usize = 32;
+
+/// Degree `s`, coefficients `a`, and initial direction numbers `m` of dimensions 2 and up.
+const DIRECTIONS: [(usize, u32, &[u32]); 7] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+];
+
+/// Largest number of dimensions supported.
+pub const MAX_DIMENSIONS: usize = DIRECTIONS.len() + 1;
+
+/// Possible errors for [`Sobol`].
+#[derive(Clone, Copy, Debug)]
+pub enum SobolError {
+    Dimensions,
+}
+
+impl std::error::Error for SobolError {}
+
+impl std::fmt::Display for SobolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Dimensions => format!(
+                "the number of dimensions has to be between 1 and {}",
+                MAX_DIMENSIONS
+            ),
+        };
+
+        write!(f, "error in the Sobol sequence: {}", msg)
+    }
+}
+
+/// Sobol sequence in the unit cube, optionally with a random digital shift.
+#[derive(Clone, Debug)]
+pub struct Sobol {
+    /// Direction numbers `v_k` of each dimension.
+    directions: Vec<[u32; BITS]>,
+    shift: Vec<u32>,
+}
+
+impl Sobol {
+    /// Sobol sequence in `dimensions` dimensions, digitally shifted with `rng` if given.
+    ///
+    /// The unshifted sequence starts at the origin.
+    pub fn new(dimensions: usize, rng: Option<&mut RNG>) -> Result<Self, SobolError> {
+        if dimensions == 0 || dimensions > MAX_DIMENSIONS {
+            return Err(SobolError::Dimensions);
+        }
+
+        let mut directions = vec![[0; BITS]; dimensions];
+        // The first dimension is the van der Corput sequence in base 2
+        for (k, v) in directions[0].iter_mut().enumerate() {
+            *v = 1 << (BITS - 1 - k);
+        }
+        for (v, &(s, a, m)) in directions[1..].iter_mut().zip(DIRECTIONS.iter()) {
+            for k in 0..BITS {
+                v[k] = if k < s {
+                    m[k] << (BITS - 1 - k)
+                } else {
+                    let mut next = v[k - s] ^ (v[k - s] >> s);
+                    for j in 1..s {
+                        if (a >> (s - 1 - j)) & 1 == 1 {
+                            next ^= v[k - j];
+                        }
+                    }
+                    next
+                };
+            }
+        }
+
+        let shift = match rng {
+            Some(rng) => (0..dimensions)
+                .map(|_| (rng.get_uniform() * (1u64 << BITS) as Float) as u32)
+                .collect(),
+            None => vec![0; dimensions],
+        };
+
+        Ok(Self { directions, shift })
+    }
+
+    pub fn get_dimensions(&self) -> usize {
+        self.directions.len()
+    }
+
+    /// Point `index` of the sequence (only the lowest 32 bits of the index are used).
+    pub fn point(&self, index: u64) -> Vec<Float> {
+        self.directions
+            .iter()
+            .zip(&self.shift)
+            .map(|(v, &shift)| {
+                let bits = (0..BITS)
+                    .filter(|&k| (index >> k) & 1 == 1)
+                    .fold(shift, |x, k| x ^ v[k]);
+                (bits as Float + 0.5) / (1u64 << BITS) as Float
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_fill_the_strata() {
+        let mut rng = RNG::from_seed(2);
+        for sobol in [
+            Sobol::new(MAX_DIMENSIONS, None).unwrap(),
+            Sobol::new(MAX_DIMENSIONS, Some(&mut rng)).unwrap(),
+        ] {
+            let n = 64;
+            let points: Vec<Vec<Float>> = (0..n).map(|ind| sobol.point(ind)).collect();
+            for dim in 0..MAX_DIMENSIONS {
+                let mut strata: Vec<usize> = points
+                    .iter()
+                    .map(|p| (p[dim] * n as Float) as usize)
+                    .collect();
+                strata.sort();
+                assert_eq!(strata, (0..n as usize).collect::<Vec<_>>());
+            }
+        }
+
+        assert_eq!(
+            Sobol::new(2, None).unwrap().point(3)[1],
+            0.25 + 0.5 / 4294967296.0
+        );
+        assert!(Sobol::new(MAX_DIMENSIONS + 1, None).is_err());
+    }
+}
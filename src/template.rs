@@ -0,0 +1,210 @@
+//! Settings files as templates, parameterized from the command line or environment.
+//!
+//! A settings file may contain placeholders `${NAME}`, which are replaced
+//! by the value of the variable `NAME` before the JSON is parsed. The value
+//! is inserted as is, such that a placeholder can stand for a number
+//! (`"gain": ${GAIN}`) or for part of a string (`"group": "gain_${GAIN}"`).
+//! The variables are given as `NAME=value` assignments (e.g. the `--set`
+//! option of the binary), falling back to the environment variables, and a
+//! placeholder of an undefined variable is an error.
+//!
+//! The assignments whose names are not used as placeholders are overrides of
+//! the parsed settings instead, with the name being the path of the value in
+//! the JSON separated by dots, e.g. `parameters.gain=0.06`. The value of an
+//! override is parsed as JSON, and is used as a string if it is not valid
+//! JSON, e.g. `observer.TimeSeries.save_info.group=run_1`.
+use std::error::Error;
+use std::io::Read;
+use std::sync::OnceLock;
+
+use serde_json::{Map, Value};
+
+/// Path of a settings file standing for the standard input, e.g. `--settings-files -`.
+pub const STDIN_PATH: &str = "-";
+
+/// Possible errors for [`SettingsTemplate`].
+#[derive(Clone, Debug)]
+pub enum TemplateError {
+    Assignment(String),
+    Unterminated(String),
+    Undefined(String),
+    Override(String),
+}
+
+impl std::error::Error for TemplateError {}
+
+impl std::fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Assignment(assignment) => format!(
+                "'{}' is not an assignment of the form KEY=VALUE",
+                assignment
+            ),
+            Self::Unterminated(text) => format!("the placeholder '{}' is not closed", text),
+            Self::Undefined(name) => format!(
+                "the variable '{}' is neither set nor an environment variable",
+                name
+            ),
+            Self::Override(path) => format!(
+                "cannot override '{}', which is inside a value that is not an object",
+                path
+            ),
+        };
+
+        write!(f, "error in the settings template: {}", msg)
+    }
+}
+
+/// Variables and overrides applied to settings files when they are loaded.
+#[derive(Clone, Debug, Default)]
+pub struct SettingsTemplate {
+    assignments: Vec<(String, String)>,
+}
+
+impl SettingsTemplate {
+    /// Template with the assignments `KEY=VALUE` in `assignments`.
+    pub fn from_assignments(assignments: &[String]) -> Result<Self, TemplateError> {
+        let mut template = Self::default();
+        for assignment in assignments {
+            let (key, value) = assignment
+                .split_once('=')
+                .filter(|(key, _)| !key.trim().is_empty())
+                .ok_or_else(|| TemplateError::Assignment(assignment.to_owned()))?;
+            template.set(key.trim(), value);
+        }
+
+        Ok(template)
+    }
+
+    /// Assign `value` to `key`, replacing an earlier assignment.
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.assignments.retain(|(k, _)| k != key);
+        self.assignments.push((key.to_owned(), value.to_owned()));
+    }
+
+    /// Read the settings file `path` and return its JSON after the substitutions and overrides.
+    ///
+    /// The settings are read from the standard input if `path` is [`STDIN_PATH`].
+    pub fn load(&self, path: &str) -> Result<Value, Box<dyn Error>> {
+        self.render(&read_settings(path)?)
+    }
+
+    /// Parse the settings `text` after the substitutions, and apply the overrides.
+    pub fn render(&self, text: &str) -> Result<Value, Box<dyn Error>> {
+        let (text, used) = self.substitute(text)?;
+        let mut json: Value = serde_json::from_str(&text)?;
+        for (key, value) in self.assignments.iter().filter(|(k, _)| !used.contains(k)) {
+            set_path(&mut json, key, value)?;
+        }
+
+        Ok(json)
+    }
+
+    /// Replace the placeholders of `text`, and return the names of the variables that were used.
+    fn substitute(&self, text: &str) -> Result<(String, Vec<String>), TemplateError> {
+        let mut result = String::with_capacity(text.len());
+        let mut used = Vec::new();
+        let mut rest = text;
+        while let Some(start) = rest.find("${") {
+            result.push_str(&rest[..start]);
+            let placeholder = &rest[start..];
+            let end = placeholder
+                .find('}')
+                .ok_or_else(|| TemplateError::Unterminated(preview(placeholder)))?;
+            let name = placeholder[2..end].trim();
+            let value = match self.assignments.iter().find(|(k, _)| k == name) {
+                Some((_, value)) => {
+                    used.push(name.to_owned());
+                    value.to_owned()
+                }
+                None => {
+                    std::env::var(name).map_err(|_| TemplateError::Undefined(name.to_owned()))?
+                }
+            };
+            result.push_str(&value);
+            rest = &placeholder[end + 1..];
+        }
+        result.push_str(rest);
+
+        Ok((result, used))
+    }
+}
+
+/// Contents of the settings file `path`, or of the standard input for [`STDIN_PATH`].
+///
+/// The standard input is only read once, and its contents are reused when
+/// the settings are loaded again (e.g. for each of their repetitions).
+fn read_settings(path: &str) -> std::io::Result<String> {
+    static STDIN: OnceLock<String> = OnceLock::new();
+
+    if path != STDIN_PATH {
+        return std::fs::read_to_string(path);
+    }
+    if let Some(text) = STDIN.get() {
+        return Ok(text.clone());
+    }
+    let mut text = String::new();
+    std::io::stdin().read_to_string(&mut text)?;
+
+    Ok(STDIN.get_or_init(|| text).clone())
+}
+
+/// Set the value at the dot separated `path` of `json` to `value`, creating the missing objects.
+fn set_path(json: &mut Value, path: &str, value: &str) -> Result<(), TemplateError> {
+    let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_owned()));
+    let mut current = json;
+    for key in path.split('.') {
+        if current.is_null() {
+            *current = Value::Object(Map::new());
+        }
+        current = current
+            .as_object_mut()
+            .ok_or_else(|| TemplateError::Override(path.to_owned()))?
+            .entry(key)
+            .or_insert(Value::Null);
+    }
+    *current = value;
+
+    Ok(())
+}
+
+/// Beginning of `text` for error messages.
+fn preview(text: &str) -> String {
+    text.chars().take(20).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_and_overrides_are_applied() {
+        let template = SettingsTemplate::from_assignments(&[
+            "GAIN=0.06".to_owned(),
+            "parameters.noise=0.01".to_owned(),
+            "observer.TimeSeries.save_info.group=run_1".to_owned(),
+        ])
+        .unwrap();
+        let json = template
+            .render(r#"{"parameters": {"gain": ${GAIN}}, "label": "gain_${ GAIN }"}"#)
+            .unwrap();
+        assert_eq!(json["parameters"]["gain"], 0.06);
+        assert_eq!(json["label"], "gain_0.06");
+        // GAIN is used as a variable, not as an override
+        assert!(json.get("GAIN").is_none());
+        assert_eq!(json["parameters"]["noise"], 0.01);
+        assert_eq!(
+            json["observer"]["TimeSeries"]["save_info"]["group"],
+            "run_1"
+        );
+
+        let empty = SettingsTemplate::default();
+        assert!(empty
+            .render(r#"{"gain": ${AZIMUTHAL_FDF_UNDEFINED_VARIABLE}}"#)
+            .is_err());
+        assert!(empty.render(r#"{"gain": ${GAIN"#).is_err());
+        assert!(SettingsTemplate::from_assignments(&["GAIN".to_owned()]).is_err());
+        let nested = SettingsTemplate::from_assignments(&["gain.value=1".to_owned()]).unwrap();
+        assert!(nested.render(r#"{"gain": 0.5}"#).is_err());
+    }
+}
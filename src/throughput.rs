@@ -0,0 +1,142 @@
+//! Measurement of the simulation throughput of the current machine.
+//!
+//! A short standardized simulation (the default settings with a fixed seed,
+//! shortened to [`THROUGHPUT_STEPS`] time steps) is run as several
+//! simultaneous copies, one per thread, and timed. The measured time steps
+//! per second, relative to the [`Settings::estimated_cost`] of the
+//! standardized simulation, give the expected wall-clock time of planned
+//! simulations on the same machine and number of threads.
+use std::time::Instant;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::{Float, Settings};
+
+/// Number of time steps of the standardized simulation.
+pub const THROUGHPUT_STEPS: usize = 1_000_000;
+
+/// Seed of the standardized simulation.
+const THROUGHPUT_SEED: u64 = 2023;
+
+/// Timing of simultaneous standardized simulations.
+#[derive(Clone, Copy, Debug)]
+pub struct Throughput {
+    /// Number of simulations run at the same time.
+    pub simulations: usize,
+    /// Time steps of each simulation.
+    pub steps: usize,
+    /// Estimated cost of each simulation (see [`Settings::estimated_cost`]).
+    pub cost: Float,
+    /// Wall-clock time until all the simulations finished, in seconds.
+    pub elapsed: Float,
+}
+
+impl Throughput {
+    /// Time `simulations` standardized simulations run at the same time
+    /// (in parallel with the `parallel` feature, one after another otherwise).
+    pub fn measure(simulations: usize) -> Self {
+        let simulations = simulations.max(1);
+        let settings = standardized_settings();
+        let steps = settings.parameters.get_total_steps();
+        let cost = settings.estimated_cost();
+
+        let start = Instant::now();
+        #[cfg(feature = "parallel")]
+        let copies = (0..simulations).into_par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let copies = 0..simulations;
+        copies.for_each(|_| {
+            // The RNG is created inside each thread
+            let mut copy = standardized_settings();
+            copy.run();
+        });
+        let elapsed = start.elapsed().as_secs_f64();
+
+        Self {
+            simulations,
+            steps,
+            cost,
+            elapsed,
+        }
+    }
+
+    /// Time steps per second of all the simulations together.
+    pub fn steps_per_second(&self) -> Float {
+        (self.simulations * self.steps) as Float / self.elapsed
+    }
+
+    /// Time steps per second of each of the simultaneous simulations.
+    pub fn steps_per_second_per_simulation(&self) -> Float {
+        self.steps as Float / self.elapsed
+    }
+
+    /// Estimated wall-clock time (in seconds) of `settings`, run alongside as many other simulations.
+    pub fn estimated_seconds(&self, settings: &Settings) -> Float {
+        settings.estimated_cost() / self.cost * self.elapsed
+    }
+
+    /// Estimated wall-clock time (in seconds) of running all of `settings`, as many at a time.
+    pub fn estimated_campaign_seconds<'a>(
+        &self,
+        settings: impl IntoIterator<Item = &'a Settings>,
+    ) -> Float {
+        let cost: Float = settings.into_iter().map(Settings::estimated_cost).sum();
+        cost / (self.cost * self.simulations as Float) * self.elapsed
+    }
+}
+
+impl std::fmt::Display for Throughput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} simulation(s) of {} steps in {:.2} s: {:.3e} steps/s in total, {:.3e} steps/s per simulation",
+            self.simulations,
+            self.steps,
+            self.elapsed,
+            self.steps_per_second(),
+            self.steps_per_second_per_simulation()
+        )
+    }
+}
+
+/// Default settings with a fixed seed, shortened to [`THROUGHPUT_STEPS`] time steps.
+fn standardized_settings() -> Settings {
+    let mut settings = Settings::default();
+    settings.set_seed(Some(THROUGHPUT_SEED));
+    let cycles = THROUGHPUT_STEPS as Float * settings.parameters.get_timestep();
+    settings.parameters.set_number_of_cycles(cycles).unwrap();
+
+    settings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_scale_with_the_cost() {
+        let throughput = Throughput {
+            simulations: 4,
+            steps: THROUGHPUT_STEPS,
+            cost: standardized_settings().estimated_cost(),
+            elapsed: 2.0,
+        };
+        assert_eq!(
+            throughput.steps_per_second(),
+            4.0 * THROUGHPUT_STEPS as Float / 2.0
+        );
+
+        // Ten times as many steps take ten times as long
+        let mut settings = standardized_settings();
+        let cycles = settings.parameters.get_number_of_cycles();
+        settings
+            .parameters
+            .set_number_of_cycles(10.0 * cycles)
+            .unwrap();
+        assert!((throughput.estimated_seconds(&settings) - 20.0).abs() < 1e-9);
+        // Eight such simulations on four threads take twice as long
+        let campaign = vec![settings; 8];
+        assert!((throughput.estimated_campaign_seconds(&campaign) - 40.0).abs() < 1e-9);
+    }
+}
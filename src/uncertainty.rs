@@ -0,0 +1,483 @@
+//! Uncertainty quantification over distributions of the parameters.
+//!
+//! The uncertain parameters are sampled from the given distributions, and a
+//! simulation is run for each sample. The resulting [`OutputStatistics`] are
+//! summarized with confidence intervals, and the full mapping from the sampled
+//! parameters to the outputs can be saved for building surrogate models.
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::azimuthal_mode::{InitialMode, Mode};
+#[cfg(feature = "hdf5")]
+use crate::observers;
+use crate::observers::SaveInfo;
+use crate::{Float, OutputStatistics, SeedSequence, SensitivityParameter, Settings, Sobol, RNG};
+
+/// Possible errors for [`UncertaintyQuantification`].
+#[derive(Clone, Debug)]
+pub enum UncertaintyError {
+    Distribution,
+    Samples,
+    Antithetic,
+    InitialMode,
+    InitialModeSampling,
+    Rejected,
+}
+
+impl std::error::Error for UncertaintyError {}
+
+impl std::fmt::Display for UncertaintyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::Distribution => "invalid distribution parameters",
+            Self::Samples => "at least two samples are required",
+            Self::Antithetic => {
+                "antithetic sampling requires an even number of at least four samples"
+            }
+            Self::InitialMode => {
+                "stratified or quasi-random initial modes require a random initial mode"
+            }
+            Self::InitialModeSampling => {
+                "the initial modes can either be stratified or quasi-random"
+            }
+            Self::Rejected => "fewer than two samples have valid parameter values",
+        };
+
+        write!(f, "error in the uncertainty quantification: {}", msg)
+    }
+}
+
+/// Probability distribution of an uncertain parameter.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum Distribution {
+    Normal {
+        mean: Float,
+        std: Float,
+    },
+    Uniform {
+        lower: Float,
+        upper: Float,
+    },
+    /// Logarithm of the parameter normally distributed with mean `mu` and standard deviation `sigma`.
+    LogNormal {
+        mu: Float,
+        sigma: Float,
+    },
+}
+
+impl Distribution {
+    /// Check that the parameters of the distribution are valid.
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            Self::Normal { std, .. } => std >= 0.0,
+            Self::Uniform { lower, upper } => lower <= upper,
+            Self::LogNormal { sigma, .. } => sigma >= 0.0,
+        }
+    }
+
+    /// Draw a sample from the distribution.
+    pub fn sample(&self, rng: &mut RNG) -> Float {
+        match *self {
+            Self::Normal { mean, std } => mean + std * rng.get_normal(),
+            Self::Uniform { lower, upper } => lower + (upper - lower) * rng.get_uniform(),
+            Self::LogNormal { mu, sigma } => (mu + sigma * rng.get_normal()).exp(),
+        }
+    }
+}
+
+/// A parameter and its distribution.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct UncertainParameter {
+    pub parameter: SensitivityParameter,
+    pub distribution: Distribution,
+}
+
+/// Variance reduction strategies for the ensemble.
+///
+/// With `antithetic`, the samples are run in pairs sharing the parameter
+/// values, initial mode, and seed, where the second sample of each pair uses
+/// the mirrored noise. The pairs should be averaged before estimating the
+/// statistics of the ensemble, which is done by [`UncertaintyResult`].
+///
+/// With `stratified_initial_mode`, the random initial modes (see
+/// [`crate::azimuthal_mode::InitialMode`]) are drawn with Latin hypercube
+/// sampling over the ensemble instead of independently. With
+/// `quasi_random_initial_mode`, they are the points of a randomly shifted
+/// Sobol sequence instead (see [`crate::Sobol`]), which cover the
+/// distribution more evenly in all four dimensions together.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct VarianceReduction {
+    #[serde(default)]
+    pub antithetic: bool,
+    #[serde(default)]
+    pub stratified_initial_mode: bool,
+    #[serde(default)]
+    pub quasi_random_initial_mode: bool,
+}
+
+/// Ensemble of simulations over uncertain parameters.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct UncertaintyQuantification {
+    pub samples: usize,
+    pub parameters: Vec<UncertainParameter>,
+    pub save_info: SaveInfo,
+    #[serde(default)]
+    pub variance_reduction: VarianceReduction,
+}
+
+impl UncertaintyQuantification {
+    pub fn new(
+        samples: usize,
+        parameters: Vec<UncertainParameter>,
+        save_info: SaveInfo,
+    ) -> Result<Self, UncertaintyError> {
+        let uq = Self {
+            samples,
+            parameters,
+            save_info,
+            variance_reduction: VarianceReduction::default(),
+        };
+        uq.validate()?;
+
+        Ok(uq)
+    }
+
+    /// Load the uncertainty quantification setup from a JSON file.
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn Error>> {
+        let buffer = BufReader::new(File::open(path)?);
+        let uq: Self = serde_json::from_reader(buffer)?;
+        uq.validate()?;
+
+        Ok(uq)
+    }
+
+    fn validate(&self) -> Result<(), UncertaintyError> {
+        if self.samples < 2 {
+            return Err(UncertaintyError::Samples);
+        }
+        if self.parameters.iter().any(|p| !p.distribution.is_valid()) {
+            return Err(UncertaintyError::Distribution);
+        }
+        if self.variance_reduction.antithetic
+            && (self.samples < 4 || !self.samples.is_multiple_of(2))
+        {
+            return Err(UncertaintyError::Antithetic);
+        }
+        let reduction = &self.variance_reduction;
+        if reduction.stratified_initial_mode && reduction.quasi_random_initial_mode {
+            return Err(UncertaintyError::InitialModeSampling);
+        }
+
+        Ok(())
+    }
+
+    /// Set the variance reduction strategies.
+    pub fn set_variance_reduction(
+        &mut self,
+        variance_reduction: VarianceReduction,
+    ) -> Result<(), UncertaintyError> {
+        self.variance_reduction = variance_reduction;
+        self.validate()
+    }
+
+    /// Run the ensemble of simulations around the base `settings` (in parallel with the `parallel` feature).
+    ///
+    /// Seeded settings give reproducible samples, with pair `i` (or sample `i`
+    /// without antithetic sampling) simulated using the seed of the child `[1, i]`
+    /// of the [`SeedSequence`] of the seed. The samples are drawn using the child `[0]`.
+    ///
+    /// Samples with invalid parameter values (e.g. a negative gain drawn from a
+    /// normal distribution) are not simulated, and are left out of the result.
+    pub fn run(&self, settings: &Settings) -> Result<UncertaintyResult, Box<dyn Error>> {
+        // Make sure all the parameters can be set before starting the simulations
+        for p in self.parameters.iter() {
+            p.parameter.get(settings)?;
+        }
+
+        // Each pair of antithetic samples shares everything except the noise
+        let antithetic = self.variance_reduction.antithetic;
+        let pair: Vec<usize> = (0..self.samples)
+            .map(|ind| if antithetic { ind / 2 } else { ind })
+            .collect();
+        let number_of_groups = pair.last().map_or(0, |&p| p + 1);
+
+        // Both samples of an antithetic pair need the same seed
+        let sequence = match settings.seed {
+            Some(seed) => Some(SeedSequence::new(seed)),
+            None if antithetic => Some(SeedSequence::new(rand::random())),
+            None => None,
+        };
+
+        // Draw all the samples up front, such that they are reproducible
+        let mut rng = match &sequence {
+            Some(sequence) => RNG::from_seed(sequence.child(0).seed()),
+            None => RNG::new(),
+        };
+        let group_inputs: Vec<Vec<Float>> = (0..number_of_groups)
+            .map(|_| {
+                self.parameters
+                    .iter()
+                    .map(|p| p.distribution.sample(&mut rng))
+                    .collect()
+            })
+            .collect();
+        let inputs: Vec<Vec<Float>> = pair.iter().map(|&p| group_inputs[p].clone()).collect();
+
+        let reduction = &self.variance_reduction;
+        let initial_modes: Option<Vec<Mode>> =
+            if reduction.stratified_initial_mode || reduction.quasi_random_initial_mode {
+                let distribution = match settings.parameters.get_initial_mode() {
+                    InitialMode::Random { distribution, .. } => distribution,
+                    InitialMode::Fixed(_) => return Err(Box::new(UncertaintyError::InitialMode)),
+                };
+                if reduction.stratified_initial_mode {
+                    Some(distribution.latin_hypercube(number_of_groups, &mut rng))
+                } else {
+                    let sobol = Sobol::new(4, Some(&mut rng))?;
+                    let modes = (0..number_of_groups as u64)
+                        .map(|ind| distribution.sobol_point(&sobol, ind))
+                        .collect();
+                    Some(modes)
+                }
+            } else {
+                None
+            };
+
+        #[cfg(feature = "parallel")]
+        let samples = inputs.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let samples = inputs.iter();
+        let outputs: Vec<Option<OutputStatistics>> = samples
+            .enumerate()
+            .map(|(ind, values)| {
+                let group = pair[ind];
+                let mut run_settings = settings.clone();
+                match &sequence {
+                    Some(sequence) => {
+                        run_settings.set_seed_sequence(sequence.child(1).child(group as u64))
+                    }
+                    None => run_settings.set_seed(None),
+                }
+                run_settings.rng.set_antithetic(antithetic && ind % 2 == 1);
+
+                for (p, &value) in self.parameters.iter().zip(values) {
+                    p.parameter.set(&mut run_settings, value).ok()?;
+                }
+                if let Some(modes) = &initial_modes {
+                    run_settings.parameters.set_initial_mode(modes[group]);
+                }
+
+                Some(OutputStatistics::from_simulation(run_settings))
+            })
+            .collect();
+
+        // Leave out the rejected samples (both samples of an antithetic pair are rejected together)
+        let rejected = outputs.iter().filter(|output| output.is_none()).count();
+        let (mut kept_inputs, mut kept_outputs, mut kept_pair) =
+            (Vec::new(), Vec::new(), Vec::new());
+        for ((values, output), group) in inputs.into_iter().zip(outputs).zip(pair) {
+            if let Some(output) = output {
+                kept_inputs.push(values);
+                kept_outputs.push(output);
+                kept_pair.push(group);
+            }
+        }
+        let mut groups = kept_pair.clone();
+        groups.dedup();
+        if groups.len() < 2 {
+            return Err(Box::new(UncertaintyError::Rejected));
+        }
+
+        Ok(UncertaintyResult {
+            parameters: self.parameters.iter().map(|p| p.parameter).collect(),
+            inputs: kept_inputs,
+            outputs: kept_outputs,
+            pair: kept_pair,
+            rejected,
+        })
+    }
+}
+
+/// Summary statistics of one output over the ensemble.
+#[derive(Clone, Copy, Debug)]
+pub struct EnsembleSummary {
+    pub mean: Float,
+    pub std: Float,
+    /// Lower bound of the 95% confidence interval of the mean.
+    pub lower: Float,
+    /// Upper bound of the 95% confidence interval of the mean.
+    pub upper: Float,
+}
+
+impl EnsembleSummary {
+    fn from_values(values: &[Float]) -> Self {
+        let n = values.len() as Float;
+        let mean = values.iter().sum::<Float>() / n;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<Float>() / (n - 1.0);
+        let std = variance.sqrt();
+
+        // Normal approximation of the sampling distribution of the mean
+        let half_width = 1.96 * std / n.sqrt();
+
+        Self {
+            mean,
+            std,
+            lower: mean - half_width,
+            upper: mean + half_width,
+        }
+    }
+}
+
+impl std::fmt::Display for EnsembleSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "mean = {} (95% CI [{}, {}]), std = {}",
+            self.mean, self.lower, self.upper, self.std
+        )
+    }
+}
+
+/// Result of the uncertainty quantification.
+#[derive(Clone, Debug)]
+pub struct UncertaintyResult {
+    pub parameters: Vec<SensitivityParameter>,
+    /// Sampled values, with one row per sample and one column per parameter.
+    pub inputs: Vec<Vec<Float>>,
+    pub outputs: Vec<OutputStatistics>,
+    /// Index of the antithetic pair of each sample (the sample index without antithetic sampling).
+    pub pair: Vec<usize>,
+    /// Number of samples left out for invalid parameter values.
+    pub rejected: usize,
+}
+
+impl UncertaintyResult {
+    /// Summary of the mean amplitude over the ensemble.
+    pub fn mean_amplitude(&self) -> EnsembleSummary {
+        let values: Vec<Float> = self.outputs.iter().map(|o| o.mean_amplitude).collect();
+        self.summary(&values)
+    }
+
+    /// Summary of the spinning probability over the ensemble.
+    pub fn spinning_probability(&self) -> EnsembleSummary {
+        let values: Vec<Float> = self
+            .outputs
+            .iter()
+            .map(|o| o.spinning_probability)
+            .collect();
+        self.summary(&values)
+    }
+
+    /// Average the values of each antithetic pair before summarizing them.
+    fn summary(&self, values: &[Float]) -> EnsembleSummary {
+        let number_of_groups = self.pair.iter().max().map_or(0, |&p| p + 1);
+        let mut sums = vec![0.0; number_of_groups];
+        let mut counts = vec![0; number_of_groups];
+        for (&group, value) in self.pair.iter().zip(values) {
+            sums[group] += value;
+            counts[group] += 1;
+        }
+
+        // The pairs of rejected samples are empty
+        let group_means: Vec<Float> = sums
+            .iter()
+            .zip(&counts)
+            .filter(|(_, &count)| count > 0)
+            .map(|(sum, &count)| sum / count as Float)
+            .collect();
+        EnsembleSummary::from_values(&group_means)
+    }
+
+    /// Save the samples and the corresponding outputs.
+    #[cfg(feature = "hdf5")]
+    pub fn save(&self, save_info: &SaveInfo, settings: &Settings) -> hdf5::Result<()> {
+        let file = hdf5::File::append(save_info.get_path())?;
+        let group = file.create_group(save_info.get_group())?;
+
+        let columns = self.parameters.len();
+        let flat: Vec<Float> = self.inputs.iter().flatten().copied().collect();
+        let inputs = ndarray::Array2::from_shape_vec((self.inputs.len(), columns), flat)
+            .map_err(|e| e.to_string())?;
+        group
+            .new_dataset_builder()
+            .with_data(&inputs)
+            .create("inputs")?;
+
+        let names: Vec<&str> = self.parameters.iter().map(|p| p.name()).collect();
+        observers::save_str_attr(&group, &names.join(","), "input_names")?;
+
+        let mean_amplitude: Vec<Float> = self.outputs.iter().map(|o| o.mean_amplitude).collect();
+        observers::write_dataset(&group, &mean_amplitude, "mean_amplitude")?;
+
+        let spinning: Vec<Float> = self
+            .outputs
+            .iter()
+            .map(|o| o.spinning_probability)
+            .collect();
+        observers::write_dataset(&group, &spinning, "spinning_probability")?;
+
+        let pair: Vec<u64> = self.pair.iter().map(|&p| p as u64).collect();
+        observers::write_dataset(&group, &pair, "pair")?;
+        observers::save_attr(
+            &group,
+            &ndarray::arr0(self.rejected as u64),
+            "rejected_samples",
+        )?;
+
+        observers::save_parameters_as_attribute_json(&group, &settings.parameters)
+    }
+}
+
+impl std::fmt::Display for UncertaintyResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} samples", self.outputs.len())?;
+        if self.rejected > 0 {
+            writeln!(
+                f,
+                "{} samples rejected for invalid parameter values",
+                self.rejected
+            )?;
+        }
+        writeln!(f, "mean amplitude: {}", self.mean_amplitude())?;
+        write!(f, "spinning probability: {}", self.spinning_probability())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn confidence_interval() {
+        let summary = EnsembleSummary::from_values(&[1.0, 2.0, 3.0, 4.0]);
+
+        assert!((summary.mean - 2.5).abs() < 1e-12);
+        assert!((summary.std - (5.0 as Float / 3.0).sqrt()).abs() < 1e-12);
+        assert!((summary.upper - summary.mean - 1.96 * summary.std / 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn invalid_samples_are_rejected() {
+        // Every sample of the gain is negative, such that nothing is simulated
+        let parameters = vec![UncertainParameter {
+            parameter: SensitivityParameter::Gain,
+            distribution: Distribution::Normal {
+                mean: -1.0,
+                std: 0.01,
+            },
+        }];
+        let save_info = SaveInfo::default();
+        let uq = UncertaintyQuantification::new(4, parameters, save_info).unwrap();
+
+        let error = uq.run(&Settings::default()).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<UncertaintyError>(),
+            Some(UncertaintyError::Rejected)
+        ));
+    }
+}
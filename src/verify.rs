@@ -0,0 +1,263 @@
+//! Invariants of the model, checked over random inputs.
+//!
+//! Each check of a single input is available as a function returning a
+//! [`VerificationError`] if the invariant is violated, such that the checks
+//! can be driven by a property-based testing framework (see `tests/verify.rs`)
+//! or by [`Verification`], which draws the inputs itself and is used by the
+//! `--verify` command line option to check a user installation.
+use crate::azimuthal_mode::SystemMode;
+use crate::hrr_integral::DescribingFunction;
+use crate::{Float, Fourier, HeatReleaseRate, SeedSequence, Settings, FRAC_PI_4, PI, RNG};
+
+/// Tolerance of the Fourier round trip, relative to the largest magnitude of the signal.
+const ROUND_TRIP_TOLERANCE: Float = 1e-9;
+/// Tolerance of the rotation invariance, relative to the magnitude of the integral.
+const ROTATION_TOLERANCE: Float = 1e-9;
+/// Number of cycles of the simulations of [`Verification`].
+const SIMULATION_CYCLES: Float = 5.0;
+
+/// Violated invariants, and invalid inputs of the checks.
+#[derive(Clone, Debug)]
+pub enum VerificationError {
+    /// The nature angle logged at `time` is not in `[-pi/4, pi/4]`.
+    ChiRange {
+        time: Float,
+        chi: Float,
+    },
+    /// The signal reconstructed from its spectrum differs by `deviation`.
+    FourierRoundTrip {
+        deviation: Float,
+    },
+    /// The integral changes by `deviation` when the burners are shifted by `shift`.
+    RotationInvariance {
+        shift: u32,
+        deviation: Float,
+    },
+    /// The rotation invariance only holds for deterministic describing functions.
+    DescribingFunction,
+    /// The rotation invariance only holds for identical flames at all the burners.
+    BrokenSymmetry,
+    Cases,
+}
+
+impl std::error::Error for VerificationError {}
+
+impl std::fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            Self::ChiRange { time, chi } => {
+                format!("the nature angle {} at time {} is out of range", chi, time)
+            }
+            Self::FourierRoundTrip { deviation } => format!(
+                "the signal synthesized from its spectrum deviates by {}",
+                deviation
+            ),
+            Self::RotationInvariance { shift, deviation } => format!(
+                "the heat release rate integral changes by {} when shifting the burners by {}",
+                deviation, shift
+            ),
+            Self::DescribingFunction => {
+                "the stochastic describing function is not invariant under rotations".to_owned()
+            }
+            Self::BrokenSymmetry => {
+                "the flame profile or the disabled burners break the rotational symmetry".to_owned()
+            }
+            Self::Cases => "at least one case is required".to_owned(),
+        };
+
+        write!(f, "error in the verification: {}", msg)
+    }
+}
+
+/// Check that the nature angle of every logged mode of a simulation of `settings` is in range.
+///
+/// The observer of `settings` is replaced by a time series kept in memory.
+pub fn check_chi_range(settings: Settings) -> Result<(), VerificationError> {
+    let observer = crate::sensitivity::run_time_series(settings);
+
+    for (&time, mode) in observer.get_time().iter().zip(observer.get_modes()) {
+        let chi = mode.chi();
+        if chi.is_nan() || chi.abs() > FRAC_PI_4 {
+            return Err(VerificationError::ChiRange { time, chi });
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that [`Fourier::synthesize`] recovers `signal` from its [`Fourier::spectrum`].
+///
+/// The signal is sampled at equidistant locations around the annulus.
+pub fn check_fourier_round_trip(signal: &[Float], ntheta0: Float) -> Result<(), VerificationError> {
+    let dtheta = 2.0 * PI / signal.len() as Float;
+    let thetas: Vec<Float> = (0..signal.len()).map(|ind| ind as Float * dtheta).collect();
+
+    let spectrum = Fourier::spectrum(&thetas, signal, ntheta0);
+    let reconstructed = Fourier::synthesize(&spectrum, &thetas, ntheta0);
+
+    let scale = signal.iter().fold(1.0, |max: Float, s| max.max(s.abs()));
+    let deviation = signal
+        .iter()
+        .zip(reconstructed)
+        .fold(0.0, |max: Float, (s, r)| max.max((s - r).abs()));
+    if deviation.is_nan() || deviation > ROUND_TRIP_TOLERANCE * scale {
+        return Err(VerificationError::FourierRoundTrip { deviation });
+    }
+
+    Ok(())
+}
+
+/// Check that the heat release rate integral at `mode` is unchanged by shifting the burner indices.
+///
+/// Shifting the (equidistant) burners by `shift` indices is equivalent to
+/// rotating the mode by `shift` burner spacings, i.e. increasing the
+/// orientation angle `n theta_0` by `2 pi n shift / N` for `N` burners. The
+/// set of local amplitudes, and hence the integral, is unchanged.
+pub fn check_rotation_invariance(
+    settings: &Settings,
+    mode: &SystemMode,
+    shift: u32,
+) -> Result<(), VerificationError> {
+    if let DescribingFunction::Stochastic(_) = settings.describing_function {
+        return Err(VerificationError::DescribingFunction);
+    }
+
+    let parameters = &settings.parameters;
+    if parameters.broken_symmetry().is_some() {
+        return Err(VerificationError::BrokenSymmetry);
+    }
+
+    let spacing = 2.0 * PI / parameters.get_number_of_burners() as Float;
+    let rotated = SystemMode {
+        nth0: mode.nth0 + (parameters.get_mode_order() * shift) as Float * spacing,
+        ..*mode
+    };
+
+    let integral = settings.describing_function.integral(mode, settings);
+    let rotated = settings.describing_function.integral(&rotated, settings);
+    let components = |q: crate::Quaternion| [q.real, q.imag_i, q.imag_j, q.imag_k];
+    let scale = components(integral)
+        .iter()
+        .fold(1.0, |max: Float, c| max.max(c.abs()));
+    let deviation = components(integral)
+        .iter()
+        .zip(components(rotated))
+        .fold(0.0, |max: Float, (a, b)| max.max((a - b).abs()));
+    if deviation.is_nan() || deviation > ROTATION_TOLERANCE * scale {
+        return Err(VerificationError::RotationInvariance { shift, deviation });
+    }
+
+    Ok(())
+}
+
+/// Random inputs for the checks of the invariants around some settings.
+#[derive(Clone, Copy, Debug)]
+pub struct Verification {
+    /// Number of random inputs of each check.
+    pub cases: usize,
+    pub seed: u64,
+}
+
+/// Number of passed cases and the first violation of each invariant.
+#[derive(Clone, Debug)]
+pub struct VerificationReport {
+    pub cases: usize,
+    pub chi_range: Result<(), VerificationError>,
+    pub fourier_round_trip: Result<(), VerificationError>,
+    /// Not checked (`None`) for the stochastic describing function, and
+    /// for flames breaking the rotational symmetry.
+    pub rotation_invariance: Option<Result<(), VerificationError>>,
+}
+
+impl Verification {
+    pub fn new(cases: usize, seed: u64) -> Result<Self, VerificationError> {
+        if cases == 0 {
+            return Err(VerificationError::Cases);
+        }
+
+        Ok(Self { cases, seed })
+    }
+
+    /// Check the invariants for random inputs around `settings`.
+    ///
+    /// The nature angle is checked in short simulations of the settings with
+    /// independent seeds derived from the seed of the verification, the Fourier round trip for random signals at the
+    /// number of burners of the settings, and the rotation invariance for
+    /// random modes and shifts with the describing function of the settings.
+    pub fn run(&self, settings: &Settings) -> VerificationReport {
+        let sequence = SeedSequence::new(self.seed);
+        let mut rng = RNG::from_seed(sequence.child(0).seed());
+        let mut draw = |min: Float, max: Float| min + (max - min) * rng.get_uniform();
+        let parameters = &settings.parameters;
+        let burners = parameters.get_number_of_burners();
+
+        let chi_range = (0..self.cases as u64).try_for_each(|case| {
+            let mut settings = settings.clone();
+            settings.set_seed_sequence(sequence.child(1).child(case));
+            settings.progress = None;
+            let cycles = SIMULATION_CYCLES.min(settings.parameters.get_number_of_cycles());
+            settings
+                .parameters
+                .set_number_of_cycles(cycles)
+                .expect("shortening valid parameters keeps them valid");
+            check_chi_range(settings)
+        });
+
+        let fourier_round_trip = (0..self.cases).try_for_each(|_| {
+            let signal: Vec<Float> = (0..burners).map(|_| draw(-1.0, 1.0)).collect();
+            check_fourier_round_trip(&signal, draw(0.0, 2.0 * PI))
+        });
+
+        let rotation_invariance = match settings.describing_function {
+            DescribingFunction::Stochastic(_) => None,
+            _ if parameters.broken_symmetry().is_some() => None,
+            _ => Some((0..self.cases).try_for_each(|_| {
+                let mode = SystemMode::new(
+                    draw(0.05, 2.0),
+                    draw(0.0, 2.0 * PI),
+                    draw(0.0, 2.0 * PI),
+                    draw(-0.7, 0.7),
+                );
+                let shift = 1 + draw(0.0, burners.max(2) as Float - 1.0) as u32;
+                check_rotation_invariance(settings, &mode, shift)
+            })),
+        };
+
+        VerificationReport {
+            cases: self.cases,
+            chi_range,
+            fourier_round_trip,
+            rotation_invariance,
+        }
+    }
+}
+
+impl VerificationReport {
+    /// Whether all the checked invariants hold.
+    pub fn passed(&self) -> bool {
+        self.chi_range.is_ok()
+            && self.fourier_round_trip.is_ok()
+            && self.rotation_invariance.as_ref().is_none_or(|r| r.is_ok())
+    }
+}
+
+impl std::fmt::Display for VerificationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = |result: &Result<(), VerificationError>| match result {
+            Ok(()) => format!("passed ({} cases)", self.cases),
+            Err(e) => format!("FAILED, {}", e),
+        };
+        let rotation_invariance = match &self.rotation_invariance {
+            Some(result) => status(result),
+            None => "not checked".to_owned(),
+        };
+
+        write!(
+            f,
+            "nature angle range: {}\nFourier round trip: {}\nrotation invariance: {}",
+            status(&self.chi_range),
+            status(&self.fourier_round_trip),
+            rotation_invariance
+        )
+    }
+}
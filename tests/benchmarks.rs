@@ -0,0 +1,130 @@
+//! Stationary histograms of simulations against the closed-form densities of [`azimuthal_fdf::CubicSaturationBenchmark`].
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use azimuthal_fdf::azimuthal_mode::{ModeDistribution, SystemMode};
+use azimuthal_fdf::hrr_integral::ConventionalFDF;
+use azimuthal_fdf::observers::HistogramObserver;
+use azimuthal_fdf::prelude::*;
+use azimuthal_fdf::{CubicSaturationBenchmark, SimulationHooks, PI};
+
+/// Number of independent realizations of each benchmark.
+const REALIZATIONS: u64 = 40;
+
+/// Summed histograms of the amplitude and nature angle over the realizations.
+struct Histograms {
+    amplitude: (Vec<Float>, Vec<usize>),
+    nature_angle: (Vec<Float>, Vec<usize>),
+    diverged: u64,
+}
+
+/// Run the realizations of `settings` with consecutive seeds.
+///
+/// The explicit scheme diverges when a large noise sample is drawn close to
+/// the spinning states, where the noise of `tan 2chi` is strongest. Such
+/// realizations are discarded.
+fn simulate(settings: &Settings, nbins: usize, amplitude_limit: Float) -> Histograms {
+    let mut histograms = Histograms {
+        amplitude: (Vec::new(), vec![0; nbins]),
+        nature_angle: (Vec::new(), vec![0; nbins]),
+        diverged: 0,
+    };
+    for seed in 0..REALIZATIONS {
+        let mut settings = settings.clone();
+        let histogram = HistogramObserver::new(
+            &PathBuf::from("benchmarks.h5"),
+            None,
+            nbins,
+            amplitude_limit,
+        );
+        settings.set_observer(Observer::Histogram(histogram));
+        settings.set_seed(Some(seed));
+        settings.progress = None;
+
+        let final_mode = Arc::new(Mutex::new(SystemMode::default()));
+        let finish = Arc::clone(&final_mode);
+        let mut hooks = SimulationHooks::new();
+        hooks.on_finish(move |state| *finish.lock().unwrap() = *state.mode);
+        settings.hooks = hooks;
+
+        settings.run();
+        if !final_mode.lock().unwrap().a().is_finite() {
+            histograms.diverged += 1;
+            continue;
+        }
+        let Observer::Histogram(histogram) = settings.get_observer() else {
+            unreachable!("the observer is a histogram")
+        };
+        for ((centers, counts), (summed_centers, summed_counts)) in [
+            (histogram.amplitude_histogram(), &mut histograms.amplitude),
+            (
+                histogram.nature_angle_histogram(),
+                &mut histograms.nature_angle,
+            ),
+        ] {
+            assert_eq!(counts.len(), nbins, "the amplitude range was expanded");
+            *summed_centers = centers;
+            for (sum, count) in summed_counts.iter_mut().zip(counts) {
+                *sum += count;
+            }
+        }
+    }
+
+    histograms
+}
+
+/// Sum of the absolute differences between the binned and expected probabilities.
+fn total_deviation(centers: &[Float], counts: &[usize], density: &[Float]) -> Float {
+    let total = counts.iter().sum::<usize>() as Float;
+    let width = (centers[1] - centers[0]).abs();
+    counts
+        .iter()
+        .zip(density)
+        .map(|(&count, &p)| (count as Float / total - p * width).abs())
+        .sum()
+}
+
+#[test]
+fn linear_gain_with_cubic_saturation() {
+    // Growth rate 1, saturation coefficient 1, and noise 0.25
+    let mut parameters =
+        Parameters::new(1.0, 2.0, 0.25, 1, 12, Mode::default(), 0.002, 25.0, 10).unwrap();
+    parameters
+        .set_initial_mode_distribution(ModeDistribution {
+            amplitude: [0.8, 1.2],
+            orientation_angle: [0.0, 2.0 * PI],
+            phase: [0.0, 2.0 * PI],
+            nature_angle: [-0.6, 0.6],
+        })
+        .unwrap();
+    let settings = Settings::new(
+        parameters,
+        Saturation::Cubic(1.0),
+        Observer::TimeSeries(Default::default()),
+        DescribingFunction::Conventional(ConventionalFDF::new()),
+    );
+    let benchmark = CubicSaturationBenchmark::from_settings(&settings).unwrap();
+
+    let histograms = simulate(&settings, 20, 2.0);
+    assert!(histograms.diverged <= REALIZATIONS / 4);
+
+    let (centers, counts) = &histograms.amplitude;
+    let deviation = total_deviation(centers, counts, &benchmark.amplitude_density(centers));
+    assert!(deviation < 0.15, "amplitude deviation {}", deviation);
+
+    // The mode switches rarely between the two spinning directions, which
+    // are equally probable, so the histogram is folded about chi = 0 (the
+    // centres of bin ind and nbins - 1 - ind are opposite)
+    let (centers, counts) = &histograms.nature_angle;
+    let half = centers.len() / 2;
+    let folded: Vec<usize> = (0..half)
+        .map(|ind| counts[ind] + counts[2 * half - 1 - ind])
+        .collect();
+    let density: Vec<Float> = benchmark
+        .nature_angle_density(&centers[..half])
+        .iter()
+        .map(|p| 2.0 * p)
+        .collect();
+    let deviation = total_deviation(&centers[..half], &folded, &density);
+    assert!(deviation < 0.2, "nature angle deviation {}", deviation);
+}
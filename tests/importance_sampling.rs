@@ -0,0 +1,78 @@
+//! Weighted tail probabilities of tilted simulations against an untilted reference run.
+use std::ops::Range;
+use std::path::PathBuf;
+
+use azimuthal_fdf::hrr_integral::ConventionalFDF;
+use azimuthal_fdf::observers::HistogramObserver;
+use azimuthal_fdf::prelude::*;
+use azimuthal_fdf::ImportanceSampling;
+
+/// Amplitude above which the tail probability is estimated.
+const THRESHOLD: Float = 1.05;
+
+/// Tail probability of the amplitude over the realizations of the seeds.
+///
+/// Returns the estimate from the weights, and the fraction of the binned values
+/// above the threshold. Both are equal without importance sampling.
+fn tail_probability(
+    importance_sampling: Option<ImportanceSampling>,
+    seeds: Range<u64>,
+) -> (Float, Float) {
+    // Short simulations (two cycles) of a mode starting from unit amplitude
+    let parameters =
+        Parameters::new(1.0, 2.0, 0.25, 1, 12, Mode::default(), 0.002, 2.0, 10).unwrap();
+    let mut settings = Settings::new(
+        parameters,
+        Saturation::Cubic(1.0),
+        Observer::TimeSeries(Default::default()),
+        DescribingFunction::Conventional(ConventionalFDF::new()),
+    );
+    settings.set_importance_sampling(importance_sampling);
+    settings.progress = None;
+
+    let (mut weighted, mut counted, mut values) = (0.0, 0, 0);
+    for seed in seeds {
+        let mut settings = settings.clone();
+        let histogram =
+            HistogramObserver::new(&PathBuf::from("tilted.h5"), None, 40, 2.0 * THRESHOLD);
+        settings.set_observer(Observer::Histogram(histogram));
+        settings.set_seed(Some(seed));
+        settings.run();
+
+        let Observer::Histogram(histogram) = settings.get_observer() else {
+            unreachable!("the observer is a histogram")
+        };
+        let (centers, counts) = histogram.amplitude_histogram();
+        let (_, weights) = histogram.weighted_amplitude_histogram();
+        for ((&center, &count), &weight) in centers.iter().zip(counts).zip(weights) {
+            if center > THRESHOLD {
+                counted += count;
+                weighted += weight;
+            }
+        }
+        values += histogram.number_of_values();
+    }
+
+    if importance_sampling.is_none() {
+        weighted = counted as Float;
+    }
+    let values = values as Float;
+
+    (weighted / values, counted as Float / values)
+}
+
+#[test]
+fn weighted_tail_probability_matches_untilted_reference() {
+    let (reference, _) = tail_probability(None, 0..2000);
+    let (estimate, tilted) = tail_probability(Some(ImportanceSampling::new(0.04)), 2000..2600);
+
+    // The tilt samples the tail more than three times as often, and the
+    // weights recover the untilted probability (about 0.015)
+    assert!(tilted > 3.0 * reference, "{} {}", tilted, reference);
+    assert!(
+        (estimate - reference).abs() < 0.25 * reference,
+        "{} != {}",
+        estimate,
+        reference
+    );
+}
@@ -0,0 +1,51 @@
+//! Regression test of the phase reference of the gain component of order `2n`.
+//!
+//! The gain of a burner depends on the local amplitude of the mode at the
+//! burner (`SystemMode::local_amplitude`, following the mode ansatz of
+//! Ghirardo and Gant, reference [2] of the README), whose square is
+//! `A^2 (1 + cos(2 n theta - 2 ntheta_0) cos 2chi) / 2`. The gain is thus a
+//! function of `2 n theta - 2 ntheta_0`, and the phase of its component of
+//! order `2n` is measured from `2 ntheta_0`. Measuring it from `ntheta_0`
+//! instead, as before, made the integral depend on the orientation of the
+//! mode in a rotationally symmetric annulus.
+use azimuthal_fdf::azimuthal_mode::SystemMode;
+use azimuthal_fdf::hrr_integral::HeatReleaseRate;
+use azimuthal_fdf::{Float, Settings};
+
+/// Heat release rate integral of the default settings as (real, i, j, k).
+fn integral(nth0: Float) -> [Float; 4] {
+    let settings = Settings::default();
+    let mode = SystemMode::new(0.4, nth0, 0.2, 0.3);
+    let q = settings.describing_function.integral(&mode, &settings);
+
+    [q.real, q.imag_i, q.imag_j, q.imag_k]
+}
+
+#[test]
+fn integral_matches_previous_output_at_zero_orientation() {
+    // Output of the previous definition, for which both references coincide
+    let previous = [2.4137393827915455e-2, 0.0, 0.0, -8.302144734071377e-3];
+    for (value, expected) in integral(0.0).into_iter().zip(previous) {
+        assert!(
+            (value - expected).abs() < 1e-15,
+            "{} != {}",
+            value,
+            expected
+        );
+    }
+}
+
+#[test]
+fn integral_is_independent_of_the_orientation() {
+    // The previous definition gave [2.4660e-2, -1.4297e-3, 1.3778e-3, -7.7964e-3] at
+    // 0.7, i.e. a drift of the orientation of the order of the drift of the amplitude.
+    // The remaining differences are due to the discrete burners
+    for (rotated, aligned) in integral(0.7).into_iter().zip(integral(0.0)) {
+        assert!(
+            (rotated - aligned).abs() < 1e-5,
+            "{} != {}",
+            rotated,
+            aligned
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! Property-based tests of the invariants of [`azimuthal_fdf::verify`].
+use azimuthal_fdf::azimuthal_mode::{ModeDistribution, SystemMode};
+use azimuthal_fdf::hrr_integral::{AFDFSimplified, ConventionalFDF};
+use azimuthal_fdf::prelude::*;
+use azimuthal_fdf::{verify, PI};
+use proptest::prelude::*;
+
+/// Settings with the given describing function and number of burners.
+fn settings(describing_function: DescribingFunction, burners: u32) -> Settings {
+    let mut settings = Settings::default();
+    settings.describing_function = describing_function;
+    settings
+        .parameters
+        .set_number_of_burners(burners)
+        .expect("the number of burners is large enough to avoid aliasing");
+    settings
+}
+
+fn describing_function() -> impl Strategy<Value = DescribingFunction> {
+    prop_oneof![
+        (0.0..2.0).prop_map(|scale| DescribingFunction::Conventional(
+            ConventionalFDF::with_gain_scale(scale).unwrap()
+        )),
+        (0.0..3.0).prop_map(|r| DescribingFunction::Simplified(AFDFSimplified::new(r))),
+    ]
+}
+
+fn mode() -> impl Strategy<Value = SystemMode> {
+    (0.01..3.0, 0.0..2.0 * PI, 0.0..2.0 * PI, -0.78..0.78)
+        .prop_map(|(a, nth0, phi, chi)| SystemMode::new(a, nth0, phi, chi))
+}
+
+proptest! {
+    #[test]
+    fn fourier_round_trip(
+        signal in prop::collection::vec(-10.0..10.0, 1..40),
+        ntheta0 in -10.0..10.0,
+    ) {
+        verify::check_fourier_round_trip(&signal, ntheta0).unwrap();
+    }
+
+    #[test]
+    fn rotation_invariance(
+        describing_function in describing_function(),
+        burners in 4u32..32,
+        mode in mode(),
+        shift in 1u32..32,
+    ) {
+        let settings = settings(describing_function, burners);
+        verify::check_rotation_invariance(&settings, &mode, shift).unwrap();
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn chi_in_range(seed: u64, gain in 0.0..0.2, noise in 0.001..0.05) {
+        let mut settings = settings(DescribingFunction::default(), 12);
+        settings.set_seed(Some(seed));
+        let parameters = &mut settings.parameters;
+        parameters.set_gain(gain).unwrap();
+        parameters.set_noise(noise).unwrap();
+        parameters.set_number_of_cycles(2.0).unwrap();
+        parameters
+            .set_initial_mode_distribution(ModeDistribution::default())
+            .unwrap();
+        verify::check_chi_range(settings).unwrap();
+    }
+}